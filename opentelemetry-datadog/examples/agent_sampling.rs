@@ -1,10 +1,10 @@
 use opentelemetry::{
     global,
-    trace::{SamplingResult, Span, TraceContextExt, Tracer, TracerProvider},
+    trace::{Span, TraceContextExt, Tracer, TracerProvider},
     InstrumentationScope, Key, KeyValue, Value,
 };
-use opentelemetry_datadog::{new_pipeline, ApiVersion, DatadogTraceStateBuilder};
-use opentelemetry_sdk::trace::{self, RandomIdGenerator, ShouldSample};
+use opentelemetry_datadog::{new_pipeline, AgentSamplingRates, ApiVersion, DatadogSampler};
+use opentelemetry_sdk::trace::{self, RandomIdGenerator};
 use opentelemetry_semantic_conventions as semcov;
 use std::thread;
 use std::time::Duration;
@@ -24,47 +24,19 @@ fn bar() {
     span.end()
 }
 
-#[derive(Debug, Clone)]
-struct AgentBasedSampler;
-
-impl ShouldSample for AgentBasedSampler {
-    fn should_sample(
-        &self,
-        parent_context: Option<&opentelemetry::Context>,
-        _trace_id: opentelemetry::trace::TraceId,
-        _name: &str,
-        _span_kind: &opentelemetry::trace::SpanKind,
-        _attributes: &[opentelemetry::KeyValue],
-        _links: &[opentelemetry::trace::Link],
-    ) -> opentelemetry::trace::SamplingResult {
-        let trace_state = parent_context
-            .map(
-                |parent_context| parent_context.span().span_context().trace_state().clone(), // inherit sample decision from parent span
-            )
-            .unwrap_or_else(|| {
-                DatadogTraceStateBuilder::default()
-                    .with_priority_sampling(true) // always sample root span(span without remote or local parent)
-                    .with_measuring(true) // datadog-agent will create metric for this span for APM
-                    .build()
-            });
-
-        SamplingResult {
-            decision: opentelemetry::trace::SamplingDecision::RecordAndSample, // send all spans to datadog-agent
-            attributes: vec![],
-            trace_state,
-        }
-    }
-}
-
 fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    // Filled from the agent's responses, and read by the sampler for every new trace
+    let rates = AgentSamplingRates::new();
+
     let mut config = trace::Config::default();
-    config.sampler = Box::new(AgentBasedSampler);
+    config.sampler = Box::new(DatadogSampler::new(rates.clone(), "agent-sampling-demo"));
     config.id_generator = Box::new(RandomIdGenerator::default());
 
     let provider = new_pipeline()
         .with_service_name("agent-sampling-demo")
         .with_api_version(ApiVersion::Version05)
         .with_trace_config(config)
+        .with_agent_sampling_rates(rates)
         .install_simple()?;
     global::set_tracer_provider(provider.clone());
     let scope = InstrumentationScope::builder("opentelemetry-datadog-demo")
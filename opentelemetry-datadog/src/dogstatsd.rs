@@ -0,0 +1,716 @@
+//! DogStatsD metrics exporter, see [`DogStatsDExporter`]
+
+use crate::exporter::UnifiedTags;
+use crate::Error;
+use opentelemetry::KeyValue;
+use opentelemetry_sdk::error::{OTelSdkError, OTelSdkResult};
+use opentelemetry_sdk::metrics::data::{
+    AggregatedMetrics, ExponentialBucket, Metric, MetricData, ResourceMetrics,
+};
+use opentelemetry_sdk::metrics::exporter::PushMetricExporter;
+use opentelemetry_sdk::metrics::Temporality;
+use std::fmt::{Display, Write as _};
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+#[cfg(unix)]
+use std::os::unix::net::UnixDatagram;
+#[cfg(unix)]
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+const DEFAULT_AGENT_HOST: &str = "127.0.0.1";
+const DEFAULT_DOGSTATSD_PORT: u16 = 8125;
+const AGENT_HOST_ENV: &str = "DD_AGENT_HOST";
+const DOGSTATSD_PORT_ENV: &str = "DD_DOGSTATSD_PORT";
+
+// https://docs.datadoghq.com/developers/dogstatsd/high_throughput/#ensure-proper-packet-sizes
+const DEFAULT_UDP_MAX_PACKET_SIZE: usize = 1432;
+#[cfg(unix)]
+const DEFAULT_UDS_MAX_PACKET_SIZE: usize = 8192;
+
+#[derive(Debug)]
+enum Endpoint {
+    Udp(String),
+    #[cfg(unix)]
+    Unix(PathBuf),
+}
+
+#[derive(Debug)]
+enum Socket {
+    Udp(UdpSocket),
+    #[cfg(unix)]
+    Unix(UnixDatagram, PathBuf),
+}
+
+impl Socket {
+    fn send(&self, packet: &[u8]) -> std::io::Result<usize> {
+        match self {
+            Socket::Udp(socket) => socket.send(packet),
+            #[cfg(unix)]
+            Socket::Unix(socket, path) => socket.send_to(packet, path),
+        }
+    }
+}
+
+/// Builder for [`DogStatsDExporter`]
+pub struct DogStatsDExporterBuilder {
+    endpoint: Option<Endpoint>,
+    max_packet_size: Option<usize>,
+    unified_tags: UnifiedTags,
+}
+
+impl DogStatsDExporterBuilder {
+    /// Send datagrams over UDP to `address`, as `host:port`.
+    ///
+    /// Defaults to `DD_AGENT_HOST` and `DD_DOGSTATSD_PORT`, or `127.0.0.1:8125`.
+    pub fn with_udp_address<T: Into<String>>(mut self, address: T) -> Self {
+        self.endpoint = Some(Endpoint::Udp(address.into()));
+        self
+    }
+
+    /// Send datagrams to the agent's Unix datagram socket at `path`, usually
+    /// `/var/run/datadog/dsd.socket`.
+    #[cfg(unix)]
+    pub fn with_unix_socket<T: Into<PathBuf>>(mut self, path: T) -> Self {
+        self.endpoint = Some(Endpoint::Unix(path.into()));
+        self
+    }
+
+    /// Maximum size of the packets datagrams are batched into.
+    ///
+    /// Defaults to 1432 bytes over UDP, which fits the usual 1500 bytes network MTU, and
+    /// 8192 bytes over Unix sockets. Datagrams larger than this are sent on their own.
+    pub fn with_max_packet_size(mut self, max_packet_size: usize) -> Self {
+        self.max_packet_size = Some(max_packet_size);
+        self
+    }
+
+    /// Assign the service name tag of all metrics
+    pub fn with_service_name<T: Into<String>>(mut self, service_name: T) -> Self {
+        self.unified_tags.set_service(Some(service_name.into()));
+        self
+    }
+
+    /// Assign the version tag of all metrics
+    pub fn with_version<T: Into<String>>(mut self, version: T) -> Self {
+        self.unified_tags.set_version(Some(version.into()));
+        self
+    }
+
+    /// Assign the env tag of all metrics
+    pub fn with_env<T: Into<String>>(mut self, env: T) -> Self {
+        self.unified_tags.set_env(Some(env.into()));
+        self
+    }
+
+    /// Create the exporter and its socket
+    pub fn build(self) -> Result<DogStatsDExporter, Error> {
+        let endpoint = self.endpoint.unwrap_or_else(|| {
+            let host =
+                std::env::var(AGENT_HOST_ENV).unwrap_or_else(|_| DEFAULT_AGENT_HOST.to_string());
+            let port = std::env::var(DOGSTATSD_PORT_ENV)
+                .ok()
+                .and_then(|port| port.parse().ok())
+                .unwrap_or(DEFAULT_DOGSTATSD_PORT);
+            Endpoint::Udp(format!("{host}:{port}"))
+        });
+
+        let (socket, default_max_packet_size) = match endpoint {
+            Endpoint::Udp(address) => (
+                Socket::Udp(udp_socket(&address)?),
+                DEFAULT_UDP_MAX_PACKET_SIZE,
+            ),
+            #[cfg(unix)]
+            Endpoint::Unix(path) => {
+                // A full socket buffer would otherwise block the export until the agent reads
+                let socket = UnixDatagram::unbound().map_err(socket_error)?;
+                socket.set_nonblocking(true).map_err(socket_error)?;
+                (Socket::Unix(socket, path), DEFAULT_UDS_MAX_PACKET_SIZE)
+            }
+        };
+
+        let mut constant_tags = String::new();
+        for tag in [
+            &self.unified_tags.env,
+            &self.unified_tags.service,
+            &self.unified_tags.version,
+        ] {
+            if let Some(value) = &tag.value {
+                write_tag(&mut constant_tags, tag.get_tag_name(), value);
+            }
+        }
+
+        Ok(DogStatsDExporter {
+            socket,
+            max_packet_size: self.max_packet_size.unwrap_or(default_max_packet_size),
+            constant_tags,
+            is_shutdown: AtomicBool::new(false),
+        })
+    }
+}
+
+fn udp_socket(address: &str) -> Result<UdpSocket, Error> {
+    let address = address
+        .to_socket_addrs()
+        .map_err(socket_error)?
+        .next()
+        .ok_or_else(|| Error::Other(format!("no address found for {address}")))?;
+    let local: SocketAddr = match address {
+        SocketAddr::V4(_) => ([0, 0, 0, 0], 0).into(),
+        SocketAddr::V6(_) => ([0u16; 8], 0).into(),
+    };
+    let socket = UdpSocket::bind(local).map_err(socket_error)?;
+    socket.connect(address).map_err(socket_error)?;
+    Ok(socket)
+}
+
+fn socket_error(err: std::io::Error) -> Error {
+    Error::Other(format!("dogstatsd socket error: {err}"))
+}
+
+/// Exports OpenTelemetry metrics to the Datadog agent as [DogStatsD] datagrams, over UDP or a
+/// Unix datagram socket:
+///
+/// - monotonic sums are sent as counts (`c`), with delta temporality so that each export
+///   sends the increase since the previous one,
+/// - gauges and non-monotonic sums are sent as gauges (`g`),
+/// - histograms are sent as distributions (`d`). The exporter only sees the aggregated buckets,
+///   so each non-empty bucket is sent as a single value, the middle of the bucket narrowed down
+///   by the recorded min and max, with a sample rate of one over the bucket count.
+///
+/// Attributes are sent as tags, along with the `env`, `service` and `version` unified service
+/// tags. Datagrams are batched into packets of up to [`DogStatsDExporterBuilder::with_max_packet_size`]
+/// bytes.
+///
+/// [DogStatsD]: https://docs.datadoghq.com/developers/dogstatsd/datagram_shell
+///
+/// ```no_run
+/// use opentelemetry::{global, metrics::MeterProvider as _};
+/// use opentelemetry_datadog::DogStatsDExporter;
+/// use opentelemetry_sdk::metrics::{PeriodicReader, SdkMeterProvider};
+///
+/// # fn main() -> Result<(), opentelemetry_datadog::Error> {
+/// let exporter = DogStatsDExporter::builder()
+///     .with_service_name("my-service")
+///     .with_env("prod")
+///     .build()?;
+/// let provider = SdkMeterProvider::builder()
+///     .with_reader(PeriodicReader::builder(exporter).build())
+///     .build();
+/// global::set_meter_provider(provider.clone());
+///
+/// let counter = provider.meter("my-library").u64_counter("requests").build();
+/// counter.add(1, &[]);
+///
+/// let _ = provider.shutdown();
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct DogStatsDExporter {
+    socket: Socket,
+    max_packet_size: usize,
+    // Unified service tags, pre-formatted as `key:value,` for every datagram
+    constant_tags: String,
+    is_shutdown: AtomicBool,
+}
+
+impl DogStatsDExporter {
+    /// Create a builder, sending to the local agent over UDP by default
+    pub fn builder() -> DogStatsDExporterBuilder {
+        DogStatsDExporterBuilder {
+            endpoint: None,
+            max_packet_size: None,
+            unified_tags: UnifiedTags::new(),
+        }
+    }
+
+    fn write_metric(&self, packets: &mut Packets<'_>, metric: &Metric) {
+        match metric.data() {
+            AggregatedMetrics::F64(data) => self.write_data(packets, metric.name(), data),
+            AggregatedMetrics::U64(data) => self.write_data(packets, metric.name(), data),
+            AggregatedMetrics::I64(data) => self.write_data(packets, metric.name(), data),
+        }
+    }
+
+    fn write_data<T: Numeric>(&self, packets: &mut Packets<'_>, name: &str, data: &MetricData<T>) {
+        match data {
+            MetricData::Gauge(gauge) => {
+                for point in gauge.data_points() {
+                    self.write_datagram(packets, name, point.value(), "g", 1, point.attributes());
+                }
+            }
+            MetricData::Sum(sum) => {
+                let metric_type = if sum.is_monotonic() && sum.temporality() == Temporality::Delta {
+                    "c"
+                } else {
+                    "g"
+                };
+                for point in sum.data_points() {
+                    self.write_datagram(
+                        packets,
+                        name,
+                        point.value(),
+                        metric_type,
+                        1,
+                        point.attributes(),
+                    );
+                }
+            }
+            MetricData::Histogram(histogram) => {
+                for point in histogram.data_points() {
+                    let bounds: Vec<f64> = point.bounds().collect();
+                    let (min, max) = (point.min().map(T::to_f64), point.max().map(T::to_f64));
+                    for (i, count) in point.bucket_counts().enumerate() {
+                        if count == 0 {
+                            continue;
+                        }
+                        let value = bucket_value(
+                            if i == 0 {
+                                None
+                            } else {
+                                bounds.get(i - 1).copied()
+                            },
+                            bounds.get(i).copied(),
+                            min,
+                            max,
+                        )
+                        .unwrap_or_else(|| point.sum().to_f64() / point.count() as f64);
+                        self.write_datagram(packets, name, value, "d", count, point.attributes());
+                    }
+                }
+            }
+            MetricData::ExponentialHistogram(histogram) => {
+                for point in histogram.data_points() {
+                    let base = 2f64.powf(2f64.powi(-(point.scale() as i32)));
+                    let (min, max) = (point.min().map(T::to_f64), point.max().map(T::to_f64));
+                    if point.zero_count() > 0 {
+                        let count = point.zero_count();
+                        self.write_datagram(packets, name, 0.0, "d", count, point.attributes());
+                    }
+                    let buckets = exponential_bucket_values(point.positive_bucket(), base)
+                        .map(|(lower, upper, count)| (bucket_value(lower, upper, min, max), count))
+                        .chain(
+                            exponential_bucket_values(point.negative_bucket(), base).map(
+                                |(lower, upper, count)| {
+                                    let negated = (upper.map(|v| -v), lower.map(|v| -v));
+                                    (bucket_value(negated.0, negated.1, min, max), count)
+                                },
+                            ),
+                        );
+                    for (value, count) in buckets {
+                        if let Some(value) = value.filter(|_| count > 0) {
+                            self.write_datagram(
+                                packets,
+                                name,
+                                value,
+                                "d",
+                                count,
+                                point.attributes(),
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // <name>:<value>|<type>|@<sample rate>|#<tags>
+    fn write_datagram<'a, V: Display>(
+        &self,
+        packets: &mut Packets<'_>,
+        name: &str,
+        value: V,
+        metric_type: &str,
+        count: u64,
+        attributes: impl Iterator<Item = &'a KeyValue>,
+    ) {
+        let mut datagram = String::with_capacity(64);
+        write_sanitized(&mut datagram, name, &[':', '|', '@', '#', ',', '\n']);
+        let _ = write!(datagram, ":{value}|{metric_type}");
+        if count > 1 {
+            let _ = write!(datagram, "|@{}", 1.0 / count as f64);
+        }
+
+        let mut tags = self.constant_tags.clone();
+        for kv in attributes {
+            write_tag(&mut tags, kv.key.as_str(), &kv.value.as_str());
+        }
+        if !tags.is_empty() {
+            datagram.push_str("|#");
+            datagram.push_str(tags.trim_end_matches(','));
+        }
+        packets.push(&datagram);
+    }
+}
+
+impl PushMetricExporter for DogStatsDExporter {
+    async fn export(&self, metrics: &ResourceMetrics) -> OTelSdkResult {
+        if self.is_shutdown.load(Ordering::Relaxed) {
+            return Err(OTelSdkError::AlreadyShutdown);
+        }
+
+        let mut packets = Packets::new(&self.socket, self.max_packet_size);
+        for scope_metrics in metrics.scope_metrics() {
+            for metric in scope_metrics.metrics() {
+                self.write_metric(&mut packets, metric);
+            }
+        }
+        packets.finish()
+    }
+
+    fn force_flush(&self) -> OTelSdkResult {
+        // Every export is sent right away
+        Ok(())
+    }
+
+    fn shutdown_with_timeout(&self, _timeout: Duration) -> OTelSdkResult {
+        self.is_shutdown.store(true, Ordering::Relaxed);
+        Ok(())
+    }
+
+    fn temporality(&self) -> Temporality {
+        // DogStatsD counts are increments
+        Temporality::Delta
+    }
+}
+
+/// Batches newline separated datagrams into packets of at most `max_size` bytes
+struct Packets<'a> {
+    socket: &'a Socket,
+    max_size: usize,
+    buffer: Vec<u8>,
+    error: Option<std::io::Error>,
+    // Packets not sent because the socket buffer was full
+    dropped: usize,
+}
+
+impl<'a> Packets<'a> {
+    fn new(socket: &'a Socket, max_size: usize) -> Self {
+        Packets {
+            socket,
+            max_size,
+            buffer: Vec::with_capacity(max_size),
+            error: None,
+            dropped: 0,
+        }
+    }
+
+    fn push(&mut self, datagram: &str) {
+        if !self.buffer.is_empty() && self.buffer.len() + 1 + datagram.len() > self.max_size {
+            self.send();
+        }
+        if !self.buffer.is_empty() {
+            self.buffer.push(b'\n');
+        }
+        self.buffer.extend_from_slice(datagram.as_bytes());
+    }
+
+    fn send(&mut self) {
+        match self.socket.send(&self.buffer) {
+            Ok(_) => {}
+            Err(err) if err.kind() == std::io::ErrorKind::WouldBlock => self.dropped += 1,
+            Err(err) => {
+                // Keep sending the other packets, the first error is reported
+                self.error.get_or_insert(err);
+            }
+        }
+        self.buffer.clear();
+    }
+
+    fn finish(mut self) -> OTelSdkResult {
+        if !self.buffer.is_empty() {
+            self.send();
+        }
+        match (self.error, self.dropped) {
+            (Some(err), _) => Err(OTelSdkError::InternalFailure(format!(
+                "failed to send dogstatsd packets: {err}"
+            ))),
+            (None, 0) => Ok(()),
+            (None, dropped) => Err(OTelSdkError::InternalFailure(format!(
+                "dropped {dropped} dogstatsd packets, the agent socket buffer is full"
+            ))),
+        }
+    }
+}
+
+trait Numeric: Display + Copy {
+    fn to_f64(self) -> f64;
+}
+
+impl Numeric for f64 {
+    fn to_f64(self) -> f64 {
+        self
+    }
+}
+
+impl Numeric for u64 {
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+}
+
+impl Numeric for i64 {
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+}
+
+/// Value sent for a histogram bucket: the middle of its bounds, narrowed to the recorded
+/// min and max. Unbounded buckets without min or max fall back to their finite bound.
+fn bucket_value(
+    lower: Option<f64>,
+    upper: Option<f64>,
+    min: Option<f64>,
+    max: Option<f64>,
+) -> Option<f64> {
+    let lower = match (lower, min) {
+        (Some(lower), Some(min)) => Some(lower.max(min)),
+        (lower, min) => lower.or(min),
+    };
+    let upper = match (upper, max) {
+        (Some(upper), Some(max)) => Some(upper.min(max)),
+        (upper, max) => upper.or(max),
+    };
+    match (lower, upper) {
+        (Some(lower), Some(upper)) => Some((lower + upper) / 2.0),
+        (lower, upper) => lower.or(upper),
+    }
+}
+
+// Bucket `i` of an exponential histogram holds the values in (base^(offset+i), base^(offset+i+1)]
+fn exponential_bucket_values(
+    bucket: &ExponentialBucket,
+    base: f64,
+) -> impl Iterator<Item = (Option<f64>, Option<f64>, u64)> + '_ {
+    bucket.counts().enumerate().map(move |(i, count)| {
+        let index = bucket.offset() + i as i32;
+        (Some(base.powi(index)), Some(base.powi(index + 1)), count)
+    })
+}
+
+// Appends `key:value,`, replacing the characters that delimit tags and datagrams
+fn write_tag(tags: &mut String, key: &str, value: &str) {
+    const RESERVED: &[char] = &['|', ',', '#', '\n'];
+    write_sanitized(tags, key, RESERVED);
+    if !value.is_empty() {
+        tags.push(':');
+        write_sanitized(tags, value, RESERVED);
+    }
+    tags.push(',');
+}
+
+fn write_sanitized(out: &mut String, value: &str, reserved: &[char]) {
+    out.extend(
+        value
+            .chars()
+            .map(|c| if reserved.contains(&c) { '_' } else { c }),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry::metrics::MeterProvider as _;
+    use opentelemetry_sdk::metrics::{PeriodicReader, SdkMeterProvider};
+
+    fn listen() -> (UdpSocket, String) {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        socket
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let address = socket.local_addr().unwrap().to_string();
+        (socket, address)
+    }
+
+    fn receive(socket: &UdpSocket) -> Vec<String> {
+        let mut datagrams = Vec::new();
+        let mut buf = [0; 65536];
+        socket.set_nonblocking(false).unwrap();
+        let len = socket.recv(&mut buf).unwrap();
+        datagrams.extend(
+            std::str::from_utf8(&buf[..len])
+                .unwrap()
+                .lines()
+                .map(str::to_string),
+        );
+        socket.set_nonblocking(true).unwrap();
+        while let Ok(len) = socket.recv(&mut buf) {
+            datagrams.extend(
+                std::str::from_utf8(&buf[..len])
+                    .unwrap()
+                    .lines()
+                    .map(str::to_string),
+            );
+        }
+        datagrams.sort();
+        datagrams
+    }
+
+    fn provider(exporter: DogStatsDExporter) -> SdkMeterProvider {
+        SdkMeterProvider::builder()
+            .with_reader(PeriodicReader::builder(exporter).build())
+            .build()
+    }
+
+    #[test]
+    fn test_export() {
+        let (socket, address) = listen();
+        let exporter = DogStatsDExporter::builder()
+            .with_udp_address(address)
+            .with_service_name("test-service")
+            .with_env("test-env")
+            .with_version("1.0")
+            .build()
+            .unwrap();
+        let provider = provider(exporter);
+        let meter = provider.meter("test");
+
+        let counter = meter.u64_counter("requests").build();
+        counter.add(2, &[KeyValue::new("route", "/users")]);
+        counter.add(3, &[KeyValue::new("route", "/users")]);
+        meter
+            .i64_up_down_counter("connections")
+            .build()
+            .add(-4, &[]);
+        meter
+            .f64_gauge("temperature")
+            .build()
+            .record(21.5, &[KeyValue::new("room", "a|b,c")]);
+        let histogram = meter.f64_histogram("latency").build();
+        histogram.record(3.0, &[]);
+        histogram.record(7.0, &[]);
+        histogram.record(8.0, &[]);
+
+        provider.force_flush().unwrap();
+        let tags = "env:test-env,service:test-service,version:1.0";
+        assert_eq!(
+            receive(&socket),
+            [
+                format!("connections:-4|g|#{tags}"),
+                format!("latency:4|d|#{tags}"),
+                format!("latency:6.5|d|@0.5|#{tags}"),
+                format!("requests:5|c|#{tags},route:/users"),
+                format!("temperature:21.5|g|#{tags},room:a_b_c"),
+            ]
+        );
+
+        // Counts are deltas
+        counter.add(1, &[KeyValue::new("route", "/users")]);
+        provider.force_flush().unwrap();
+        let received = receive(&socket);
+        assert!(received.contains(&format!("requests:1|c|#{tags},route:/users")));
+        assert!(!received
+            .iter()
+            .any(|datagram| datagram.starts_with("latency")));
+
+        provider.shutdown().unwrap();
+    }
+
+    #[test]
+    fn test_packet_batching() {
+        let (socket, address) = listen();
+        let exporter = DogStatsDExporter::builder()
+            .with_udp_address(address)
+            .with_max_packet_size(64)
+            .build()
+            .unwrap();
+        let provider = provider(exporter);
+        let counter = provider.meter("test").u64_counter("counter").build();
+        for i in 0..20 {
+            counter.add(1, &[KeyValue::new("index", i)]);
+        }
+        provider.force_flush().unwrap();
+
+        let mut buf = [0; 65536];
+        let mut datagrams = 0;
+        while datagrams < 20 {
+            let len = socket.recv(&mut buf).unwrap();
+            assert!(len <= 64);
+            datagrams += std::str::from_utf8(&buf[..len]).unwrap().lines().count();
+        }
+        assert_eq!(datagrams, 20);
+    }
+
+    #[test]
+    fn test_shutdown() {
+        let (_socket, address) = listen();
+        let exporter = DogStatsDExporter::builder()
+            .with_udp_address(address)
+            .build()
+            .unwrap();
+        exporter.shutdown().unwrap();
+        let result = futures_executor::block_on(exporter.export(&ResourceMetrics::default()));
+        assert!(matches!(result, Err(OTelSdkError::AlreadyShutdown)));
+    }
+
+    #[test]
+    fn test_bucket_value() {
+        assert_eq!(bucket_value(Some(0.0), Some(5.0), None, None), Some(2.5));
+        assert_eq!(
+            bucket_value(Some(0.0), Some(5.0), Some(3.0), Some(7.0)),
+            Some(4.0)
+        );
+        assert_eq!(
+            bucket_value(None, Some(5.0), Some(1.0), Some(3.0)),
+            Some(2.0)
+        );
+        assert_eq!(bucket_value(Some(5.0), None, None, None), Some(5.0));
+        assert_eq!(bucket_value(None, None, None, None), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_unix_socket() {
+        let dir = std::env::temp_dir().join(format!("dsd-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("dsd.socket");
+        let _ = std::fs::remove_file(&path);
+        let socket = UnixDatagram::bind(&path).unwrap();
+        socket
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+
+        let exporter = DogStatsDExporter::builder()
+            .with_unix_socket(&path)
+            .build()
+            .unwrap();
+        let provider = provider(exporter);
+        provider
+            .meter("test")
+            .u64_counter("counter")
+            .build()
+            .add(1, &[]);
+        provider.force_flush().unwrap();
+
+        let mut buf = [0; 8192];
+        let len = socket.recv(&mut buf).unwrap();
+        assert!(std::str::from_utf8(&buf[..len])
+            .unwrap()
+            .starts_with("counter:1|c"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_unix_socket_full() {
+        let dir = std::env::temp_dir().join(format!("dsd-full-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("dsd.socket");
+        let _ = std::fs::remove_file(&path);
+        // Never read from, so that its buffer fills up
+        let _agent = UnixDatagram::bind(&path).unwrap();
+
+        let exporter = DogStatsDExporter::builder()
+            .with_unix_socket(&path)
+            .build()
+            .unwrap();
+        let mut packets = Packets::new(&exporter.socket, 16);
+        for _ in 0..10_000 {
+            packets.push("counter:1|c|#env:prod");
+        }
+        let err = packets.finish().unwrap_err();
+        assert!(err.to_string().contains("socket buffer is full"), "{err}");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,281 @@
+use crate::exporter::Error;
+use crate::propagator::{
+    DatadogTraceState, DatadogTraceStateBuilder, TRACE_STATE_PRIORITY_SAMPLING,
+};
+use opentelemetry::trace::{
+    Link, SamplingDecision, SamplingResult, SpanKind, TraceContextExt, TraceId,
+};
+use opentelemetry::{Context, KeyValue};
+use opentelemetry_sdk::trace::ShouldSample;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+// Key of the rate the agent applies to services it has no specific rate for
+// https://github.com/DataDog/datadog-agent/blob/7.52.0/pkg/trace/sampler/prioritysampler.go#L27
+const DEFAULT_RATE_KEY: &str = "service:,env:";
+
+// Same factor as the other Datadog tracers, so that they agree on which traces a rate keeps
+// https://github.com/DataDog/dd-trace-go/blob/v1.60.0/ddtrace/tracer/sampler.go#L71
+const KNUTH_FACTOR: u64 = 1_111_111_111_111_111_111;
+
+/// Sampling rates sent by the Datadog agent in its responses to trace payloads.
+///
+/// The agent adjusts these rates so that the traces it receives match its target throughput.
+/// The table is keyed by `service:<name>,env:<env>` and shared between the exporter, which
+/// refreshes it from every agent response, and [`DatadogSampler`], which reads it. Clones
+/// share the same table.
+#[derive(Clone, Debug, Default)]
+pub struct AgentSamplingRates {
+    rates: Arc<RwLock<HashMap<String, f64>>>,
+}
+
+impl AgentSamplingRates {
+    /// Create an empty rate table
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Rate for `service` in `env`, or the agent's default rate if it sent none for them
+    pub fn rate(&self, service: &str, env: &str) -> Option<f64> {
+        self.rate_for_key(&rate_key(service, env))
+    }
+
+    /// Replace the whole table, e.g. with rates obtained out of band
+    pub fn update<I: IntoIterator<Item = (String, f64)>>(&self, rates: I) {
+        let rates = rates
+            .into_iter()
+            .map(|(key, rate)| (key, rate.clamp(0.0, 1.0)))
+            .collect();
+        *self.rates.write().unwrap_or_else(|e| e.into_inner()) = rates;
+    }
+
+    fn rate_for_key(&self, key: &str) -> Option<f64> {
+        let rates = self.rates.read().unwrap_or_else(|e| e.into_inner());
+        rates
+            .get(key)
+            .or_else(|| rates.get(DEFAULT_RATE_KEY))
+            .copied()
+    }
+
+    /// Update the table from the JSON body of an agent response, e.g.
+    /// `{"rate_by_service":{"service:,env:":1,"service:web,env:prod":0.5}}`
+    pub(crate) fn update_from_response(&self, body: &[u8]) -> Result<(), Error> {
+        let response: serde_json::Value = serde_json::from_slice(body)
+            .map_err(|e| Error::Other(format!("invalid agent response: {e}")))?;
+        let rates = response
+            .get("rate_by_service")
+            .and_then(|rates| rates.as_object())
+            .ok_or_else(|| Error::Other("agent response has no rate_by_service".to_string()))?;
+        self.update(
+            rates
+                .iter()
+                .filter_map(|(key, rate)| Some((key.clone(), rate.as_f64()?))),
+        );
+        Ok(())
+    }
+}
+
+fn rate_key(service: &str, env: &str) -> String {
+    format!("service:{service},env:{env}")
+}
+
+/// Head sampler applying the rates of the Datadog agent, the way Datadog tracers do.
+///
+/// Root spans are kept with the probability the agent set for the sampler's service and env,
+/// and the decision is recorded as the Datadog sampling priority in the trace state, so that
+/// it is propagated and exported. Child spans inherit the decision of their parent. All spans
+/// are recorded and exported: the agent drops the traces whose priority is reject after
+/// computing its statistics.
+///
+/// ```no_run
+/// use opentelemetry_datadog::{new_pipeline, AgentSamplingRates, DatadogSampler};
+/// use opentelemetry_sdk::trace;
+///
+/// # fn main() -> Result<(), opentelemetry_sdk::trace::TraceError> {
+/// let rates = AgentSamplingRates::new();
+/// let mut config = trace::Config::default();
+/// config.sampler = Box::new(DatadogSampler::new(rates.clone(), "my_app").with_env("prod"));
+///
+/// let provider = new_pipeline()
+///     .with_service_name("my_app")
+///     .with_env("prod")
+///     .with_trace_config(config)
+///     .with_agent_sampling_rates(rates)
+///     .install_batch()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct DatadogSampler {
+    rates: AgentSamplingRates,
+    key: String,
+    service: String,
+    default_rate: f64,
+}
+
+impl DatadogSampler {
+    /// Create a sampler for traces of `service`, reading rates from `rates`
+    pub fn new<T: Into<String>>(rates: AgentSamplingRates, service: T) -> Self {
+        let service = service.into();
+        DatadogSampler {
+            rates,
+            key: rate_key(&service, ""),
+            service,
+            default_rate: 1.0,
+        }
+    }
+
+    /// Assign the env whose rate applies (empty by default)
+    pub fn with_env<T: Into<String>>(mut self, env: T) -> Self {
+        self.key = rate_key(&self.service, &env.into());
+        self
+    }
+
+    /// Rate applied until the agent has sent any (1.0, i.e. keep everything, by default)
+    pub fn with_default_rate(mut self, rate: f64) -> Self {
+        self.default_rate = rate.clamp(0.0, 1.0);
+        self
+    }
+}
+
+impl ShouldSample for DatadogSampler {
+    fn should_sample(
+        &self,
+        parent_context: Option<&Context>,
+        trace_id: TraceId,
+        _name: &str,
+        _span_kind: &SpanKind,
+        _attributes: &[KeyValue],
+        _links: &[Link],
+    ) -> SamplingResult {
+        let parent_trace_state = parent_context
+            .filter(|cx| cx.has_active_span())
+            .map(|cx| cx.span().span_context().trace_state().clone());
+        let trace_state = match parent_trace_state {
+            // The decision was made upstream, possibly by another tracer
+            Some(trace_state) if trace_state.get(TRACE_STATE_PRIORITY_SAMPLING).is_some() => {
+                trace_state
+            }
+            Some(trace_state) => trace_state.with_priority_sampling(self.keep(trace_id)),
+            None => DatadogTraceStateBuilder::default()
+                .with_priority_sampling(self.keep(trace_id))
+                .build(),
+        };
+
+        SamplingResult {
+            decision: SamplingDecision::RecordAndSample,
+            attributes: Vec::new(),
+            trace_state,
+        }
+    }
+}
+
+impl DatadogSampler {
+    fn keep(&self, trace_id: TraceId) -> bool {
+        let rate = self
+            .rates
+            .rate_for_key(&self.key)
+            .unwrap_or(self.default_rate);
+        sampled_by_rate(trace_id, rate)
+    }
+}
+
+fn sampled_by_rate(trace_id: TraceId, rate: f64) -> bool {
+    if rate >= 1.0 {
+        return true;
+    }
+    if rate <= 0.0 {
+        return false;
+    }
+    // Datadog trace ids are the lower 64 bits of the OpenTelemetry ones
+    let id = u128::from_be_bytes(trace_id.to_bytes()) as u64;
+    id.wrapping_mul(KNUTH_FACTOR) < (rate * u64::MAX as f64) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry::trace::{SpanContext, SpanId, TraceFlags, TraceState};
+    use opentelemetry_sdk::testing::trace::TestSpan;
+
+    fn sample(sampler: &DatadogSampler, parent: Option<&Context>, trace_id: u128) -> bool {
+        sampler
+            .should_sample(
+                parent,
+                TraceId::from(trace_id),
+                "span",
+                &SpanKind::Internal,
+                &[],
+                &[],
+            )
+            .trace_state
+            .priority_sampling_enabled()
+    }
+
+    #[test]
+    fn test_update_from_response() {
+        let rates = AgentSamplingRates::new();
+        rates
+            .update_from_response(
+                br#"{"rate_by_service":{"service:,env:":0.25,"service:web,env:prod":0.5,"service:db,env:prod":7}}"#,
+            )
+            .unwrap();
+
+        assert_eq!(rates.rate("web", "prod"), Some(0.5));
+        assert_eq!(rates.rate("db", "prod"), Some(1.0));
+        assert_eq!(rates.rate("web", "staging"), Some(0.25));
+
+        assert!(rates.update_from_response(b"OK").is_err());
+        assert!(rates.update_from_response(br#"{"other":1}"#).is_err());
+        assert_eq!(rates.rate("web", "prod"), Some(0.5));
+    }
+
+    #[test]
+    fn test_root_spans_follow_agent_rate() {
+        let rates = AgentSamplingRates::new();
+        let sampler = DatadogSampler::new(rates.clone(), "web").with_env("prod");
+        assert!(sample(&sampler, None, 1));
+
+        rates.update([("service:web,env:prod".to_string(), 0.0)]);
+        assert!(!sample(&sampler, None, 1));
+
+        rates.update([("service:web,env:prod".to_string(), 0.5)]);
+        let kept = (0..10_000u128)
+            .filter(|id| sample(&sampler, None, *id))
+            .count();
+        assert!((4_500..5_500).contains(&kept), "kept {kept} of 10000");
+        // The decision only depends on the trace id
+        assert_eq!(sample(&sampler, None, 42), sample(&sampler, None, 42));
+    }
+
+    #[test]
+    fn test_children_inherit_parent_decision() {
+        let rates = AgentSamplingRates::new();
+        rates.update([(DEFAULT_RATE_KEY.to_string(), 0.0)]);
+        let sampler = DatadogSampler::new(rates, "web");
+
+        let parent = |trace_state: TraceState| {
+            Context::new().with_span(TestSpan(SpanContext::new(
+                TraceId::from(1),
+                SpanId::from(1),
+                TraceFlags::SAMPLED,
+                true,
+                trace_state,
+            )))
+        };
+        let kept = parent(
+            DatadogTraceStateBuilder::default()
+                .with_priority_sampling(true)
+                .build(),
+        );
+        assert!(sample(&sampler, Some(&kept), 1));
+        // Without an upstream decision the agent rate applies
+        assert!(!sample(&sampler, Some(&parent(TraceState::default())), 1));
+    }
+
+    #[test]
+    fn test_default_rate_before_agent_response() {
+        let sampler = DatadogSampler::new(AgentSamplingRates::new(), "web").with_default_rate(0.0);
+        assert!(!sample(&sampler, None, 1));
+    }
+}
@@ -1,14 +1,21 @@
+#[cfg(feature = "agent-sampling")]
+mod agent_sampling;
 mod intern;
 mod model;
+mod stats;
+
+#[cfg(feature = "agent-sampling")]
+pub use agent_sampling::{AgentSamplingRates, DatadogSampler};
 
 pub use model::ApiVersion;
 pub use model::Error;
 pub use model::FieldMappingFn;
 
 use crate::exporter::model::FieldMapping;
+use crate::exporter::stats::StatsConcentrator;
 use http::{Method, Request, Uri};
-use opentelemetry::{Key, KeyValue};
-use opentelemetry_http::{HttpClient, ResponseExt};
+use opentelemetry::{otel_warn, Key, KeyValue};
+use opentelemetry_http::{Bytes, HttpClient, ResponseExt};
 use opentelemetry_sdk::{
     error::{OTelSdkError, OTelSdkResult},
     resource::{ResourceDetector, SdkProvidedResourceDetector},
@@ -19,10 +26,13 @@ use opentelemetry_sdk::{
 use opentelemetry_semantic_conventions as semcov;
 use std::borrow::Cow;
 use std::fmt::{Debug, Formatter};
+use std::sync::mpsc::{self, RecvTimeoutError};
 use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, SystemTime};
 use url::Url;
 
-use self::model::unified_tags::UnifiedTags;
+pub(crate) use self::model::unified_tags::UnifiedTags;
 
 /// Default Datadog collector endpoint
 const DEFAULT_AGENT_ENDPOINT: &str = "http://127.0.0.1:8126";
@@ -34,6 +44,12 @@ const DATADOG_TRACE_COUNT_HEADER: &str = "X-Datadog-Trace-Count";
 const DATADOG_META_LANG_HEADER: &str = "Datadog-Meta-Lang";
 const DATADOG_META_TRACER_VERSION_HEADER: &str = "Datadog-Meta-Tracer-Version";
 
+/// Header name used to tell the agent not to compute stats from the payload's spans again
+const DATADOG_CLIENT_COMPUTED_STATS_HEADER: &str = "Datadog-Client-Computed-Stats";
+
+/// Path of the agent endpoint receiving client computed stats
+const STATS_PATH: &str = "/v0.6/stats";
+
 // Struct to hold the mapping between Opentelemetry spans and datadog spans.
 pub struct Mapping {
     resource: Option<FieldMapping>,
@@ -64,9 +80,15 @@ pub struct DatadogExporter {
     request_url: Uri,
     model_config: ModelConfig,
     api_version: ApiVersion,
+    #[cfg(feature = "gzip")]
+    gzip_compression: bool,
+    otlp_mapping: bool,
     mapping: Mapping,
     unified_tags: UnifiedTags,
     resource: Option<Resource>,
+    stats: Option<StatsConcentrator>,
+    #[cfg(feature = "agent-sampling")]
+    sampling_rates: Option<AgentSamplingRates>,
 }
 
 impl DatadogExporter {
@@ -83,9 +105,15 @@ impl DatadogExporter {
             request_url,
             model_config,
             api_version,
+            #[cfg(feature = "gzip")]
+            gzip_compression: false,
+            otlp_mapping: false,
             mapping,
             unified_tags,
             resource: None,
+            stats: None,
+            #[cfg(feature = "agent-sampling")]
+            sampling_rates: None,
         }
     }
 
@@ -93,8 +121,14 @@ impl DatadogExporter {
         &self,
         mut batch: Vec<SpanData>,
     ) -> Result<http::Request<Vec<u8>>, OTelSdkError> {
+        if self.otlp_mapping {
+            batch.iter_mut().for_each(model::otlp::map_span);
+        }
         let traces: Vec<&[SpanData]> = group_into_traces(&mut batch);
         let trace_count = traces.len();
+        if let Some(stats) = &self.stats {
+            stats.add(&traces, &self.model_config, &self.mapping);
+        }
         let data = self
             .api_version
             .encode(
@@ -105,7 +139,7 @@ impl DatadogExporter {
                 self.resource.as_ref(),
             )
             .map_err(|e| OTelSdkError::InternalFailure(format!("{e:?}")))?;
-        let req = Request::builder()
+        let mut req = Request::builder()
             .method(Method::POST)
             .uri(self.request_url.clone())
             .header(http::header::CONTENT_TYPE, self.api_version.content_type())
@@ -114,11 +148,63 @@ impl DatadogExporter {
             .header(
                 DATADOG_META_TRACER_VERSION_HEADER,
                 env!("CARGO_PKG_VERSION"),
-            )
+            );
+        if self.stats.is_some() {
+            req = req.header(DATADOG_CLIENT_COMPUTED_STATS_HEADER, "yes");
+        }
+        #[cfg(feature = "gzip")]
+        let data = if self.gzip_compression && self.api_version.supports_compression() {
+            req = req.header(http::header::CONTENT_ENCODING, "gzip");
+            gzip_compress(&data).map_err(|e| OTelSdkError::InternalFailure(format!("{e:?}")))?
+        } else {
+            data
+        };
+        let req = req
             .body(data)
             .map_err(|e| OTelSdkError::InternalFailure(format!("{e:?}")));
         Ok(req)?
     }
+
+    fn build_stats_request(
+        &self,
+        force: bool,
+    ) -> Result<Option<http::Request<Vec<u8>>>, OTelSdkError> {
+        let Some(stats) = &self.stats else {
+            return Ok(None);
+        };
+        let Some(data) = stats
+            .flush(
+                SystemTime::now(),
+                force,
+                &self.model_config,
+                &self.unified_tags,
+                self.resource.as_ref(),
+            )
+            .map_err(|e| OTelSdkError::InternalFailure(format!("{e:?}")))?
+        else {
+            return Ok(None);
+        };
+        Request::builder()
+            .method(Method::POST)
+            .uri(stats.request_url().clone())
+            .header(http::header::CONTENT_TYPE, "application/msgpack")
+            .header(DATADOG_META_LANG_HEADER, "rust")
+            .header(
+                DATADOG_META_TRACER_VERSION_HEADER,
+                env!("CARGO_PKG_VERSION"),
+            )
+            .body(data)
+            .map(Some)
+            .map_err(|e| OTelSdkError::InternalFailure(format!("{e:?}")))
+    }
+
+    /// Send the stats of the buckets that are over, or of all of them if `force` is set
+    async fn send_stats(&self, force: bool) -> OTelSdkResult {
+        if let Some(request) = self.build_stats_request(force)? {
+            send_request(self.client.clone(), request).await?;
+        }
+        Ok(())
+    }
 }
 
 impl Debug for DatadogExporter {
@@ -148,9 +234,15 @@ pub struct DatadogPipelineBuilder {
     agent_endpoint: String,
     trace_config: Option<Config>,
     api_version: ApiVersion,
+    #[cfg(feature = "gzip")]
+    gzip_compression: bool,
+    otlp_mapping: bool,
+    stats_computation: bool,
     client: Option<Arc<dyn HttpClient>>,
     mapping: Mapping,
     unified_tags: UnifiedTags,
+    #[cfg(feature = "agent-sampling")]
+    sampling_rates: Option<AgentSamplingRates>,
 }
 
 impl Default for DatadogPipelineBuilder {
@@ -160,7 +252,13 @@ impl Default for DatadogPipelineBuilder {
             trace_config: None,
             mapping: Mapping::empty(),
             api_version: ApiVersion::Version05,
+            #[cfg(feature = "gzip")]
+            gzip_compression: false,
+            otlp_mapping: false,
+            stats_computation: false,
             unified_tags: UnifiedTags::new(),
+            #[cfg(feature = "agent-sampling")]
+            sampling_rates: None,
             #[cfg(all(
                 not(feature = "reqwest-client"),
                 not(feature = "reqwest-blocking-client"),
@@ -264,13 +362,22 @@ impl DatadogPipelineBuilder {
     }
 
     fn build_exporter_with_service_name(
-        self,
+        mut self,
         service_name: String,
     ) -> Result<DatadogExporter, TraceError> {
+        if self.otlp_mapping {
+            // Custom mappings still take precedence
+            self.mapping
+                .name
+                .get_or_insert_with(|| Arc::new(model::otlp::name_mapping));
+            self.mapping
+                .resource
+                .get_or_insert_with(|| Arc::new(model::otlp::resource_mapping));
+        }
         if let Some(client) = self.client {
             let model_config = ModelConfig { service_name };
 
-            let exporter = DatadogExporter::new(
+            let mut exporter = DatadogExporter::new(
                 model_config,
                 Self::build_endpoint(&self.agent_endpoint, self.api_version.path())?,
                 self.api_version,
@@ -278,6 +385,21 @@ impl DatadogPipelineBuilder {
                 self.mapping,
                 self.unified_tags,
             );
+            exporter.otlp_mapping = self.otlp_mapping;
+            if self.stats_computation {
+                exporter.stats = Some(StatsConcentrator::new(Self::build_endpoint(
+                    &self.agent_endpoint,
+                    STATS_PATH,
+                )?));
+            }
+            #[cfg(feature = "gzip")]
+            {
+                exporter.gzip_compression = self.gzip_compression;
+            }
+            #[cfg(feature = "agent-sampling")]
+            {
+                exporter.sampling_rates = self.sampling_rates;
+            }
             Ok(exporter)
         } else {
             Err(Error::NoHttpClient.into())
@@ -349,6 +471,16 @@ impl DatadogPipelineBuilder {
         self
     }
 
+    /// Gzip compress trace payloads (disabled by default).
+    ///
+    /// Only applies to [`ApiVersion::Version07`], the other versions are always sent
+    /// uncompressed.
+    #[cfg(feature = "gzip")]
+    pub fn with_gzip_compression(mut self, enabled: bool) -> Self {
+        self.gzip_compression = enabled;
+        self
+    }
+
     /// Custom the value used for `resource` field in datadog spans.
     /// See [`FieldMappingFn`] for details.
     pub fn with_resource_mapping<F>(mut self, f: F) -> Self
@@ -378,6 +510,58 @@ impl DatadogPipelineBuilder {
         self.mapping.service_name = Some(Arc::new(f));
         self
     }
+
+    /// Map spans the way the agent maps the spans it receives over OTLP (disabled by default).
+    ///
+    /// The Datadog name is derived from the span kind and semantic conventions, e.g.
+    /// `http.server.request` or `postgresql.query`, the resource from the HTTP route, database
+    /// statement or RPC method, and the span type from the kind and database system. The
+    /// `span.kind` and `http.status_code` tags are added too. The `operation.name`,
+    /// `resource.name` and `span.type` attributes override the derived values, and custom name
+    /// and resource mappings still apply.
+    pub fn with_otlp_mapping(mut self, enabled: bool) -> Self {
+        self.otlp_mapping = enabled;
+        self
+    }
+
+    /// Compute APM stats (hits, errors and latency distributions) from the exported spans
+    /// and send them to the agent's `/v0.6/stats` endpoint (disabled by default).
+    ///
+    /// The agent then stops computing stats from the traces it receives, so that they stay
+    /// accurate when it drops traces. Stats are aggregated in 10 second buckets, sent along
+    /// with the first batch exported after a bucket is over, and on `force_flush` and
+    /// shutdown. A stats payload that cannot be sent is logged and does not fail the export.
+    ///
+    /// Only the spans that reach the exporter are counted: use a sampler that records every
+    /// span, such as the default `Sampler::AlwaysOn` or `DatadogSampler`, which keeps every
+    /// span and only sets its sampling priority. With a sampler that drops spans, such as a
+    /// ratio based or parent based one, the stats only cover the sampled traces.
+    pub fn with_stats_computation(mut self, enabled: bool) -> Self {
+        self.stats_computation = enabled;
+        self
+    }
+
+    /// Update `rates` with the sampling rates the agent sends back for each payload.
+    ///
+    /// Share the same table with a [`DatadogSampler`] so that head sampling follows the
+    /// agent's targets.
+    #[cfg(feature = "agent-sampling")]
+    pub fn with_agent_sampling_rates(mut self, rates: AgentSamplingRates) -> Self {
+        self.sampling_rates = Some(rates);
+        self
+    }
+}
+
+#[cfg(feature = "gzip")]
+fn gzip_compress(data: &[u8]) -> std::io::Result<Vec<u8>> {
+    use std::io::Write;
+
+    let mut encoder = flate2::write::GzEncoder::new(
+        Vec::with_capacity(data.len() / 4),
+        flate2::Compression::default(),
+    );
+    encoder.write_all(data)?;
+    encoder.finish()
 }
 
 fn group_into_traces(spans: &mut [SpanData]) -> Vec<&[SpanData]> {
@@ -406,18 +590,18 @@ fn group_into_traces(spans: &mut [SpanData]) -> Vec<&[SpanData]> {
 async fn send_request(
     client: Arc<dyn HttpClient>,
     request: http::Request<Vec<u8>>,
-) -> OTelSdkResult {
+) -> Result<Bytes, OTelSdkError> {
     #[allow(deprecated)]
     let response = client
         .send(request)
         .await
         .map_err(|e| OTelSdkError::InternalFailure(format!("HTTP request failed: {e}")))?;
 
-    response
+    let response = response
         .error_for_status()
         .map_err(|e| OTelSdkError::InternalFailure(format!("HTTP response error: {e}")))?;
 
-    Ok(())
+    Ok(response.into_body())
 }
 
 impl SpanExporter for DatadogExporter {
@@ -428,12 +612,52 @@ impl SpanExporter for DatadogExporter {
             Err(err) => return Err(err),
         };
 
-        let client = self.client.clone();
-        send_request(client, request).await
+        #[cfg(feature = "agent-sampling")]
+        let body = send_request(self.client.clone(), request).await?;
+        #[cfg(not(feature = "agent-sampling"))]
+        send_request(self.client.clone(), request).await?;
+
+        // The traces were delivered, losing a stats payload must not fail their export
+        if let Err(err) = self.send_stats(false).await {
+            otel_warn!(name: "DatadogExporter.StatsExportFailed", error = format!("{err}"));
+        }
+
+        #[cfg(feature = "agent-sampling")]
+        if let Some(rates) = &self.sampling_rates {
+            // Agents that do not compute rates answer with a plain "OK"; keep the last rates
+            let _ = rates.update_from_response(&body);
+        }
+        Ok(())
     }
     fn set_resource(&mut self, resource: &Resource) {
         self.resource = Some(resource.clone());
     }
+
+    /// Send the stats of all the spans exported so far, including the current bucket
+    fn force_flush(&mut self) -> OTelSdkResult {
+        futures_executor::block_on(self.send_stats(true))
+    }
+
+    /// Send the remaining stats, waiting at most `timeout` for the agent
+    fn shutdown_with_timeout(&mut self, timeout: Duration) -> OTelSdkResult {
+        let Some(request) = self.build_stats_request(true)? else {
+            return Ok(());
+        };
+        // The request runs on its own thread so that an agent that does not answer cannot
+        // block the shutdown past `timeout`
+        let client = self.client.clone();
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let _ = sender.send(futures_executor::block_on(send_request(client, request)));
+        });
+        match receiver.recv_timeout(timeout) {
+            Ok(result) => result.map(|_| ()),
+            Err(RecvTimeoutError::Timeout) => Err(OTelSdkError::Timeout(timeout)),
+            Err(RecvTimeoutError::Disconnected) => Err(OTelSdkError::InternalFailure(
+                "stats request thread panicked".to_string(),
+            )),
+        }
+    }
 }
 
 /// Helper struct to custom the mapping between Opentelemetry spans and datadog spans.
@@ -461,6 +685,7 @@ mod tests {
 
     use crate::exporter::model::tests::get_span;
     use bytes::Bytes;
+    use opentelemetry::trace::SpanKind;
 
     #[test]
     fn test_out_of_order_group() {
@@ -554,4 +779,218 @@ mod tests {
             .install_batch()
             .unwrap();
     }
+
+    #[test]
+    fn test_stats_computation() {
+        let exporter = new_pipeline()
+            .with_service_name("test_service")
+            .with_http_client(DummyClient)
+            .with_agent_endpoint("http://localhost:8126?api_key=123")
+            .with_stats_computation(true)
+            .build_exporter()
+            .unwrap();
+        assert_eq!(
+            exporter.stats.as_ref().unwrap().request_url(),
+            "http://localhost:8126/v0.6/stats?api_key=123"
+        );
+
+        let request = exporter.build_request(vec![get_span(1, 0, 1)]).unwrap();
+        assert_eq!(
+            request.headers()[DATADOG_CLIENT_COMPUTED_STATS_HEADER],
+            "yes"
+        );
+        // The span's bucket is still open
+        assert!(exporter.build_stats_request(false).unwrap().is_none());
+
+        let request = new_pipeline()
+            .with_http_client(DummyClient)
+            .build_exporter()
+            .unwrap()
+            .build_request(vec![get_span(1, 0, 1)])
+            .unwrap();
+        assert!(request
+            .headers()
+            .get(DATADOG_CLIENT_COMPUTED_STATS_HEADER)
+            .is_none());
+    }
+
+    #[test]
+    fn test_otlp_mapping() {
+        let contains = |payload: &[u8], value: &str| {
+            payload
+                .windows(value.len())
+                .any(|window| window == value.as_bytes())
+        };
+        let http_span = || {
+            let mut span = get_span(1, 0, 1);
+            span.span_kind = SpanKind::Server;
+            span.attributes = vec![
+                KeyValue::new("http.request.method", "GET"),
+                KeyValue::new("http.route", "/users/:id"),
+            ];
+            span
+        };
+
+        let request = new_pipeline()
+            .with_http_client(DummyClient)
+            .with_otlp_mapping(true)
+            .build_exporter()
+            .unwrap()
+            .build_request(vec![http_span()])
+            .unwrap();
+        assert!(contains(request.body(), "http.server.request"));
+        assert!(contains(request.body(), "GET /users/:id"));
+
+        // Custom mappings take precedence
+        let request = new_pipeline()
+            .with_http_client(DummyClient)
+            .with_otlp_mapping(true)
+            .with_name_mapping(|_, _| "custom_name")
+            .build_exporter()
+            .unwrap()
+            .build_request(vec![http_span()])
+            .unwrap();
+        assert!(contains(request.body(), "custom_name"));
+        assert!(contains(request.body(), "GET /users/:id"));
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn test_gzip_compression() {
+        use std::io::Read;
+
+        let build = |api_version: ApiVersion| {
+            new_pipeline()
+                .with_service_name("test_service")
+                .with_http_client(DummyClient)
+                .with_api_version(api_version)
+                .with_gzip_compression(true)
+                .build_exporter()
+                .unwrap()
+                .build_request(vec![get_span(1, 1, 1)])
+                .unwrap()
+        };
+
+        let request = build(ApiVersion::Version07);
+        assert_eq!(request.uri().path(), "/v0.7/traces");
+        assert_eq!(request.headers()[http::header::CONTENT_ENCODING], "gzip");
+        let mut payload = Vec::new();
+        flate2::read::GzDecoder::new(&request.body()[..])
+            .read_to_end(&mut payload)
+            .unwrap();
+        let payload = rmpv::decode::read_value(&mut &payload[..]).unwrap();
+        assert_eq!(payload["chunks"].as_array().map(Vec::len), Some(1));
+
+        // Older versions are always sent as is
+        let request = build(Version05);
+        assert!(request
+            .headers()
+            .get(http::header::CONTENT_ENCODING)
+            .is_none());
+    }
+
+    #[test]
+    fn test_stats_are_sent_on_force_flush() {
+        #[derive(Debug)]
+        struct StatsClient {
+            paths: Arc<std::sync::Mutex<Vec<String>>>,
+        }
+
+        #[async_trait::async_trait]
+        impl HttpClient for StatsClient {
+            async fn send_bytes(
+                &self,
+                request: Request<Bytes>,
+            ) -> Result<http::Response<Bytes>, opentelemetry_http::HttpError> {
+                let path = request.uri().path().to_string();
+                let status = if path == STATS_PATH { 500 } else { 200 };
+                self.paths.lock().unwrap().push(path);
+                Ok(http::Response::builder()
+                    .status(status)
+                    .body(Bytes::new())
+                    .unwrap())
+            }
+        }
+
+        let paths = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let client = StatsClient {
+            paths: paths.clone(),
+        };
+        let mut exporter = new_pipeline()
+            .with_service_name("test_service")
+            .with_http_client(client)
+            .with_stats_computation(true)
+            .build_exporter()
+            .unwrap();
+        futures_executor::block_on(exporter.export(vec![get_span(1, 0, 1)])).unwrap();
+        assert_eq!(paths.lock().unwrap().len(), 1);
+
+        // The open bucket is sent, and the agent's error reported
+        assert!(exporter.force_flush().is_err());
+        assert_eq!(paths.lock().unwrap()[1], STATS_PATH);
+        exporter.shutdown().unwrap();
+    }
+
+    #[test]
+    fn test_shutdown_stats_send_is_bounded_by_timeout() {
+        #[derive(Debug)]
+        struct SlowStatsClient;
+
+        #[async_trait::async_trait]
+        impl HttpClient for SlowStatsClient {
+            async fn send_bytes(
+                &self,
+                request: Request<Bytes>,
+            ) -> Result<http::Response<Bytes>, opentelemetry_http::HttpError> {
+                if request.uri().path() == STATS_PATH {
+                    std::thread::sleep(Duration::from_secs(5));
+                }
+                Ok(http::Response::new(Bytes::new()))
+            }
+        }
+
+        let mut exporter = new_pipeline()
+            .with_service_name("test_service")
+            .with_http_client(SlowStatsClient)
+            .with_stats_computation(true)
+            .build_exporter()
+            .unwrap();
+        futures_executor::block_on(exporter.export(vec![get_span(1, 0, 1)])).unwrap();
+
+        let start = std::time::Instant::now();
+        let result = exporter.shutdown_with_timeout(Duration::from_millis(50));
+        assert!(matches!(result, Err(OTelSdkError::Timeout(_))));
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[cfg(feature = "agent-sampling")]
+    #[test]
+    fn test_agent_rates_are_updated() {
+        #[derive(Debug)]
+        struct RatesClient;
+
+        #[async_trait::async_trait]
+        impl HttpClient for RatesClient {
+            async fn send_bytes(
+                &self,
+                _request: Request<Bytes>,
+            ) -> Result<http::Response<Bytes>, opentelemetry_http::HttpError> {
+                Ok(http::Response::new(Bytes::from_static(
+                    br#"{"rate_by_service":{"service:,env:":1,"service:test_service,env:prod":0.25}}"#,
+                )))
+            }
+        }
+
+        let rates = AgentSamplingRates::new();
+        let exporter = new_pipeline()
+            .with_service_name("test_service")
+            .with_http_client(RatesClient)
+            .with_agent_sampling_rates(rates.clone())
+            .build_exporter()
+            .unwrap();
+        futures_executor::block_on(exporter.export(vec![get_span(1, 1, 1)])).unwrap();
+
+        assert_eq!(rates.rate("test_service", "prod"), Some(0.25));
+        assert_eq!(rates.rate("other", "prod"), Some(1.0));
+    }
 }
@@ -1,4 +1,5 @@
 use crate::exporter::ModelConfig;
+use crate::propagator::{DatadogTraceState, TRACE_ID_UPPER_TAG};
 use http::uri;
 use opentelemetry_sdk::{
     trace::{self, SpanData},
@@ -7,15 +8,20 @@ use opentelemetry_sdk::{
 use std::fmt::Debug;
 use url::ParseError;
 
+pub(crate) use self::span_meta::get_span_meta;
 use self::unified_tags::UnifiedTags;
 
 use super::Mapping;
 
+pub(crate) mod otlp;
+mod span_meta;
 pub mod unified_tags;
 mod v03;
 mod v05;
+mod v07;
 
-// todo: we should follow the same mapping defined in https://github.com/DataDog/datadog-agent/blob/main/pkg/trace/api/otlp.go
+// The default mapping predates the agent's OTLP ingest, whose rules are followed by the opt-in
+// `otlp` mapping, see `DatadogPipelineBuilder::with_otlp_mapping`.
 
 // https://github.com/DataDog/dd-trace-js/blob/c89a35f7d27beb4a60165409376e170eacb194c5/packages/dd-trace/src/constants.js#L4
 static SAMPLING_PRIORITY_KEY: &str = "_sampling_priority_v1";
@@ -77,6 +83,66 @@ fn default_resource_mapping<'a>(span: &'a SpanData, _config: &'a ModelConfig) ->
     span.name.as_ref()
 }
 
+impl Mapping {
+    pub(crate) fn service_name<'a>(&self, span: &'a SpanData, config: &'a ModelConfig) -> &'a str {
+        match &self.service_name {
+            Some(f) => f(span, config),
+            None => default_service_name_mapping(span, config),
+        }
+    }
+
+    pub(crate) fn name<'a>(&self, span: &'a SpanData, config: &'a ModelConfig) -> &'a str {
+        match &self.name {
+            Some(f) => f(span, config),
+            None => default_name_mapping(span, config),
+        }
+    }
+
+    pub(crate) fn resource<'a>(&self, span: &'a SpanData, config: &'a ModelConfig) -> &'a str {
+        match &self.resource {
+            Some(f) => f(span, config),
+            None => default_resource_mapping(span, config),
+        }
+    }
+}
+
+#[cfg(not(feature = "agent-sampling"))]
+fn get_sampling_priority(_span: &SpanData) -> f64 {
+    1.0
+}
+
+#[cfg(feature = "agent-sampling")]
+fn get_sampling_priority(span: &SpanData) -> f64 {
+    if span.span_context.trace_state().priority_sampling_enabled() {
+        1.0
+    } else {
+        0.0
+    }
+}
+
+pub(crate) fn get_measuring(span: &SpanData) -> f64 {
+    if span.span_context.trace_state().measuring_enabled() {
+        1.0
+    } else {
+        0.0
+    }
+}
+
+/// `_dd.p.*` tags of a trace, written in the meta of its spans. They include the upper 64 bits
+/// of the trace id, since the ids of Datadog spans only hold the lower 64.
+fn get_trace_tags(trace: &[SpanData]) -> Vec<(String, String)> {
+    let Some(span) = trace.first() else {
+        return Vec::new();
+    };
+    let mut tags = span.span_context.trace_state().propagation_tags();
+    tags.retain(|(key, _)| key != TRACE_ID_UPPER_TAG);
+    let upper = (u128::from_be_bytes(span.span_context.trace_id().to_bytes()) >> 64) as u64;
+    if upper != 0 {
+        tags.push((TRACE_ID_UPPER_TAG.to_string(), format!("{upper:016x}")));
+    }
+    tags
+}
+
 /// Wrap type for errors from opentelemetry datadog exporter
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
@@ -129,6 +195,11 @@ pub enum ApiVersion {
     Version03,
     /// Version 0.5 - requires datadog-agent v7.22.0 or above
     Version05,
+    /// Version 0.7 - requires datadog-agent v7.34.0 or above
+    ///
+    /// Carries container, env and app version metadata along with per trace sampling
+    /// priority and origin. Payloads can be gzip compressed with the `gzip` feature.
+    Version07,
 }
 
 impl ApiVersion {
@@ -136,6 +207,7 @@ impl ApiVersion {
         match self {
             ApiVersion::Version03 => "/v0.3/traces",
             ApiVersion::Version05 => "/v0.5/traces",
+            ApiVersion::Version07 => "/v0.7/traces",
         }
     }
 
@@ -143,6 +215,7 @@ impl ApiVersion {
         match self {
             ApiVersion::Version03 => "application/msgpack",
             ApiVersion::Version05 => "application/msgpack",
+            ApiVersion::Version07 => "application/msgpack",
         }
     }
 
@@ -158,40 +231,37 @@ impl ApiVersion {
             Self::Version03 => v03::encode(
                 model_config,
                 traces,
-                |span, config| match &mapping.service_name {
-                    Some(f) => f(span, config),
-                    None => default_service_name_mapping(span, config),
-                },
-                |span, config| match &mapping.name {
-                    Some(f) => f(span, config),
-                    None => default_name_mapping(span, config),
-                },
-                |span, config| match &mapping.resource {
-                    Some(f) => f(span, config),
-                    None => default_resource_mapping(span, config),
-                },
+                |span, config| mapping.service_name(span, config),
+                |span, config| mapping.name(span, config),
+                |span, config| mapping.resource(span, config),
                 resource,
             ),
             Self::Version05 => v05::encode(
                 model_config,
                 traces,
-                |span, config| match &mapping.service_name {
-                    Some(f) => f(span, config),
-                    None => default_service_name_mapping(span, config),
-                },
-                |span, config| match &mapping.name {
-                    Some(f) => f(span, config),
-                    None => default_name_mapping(span, config),
-                },
-                |span, config| match &mapping.resource {
-                    Some(f) => f(span, config),
-                    None => default_resource_mapping(span, config),
-                },
+                |span, config| mapping.service_name(span, config),
+                |span, config| mapping.name(span, config),
+                |span, config| mapping.resource(span, config),
+                unified_tags,
+                resource,
+            ),
+            Self::Version07 => v07::encode(
+                model_config,
+                traces,
+                |span, config| mapping.service_name(span, config),
+                |span, config| mapping.name(span, config),
+                |span, config| mapping.resource(span, config),
                 unified_tags,
                 resource,
             ),
         }
     }
+
+    /// Whether the agent accepts gzip compressed payloads for this version
+    #[cfg(feature = "gzip")]
+    pub(crate) fn supports_compression(self) -> bool {
+        matches!(self, ApiVersion::Version07)
+    }
 }
 
 #[cfg(test)]
@@ -306,4 +376,164 @@ pub(crate) mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_encode_v07() -> Result<(), Box<dyn std::error::Error>> {
+        let mut origin_span = get_span(7, 99, 100);
+        origin_span
+            .attributes
+            .push(KeyValue::new("_dd.origin", "synthetics"));
+        let traces = [
+            vec![get_span(7, 1, 99), origin_span],
+            vec![get_span(8, 0, 1)],
+        ];
+        let model_config = ModelConfig {
+            service_name: "service_name".to_string(),
+            ..Default::default()
+        };
+        let resource = Resource::builder_empty()
+            .with_attribute(KeyValue::new("host.name", "test"))
+            .with_attribute(KeyValue::new("container.id", "abc123"))
+            .build();
+
+        let mut unified_tags = UnifiedTags::new();
+        unified_tags.set_env(Some(String::from("test-env")));
+        unified_tags.set_version(Some(String::from("test-version")));
+        unified_tags.set_service(Some(String::from("test-service")));
+
+        let encoded = ApiVersion::Version07.encode(
+            &model_config,
+            traces.iter().map(|x| &x[..]).collect(),
+            &Mapping::empty(),
+            &unified_tags,
+            Some(&resource),
+        )?;
+        let payload = rmpv::decode::read_value(&mut &encoded[..])?;
+
+        assert_eq!(payload["container_id"].as_str(), Some("abc123"));
+        assert_eq!(payload["language_name"].as_str(), Some("rust"));
+        assert_eq!(payload["env"].as_str(), Some("test-env"));
+        assert_eq!(payload["hostname"].as_str(), Some("test"));
+        assert_eq!(payload["app_version"].as_str(), Some("test-version"));
+
+        let chunks = payload["chunks"].as_array().unwrap();
+        assert_eq!(chunks.len(), 2);
+        let priority = get_sampling_priority(&traces[0][0]);
+        assert_eq!(chunks[0]["priority"].as_i64(), Some(priority as i64));
+        assert_eq!(chunks[0]["origin"].as_str(), Some("synthetics"));
+        assert_eq!(chunks[1]["origin"].as_str(), Some(""));
+        assert_eq!(chunks[0]["dropped_trace"].as_bool(), Some(false));
+
+        let span = &chunks[0]["spans"][1];
+        assert_eq!(span["service"].as_str(), Some("service_name"));
+        assert_eq!(span["name"].as_str(), Some("component"));
+        assert_eq!(span["resource"].as_str(), Some("resource"));
+        assert_eq!(span["type"].as_str(), Some("web"));
+        assert_eq!(span["trace_id"].as_u64(), Some(7));
+        assert_eq!(span["span_id"].as_u64(), Some(100));
+        assert_eq!(span["parent_id"].as_u64(), Some(99));
+        assert_eq!(span["duration"].as_i64(), Some(1_000_000_000));
+        assert_eq!(span["meta"]["env"].as_str(), Some("test-env"));
+        assert_eq!(span["meta"]["_dd.origin"].as_str(), Some("synthetics"));
+        assert_eq!(
+            span["metrics"]["_sampling_priority_v1"].as_f64(),
+            Some(priority)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_encode_span_meta() -> Result<(), Box<dyn std::error::Error>> {
+        let mut span = get_span(7, 1, 99);
+        span.status = Status::error("request failed");
+        span.links.links = vec![opentelemetry::trace::Link::with_context(SpanContext::new(
+            TraceId::from(8),
+            SpanId::from(9),
+            TraceFlags::default(),
+            false,
+            TraceState::default(),
+        ))];
+        let traces = [vec![span]];
+        let links =
+            r#"[{"span_id":"0000000000000009","trace_id":"00000000000000000000000000000008"}]"#;
+
+        for api_version in [
+            ApiVersion::Version03,
+            ApiVersion::Version05,
+            ApiVersion::Version07,
+        ] {
+            let encoded = api_version.encode(
+                &ModelConfig::default(),
+                traces.iter().map(|x| &x[..]).collect(),
+                &Mapping::empty(),
+                &UnifiedTags::new(),
+                None,
+            )?;
+            let payload = rmpv::decode::read_value(&mut &encoded[..])?;
+            let meta: Vec<(String, String)> = match api_version {
+                ApiVersion::Version03 => meta_entries(&payload[0][0]["meta"], |v| v),
+                ApiVersion::Version05 => {
+                    let dictionary = &payload[0];
+                    meta_entries(&payload[1][0][0][9], |v| {
+                        &dictionary[v.as_u64().unwrap() as usize]
+                    })
+                }
+                ApiVersion::Version07 => {
+                    meta_entries(&payload["chunks"][0]["spans"][0]["meta"], |v| v)
+                }
+            };
+            assert!(
+                meta.contains(&("error.message".to_string(), "request failed".to_string())),
+                "{api_version:?}"
+            );
+            assert!(
+                meta.contains(&("_dd.span_links".to_string(), links.to_string())),
+                "{api_version:?}"
+            );
+        }
+
+        Ok(())
+    }
+
+    fn meta_entries<'a>(
+        meta: &'a rmpv::Value,
+        resolve: impl Fn(&'a rmpv::Value) -> &'a rmpv::Value,
+    ) -> Vec<(String, String)> {
+        meta.as_map()
+            .unwrap()
+            .iter()
+            .map(|(key, value)| {
+                (
+                    resolve(key).as_str().unwrap().to_string(),
+                    resolve(value).as_str().unwrap().to_string(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_trace_tags() {
+        let mut span = get_span(0x640cfd8d00000000_u128 << 64 | 7, 1, 99);
+        assert_eq!(
+            get_trace_tags(&[span.clone()]),
+            [("_dd.p.tid".to_string(), "640cfd8d00000000".to_string())]
+        );
+
+        span.span_context = SpanContext::new(
+            TraceId::from(7),
+            SpanId::from(99),
+            TraceFlags::default(),
+            false,
+            TraceState::default().with_propagation_tags(&[
+                ("_dd.p.dm".to_string(), "-4".to_string()),
+                ("_dd.p.tid".to_string(), "640cfd8d00000000".to_string()),
+            ]),
+        );
+        // A stale upper half is not written for a 64-bit trace id
+        assert_eq!(
+            get_trace_tags(&[span]),
+            [("_dd.p.dm".to_string(), "-4".to_string())]
+        );
+    }
 }
@@ -0,0 +1,434 @@
+//! Mapping of OpenTelemetry spans to Datadog spans following the agent's OTLP ingest.
+//!
+//! The names, resources and types are derived from the span kind and semantic conventions
+//! the way the agent does when it receives OTLP, so that spans look the same in Datadog
+//! whichever way they are sent.
+//!
+//! https://github.com/DataDog/datadog-agent/blob/7.52.0/pkg/trace/traceutil/otel_util.go
+
+use crate::exporter::ModelConfig;
+use opentelemetry::trace::SpanKind;
+use opentelemetry::KeyValue;
+use opentelemetry_sdk::trace::SpanData;
+use std::borrow::Cow;
+
+// Attributes holding the Datadog fields, set by users to override the mapping or by
+// `map_span` for the name and resource mappings to read
+const OPERATION_NAME_KEY: &str = "operation.name";
+const RESOURCE_NAME_KEY: &str = "resource.name";
+const SPAN_TYPE_KEY: &str = "span.type";
+
+const SPAN_KIND_KEY: &str = "span.kind";
+const HTTP_STATUS_CODE_KEY: &str = "http.status_code";
+
+const HTTP_METHOD_KEYS: [&str; 2] = ["http.request.method", "http.method"];
+const HTTP_RESPONSE_STATUS_CODE_KEY: &str = "http.response.status_code";
+const HTTP_ROUTE_KEY: &str = "http.route";
+const DB_SYSTEM_KEY: &str = "db.system";
+const DB_STATEMENT_KEYS: [&str; 2] = ["db.query.text", "db.statement"];
+const MESSAGING_SYSTEM_KEY: &str = "messaging.system";
+const MESSAGING_OPERATION_KEY: &str = "messaging.operation";
+const MESSAGING_DESTINATION_KEYS: [&str; 2] =
+    ["messaging.destination.name", "messaging.destination"];
+const RPC_SYSTEM_KEY: &str = "rpc.system";
+const RPC_SERVICE_KEY: &str = "rpc.service";
+const RPC_METHOD_KEY: &str = "rpc.method";
+const FAAS_INVOKED_PROVIDER_KEY: &str = "faas.invoked_provider";
+const FAAS_INVOKED_NAME_KEY: &str = "faas.invoked_name";
+const FAAS_TRIGGER_KEY: &str = "faas.trigger";
+const GRAPHQL_OPERATION_TYPE_KEY: &str = "graphql.operation.type";
+const GRAPHQL_OPERATION_NAME_KEY: &str = "graphql.operation.name";
+const NETWORK_PROTOCOL_NAME_KEY: &str = "network.protocol.name";
+
+// https://github.com/DataDog/datadog-agent/blob/7.52.0/pkg/trace/traceutil/otel_util.go#L42
+const SQL_DB_SYSTEMS: [&str; 36] = [
+    "other_sql",
+    "mssql",
+    "mysql",
+    "oracle",
+    "db2",
+    "postgresql",
+    "redshift",
+    "cloudscape",
+    "hsqldb",
+    "maxdb",
+    "ingres",
+    "firstsql",
+    "edb",
+    "cache",
+    "firebird",
+    "derby",
+    "informix",
+    "mariadb",
+    "sqlite",
+    "sybase",
+    "teradata",
+    "vertica",
+    "h2",
+    "coldfusion",
+    "cockroachdb",
+    "progress",
+    "hanadb",
+    "adabas",
+    "filemaker",
+    "instantdb",
+    "interbase",
+    "netezza",
+    "pervasive",
+    "pointbase",
+    "clickhouse",
+    "spanner",
+];
+
+/// Add the attributes the agent derives from OTLP spans: `operation.name`, `resource.name`,
+/// `span.type`, `span.kind` and `http.status_code`. Attributes already set on the span are
+/// kept. The `error.*` tags are derived for every span when it is encoded.
+pub(crate) fn map_span(span: &mut SpanData) {
+    let mut derived = vec![
+        KeyValue::new(OPERATION_NAME_KEY, operation_name(span)),
+        KeyValue::new(RESOURCE_NAME_KEY, resource_name(span)),
+        KeyValue::new(SPAN_TYPE_KEY, span_type(span)),
+        KeyValue::new(SPAN_KIND_KEY, span_kind_name(&span.span_kind)),
+    ];
+    if let Some(code) = attribute(span, HTTP_RESPONSE_STATUS_CODE_KEY) {
+        derived.push(KeyValue::new(HTTP_STATUS_CODE_KEY, code.into_owned()));
+    }
+
+    for kv in derived {
+        if !span.attributes.iter().any(|attr| attr.key == kv.key) {
+            span.attributes.push(kv);
+        }
+    }
+}
+
+/// Datadog name of spans passed through [`map_span`]
+pub(crate) fn name_mapping<'a>(span: &'a SpanData, _config: &'a ModelConfig) -> &'a str {
+    string_attribute(span, OPERATION_NAME_KEY).unwrap_or(span.name.as_ref())
+}
+
+/// Datadog resource of spans passed through [`map_span`]
+pub(crate) fn resource_mapping<'a>(span: &'a SpanData, _config: &'a ModelConfig) -> &'a str {
+    string_attribute(span, RESOURCE_NAME_KEY).unwrap_or(span.name.as_ref())
+}
+
+fn operation_name(span: &SpanData) -> String {
+    if let Some(name) = attribute(span, OPERATION_NAME_KEY) {
+        return name.into_owned();
+    }
+    let is_client = span.span_kind == SpanKind::Client;
+    let is_server = span.span_kind == SpanKind::Server;
+
+    let name = if first_attribute(span, &HTTP_METHOD_KEYS).is_some() && (is_client || is_server) {
+        if is_server {
+            "http.server.request".to_string()
+        } else {
+            "http.client.request".to_string()
+        }
+    } else if let (true, Some(db_system)) = (is_client, attribute(span, DB_SYSTEM_KEY)) {
+        format!("{db_system}.query")
+    } else if let (Some(system), Some(operation), false) = (
+        attribute(span, MESSAGING_SYSTEM_KEY),
+        attribute(span, MESSAGING_OPERATION_KEY),
+        span.span_kind == SpanKind::Internal,
+    ) {
+        format!("{system}.{operation}")
+    } else if let Some(rpc_system) =
+        attribute(span, RPC_SYSTEM_KEY).filter(|_| is_client || is_server)
+    {
+        match (rpc_system.as_ref(), is_client) {
+            ("aws-api", true) => match attribute(span, RPC_SERVICE_KEY) {
+                Some(service) => format!("aws.{service}.request"),
+                None => "aws.client.request".to_string(),
+            },
+            (_, true) => format!("{rpc_system}.client.request"),
+            (_, false) => format!("{rpc_system}.server.request"),
+        }
+    } else if let (true, Some(provider), Some(function)) = (
+        is_client,
+        attribute(span, FAAS_INVOKED_PROVIDER_KEY),
+        attribute(span, FAAS_INVOKED_NAME_KEY),
+    ) {
+        format!("{provider}.{function}.invoke")
+    } else if let (true, Some(trigger)) = (is_server, attribute(span, FAAS_TRIGGER_KEY)) {
+        format!("{trigger}.invoke")
+    } else if attribute(span, GRAPHQL_OPERATION_TYPE_KEY).is_some() {
+        "graphql.server.request".to_string()
+    } else if let Some(protocol) =
+        attribute(span, NETWORK_PROTOCOL_NAME_KEY).filter(|_| is_client || is_server)
+    {
+        if is_server {
+            format!("{protocol}.server.request")
+        } else {
+            format!("{protocol}.client.request")
+        }
+    } else {
+        match span.span_kind {
+            SpanKind::Server => "server.request".to_string(),
+            SpanKind::Client => "client.request".to_string(),
+            ref kind => span_kind_name(kind).to_string(),
+        }
+    };
+    name.to_lowercase()
+}
+
+fn resource_name(span: &SpanData) -> String {
+    if let Some(resource) = attribute(span, RESOURCE_NAME_KEY) {
+        return resource.into_owned();
+    }
+
+    if let Some(method) = first_attribute(span, &HTTP_METHOD_KEYS) {
+        let method = if method == "_OTHER" {
+            Cow::Borrowed("HTTP")
+        } else {
+            method
+        };
+        return match attribute(span, HTTP_ROUTE_KEY) {
+            Some(route) if span.span_kind == SpanKind::Server => format!("{method} {route}"),
+            _ => method.into_owned(),
+        };
+    }
+    if let Some(operation) = attribute(span, MESSAGING_OPERATION_KEY) {
+        return match first_attribute(span, &MESSAGING_DESTINATION_KEYS) {
+            Some(destination) => format!("{operation} {destination}"),
+            None => operation.into_owned(),
+        };
+    }
+    if let Some(method) = attribute(span, RPC_METHOD_KEY) {
+        return match attribute(span, RPC_SERVICE_KEY) {
+            Some(service) => format!("{method} {service}"),
+            None => method.into_owned(),
+        };
+    }
+    if let Some(operation_type) = attribute(span, GRAPHQL_OPERATION_TYPE_KEY) {
+        return match attribute(span, GRAPHQL_OPERATION_NAME_KEY) {
+            Some(name) => format!("{operation_type} {name}"),
+            None => operation_type.into_owned(),
+        };
+    }
+    if attribute(span, DB_SYSTEM_KEY).is_some() {
+        if let Some(statement) = first_attribute(span, &DB_STATEMENT_KEYS) {
+            return statement.into_owned();
+        }
+    }
+    span.name.to_string()
+}
+
+fn span_type(span: &SpanData) -> String {
+    if let Some(span_type) = attribute(span, SPAN_TYPE_KEY) {
+        return span_type.into_owned();
+    }
+    let db_system = attribute(span, DB_SYSTEM_KEY);
+    match span.span_kind {
+        SpanKind::Server => "web",
+        SpanKind::Client => match db_system.as_deref() {
+            None => "http",
+            Some(system) if SQL_DB_SYSTEMS.contains(&system) => "sql",
+            Some(
+                system @ ("redis" | "memcached" | "mongodb" | "elasticsearch" | "opensearch"
+                | "cassandra"),
+            ) => system,
+            Some(_) => "db",
+        },
+        _ => "custom",
+    }
+    .to_string()
+}
+
+fn span_kind_name(kind: &SpanKind) -> &'static str {
+    match kind {
+        SpanKind::Client => "client",
+        SpanKind::Server => "server",
+        SpanKind::Producer => "producer",
+        SpanKind::Consumer => "consumer",
+        SpanKind::Internal => "internal",
+    }
+}
+
+fn attribute<'a>(span: &'a SpanData, key: &str) -> Option<Cow<'a, str>> {
+    span.attributes
+        .iter()
+        .find(|kv| kv.key.as_str() == key)
+        .map(|kv| kv.value.as_str())
+}
+
+fn first_attribute<'a>(span: &'a SpanData, keys: &[&str]) -> Option<Cow<'a, str>> {
+    keys.iter().find_map(|key| attribute(span, key))
+}
+
+fn string_attribute<'a>(span: &'a SpanData, key: &str) -> Option<&'a str> {
+    match attribute(span, key)? {
+        Cow::Borrowed(value) => Some(value),
+        Cow::Owned(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exporter::model::get_span_meta;
+    use crate::exporter::model::tests::get_span;
+    use opentelemetry::trace::{Event, Status};
+    use std::time::SystemTime;
+
+    fn mapped(kind: SpanKind, attributes: Vec<KeyValue>) -> SpanData {
+        let mut span = get_span(7, 1, 99);
+        span.span_kind = kind;
+        span.attributes = attributes;
+        map_span(&mut span);
+        span
+    }
+
+    fn fields(span: &SpanData) -> (String, String, String) {
+        let config = ModelConfig::default();
+        (
+            name_mapping(span, &config).to_string(),
+            resource_mapping(span, &config).to_string(),
+            attribute(span, SPAN_TYPE_KEY).unwrap().into_owned(),
+        )
+    }
+
+    #[test]
+    fn test_http() {
+        let server = mapped(
+            SpanKind::Server,
+            vec![
+                KeyValue::new("http.request.method", "GET"),
+                KeyValue::new("http.route", "/users/:id"),
+                KeyValue::new("http.response.status_code", 200),
+            ],
+        );
+        assert_eq!(
+            fields(&server),
+            (
+                "http.server.request".into(),
+                "GET /users/:id".into(),
+                "web".into()
+            )
+        );
+        assert_eq!(attribute(&server, "span.kind").as_deref(), Some("server"));
+        assert_eq!(
+            attribute(&server, "http.status_code").as_deref(),
+            Some("200")
+        );
+
+        let client = mapped(
+            SpanKind::Client,
+            vec![
+                KeyValue::new("http.method", "_OTHER"),
+                KeyValue::new("http.route", "/users/:id"),
+            ],
+        );
+        assert_eq!(
+            fields(&client),
+            ("http.client.request".into(), "HTTP".into(), "http".into())
+        );
+    }
+
+    #[test]
+    fn test_db_messaging_rpc() {
+        let db = mapped(
+            SpanKind::Client,
+            vec![
+                KeyValue::new("db.system", "postgresql"),
+                KeyValue::new("db.statement", "SELECT 1"),
+            ],
+        );
+        assert_eq!(
+            fields(&db),
+            ("postgresql.query".into(), "SELECT 1".into(), "sql".into())
+        );
+        let redis = mapped(SpanKind::Client, vec![KeyValue::new("db.system", "Redis")]);
+        assert_eq!(
+            fields(&redis),
+            ("redis.query".into(), "resource".into(), "db".into())
+        );
+
+        let consumer = mapped(
+            SpanKind::Consumer,
+            vec![
+                KeyValue::new("messaging.system", "kafka"),
+                KeyValue::new("messaging.operation", "receive"),
+                KeyValue::new("messaging.destination.name", "orders"),
+            ],
+        );
+        assert_eq!(
+            fields(&consumer),
+            (
+                "kafka.receive".into(),
+                "receive orders".into(),
+                "custom".into()
+            )
+        );
+
+        let aws = mapped(
+            SpanKind::Client,
+            vec![
+                KeyValue::new("rpc.system", "aws-api"),
+                KeyValue::new("rpc.service", "S3"),
+                KeyValue::new("rpc.method", "GetObject"),
+            ],
+        );
+        assert_eq!(
+            fields(&aws),
+            (
+                "aws.s3.request".into(),
+                "GetObject S3".into(),
+                "http".into()
+            )
+        );
+        let grpc = mapped(SpanKind::Server, vec![KeyValue::new("rpc.system", "grpc")]);
+        assert_eq!(fields(&grpc).0, "grpc.server.request");
+    }
+
+    #[test]
+    fn test_fallbacks_and_overrides() {
+        let internal = mapped(SpanKind::Internal, vec![]);
+        assert_eq!(
+            fields(&internal),
+            ("internal".into(), "resource".into(), "custom".into())
+        );
+
+        let overridden = mapped(
+            SpanKind::Server,
+            vec![
+                KeyValue::new("http.request.method", "GET"),
+                KeyValue::new("operation.name", "custom.op"),
+                KeyValue::new("resource.name", "custom resource"),
+                KeyValue::new("span.type", "worker"),
+            ],
+        );
+        assert_eq!(
+            fields(&overridden),
+            (
+                "custom.op".into(),
+                "custom resource".into(),
+                "worker".into()
+            )
+        );
+        assert_eq!(overridden.attributes.len(), 5);
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut span = get_span(7, 1, 99);
+        span.status = Status::error("request failed");
+        span.events.events.push(Event::new(
+            "exception",
+            SystemTime::UNIX_EPOCH,
+            vec![
+                KeyValue::new("exception.type", "io::Error"),
+                KeyValue::new("exception.message", "connection reset"),
+                KeyValue::new("exception.stacktrace", "at main.rs:1"),
+            ],
+            0,
+        ));
+        map_span(&mut span);
+
+        // Errors are mapped with the span meta, as for spans that are not mapped
+        assert!(attribute(&span, "error.message").is_none());
+        let meta = get_span_meta(&span, None);
+        assert!(meta.contains(&("error.message", "connection reset".to_string())));
+        assert!(meta.contains(&("error.type", "io::Error".to_string())));
+        assert!(meta.contains(&("error.stack", "at main.rs:1".to_string())));
+    }
+}
@@ -0,0 +1,288 @@
+//! Meta tags derived from the span status, events and links, which Datadog spans have no
+//! dedicated fields for. This is the only place exceptions and error statuses are mapped to
+//! the `error.*` tags, with or without the OTLP mapping.
+//!
+//! Events and links are serialized to JSON the way the agent does for the spans it receives
+//! over OTLP, so that the Datadog UI renders them alike.
+//!
+//! https://github.com/DataDog/datadog-agent/blob/7.52.0/pkg/trace/api/otlp.go#L576
+
+use opentelemetry::trace::{Event, Link, Status};
+use opentelemetry::{Array, Key, KeyValue, Value};
+use opentelemetry_sdk::trace::SpanData;
+use opentelemetry_sdk::Resource;
+use serde_json::{Map, Number};
+use std::time::SystemTime;
+
+const EVENTS_KEY: &str = "events";
+const SPAN_LINKS_KEY: &str = "_dd.span_links";
+const ERROR_MESSAGE_KEY: &str = "error.message";
+const ERROR_TYPE_KEY: &str = "error.type";
+const ERROR_STACK_KEY: &str = "error.stack";
+
+const EXCEPTION_EVENT_NAME: &str = "exception";
+const EXCEPTION_MESSAGE_KEY: &str = "exception.message";
+const EXCEPTION_TYPE_KEY: &str = "exception.type";
+const EXCEPTION_STACKTRACE_KEY: &str = "exception.stacktrace";
+
+/// Meta for the span's events, links and error details. Keys already set as span or resource
+/// attributes are left out so that the span meta has no duplicates.
+pub(crate) fn get_span_meta(
+    span: &SpanData,
+    resource: Option<&Resource>,
+) -> Vec<(&'static str, String)> {
+    let mut meta = Vec::new();
+
+    // The last recorded exception is the one that ended the span
+    if let Some(exception) = span
+        .events
+        .iter()
+        .rev()
+        .find(|event| event.name == EXCEPTION_EVENT_NAME)
+    {
+        for (from, to) in [
+            (EXCEPTION_MESSAGE_KEY, ERROR_MESSAGE_KEY),
+            (EXCEPTION_TYPE_KEY, ERROR_TYPE_KEY),
+            (EXCEPTION_STACKTRACE_KEY, ERROR_STACK_KEY),
+        ] {
+            if let Some(kv) = exception
+                .attributes
+                .iter()
+                .find(|kv| kv.key.as_str() == from)
+            {
+                meta.push((to, kv.value.as_str().into_owned()));
+            }
+        }
+    }
+    if let Status::Error { description } = &span.status {
+        if !description.is_empty() && !meta.iter().any(|(key, _)| *key == ERROR_MESSAGE_KEY) {
+            meta.push((ERROR_MESSAGE_KEY, description.to_string()));
+        }
+    }
+
+    if !span.events.is_empty() {
+        let events = span.events.iter().map(event_to_json).collect();
+        meta.push((EVENTS_KEY, serde_json::Value::Array(events).to_string()));
+    }
+    if !span.links.is_empty() {
+        let links = span.links.iter().map(link_to_json).collect();
+        meta.push((SPAN_LINKS_KEY, serde_json::Value::Array(links).to_string()));
+    }
+
+    meta.retain(|(key, _)| {
+        !span.attributes.iter().any(|kv| kv.key.as_str() == *key)
+            && !resource.is_some_and(|resource| resource.get(&Key::from_static_str(key)).is_some())
+    });
+    meta
+}
+
+fn event_to_json(event: &Event) -> serde_json::Value {
+    let mut json = Map::new();
+    json.insert("name".to_string(), event.name.to_string().into());
+    json.insert(
+        "time_unix_nano".to_string(),
+        (event
+            .timestamp
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|time| time.as_nanos() as u64)
+            .unwrap_or_default())
+        .into(),
+    );
+    if !event.attributes.is_empty() {
+        json.insert(
+            "attributes".to_string(),
+            attributes_to_json(&event.attributes, value_to_json),
+        );
+    }
+    if event.dropped_attributes_count > 0 {
+        json.insert(
+            "dropped_attributes_count".to_string(),
+            event.dropped_attributes_count.into(),
+        );
+    }
+    json.into()
+}
+
+fn link_to_json(link: &Link) -> serde_json::Value {
+    let context = &link.span_context;
+    let mut json = Map::new();
+    json.insert(
+        "trace_id".to_string(),
+        format!("{:032x}", context.trace_id()).into(),
+    );
+    json.insert(
+        "span_id".to_string(),
+        format!("{:016x}", context.span_id()).into(),
+    );
+    let trace_state = context.trace_state().header();
+    if !trace_state.is_empty() {
+        json.insert("tracestate".to_string(), trace_state.into());
+    }
+    // The agent keeps link attributes as strings
+    if !link.attributes.is_empty() {
+        json.insert(
+            "attributes".to_string(),
+            attributes_to_json(&link.attributes, |value| value.as_str().into_owned().into()),
+        );
+    }
+    if link.dropped_attributes_count > 0 {
+        json.insert(
+            "dropped_attributes_count".to_string(),
+            link.dropped_attributes_count.into(),
+        );
+    }
+    json.into()
+}
+
+fn attributes_to_json(
+    attributes: &[KeyValue],
+    to_json: impl Fn(&Value) -> serde_json::Value,
+) -> serde_json::Value {
+    attributes
+        .iter()
+        .map(|kv| (kv.key.to_string(), to_json(&kv.value)))
+        .collect::<Map<_, _>>()
+        .into()
+}
+
+fn value_to_json(value: &Value) -> serde_json::Value {
+    match value {
+        Value::Bool(value) => (*value).into(),
+        Value::I64(value) => (*value).into(),
+        Value::F64(value) => float_to_json(*value),
+        Value::String(value) => value.as_str().into(),
+        Value::Array(Array::Bool(values)) => values.as_slice().into(),
+        Value::Array(Array::I64(values)) => values.as_slice().into(),
+        Value::Array(Array::F64(values)) => values.iter().copied().map(float_to_json).collect(),
+        Value::Array(Array::String(values)) => values.iter().map(|value| value.as_str()).collect(),
+        value => value.as_str().into_owned().into(),
+    }
+}
+
+// JSON has no NaN or infinities, write them as strings
+fn float_to_json(value: f64) -> serde_json::Value {
+    Number::from_f64(value)
+        .map(serde_json::Value::Number)
+        .unwrap_or_else(|| value.to_string().into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exporter::model::tests::get_span;
+    use opentelemetry::trace::{SpanContext, SpanId, TraceFlags, TraceId, TraceState};
+    use std::time::Duration;
+
+    #[test]
+    fn test_no_events_or_links() {
+        assert!(get_span_meta(&get_span(7, 1, 99), None).is_empty());
+    }
+
+    #[test]
+    fn test_events() {
+        let mut span = get_span(7, 1, 99);
+        span.events.events = vec![Event::new(
+            "cache miss",
+            SystemTime::UNIX_EPOCH + Duration::from_nanos(1_500),
+            vec![
+                KeyValue::new("key", "user:1"),
+                KeyValue::new("retries", 2),
+                KeyValue::new("ratio", f64::NAN),
+                KeyValue::new("hit", false),
+            ],
+            1,
+        )];
+
+        assert_eq!(
+            get_span_meta(&span, None),
+            vec![(
+                EVENTS_KEY,
+                r#"[{"attributes":{"hit":false,"key":"user:1","ratio":"NaN","retries":2},"dropped_attributes_count":1,"name":"cache miss","time_unix_nano":1500}]"#
+                    .to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut span = get_span(7, 1, 99);
+        span.status = Status::error("request failed");
+        assert_eq!(
+            get_span_meta(&span, None),
+            vec![(ERROR_MESSAGE_KEY, "request failed".to_string())]
+        );
+
+        span.events.events = vec![Event::new(
+            EXCEPTION_EVENT_NAME,
+            SystemTime::UNIX_EPOCH,
+            vec![
+                KeyValue::new(EXCEPTION_TYPE_KEY, "io::Error"),
+                KeyValue::new(EXCEPTION_MESSAGE_KEY, "connection reset"),
+                KeyValue::new(EXCEPTION_STACKTRACE_KEY, "main.rs:1"),
+            ],
+            0,
+        )];
+        let meta = get_span_meta(&span, None);
+        assert_eq!(
+            meta[..3],
+            [
+                (ERROR_MESSAGE_KEY, "connection reset".to_string()),
+                (ERROR_TYPE_KEY, "io::Error".to_string()),
+                (ERROR_STACK_KEY, "main.rs:1".to_string()),
+            ]
+        );
+        assert_eq!(meta[3].0, EVENTS_KEY);
+
+        // Attributes set by the user are kept
+        span.attributes = vec![KeyValue::new(ERROR_TYPE_KEY, "custom")];
+        let meta = get_span_meta(&span, None);
+        assert_eq!(meta.len(), 3);
+        assert!(!meta.iter().any(|(key, _)| *key == ERROR_TYPE_KEY));
+
+        // Resource attributes are written in the meta too
+        let resource = Resource::builder_empty()
+            .with_attribute(KeyValue::new(ERROR_STACK_KEY, "custom"))
+            .build();
+        let meta = get_span_meta(&span, Some(&resource));
+        assert_eq!(meta.len(), 2);
+        assert!(!meta.iter().any(|(key, _)| *key == ERROR_STACK_KEY));
+    }
+
+    #[test]
+    fn test_links() {
+        let mut span = get_span(7, 1, 99);
+        let trace_state = TraceState::from_key_value([("dd", "s:1")]).unwrap();
+        span.links.links = vec![
+            Link::new(
+                SpanContext::new(
+                    TraceId::from(0x1234u128 << 64 | 5),
+                    SpanId::from(6),
+                    TraceFlags::SAMPLED,
+                    true,
+                    trace_state,
+                ),
+                vec![KeyValue::new("retries", 2)],
+                0,
+            ),
+            Link::with_context(SpanContext::new(
+                TraceId::from(7),
+                SpanId::from(8),
+                TraceFlags::default(),
+                false,
+                TraceState::default(),
+            )),
+        ];
+
+        assert_eq!(
+            get_span_meta(&span, None),
+            vec![(
+                SPAN_LINKS_KEY,
+                concat!(
+                    r#"[{"attributes":{"retries":"2"},"span_id":"0000000000000006","trace_id":"00000000000012340000000000000005","tracestate":"dd=s:1"},"#,
+                    r#"{"span_id":"0000000000000008","trace_id":"00000000000000000000000000000007"}]"#
+                )
+                .to_string()
+            )]
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use crate::exporter::model::{Error, SAMPLING_PRIORITY_KEY};
+use crate::exporter::model::{get_span_meta, get_trace_tags, Error, SAMPLING_PRIORITY_KEY};
 use crate::exporter::ModelConfig;
 use opentelemetry::trace::Status;
 use opentelemetry_sdk::trace::SpanData;
@@ -23,6 +23,7 @@ where
 
     for trace in traces.into_iter() {
         rmp::encode::write_array_len(&mut encoded, trace.len() as u32)?;
+        let trace_tags = get_trace_tags(trace);
 
         for span in trace {
             // Safe until the year 2262 when Datadog will need to change their API
@@ -96,10 +97,14 @@ where
                 },
             )?;
 
+            let span_meta = get_span_meta(span, resource);
             rmp::encode::write_str(&mut encoded, "meta")?;
             rmp::encode::write_map_len(
                 &mut encoded,
-                (span.attributes.len() + resource.map(|r| r.len()).unwrap_or(0)) as u32,
+                (span.attributes.len()
+                    + resource.map(|r| r.len()).unwrap_or(0)
+                    + trace_tags.len()
+                    + span_meta.len()) as u32,
             )?;
             if let Some(resource) = resource {
                 for (key, value) in resource.iter() {
@@ -111,6 +116,14 @@ where
                 rmp::encode::write_str(&mut encoded, kv.key.as_str())?;
                 rmp::encode::write_str(&mut encoded, kv.value.as_str().as_ref())?;
             }
+            for (key, value) in &trace_tags {
+                rmp::encode::write_str(&mut encoded, key)?;
+                rmp::encode::write_str(&mut encoded, value)?;
+            }
+            for (key, value) in &span_meta {
+                rmp::encode::write_str(&mut encoded, key)?;
+                rmp::encode::write_str(&mut encoded, value)?;
+            }
 
             rmp::encode::write_str(&mut encoded, "metrics")?;
             rmp::encode::write_map_len(&mut encoded, 1)?;
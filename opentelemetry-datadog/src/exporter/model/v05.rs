@@ -1,7 +1,9 @@
 use crate::exporter::intern::StringInterner;
-use crate::exporter::model::{DD_MEASURED_KEY, SAMPLING_PRIORITY_KEY};
+use crate::exporter::model::{
+    get_measuring, get_sampling_priority, get_span_meta, get_trace_tags, DD_MEASURED_KEY,
+    SAMPLING_PRIORITY_KEY,
+};
 use crate::exporter::{Error, ModelConfig};
-use crate::propagator::DatadogTraceState;
 use opentelemetry::trace::Status;
 use opentelemetry_sdk::trace::SpanData;
 use opentelemetry_sdk::Resource;
@@ -82,6 +84,17 @@ where
     for<'a> N: Fn(&'a SpanData, &'a ModelConfig) -> &'a str,
     for<'a> R: Fn(&'a SpanData, &'a ModelConfig) -> &'a str,
 {
+    // Computed upfront so that the interner can borrow them
+    let trace_tags: Vec<_> = traces.iter().map(|trace| get_trace_tags(trace)).collect();
+    let span_meta: Vec<Vec<_>> = traces
+        .iter()
+        .map(|trace| {
+            trace
+                .iter()
+                .map(|span| get_span_meta(span, resource))
+                .collect()
+        })
+        .collect();
     let mut interner = StringInterner::new();
     let mut encoded_traces = encode_traces(
         &mut interner,
@@ -90,6 +103,8 @@ where
         get_name,
         get_resource,
         &traces,
+        &trace_tags,
+        &span_meta,
         unified_tags,
         resource,
     )?;
@@ -127,28 +142,6 @@ fn write_unified_tag<'a>(
     Ok(())
 }
 
-#[cfg(not(feature = "agent-sampling"))]
-fn get_sampling_priority(_span: &SpanData) -> f64 {
-    1.0
-}
-
-#[cfg(feature = "agent-sampling")]
-fn get_sampling_priority(span: &SpanData) -> f64 {
-    if span.span_context.trace_state().priority_sampling_enabled() {
-        1.0
-    } else {
-        0.0
-    }
-}
-
-fn get_measuring(span: &SpanData) -> f64 {
-    if span.span_context.trace_state().measuring_enabled() {
-        1.0
-    } else {
-        0.0
-    }
-}
-
 #[allow(clippy::too_many_arguments)]
 fn encode_traces<'interner, S, N, R>(
     interner: &mut StringInterner<'interner>,
@@ -157,6 +150,8 @@ fn encode_traces<'interner, S, N, R>(
     get_name: N,
     get_resource: R,
     traces: &'interner [&[SpanData]],
+    trace_tags: &'interner [Vec<(String, String)>],
+    span_meta: &'interner [Vec<Vec<(&'static str, String)>>],
     unified_tags: &'interner UnifiedTags,
     resource: Option<&'interner Resource>,
 ) -> Result<Vec<u8>, Error>
@@ -168,10 +163,10 @@ where
     let mut encoded = Vec::new();
     rmp::encode::write_array_len(&mut encoded, traces.len() as u32)?;
 
-    for trace in traces.iter() {
+    for ((trace, trace_tags), span_meta) in traces.iter().zip(trace_tags).zip(span_meta) {
         rmp::encode::write_array_len(&mut encoded, trace.len() as u32)?;
 
-        for span in trace.iter() {
+        for (span, span_meta) in trace.iter().zip(span_meta) {
             // Safe until the year 2262 when Datadog will need to change their API
             let start = span
                 .start_time
@@ -228,7 +223,10 @@ where
 
             rmp::encode::write_map_len(
                 &mut encoded,
-                (span.attributes.len() + resource.map(|r| r.len()).unwrap_or(0)) as u32
+                (span.attributes.len()
+                    + resource.map(|r| r.len()).unwrap_or(0)
+                    + trace_tags.len()
+                    + span_meta.len()) as u32
                     + unified_tags.compute_attribute_size()
                     + GIT_META_TAGS_COUNT,
             )?;
@@ -246,6 +244,16 @@ where
                 rmp::encode::write_u32(&mut encoded, interner.intern_value(&kv.value))?;
             }
 
+            for (key, value) in trace_tags {
+                rmp::encode::write_u32(&mut encoded, interner.intern(key))?;
+                rmp::encode::write_u32(&mut encoded, interner.intern(value))?;
+            }
+
+            for (key, value) in span_meta {
+                rmp::encode::write_u32(&mut encoded, interner.intern(key))?;
+                rmp::encode::write_u32(&mut encoded, interner.intern(value))?;
+            }
+
             if let (Some(repository_url), Some(commit_sha)) = (
                 option_env!("DD_GIT_REPOSITORY_URL"),
                 option_env!("DD_GIT_COMMIT_SHA"),
@@ -0,0 +1,298 @@
+use crate::exporter::model::{
+    get_measuring, get_sampling_priority, get_span_meta, get_trace_tags, DD_MEASURED_KEY,
+    SAMPLING_PRIORITY_KEY,
+};
+use crate::exporter::{Error, ModelConfig};
+use opentelemetry::trace::Status;
+use opentelemetry::Value;
+use opentelemetry_sdk::trace::SpanData;
+use opentelemetry_sdk::Resource;
+use std::time::SystemTime;
+
+use super::unified_tags::{UnifiedTagField, UnifiedTags};
+
+const TRACER_PAYLOAD_NUM_FIELDS: u32 = 10;
+const CHUNK_NUM_FIELDS: u32 = 5;
+const SPAN_NUM_FIELDS: u32 = 12;
+const METRICS_LEN: u32 = 2;
+
+// https://github.com/DataDog/datadog-agent/blob/7.52.0/pkg/trace/traceutil/span.go#L22
+const ORIGIN_KEY: &str = "_dd.origin";
+const CONTAINER_ID_KEY: &str = "container.id";
+const HOSTNAME_KEY: &str = "host.name";
+
+// Protocol documentation sourced from https://github.com/DataDog/datadog-agent/blob/7.52.0/pkg/proto/datadog/trace/tracer_payload.proto
+//
+// The payload is a single TracerPayload map, keyed by field name:
+//
+// 	container_id      (string)  ID of the container the tracer runs in, from `container.id`
+// 	language_name     (string)
+// 	language_version  (string)
+// 	tracer_version    (string)
+// 	runtime_id        (string)
+// 	chunks            (array)   one TraceChunk per trace
+// 	tags              (map[string]string)
+// 	env               (string)  unified service tagging env
+// 	hostname          (string)  from `host.name`
+// 	app_version       (string)  unified service tagging version
+//
+// A TraceChunk is a map with `priority` (int32), `origin` (string), `spans` (array),
+// `tags` (map[string]string) and `dropped_trace` (bool). Spans are maps with the same keys
+// as in v0.3, i.e. strings instead of v0.5's indices into a string table.
+//
+pub(crate) fn encode<S, N, R>(
+    model_config: &ModelConfig,
+    traces: Vec<&[SpanData]>,
+    get_service_name: S,
+    get_name: N,
+    get_resource: R,
+    unified_tags: &UnifiedTags,
+    resource: Option<&Resource>,
+) -> Result<Vec<u8>, Error>
+where
+    for<'a> S: Fn(&'a SpanData, &'a ModelConfig) -> &'a str,
+    for<'a> N: Fn(&'a SpanData, &'a ModelConfig) -> &'a str,
+    for<'a> R: Fn(&'a SpanData, &'a ModelConfig) -> &'a str,
+{
+    let resource_str = |key: &'static str| {
+        resource
+            .and_then(|r| r.get(&key.into()))
+            .map(|value| value.as_str().into_owned())
+            .unwrap_or_default()
+    };
+
+    let mut encoded = Vec::with_capacity(traces.len() * 512);
+    rmp::encode::write_map_len(&mut encoded, TRACER_PAYLOAD_NUM_FIELDS)?;
+    write_str_field(
+        &mut encoded,
+        "container_id",
+        &resource_str(CONTAINER_ID_KEY),
+    )?;
+    write_str_field(&mut encoded, "language_name", "rust")?;
+    write_str_field(&mut encoded, "language_version", "")?;
+    write_str_field(&mut encoded, "tracer_version", env!("CARGO_PKG_VERSION"))?;
+    write_str_field(&mut encoded, "runtime_id", "")?;
+
+    rmp::encode::write_str(&mut encoded, "chunks")?;
+    rmp::encode::write_array_len(&mut encoded, traces.len() as u32)?;
+    for trace in traces {
+        encode_chunk(
+            &mut encoded,
+            model_config,
+            trace,
+            &get_service_name,
+            &get_name,
+            &get_resource,
+            unified_tags,
+            resource,
+        )?;
+    }
+
+    rmp::encode::write_str(&mut encoded, "tags")?;
+    rmp::encode::write_map_len(&mut encoded, 0)?;
+    write_str_field(
+        &mut encoded,
+        "env",
+        unified_tags.env.value.as_deref().unwrap_or_default(),
+    )?;
+    write_str_field(&mut encoded, "hostname", &resource_str(HOSTNAME_KEY))?;
+    write_str_field(
+        &mut encoded,
+        "app_version",
+        unified_tags.version.value.as_deref().unwrap_or_default(),
+    )?;
+
+    Ok(encoded)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn encode_chunk<S, N, R>(
+    encoded: &mut Vec<u8>,
+    model_config: &ModelConfig,
+    trace: &[SpanData],
+    get_service_name: &S,
+    get_name: &N,
+    get_resource: &R,
+    unified_tags: &UnifiedTags,
+    resource: Option<&Resource>,
+) -> Result<(), Error>
+where
+    for<'a> S: Fn(&'a SpanData, &'a ModelConfig) -> &'a str,
+    for<'a> N: Fn(&'a SpanData, &'a ModelConfig) -> &'a str,
+    for<'a> R: Fn(&'a SpanData, &'a ModelConfig) -> &'a str,
+{
+    // All spans of a trace share the decision of its root
+    let priority = trace.first().map(get_sampling_priority).unwrap_or(1.0) as i32;
+    let origin = trace
+        .iter()
+        .find_map(|span| find_attribute(span, ORIGIN_KEY))
+        .map(|value| value.as_str())
+        .unwrap_or_default();
+
+    rmp::encode::write_map_len(encoded, CHUNK_NUM_FIELDS)?;
+    rmp::encode::write_str(encoded, "priority")?;
+    rmp::encode::write_i32(encoded, priority)?;
+    write_str_field(encoded, "origin", &origin)?;
+
+    let trace_tags = get_trace_tags(trace);
+    rmp::encode::write_str(encoded, "spans")?;
+    rmp::encode::write_array_len(encoded, trace.len() as u32)?;
+    for span in trace {
+        encode_span(
+            encoded,
+            model_config,
+            span,
+            &trace_tags,
+            get_service_name,
+            get_name,
+            get_resource,
+            unified_tags,
+            resource,
+        )?;
+    }
+
+    rmp::encode::write_str(encoded, "tags")?;
+    rmp::encode::write_map_len(encoded, 0)?;
+    rmp::encode::write_str(encoded, "dropped_trace")?;
+    rmp::encode::write_bool(encoded, false).map_err(|_| Error::MessagePackError)?;
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn encode_span<S, N, R>(
+    encoded: &mut Vec<u8>,
+    model_config: &ModelConfig,
+    span: &SpanData,
+    trace_tags: &[(String, String)],
+    get_service_name: &S,
+    get_name: &N,
+    get_resource: &R,
+    unified_tags: &UnifiedTags,
+    resource: Option<&Resource>,
+) -> Result<(), Error>
+where
+    for<'a> S: Fn(&'a SpanData, &'a ModelConfig) -> &'a str,
+    for<'a> N: Fn(&'a SpanData, &'a ModelConfig) -> &'a str,
+    for<'a> R: Fn(&'a SpanData, &'a ModelConfig) -> &'a str,
+{
+    // Safe until the year 2262 when Datadog will need to change their API
+    let start = span
+        .start_time
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_nanos() as i64;
+
+    let duration = span
+        .end_time
+        .duration_since(span.start_time)
+        .map(|x| x.as_nanos() as i64)
+        .unwrap_or(0);
+
+    let span_type = find_attribute(span, "span.type")
+        .map(|value| value.as_str())
+        .unwrap_or_default();
+
+    rmp::encode::write_map_len(encoded, SPAN_NUM_FIELDS)?;
+    // Datadog span name is OpenTelemetry component name - see module docs for more information
+    write_str_field(encoded, "service", get_service_name(span, model_config))?;
+    write_str_field(encoded, "name", get_name(span, model_config))?;
+    write_str_field(encoded, "resource", get_resource(span, model_config))?;
+
+    rmp::encode::write_str(encoded, "trace_id")?;
+    rmp::encode::write_u64(
+        encoded,
+        u128::from_be_bytes(span.span_context.trace_id().to_bytes()) as u64,
+    )?;
+    rmp::encode::write_str(encoded, "span_id")?;
+    rmp::encode::write_u64(
+        encoded,
+        u64::from_be_bytes(span.span_context.span_id().to_bytes()),
+    )?;
+    rmp::encode::write_str(encoded, "parent_id")?;
+    rmp::encode::write_u64(encoded, u64::from_be_bytes(span.parent_span_id.to_bytes()))?;
+    rmp::encode::write_str(encoded, "start")?;
+    rmp::encode::write_i64(encoded, start)?;
+    rmp::encode::write_str(encoded, "duration")?;
+    rmp::encode::write_i64(encoded, duration)?;
+    rmp::encode::write_str(encoded, "error")?;
+    rmp::encode::write_i32(
+        encoded,
+        match span.status {
+            Status::Error { .. } => 1,
+            _ => 0,
+        },
+    )?;
+
+    let git_tags = match (
+        option_env!("DD_GIT_REPOSITORY_URL"),
+        option_env!("DD_GIT_COMMIT_SHA"),
+    ) {
+        (Some(repository_url), Some(commit_sha)) => vec![
+            ("git.repository_url", repository_url),
+            ("git.commit.sha", commit_sha),
+        ],
+        _ => vec![],
+    };
+
+    let span_meta = get_span_meta(span, resource);
+    rmp::encode::write_str(encoded, "meta")?;
+    rmp::encode::write_map_len(
+        encoded,
+        (span.attributes.len()
+            + resource.map(|r| r.len()).unwrap_or(0)
+            + trace_tags.len()
+            + span_meta.len()
+            + git_tags.len()) as u32
+            + unified_tags.compute_attribute_size(),
+    )?;
+    if let Some(resource) = resource {
+        for (key, value) in resource.iter() {
+            write_str_field(encoded, key.as_str(), &value.as_str())?;
+        }
+    }
+    write_unified_tag(encoded, &unified_tags.service)?;
+    write_unified_tag(encoded, &unified_tags.env)?;
+    write_unified_tag(encoded, &unified_tags.version)?;
+    for kv in span.attributes.iter() {
+        write_str_field(encoded, kv.key.as_str(), &kv.value.as_str())?;
+    }
+    for (key, value) in trace_tags {
+        write_str_field(encoded, key, value)?;
+    }
+    for (key, value) in span_meta {
+        write_str_field(encoded, key, &value)?;
+    }
+    for (key, value) in git_tags {
+        write_str_field(encoded, key, value)?;
+    }
+
+    rmp::encode::write_str(encoded, "metrics")?;
+    rmp::encode::write_map_len(encoded, METRICS_LEN)?;
+    rmp::encode::write_str(encoded, SAMPLING_PRIORITY_KEY)?;
+    rmp::encode::write_f64(encoded, get_sampling_priority(span))?;
+    rmp::encode::write_str(encoded, DD_MEASURED_KEY)?;
+    rmp::encode::write_f64(encoded, get_measuring(span))?;
+
+    write_str_field(encoded, "type", &span_type)?;
+    Ok(())
+}
+
+fn find_attribute<'a>(span: &'a SpanData, key: &str) -> Option<&'a Value> {
+    span.attributes
+        .iter()
+        .find(|kv| kv.key.as_str() == key)
+        .map(|kv| &kv.value)
+}
+
+fn write_unified_tag(encoded: &mut Vec<u8>, tag: &UnifiedTagField) -> Result<(), Error> {
+    if let Some(tag_value) = &tag.value {
+        write_str_field(encoded, tag.get_tag_name(), tag_value)?;
+    }
+    Ok(())
+}
+
+fn write_str_field(encoded: &mut Vec<u8>, key: &str, value: &str) -> Result<(), Error> {
+    rmp::encode::write_str(encoded, key)?;
+    rmp::encode::write_str(encoded, value)?;
+    Ok(())
+}
@@ -0,0 +1,432 @@
+mod sketch;
+
+use self::sketch::LatencySketch;
+use crate::exporter::model::get_measuring;
+use crate::exporter::model::unified_tags::UnifiedTags;
+use crate::exporter::{Error, Mapping, ModelConfig};
+use http::Uri;
+use opentelemetry::trace::{SpanId, SpanKind, Status};
+use opentelemetry::{Key, Value};
+use opentelemetry_sdk::trace::SpanData;
+use opentelemetry_sdk::Resource;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+// Same resolution as the agent and the other Datadog tracers
+const BUCKET_SIZE: Duration = Duration::from_secs(10);
+
+const CLIENT_STATS_PAYLOAD_NUM_FIELDS: u32 = 9;
+const BUCKET_NUM_FIELDS: u32 = 3;
+const GROUPED_STATS_NUM_FIELDS: u32 = 13;
+
+const HTTP_STATUS_CODE_KEYS: [&str; 2] = ["http.response.status_code", "http.status_code"];
+
+/// Dimensions stats are aggregated by
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct AggregationKey {
+    service: String,
+    name: String,
+    resource: String,
+    span_type: String,
+    http_status_code: u32,
+    top_level: bool,
+}
+
+#[derive(Debug)]
+struct GroupedStats {
+    hits: u64,
+    top_level_hits: u64,
+    errors: u64,
+    duration: u64,
+    ok_summary: LatencySketch,
+    error_summary: LatencySketch,
+}
+
+impl GroupedStats {
+    fn new() -> Self {
+        GroupedStats {
+            hits: 0,
+            top_level_hits: 0,
+            errors: 0,
+            duration: 0,
+            ok_summary: LatencySketch::new(),
+            error_summary: LatencySketch::new(),
+        }
+    }
+}
+
+type Bucket = HashMap<AggregationKey, GroupedStats>;
+
+/// Aggregates spans into the APM stats the agent would otherwise compute itself.
+///
+/// Spans are counted in 10 second buckets by end time, which are sent to the agent's
+/// `/v0.6/stats` endpoint once they are over. Computing stats before the agent drops traces
+/// keeps hit counts, error rates and latencies accurate, as long as the SDK sampler records
+/// every span.
+///
+/// Like in the agent, only top-level spans (roots and service entry points), measured spans
+/// and spans of a non internal kind are counted.
+pub(crate) struct StatsConcentrator {
+    request_url: Uri,
+    state: Mutex<State>,
+}
+
+struct State {
+    // Keyed by bucket start, in nanoseconds since the epoch
+    buckets: BTreeMap<u64, Bucket>,
+    // Start of the oldest bucket not flushed yet, spans ending before it are counted in it
+    oldest_start: u64,
+    sequence: u64,
+}
+
+impl StatsConcentrator {
+    pub(crate) fn new(request_url: Uri) -> Self {
+        StatsConcentrator {
+            request_url,
+            state: Mutex::new(State {
+                buckets: BTreeMap::new(),
+                oldest_start: bucket_start(SystemTime::now()),
+                sequence: 0,
+            }),
+        }
+    }
+
+    pub(crate) fn request_url(&self) -> &Uri {
+        &self.request_url
+    }
+
+    /// Count the spans of `traces`, each slice holding the spans of one trace
+    pub(crate) fn add(
+        &self,
+        traces: &[&[SpanData]],
+        model_config: &ModelConfig,
+        mapping: &Mapping,
+    ) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        for trace in traces {
+            let services: HashMap<_, _> = trace
+                .iter()
+                .map(|span| {
+                    (
+                        span.span_context.span_id(),
+                        mapping.service_name(span, model_config),
+                    )
+                })
+                .collect();
+
+            for span in trace.iter() {
+                let service = mapping.service_name(span, model_config);
+                // Roots and children of remote spans enter a service. A local parent missing from
+                // the batch was exported before, and is taken to be of the same service.
+                let top_level = span.parent_span_id == SpanId::INVALID
+                    || span.parent_span_is_remote
+                    || services
+                        .get(&span.parent_span_id)
+                        .is_some_and(|parent_service| *parent_service != service);
+                if !top_level && get_measuring(span) == 0.0 && span.span_kind == SpanKind::Internal
+                {
+                    continue;
+                }
+
+                let key = AggregationKey {
+                    service: service.to_string(),
+                    name: mapping.name(span, model_config).to_string(),
+                    resource: mapping.resource(span, model_config).to_string(),
+                    span_type: find_attribute(span, "span.type")
+                        .map(|value| value.as_str().into_owned())
+                        .unwrap_or_default(),
+                    http_status_code: http_status_code(span),
+                    top_level,
+                };
+                let start = bucket_start(span.end_time).max(state.oldest_start);
+                let stats = state
+                    .buckets
+                    .entry(start)
+                    .or_default()
+                    .entry(key)
+                    .or_insert_with(GroupedStats::new);
+
+                let duration = span
+                    .end_time
+                    .duration_since(span.start_time)
+                    .map(|x| x.as_nanos() as u64)
+                    .unwrap_or(0);
+                stats.hits += 1;
+                stats.duration += duration;
+                if top_level {
+                    stats.top_level_hits += 1;
+                }
+                if let Status::Error { .. } = span.status {
+                    stats.errors += 1;
+                    stats.error_summary.add(duration as f64);
+                } else {
+                    stats.ok_summary.add(duration as f64);
+                }
+            }
+        }
+    }
+
+    /// Encode the buckets that are over at `now`, or all of them if `force` is set, into a
+    /// stats payload, or return `None` if there are none
+    pub(crate) fn flush(
+        &self,
+        now: SystemTime,
+        force: bool,
+        model_config: &ModelConfig,
+        unified_tags: &UnifiedTags,
+        resource: Option<&Resource>,
+    ) -> Result<Option<Vec<u8>>, Error> {
+        let current_start = bucket_start(now);
+        let (buckets, sequence) = {
+            let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+            let open = if force {
+                BTreeMap::new()
+            } else {
+                state.buckets.split_off(&current_start)
+            };
+            let buckets = std::mem::replace(&mut state.buckets, open);
+            state.oldest_start = state.oldest_start.max(current_start);
+            if buckets.is_empty() {
+                return Ok(None);
+            }
+            state.sequence += 1;
+            (buckets, state.sequence)
+        };
+
+        let hostname = resource
+            .and_then(|r| r.get(&Key::from_static_str("host.name")))
+            .map(|value| value.as_str().into_owned())
+            .unwrap_or_default();
+
+        let mut encoded = Vec::with_capacity(buckets.len() * 256);
+        rmp::encode::write_map_len(&mut encoded, CLIENT_STATS_PAYLOAD_NUM_FIELDS)?;
+        write_str_field(&mut encoded, "Hostname", &hostname)?;
+        write_str_field(
+            &mut encoded,
+            "Env",
+            unified_tags.env.value.as_deref().unwrap_or_default(),
+        )?;
+        write_str_field(
+            &mut encoded,
+            "Version",
+            unified_tags.version.value.as_deref().unwrap_or_default(),
+        )?;
+        rmp::encode::write_str(&mut encoded, "Stats")?;
+        rmp::encode::write_array_len(&mut encoded, buckets.len() as u32)?;
+        for (start, bucket) in buckets {
+            encode_bucket(&mut encoded, start, bucket)?;
+        }
+        write_str_field(&mut encoded, "Lang", "rust")?;
+        write_str_field(&mut encoded, "TracerVersion", env!("CARGO_PKG_VERSION"))?;
+        write_str_field(&mut encoded, "RuntimeID", "")?;
+        rmp::encode::write_str(&mut encoded, "Sequence")?;
+        rmp::encode::write_uint(&mut encoded, sequence)?;
+        write_str_field(&mut encoded, "Service", &model_config.service_name)?;
+
+        Ok(Some(encoded))
+    }
+}
+
+// Protocol documentation sourced from https://github.com/DataDog/datadog-agent/blob/7.52.0/pkg/proto/datadog/trace/stats.proto
+fn encode_bucket(encoded: &mut Vec<u8>, start: u64, bucket: Bucket) -> Result<(), Error> {
+    rmp::encode::write_map_len(encoded, BUCKET_NUM_FIELDS)?;
+    rmp::encode::write_str(encoded, "Start")?;
+    rmp::encode::write_uint(encoded, start)?;
+    rmp::encode::write_str(encoded, "Duration")?;
+    rmp::encode::write_uint(encoded, BUCKET_SIZE.as_nanos() as u64)?;
+    rmp::encode::write_str(encoded, "Stats")?;
+    rmp::encode::write_array_len(encoded, bucket.len() as u32)?;
+    for (key, stats) in bucket {
+        rmp::encode::write_map_len(encoded, GROUPED_STATS_NUM_FIELDS)?;
+        write_str_field(encoded, "Service", &key.service)?;
+        write_str_field(encoded, "Name", &key.name)?;
+        write_str_field(encoded, "Resource", &key.resource)?;
+        rmp::encode::write_str(encoded, "HTTPStatusCode")?;
+        rmp::encode::write_uint(encoded, key.http_status_code.into())?;
+        write_str_field(encoded, "Type", &key.span_type)?;
+        write_str_field(encoded, "DBType", "")?;
+        rmp::encode::write_str(encoded, "Hits")?;
+        rmp::encode::write_uint(encoded, stats.hits)?;
+        rmp::encode::write_str(encoded, "Errors")?;
+        rmp::encode::write_uint(encoded, stats.errors)?;
+        rmp::encode::write_str(encoded, "Duration")?;
+        rmp::encode::write_uint(encoded, stats.duration)?;
+        rmp::encode::write_str(encoded, "OkSummary")?;
+        rmp::encode::write_bin(encoded, &stats.ok_summary.encode())?;
+        rmp::encode::write_str(encoded, "ErrorSummary")?;
+        rmp::encode::write_bin(encoded, &stats.error_summary.encode())?;
+        rmp::encode::write_str(encoded, "Synthetics")?;
+        rmp::encode::write_bool(encoded, false).map_err(|_| Error::MessagePackError)?;
+        rmp::encode::write_str(encoded, "TopLevelHits")?;
+        rmp::encode::write_uint(encoded, stats.top_level_hits)?;
+    }
+    Ok(())
+}
+
+fn bucket_start(time: SystemTime) -> u64 {
+    let nanos = time
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|x| x.as_nanos() as u64)
+        .unwrap_or(0);
+    nanos - nanos % BUCKET_SIZE.as_nanos() as u64
+}
+
+fn find_attribute<'a>(span: &'a SpanData, key: &str) -> Option<&'a Value> {
+    span.attributes
+        .iter()
+        .find(|kv| kv.key.as_str() == key)
+        .map(|kv| &kv.value)
+}
+
+fn http_status_code(span: &SpanData) -> u32 {
+    HTTP_STATUS_CODE_KEYS
+        .iter()
+        .find_map(|key| match find_attribute(span, key)? {
+            Value::I64(code) => u32::try_from(*code).ok(),
+            Value::String(code) => code.as_str().parse().ok(),
+            _ => None,
+        })
+        .unwrap_or(0)
+}
+
+fn write_str_field(encoded: &mut Vec<u8>, key: &str, value: &str) -> Result<(), Error> {
+    rmp::encode::write_str(encoded, key)?;
+    rmp::encode::write_str(encoded, value)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exporter::model::tests::get_span;
+    use opentelemetry::KeyValue;
+
+    fn flush(concentrator: &StatsConcentrator, now: SystemTime) -> Option<rmpv::Value> {
+        let model_config = ModelConfig {
+            service_name: "service_name".to_string(),
+        };
+        concentrator
+            .flush(now, false, &model_config, &UnifiedTags::new(), None)
+            .unwrap()
+            .map(|payload| rmpv::decode::read_value(&mut &payload[..]).unwrap())
+    }
+
+    #[test]
+    fn test_aggregation() {
+        let concentrator = StatsConcentrator::new(Uri::from_static("http://localhost/v0.6/stats"));
+        let model_config = ModelConfig {
+            service_name: "service_name".to_string(),
+        };
+        let now = SystemTime::now();
+
+        let root = get_span(1, 0, 1);
+        let mut failed = get_span(2, 0, 2);
+        failed.status = Status::error("boom");
+        failed
+            .attributes
+            .push(KeyValue::new("http.response.status_code", 500));
+        // Internal child of a span of the same service, not counted
+        let child = {
+            let mut child = get_span(1, 1, 3);
+            child.span_kind = SpanKind::Internal;
+            child
+        };
+        let client_child = get_span(1, 1, 4);
+        let batch = [root, child, client_child, failed];
+        concentrator.add(
+            &[&batch[..3], &batch[3..]],
+            &model_config,
+            &Mapping::empty(),
+        );
+
+        // Nothing is sent until the bucket is over
+        assert!(flush(&concentrator, now).is_none());
+
+        let payload = flush(&concentrator, now + BUCKET_SIZE).unwrap();
+        assert_eq!(payload["Service"].as_str(), Some("service_name"));
+        assert_eq!(payload["Sequence"].as_u64(), Some(1));
+        let buckets = payload["Stats"].as_array().unwrap();
+        assert_eq!(buckets.len(), 1);
+        assert_eq!(buckets[0]["Start"].as_u64(), Some(bucket_start(now)));
+
+        let mut groups = buckets[0]["Stats"].as_array().unwrap().clone();
+        groups.sort_by_key(|group| group["HTTPStatusCode"].as_u64());
+        assert_eq!(groups.len(), 3);
+        let hits = |group: &rmpv::Value| {
+            (
+                group["Hits"].as_u64().unwrap(),
+                group["TopLevelHits"].as_u64().unwrap(),
+                group["Errors"].as_u64().unwrap(),
+            )
+        };
+        let (top_level, client): (Vec<_>, Vec<_>) = groups[..2]
+            .iter()
+            .partition(|group| group["TopLevelHits"].as_u64() == Some(1));
+        assert_eq!(hits(top_level[0]), (1, 1, 0));
+        assert_eq!(hits(client[0]), (1, 0, 0));
+        assert_eq!(client[0]["Duration"].as_u64(), Some(1_000_000_000));
+        assert_eq!(groups[2]["HTTPStatusCode"].as_u64(), Some(500));
+        assert_eq!(hits(&groups[2]), (1, 1, 1));
+        assert_eq!(groups[2]["Resource"].as_str(), Some("resource"));
+        assert_eq!(groups[2]["Type"].as_str(), Some("web"));
+
+        // Flushed buckets are not sent again
+        assert!(flush(&concentrator, now + BUCKET_SIZE).is_none());
+    }
+
+    #[test]
+    fn test_top_level_across_batches() {
+        let concentrator = StatsConcentrator::new(Uri::from_static("http://localhost/v0.6/stats"));
+        let now = SystemTime::now();
+
+        // The parent of both spans is not in the batch, only the remote one enters the service
+        let local_child = get_span(1, 1, 2);
+        let mut remote_child = get_span(2, 3, 4);
+        remote_child.parent_span_is_remote = true;
+        remote_child.name = "remote".into();
+        concentrator.add(
+            &[&[local_child], &[remote_child]],
+            &ModelConfig::default(),
+            &Mapping::empty(),
+        );
+
+        // Forced flushes send the buckets that are still open too
+        let payload = concentrator
+            .flush(
+                now,
+                true,
+                &ModelConfig::default(),
+                &UnifiedTags::new(),
+                None,
+            )
+            .unwrap()
+            .map(|payload| rmpv::decode::read_value(&mut &payload[..]).unwrap())
+            .unwrap();
+        let groups = payload["Stats"][0]["Stats"].as_array().unwrap();
+        let top_level_hits = |resource: &str| {
+            groups
+                .iter()
+                .find(|group| group["Resource"].as_str() == Some(resource))
+                .and_then(|group| group["TopLevelHits"].as_u64())
+        };
+        assert_eq!(top_level_hits("resource"), Some(0));
+        assert_eq!(top_level_hits("remote"), Some(1));
+    }
+
+    #[test]
+    fn test_late_spans_count_in_oldest_bucket() {
+        let concentrator = StatsConcentrator::new(Uri::from_static("http://localhost/v0.6/stats"));
+        let model_config = ModelConfig::default();
+        let now = SystemTime::now();
+        assert!(flush(&concentrator, now + 2 * BUCKET_SIZE).is_none());
+
+        // Ends at the epoch, long before the oldest bucket still open
+        concentrator.add(&[&[get_span(1, 0, 1)]], &model_config, &Mapping::empty());
+        let state = concentrator.state.lock().unwrap();
+        assert_eq!(
+            state.buckets.keys().copied().collect::<Vec<_>>(),
+            [bucket_start(now + 2 * BUCKET_SIZE)]
+        );
+    }
+}
@@ -0,0 +1,148 @@
+use std::collections::BTreeMap;
+
+// Same accuracy as the sketches of the other Datadog tracers
+// https://github.com/DataDog/dd-trace-go/blob/v1.60.0/ddtrace/tracer/stats.go#L19
+const RELATIVE_ACCURACY: f64 = 0.01;
+
+/// Latency distribution in the DDSketch format the agent expects in stats payloads.
+///
+/// Values are mapped to logarithmic bins so that any quantile read back from the sketch is
+/// within 1% of the exact value.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct LatencySketch {
+    bins: BTreeMap<i32, f64>,
+    zero_count: f64,
+}
+
+impl LatencySketch {
+    pub(crate) fn new() -> Self {
+        LatencySketch {
+            bins: BTreeMap::new(),
+            zero_count: 0.0,
+        }
+    }
+
+    /// Add a duration in nanoseconds
+    pub(crate) fn add(&mut self, value: f64) {
+        if value <= 0.0 {
+            self.zero_count += 1.0;
+        } else {
+            *self.bins.entry(index(value)).or_default() += 1.0;
+        }
+    }
+
+    /// Encode as a `DDSketch` protobuf message
+    /// https://github.com/DataDog/sketches-go/blob/v1.4.4/ddsketch/pb/ddsketch.proto
+    pub(crate) fn encode(&self) -> Vec<u8> {
+        let mut mapping = Vec::with_capacity(9);
+        // indexOffset 0 and interpolation NONE are the proto defaults
+        write_double_field(&mut mapping, 1, gamma());
+
+        let mut store = Vec::with_capacity(self.bins.len() * 12);
+        for (&index, &count) in &self.bins {
+            // binCounts = 1, a map<sint32, double>
+            let mut entry = Vec::with_capacity(16);
+            write_tag(&mut entry, 1, WIRE_VARINT);
+            write_varint(&mut entry, zigzag(index));
+            write_double_field(&mut entry, 2, count);
+            write_bytes_field(&mut store, 1, &entry);
+        }
+
+        let mut sketch = Vec::with_capacity(mapping.len() + store.len() + 16);
+        write_bytes_field(&mut sketch, 1, &mapping);
+        if !store.is_empty() {
+            write_bytes_field(&mut sketch, 2, &store);
+        }
+        if self.zero_count > 0.0 {
+            write_double_field(&mut sketch, 4, self.zero_count);
+        }
+        sketch
+    }
+}
+
+fn gamma() -> f64 {
+    (1.0 + RELATIVE_ACCURACY) / (1.0 - RELATIVE_ACCURACY)
+}
+
+// Logarithmic mapping, bin `i` holds the values in (gamma^(i-1), gamma^i]
+fn index(value: f64) -> i32 {
+    (value.ln() / gamma().ln()).ceil() as i32
+}
+
+const WIRE_VARINT: u8 = 0;
+const WIRE_FIXED64: u8 = 1;
+const WIRE_LENGTH_DELIMITED: u8 = 2;
+
+fn write_tag(buf: &mut Vec<u8>, field: u8, wire_type: u8) {
+    buf.push(field << 3 | wire_type);
+}
+
+fn write_varint(buf: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buf.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    buf.push(value as u8);
+}
+
+fn write_double_field(buf: &mut Vec<u8>, field: u8, value: f64) {
+    write_tag(buf, field, WIRE_FIXED64);
+    buf.extend_from_slice(&value.to_le_bytes());
+}
+
+fn write_bytes_field(buf: &mut Vec<u8>, field: u8, bytes: &[u8]) {
+    write_tag(buf, field, WIRE_LENGTH_DELIMITED);
+    write_varint(buf, bytes.len() as u64);
+    buf.extend_from_slice(bytes);
+}
+
+fn zigzag(value: i32) -> u64 {
+    ((value << 1) ^ (value >> 31)) as u32 as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_index_accuracy() {
+        for value in [1.0, 999.0, 1_000.0, 12_345.0, 1e9, 3.6e12] {
+            let index = index(value);
+            let lower = gamma().powi(index - 1);
+            let upper = gamma().powi(index);
+            assert!(lower < value && value <= upper * (1.0 + 1e-9), "{value}");
+            // Reading a bin back as its midpoint stays within the promised accuracy
+            let estimate = 2.0 * lower * upper / (lower + upper);
+            assert!((estimate - value).abs() / value <= RELATIVE_ACCURACY);
+        }
+    }
+
+    #[test]
+    fn test_encode() {
+        let mut sketch = LatencySketch::new();
+        assert_eq!(sketch.encode()[..2], [0x0a, 9]);
+        assert_eq!(sketch.encode().len(), 11);
+
+        sketch.add(1.0);
+        sketch.add(1.0);
+        sketch.add(0.0);
+        let encoded = sketch.encode();
+        let mut expected = vec![0x0a, 9, 0x09];
+        expected.extend_from_slice(&gamma().to_le_bytes());
+        // positiveValues { binCounts { key: 0, value: 2.0 } }
+        expected.extend_from_slice(&[0x12, 13, 0x0a, 11, 0x08, 0, 0x11]);
+        expected.extend_from_slice(&2.0f64.to_le_bytes());
+        // zeroCount: 1.0
+        expected.push(0x21);
+        expected.extend_from_slice(&1.0f64.to_le_bytes());
+        assert_eq!(encoded, expected);
+    }
+
+    #[test]
+    fn test_zigzag() {
+        assert_eq!(zigzag(0), 0);
+        assert_eq!(zigzag(-1), 1);
+        assert_eq!(zigzag(1), 2);
+        assert_eq!(zigzag(i32::MIN), u32::MAX as u64);
+    }
+}
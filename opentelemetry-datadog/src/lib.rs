@@ -32,6 +32,8 @@
 //! For standard values see [here](https://github.com/DataDog/dd-trace-go/blob/ecb0b805ef25b00888a2fb62d465a5aa95e7301e/ddtrace/ext/app_types.go#L31).
 //!
 //! If the default mapping is not fit for your use case, you may change some of them by providing [`FieldMappingFn`]s in pipeline.
+//! Alternatively, [`DatadogPipelineBuilder::with_otlp_mapping`] derives names, resources and types
+//! from the semantic conventions the way the Datadog agent does for spans it receives over OTLP.
 //!
 //! ## Performance
 //!
@@ -149,12 +151,18 @@
 //!
 //! ```
 
+#[cfg(feature = "metrics")]
+mod dogstatsd;
 mod exporter;
 
+#[cfg(feature = "metrics")]
+pub use dogstatsd::{DogStatsDExporter, DogStatsDExporterBuilder};
 pub use exporter::{
     new_pipeline, ApiVersion, DatadogExporter, DatadogPipelineBuilder, Error, FieldMappingFn,
     ModelConfig,
 };
+#[cfg(feature = "agent-sampling")]
+pub use exporter::{AgentSamplingRates, DatadogSampler};
 pub use propagator::{DatadogPropagator, DatadogTraceState, DatadogTraceStateBuilder};
 
 mod propagator {
@@ -168,23 +176,34 @@ mod propagator {
     const DATADOG_TRACE_ID_HEADER: &str = "x-datadog-trace-id";
     const DATADOG_PARENT_ID_HEADER: &str = "x-datadog-parent-id";
     const DATADOG_SAMPLING_PRIORITY_HEADER: &str = "x-datadog-sampling-priority";
+    const DATADOG_TAGS_HEADER: &str = "x-datadog-tags";
+
+    const PROPAGATION_TAG_PREFIX: &str = "_dd.p.";
+    pub(crate) const TRACE_ID_UPPER_TAG: &str = "_dd.p.tid";
+    // Same default limit as the other Datadog tracers (DD_TRACE_X_DATADOG_TAGS_MAX_LENGTH)
+    const DATADOG_TAGS_MAX_LENGTH: usize = 512;
 
     const TRACE_FLAG_DEFERRED: TraceFlags = TraceFlags::new(0x02);
     #[cfg(feature = "agent-sampling")]
-    const TRACE_STATE_PRIORITY_SAMPLING: &str = "psr";
+    pub(crate) const TRACE_STATE_PRIORITY_SAMPLING: &str = "psr";
     const TRACE_STATE_MEASURE: &str = "m";
     const TRACE_STATE_TRUE_VALUE: &str = "1";
     const TRACE_STATE_FALSE_VALUE: &str = "0";
+    // Propagation tags are kept the way Datadog encodes them in W3C `tracestate`, i.e.
+    // `dd=t.dm:-4;t.usr.id:123` for `_dd.p.dm=-4,_dd.p.usr.id=123`
+    const TRACE_STATE_PROPAGATION_TAGS: &str = "dd";
+    const TRACE_STATE_TAG_PREFIX: &str = "t.";
 
     // TODO Replace this with LazyLock when MSRV is 1.80+
-    static TRACE_CONTEXT_HEADER_FIELDS: OnceLock<[String; 3]> = OnceLock::new();
+    static TRACE_CONTEXT_HEADER_FIELDS: OnceLock<[String; 4]> = OnceLock::new();
 
-    fn trace_context_header_fields() -> &'static [String; 3] {
+    fn trace_context_header_fields() -> &'static [String; 4] {
         TRACE_CONTEXT_HEADER_FIELDS.get_or_init(|| {
             [
                 DATADOG_TRACE_ID_HEADER.to_owned(),
                 DATADOG_PARENT_ID_HEADER.to_owned(),
                 DATADOG_SAMPLING_PRIORITY_HEADER.to_owned(),
+                DATADOG_TAGS_HEADER.to_owned(),
             ]
         })
     }
@@ -257,6 +276,24 @@ mod propagator {
 
         #[cfg(feature = "agent-sampling")]
         fn priority_sampling_enabled(&self) -> bool;
+
+        /// Replace the `_dd.p.*` propagation tags of the trace, e.g. `_dd.p.dm`.
+        ///
+        /// Tags that are not valid in the `x-datadog-tags` header or cannot be stored in a
+        /// trace state are dropped. The other fields of the `dd` trace state entry, such as
+        /// the sampling priority `s` or the origin `o`, are kept.
+        ///
+        /// The default implementation has no tags to store and returns the trace state of
+        /// `self` as built by `with_measuring`.
+        fn with_propagation_tags(&self, tags: &[(String, String)]) -> TraceState {
+            let _ = tags;
+            self.with_measuring(self.measuring_enabled())
+        }
+
+        /// `_dd.p.*` propagation tags of the trace. None by default.
+        fn propagation_tags(&self) -> Vec<(String, String)> {
+            Vec::new()
+        }
     }
 
     impl DatadogTraceState for TraceState {
@@ -286,6 +323,96 @@ mod propagator {
                 .map(trace_flag_to_boolean)
                 .unwrap_or_default()
         }
+
+        fn with_propagation_tags(&self, tags: &[(String, String)]) -> TraceState {
+            let existing = self.get(TRACE_STATE_PROPAGATION_TAGS).unwrap_or_default();
+            let other_fields = existing
+                .split(';')
+                .filter(|field| !field.is_empty() && !field.starts_with(TRACE_STATE_TAG_PREFIX))
+                .map(str::to_string);
+            let tags = tags
+                .iter()
+                .filter(|(key, value)| {
+                    is_valid_tag(key, value)
+                        && !key.contains([':', ';'])
+                        && !value.contains([';', '~'])
+                })
+                .filter_map(|(key, value)| {
+                    let name = key.strip_prefix(PROPAGATION_TAG_PREFIX)?;
+                    Some(format!(
+                        "{TRACE_STATE_TAG_PREFIX}{name}:{}",
+                        value.replace('=', "~")
+                    ))
+                });
+            let value = other_fields.chain(tags).collect::<Vec<_>>().join(";");
+            if value.is_empty() {
+                self.delete(TRACE_STATE_PROPAGATION_TAGS)
+            } else {
+                self.insert(TRACE_STATE_PROPAGATION_TAGS, value)
+            }
+            .unwrap_or_else(|_err| self.clone())
+        }
+
+        fn propagation_tags(&self) -> Vec<(String, String)> {
+            self.get(TRACE_STATE_PROPAGATION_TAGS)
+                .map(|value| {
+                    value
+                        .split(';')
+                        .filter_map(|tag| {
+                            let (name, value) =
+                                tag.strip_prefix(TRACE_STATE_TAG_PREFIX)?.split_once(':')?;
+                            Some((
+                                format!("{PROPAGATION_TAG_PREFIX}{name}"),
+                                value.replace('~', "="),
+                            ))
+                        })
+                        .collect()
+                })
+                .unwrap_or_default()
+        }
+    }
+
+    // Keys and values of `x-datadog-tags` are printable ASCII, keys can't contain spaces or '='
+    fn is_valid_tag(key: &str, value: &str) -> bool {
+        !key.is_empty()
+            && !value.is_empty()
+            && key
+                .bytes()
+                .all(|b| b.is_ascii_graphic() && b != b',' && b != b'=')
+            && value
+                .bytes()
+                .all(|b| (b' '..=b'~').contains(&b) && b != b',')
+    }
+
+    /// Parse the `_dd.p.*` tags of an `x-datadog-tags` header, or `None` if it is too long
+    /// or malformed, in which case none of its tags can be trusted
+    fn parse_datadog_tags(header: &str) -> Option<Vec<(String, String)>> {
+        if header.len() > DATADOG_TAGS_MAX_LENGTH {
+            return None;
+        }
+        let mut tags = Vec::new();
+        for tag in header.split(',').filter(|tag| !tag.is_empty()) {
+            let (key, value) = tag.split_once('=')?;
+            if !is_valid_tag(key, value) {
+                return None;
+            }
+            if key.starts_with(PROPAGATION_TAG_PREFIX) {
+                tags.push((key.to_string(), value.to_string()));
+            }
+        }
+        Some(tags)
+    }
+
+    // The upper 64 bits of a 128-bit trace id, as 16 lowercase hex digits
+    fn parse_trace_id_upper(value: &str) -> Option<u64> {
+        if value.len() != 16
+            || !value
+                .bytes()
+                .all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b))
+        {
+            return None;
+        }
+        u64::from_str_radix(value, 16).ok()
     }
 
     enum SamplingPriority {
@@ -382,7 +509,7 @@ mod propagator {
             &self,
             extractor: &dyn Extractor,
         ) -> Result<SpanContext, ExtractError> {
-            let trace_id =
+            let mut trace_id =
                 self.extract_trace_id(extractor.get(DATADOG_TRACE_ID_HEADER).unwrap_or(""))?;
             // If we have a trace_id but can't get the parent span, we default it to invalid instead of completely erroring
             // out so that the rest of the spans aren't completely lost
@@ -405,7 +532,23 @@ mod propagator {
                 Err(_) => TRACE_FLAG_DEFERRED,
             };
 
-            let (trace_state, trace_flags) = create_trace_state_and_flags(sampled);
+            let (mut trace_state, trace_flags) = create_trace_state_and_flags(sampled);
+
+            // Tags are optional, a bad header must not lose the trace
+            let mut tags = extractor
+                .get(DATADOG_TAGS_HEADER)
+                .and_then(parse_datadog_tags)
+                .unwrap_or_default();
+            if let Some(index) = tags.iter().position(|(key, _)| key == TRACE_ID_UPPER_TAG) {
+                let (_, upper) = tags.remove(index);
+                if let Some(upper) = parse_trace_id_upper(&upper) {
+                    let lower = u128::from_be_bytes(trace_id.to_bytes());
+                    trace_id = TraceId::from(u128::from(upper) << 64 | lower);
+                }
+            }
+            if !tags.is_empty() {
+                trace_state = trace_state.with_propagation_tags(&tags);
+            }
 
             Ok(SpanContext::new(
                 trace_id,
@@ -457,6 +600,22 @@ mod propagator {
                         (sampling_priority as i32).to_string(),
                     );
                 }
+
+                let mut tags = span_context.trace_state().propagation_tags();
+                tags.retain(|(key, _)| key != TRACE_ID_UPPER_TAG);
+                let upper = (u128::from_be_bytes(span_context.trace_id().to_bytes()) >> 64) as u64;
+                if upper != 0 {
+                    tags.push((TRACE_ID_UPPER_TAG.to_string(), format!("{upper:016x}")));
+                }
+                let header = tags
+                    .iter()
+                    .map(|(key, value)| format!("{key}={value}"))
+                    .collect::<Vec<_>>()
+                    .join(",");
+                // Like other tracers, drop all tags rather than send a header peers would reject
+                if !header.is_empty() && header.len() <= DATADOG_TAGS_MAX_LENGTH {
+                    injector.set(DATADOG_TAGS_HEADER, header);
+                }
             }
         }
 
@@ -556,6 +715,164 @@ mod propagator {
             assert!(!context.has_active_span())
         }
 
+        fn extract(headers: &[(&str, &str)]) -> SpanContext {
+            let map: HashMap<String, String> = headers
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect();
+            DatadogPropagator::default()
+                .extract(&map)
+                .span()
+                .span_context()
+                .clone()
+        }
+
+        #[test]
+        fn test_extract_128_bit_trace_id() {
+            let span_context = extract(&[
+                (DATADOG_TRACE_ID_HEADER, "1234"),
+                (DATADOG_PARENT_ID_HEADER, "12"),
+                (
+                    DATADOG_TAGS_HEADER,
+                    "_dd.p.tid=640cfd8d00000000,_dd.p.dm=-4,other=1",
+                ),
+            ]);
+            assert_eq!(
+                span_context.trace_id(),
+                TraceId::from(0x640cfd8d00000000_u128 << 64 | 1234)
+            );
+            assert_eq!(
+                span_context.trace_state().propagation_tags(),
+                [("_dd.p.dm".to_string(), "-4".to_string())]
+            );
+        }
+
+        #[test]
+        fn test_extract_bad_tags() {
+            let too_long = format!("_dd.p.dm=-4,_dd.p.long={}", "x".repeat(512));
+            for tags in [
+                "_dd.p.tid=640cfd8d00000000,_dd.p.dm",
+                "_dd.p.tid=640cfd8d00000000,_dd.p dm=-4",
+                "_dd.p.tid=640cfd8d00000000,_dd.p.dm=",
+                too_long.as_str(),
+            ] {
+                // The trace is still continued, without the tags
+                let span_context = extract(&[
+                    (DATADOG_TRACE_ID_HEADER, "1234"),
+                    (DATADOG_PARENT_ID_HEADER, "12"),
+                    (DATADOG_TAGS_HEADER, tags),
+                ]);
+                assert_eq!(span_context.trace_id(), TraceId::from(1234), "{tags}");
+                assert!(span_context.trace_state().propagation_tags().is_empty());
+            }
+
+            for tid in ["640CFD8D00000000", "640cfd8d", "640cfd8d0000000g"] {
+                let span_context = extract(&[
+                    (DATADOG_TRACE_ID_HEADER, "1234"),
+                    (DATADOG_PARENT_ID_HEADER, "12"),
+                    (DATADOG_TAGS_HEADER, &format!("_dd.p.tid={tid},_dd.p.dm=-4")),
+                ]);
+                assert_eq!(span_context.trace_id(), TraceId::from(1234), "{tid}");
+                assert_eq!(span_context.trace_state().propagation_tags().len(), 1);
+            }
+        }
+
+        #[test]
+        fn test_inject_128_bit_trace_id() {
+            let trace_state = TraceState::default().with_propagation_tags(&[
+                ("_dd.p.dm".to_string(), "-4".to_string()),
+                ("_dd.p.usr.id".to_string(), "a=b".to_string()),
+            ]);
+            let span_context = SpanContext::new(
+                TraceId::from(0x640cfd8d00000000_u128 << 64 | 1234),
+                SpanId::from(12),
+                TraceFlags::SAMPLED,
+                true,
+                trace_state,
+            );
+            let mut injector: HashMap<String, String> = HashMap::new();
+            DatadogPropagator::default().inject_context(
+                &Context::current_with_span(TestSpan(span_context.clone())),
+                &mut injector,
+            );
+
+            assert_eq!(injector[DATADOG_TRACE_ID_HEADER], "1234");
+            assert_eq!(
+                injector[DATADOG_TAGS_HEADER],
+                "_dd.p.dm=-4,_dd.p.usr.id=a=b,_dd.p.tid=640cfd8d00000000"
+            );
+
+            // Round trip
+            let extracted = extract(
+                &injector
+                    .iter()
+                    .map(|(k, v)| (k.as_str(), v.as_str()))
+                    .collect::<Vec<_>>(),
+            );
+            assert_eq!(extracted.trace_id(), span_context.trace_id());
+            assert_eq!(
+                extracted.trace_state().propagation_tags(),
+                span_context.trace_state().propagation_tags()
+            );
+
+            // Tags that can't be propagated are dropped
+            let trace_state = TraceState::default().with_propagation_tags(&[
+                ("_dd.p.dm".to_string(), "-4".to_string()),
+                ("not_propagated".to_string(), "1".to_string()),
+                ("_dd.p.list".to_string(), "a,b".to_string()),
+            ]);
+            assert_eq!(
+                trace_state.propagation_tags(),
+                [("_dd.p.dm".to_string(), "-4".to_string())]
+            );
+            assert!(trace_state
+                .with_propagation_tags(&[])
+                .propagation_tags()
+                .is_empty());
+        }
+
+        #[test]
+        fn test_propagation_tags_keep_other_dd_fields() {
+            let trace_state =
+                TraceState::from_key_value([("dd", "s:2;o:rum;t.dm:-4"), ("other", "1")])
+                    .unwrap()
+                    .with_propagation_tags(&[("_dd.p.usr.id".to_string(), "123".to_string())]);
+            assert_eq!(trace_state.get("dd"), Some("s:2;o:rum;t.usr.id:123"));
+            assert_eq!(trace_state.get("other"), Some("1"));
+
+            let trace_state = trace_state.with_propagation_tags(&[]);
+            assert_eq!(trace_state.get("dd"), Some("s:2;o:rum"));
+        }
+
+        #[test]
+        fn test_propagation_tags_default_impls() {
+            struct Measured;
+
+            impl DatadogTraceState for Measured {
+                fn with_measuring(&self, enabled: bool) -> TraceState {
+                    TraceState::default().with_measuring(enabled)
+                }
+
+                fn measuring_enabled(&self) -> bool {
+                    true
+                }
+
+                #[cfg(feature = "agent-sampling")]
+                fn with_priority_sampling(&self, _enabled: bool) -> TraceState {
+                    TraceState::default()
+                }
+
+                #[cfg(feature = "agent-sampling")]
+                fn priority_sampling_enabled(&self) -> bool {
+                    false
+                }
+            }
+
+            let tags = [("_dd.p.dm".to_string(), "-4".to_string())];
+            assert!(Measured.propagation_tags().is_empty());
+            assert!(Measured.with_propagation_tags(&tags).measuring_enabled());
+        }
+
         #[test]
         fn test_inject() {
             let propagator = DatadogPropagator::default();
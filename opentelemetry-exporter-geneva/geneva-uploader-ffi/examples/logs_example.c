@@ -7,7 +7,7 @@
  * - Encoding/compressing ResourceLogs
  * - Uploading batches synchronously with geneva_upload_batch_sync
  *
- * Note: The non-blocking callback-based mechanism has been removed.
+ * See geneva_upload_batches_async in geneva_ffi.h for the non-blocking variant.
  */
 
 #include <stdio.h>
@@ -84,6 +84,8 @@ int main(void) {
     printf("  Auth Method: %s\n", auth_method == GENEVA_AUTH_CERTIFICATE ? "Certificate" : "Managed Identity");
     if (auth_method == GENEVA_AUTH_CERTIFICATE) {
         printf("  Cert Path: %s\n", cert_path);
+    } else {
+        printf("  MSI Resource: %s\n", get_env_or_default("GENEVA_MSI_RESOURCE", "(unset)"));
     }
     printf("\n");
 
@@ -104,14 +106,15 @@ int main(void) {
         cfg.auth.cert.cert_path = cert_path;
         cfg.auth.cert.cert_password = cert_password;
     } else {
-        cfg.auth.msi.objid = NULL;
+        cfg.auth.msi.objid = getenv("GENEVA_MSI_OBJECT_ID");
+        cfg.auth.msi.resource = getenv("GENEVA_MSI_RESOURCE");
     }
 
     /* Create client */
     GenevaClientHandle* client = NULL;
     GenevaError rc = geneva_client_new(&cfg, &client);
     if (rc != GENEVA_SUCCESS || client == NULL) {
-        printf("Failed to create Geneva client (code=%d)\n", rc);
+        printf("Failed to create Geneva client (code=%d): %s\n", rc, geneva_last_error_message());
         return 1;
     }
     printf("Geneva client created.\n");
@@ -130,7 +133,7 @@ int main(void) {
     EncodedBatchesHandle* batches = NULL;
     GenevaError enc_rc = geneva_encode_and_compress_logs(client, data, data_len, &batches);
     if (enc_rc != GENEVA_SUCCESS || batches == NULL) {
-        printf("Encode/compress failed (code=%d)\n", enc_rc);
+        printf("Encode/compress failed (code=%d): %s\n", enc_rc, geneva_last_error_message());
         geneva_free_buffer(data, data_len);
         geneva_client_free(client);
         return 1;
@@ -145,7 +148,7 @@ int main(void) {
         GenevaError r = geneva_upload_batch_sync(client, batches, i);
         if (r != GENEVA_SUCCESS) {
             first_err = r;
-            printf("Batch %zu upload failed with error %d\n", i, r);
+            printf("Batch %zu upload failed with error %d: %s\n", i, r, geneva_last_error_message());
             break;
         }
     }
@@ -5,7 +5,7 @@
 extern "C" {
 #endif
 
-/* Error codes returned by FFI functions (blocking API only).
+/* Error codes returned by FFI functions and passed to upload callbacks.
    NOTE: Values must remain stable for ABI compatibility. */
 typedef enum {
     /* Base codes (stable) */
@@ -34,7 +34,13 @@ typedef enum {
     GENEVA_ERR_MISSING_REGION = 134,
     GENEVA_ERR_MISSING_TENANT = 135,
     GENEVA_ERR_MISSING_ROLE_NAME = 136,
-    GENEVA_ERR_MISSING_ROLE_INSTANCE = 137
+    GENEVA_ERR_MISSING_ROLE_INSTANCE = 137,
+
+    /* Granular upload errors (granular UPLOAD_FAILED) */
+    GENEVA_ERR_UPLOAD_AUTH_FAILED = 140, /* token or certificate rejected */
+    GENEVA_ERR_UPLOAD_TRANSIENT = 141,   /* network error, throttling or 5xx; retry later */
+    GENEVA_ERR_UPLOAD_REJECTED = 142,    /* permanent rejection, e.g. 400 for a bad payload */
+    GENEVA_ERR_UPLOAD_CANCELLED = 143    /* async upload cancelled by geneva_client_free */
 } GenevaError;
 
 #ifdef __cplusplus
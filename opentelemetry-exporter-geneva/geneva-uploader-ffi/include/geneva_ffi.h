@@ -1,105 +1,226 @@
 #ifndef GENEVA_FFI_H
 #define GENEVA_FFI_H
 
+/* Generated by cbindgen from src/lib.rs - do not edit by hand. */
+
+#include <stddef.h>
 #include <stdint.h>
-#include <stdlib.h>
 #include "geneva_errors.h"
 
-#ifdef __cplusplus
-extern "C" {
-#endif
+// Auth method selecting Managed Identity (`GenevaConfig::auth.msi`)
+#define GENEVA_AUTH_MANAGED_IDENTITY 0
 
-// Opaque handles
-typedef struct GenevaClientHandle GenevaClientHandle;
+// Auth method selecting certificate auth (`GenevaConfig::auth.cert`)
+#define GENEVA_AUTH_CERTIFICATE 1
+
+// Opaque handle holding encoded batches
 typedef struct EncodedBatchesHandle EncodedBatchesHandle;
 
-// Authentication method constants
-#define GENEVA_AUTH_MANAGED_IDENTITY 0
-#define GENEVA_AUTH_CERTIFICATE 1
+// Opaque handle for GenevaClient
+typedef struct GenevaClientHandle GenevaClientHandle;
 
-/* Configuration for certificate auth (valid only when auth_method == GENEVA_AUTH_CERTIFICATE) */
+// Configuration for managed identity auth (valid only when auth_method == GENEVA_AUTH_MANAGED_IDENTITY)
 typedef struct {
-    const char* cert_path;      /* Path to certificate file */
-    const char* cert_password;  /* Certificate password */
-} GenevaCertAuthConfig;
+  // Optional: object ID of a user-assigned identity as NUL-terminated GUID string,
+  // e.g. "00000000-0000-0000-0000-000000000000". NULL selects the system-assigned identity.
+  const char *objid;
+  // Required: resource (audience) to request tokens for
+  const char *resource;
+} GenevaMSIAuthConfig;
 
-/* Configuration for managed identity auth (valid only when auth_method == GENEVA_AUTH_MANAGED_IDENTITY) */
+// Configuration for certificate auth (valid only when auth_method == GENEVA_AUTH_CERTIFICATE)
 typedef struct {
-    const char* objid; /* Optional: Azure AD object ID as NUL-terminated GUID string
-                          e.g. "00000000-0000-0000-0000-000000000000" */
-} GenevaMSIAuthConfig;
+  // Path to certificate file
+  const char *cert_path;
+  // Certificate password
+  const char *cert_password;
+} GenevaCertAuthConfig;
 
-/* Tagged union for auth-specific configuration.
-   The active member is determined by 'auth_method' in GenevaConfig. */
+// Tagged union for auth-specific configuration.
+// The active member is determined by `auth_method` in GenevaConfig.
 typedef union {
-    GenevaMSIAuthConfig msi;    /* Valid when auth_method == GENEVA_AUTH_MANAGED_IDENTITY */
-    GenevaCertAuthConfig cert;  /* Valid when auth_method == GENEVA_AUTH_CERTIFICATE */
+  // Valid when auth_method == GENEVA_AUTH_MANAGED_IDENTITY
+  GenevaMSIAuthConfig msi;
+  // Valid when auth_method == GENEVA_AUTH_CERTIFICATE
+  GenevaCertAuthConfig cert;
 } GenevaAuthConfig;
 
-/* Configuration structure for Geneva client (C-compatible, tagged union) */
+// Configuration structure for Geneva client (C-compatible, tagged union)
 typedef struct {
-    const char* endpoint;
-    const char* environment;
-    const char* account;
-    const char* namespace_name;
-    const char* region;
-    uint32_t config_major_version;
-    int32_t auth_method; /* 0 = Managed Identity, 1 = Certificate */
-    const char* tenant;
-    const char* role_name;
-    const char* role_instance;
-    GenevaAuthConfig auth; /* Active member selected by auth_method */
+  const char *endpoint;
+  const char *environment;
+  const char *account;
+  const char *namespace_name;
+  const char *region;
+  unsigned int config_major_version;
+  // GENEVA_AUTH_MANAGED_IDENTITY or GENEVA_AUTH_CERTIFICATE
+  int auth_method;
+  const char *tenant;
+  const char *role_name;
+  const char *role_instance;
+  // Active member selected by auth_method
+  GenevaAuthConfig auth;
 } GenevaConfig;
 
-/* Create a new Geneva client.
-   - On success returns GENEVA_SUCCESS and writes *out_handle.
-   - On failure returns an error code. */
-GenevaError geneva_client_new(const GenevaConfig* config,
-                              GenevaClientHandle** out_handle);
-
+// Contents and metadata of an encoded batch
+//
+// Pointers borrow from the batches handle and stay valid until it is freed. Strings are
+// UTF-8 and not NUL-terminated.
+typedef struct {
+  // Event name the batch is uploaded under
+  const char *event_name;
+  size_t event_name_len;
+  // LZ4 chunked compressed payload, as sent to the ingestion gateway
+  const uint8_t *data;
+  size_t data_len;
+  // Earliest event time in nanoseconds since the Unix epoch
+  uint64_t start_time_unix_nano;
+  // Latest event time in nanoseconds since the Unix epoch
+  uint64_t end_time_unix_nano;
+  // Semicolon-separated MD5 schema IDs of the schemas in the batch
+  const char *schema_ids;
+  size_t schema_ids_len;
+} GenevaBatchInfo;
+
+// Completion callback for asynchronous uploads.
+//
+// Called exactly once per scheduled batch with the batch index, the upload result and the
+// `user_data` passed when scheduling. Runs on a worker thread of the client's runtime;
+// callbacks for different batches may run concurrently.
+typedef void (*GenevaUploadCallback)(size_t index, GenevaError result, void *user_data);
 
-/* 1) Encode and compress logs into batches (synchronous).
-      `data` is a protobuf-encoded ExportLogsServiceRequest.
-      - On success returns GENEVA_SUCCESS and writes *out_batches.
-      - On failure returns an error code.
-      Caller must free *out_batches with geneva_batches_free. */
-GenevaError geneva_encode_and_compress_logs(GenevaClientHandle* handle,
-                                            const uint8_t* data,
-                                            size_t data_len,
-                                            EncodedBatchesHandle** out_batches);
-
-/* 1.1) Encode and compress spans into batches (synchronous).
-      `data` is a protobuf-encoded ExportTraceServiceRequest.
-      - On success returns GENEVA_SUCCESS and writes *out_batches.
-      - On failure returns an error code.
-      Caller must free *out_batches with geneva_batches_free. */
-GenevaError geneva_encode_and_compress_spans(GenevaClientHandle* handle,
-                                            const uint8_t* data,
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Returns a description of the most recent error returned on the calling thread
+//
+// Returns NULL if no function has failed on this thread yet. The message is not cleared by
+// successful calls. Results passed to upload callbacks do not set it.
+//
+// # Safety
+// - the returned string is owned by the library and is valid until the next FFI call on
+//   the same thread; callers that need it longer must copy it
+const char *geneva_last_error_message(void);
+
+// Creates a new Geneva client with explicit result semantics (no TLS needed).
+//
+// On success: returns GenevaError::Success and writes a non-null handle into *out_handle.
+// On failure: returns an error code; geneva_last_error_message describes the failure.
+//
+// # Safety
+// - config must be a valid pointer to a GenevaConfig struct
+// - out_handle must be a valid pointer to receive the client handle
+// - caller must eventually call geneva_client_free on the returned handle
+GenevaError geneva_client_new(const GenevaConfig *config, GenevaClientHandle **out_handle);
+
+// Encode and compress logs into batches (synchronous)
+//
+// # Safety
+// - handle must be a valid pointer returned by geneva_client_new
+// - data must be a valid pointer to protobuf-encoded ExportLogsServiceRequest
+// - data_len must be the correct length of the data
+// - out_batches must be non-null; on success it receives a non-null pointer the caller must free with geneva_batches_free
+GenevaError geneva_encode_and_compress_logs(GenevaClientHandle *handle,
+                                            const uint8_t *data,
                                             size_t data_len,
-                                            EncodedBatchesHandle** out_batches);
-
-// 2) Query number of batches.
-size_t geneva_batches_len(const EncodedBatchesHandle* batches);
-
-/* 3) Upload a single batch by index (synchronous).
-      - On success returns GENEVA_SUCCESS.
-      - On failure returns an error code. */
-GenevaError geneva_upload_batch_sync(GenevaClientHandle* handle,
-                                     const EncodedBatchesHandle* batches,
+                                            EncodedBatchesHandle **out_batches);
+
+// Encode and compress spans into batches (synchronous)
+//
+// # Safety
+// - handle must be a valid pointer returned by geneva_client_new
+// - data must be a valid pointer to protobuf-encoded ExportTraceServiceRequest
+// - data_len must be the correct length of the data
+// - out_batches must be non-null; on success it receives a non-null pointer the caller must free with geneva_batches_free
+GenevaError geneva_encode_and_compress_spans(GenevaClientHandle *handle,
+                                             const uint8_t *data,
+                                             size_t data_len,
+                                             EncodedBatchesHandle **out_batches);
+
+// Returns the number of batches in the encoded batches handle
+//
+// # Safety
+// - batches must be a valid pointer returned by geneva_encode_and_compress_logs, or null
+size_t geneva_batches_len(const EncodedBatchesHandle *batches);
+
+// Reads the contents and metadata of a specific batch
+//
+// # Safety
+// - batches must be a valid pointer returned by geneva_encode_and_compress_logs
+// - index must be less than the value returned by geneva_batches_len
+// - out_info must be a valid pointer to a GenevaBatchInfo
+GenevaError geneva_batch_info(const EncodedBatchesHandle *batches,
+                              size_t index,
+                              GenevaBatchInfo *out_info);
+
+// Uploads a specific batch synchronously
+//
+// # Safety
+// - handle must be a valid pointer returned by geneva_client_new
+// - batches must be a valid pointer returned by geneva_encode_and_compress_logs
+// - index must be less than the value returned by geneva_batches_len
+GenevaError geneva_upload_batch_sync(GenevaClientHandle *handle,
+                                     const EncodedBatchesHandle *batches,
                                      size_t index);
 
-
-/* 5) Free the batches handle. */
-void geneva_batches_free(EncodedBatchesHandle* batches);
-
-
-
-/* Frees a Geneva client handle */
-void geneva_client_free(GenevaClientHandle* handle);
-
+// Uploads a specific batch asynchronously on the client's runtime
+//
+// Returns `Success` once the upload is scheduled; `callback` then receives the upload result.
+// On any other return value nothing is scheduled and `callback` is not called. The batch is
+// copied, so `batches` may be freed as soon as this function returns.
+//
+// # Safety
+// - handle must be a valid pointer returned by geneva_client_new
+// - batches must be a valid pointer returned by geneva_encode_and_compress_logs
+// - index must be less than the value returned by geneva_batches_len
+// - callback must be non-null and, together with user_data, safe to call from any thread
+//   until it has been called
+GenevaError geneva_upload_batch_async(GenevaClientHandle *handle,
+                                      const EncodedBatchesHandle *batches,
+                                      size_t index,
+                                      GenevaUploadCallback callback,
+                                      void *user_data);
+
+// Uploads all batches asynchronously on the client's runtime
+//
+// Batches are uploaded concurrently. Returns `Success` once the uploads are scheduled;
+// `callback` is then called once per batch, i.e. `geneva_batches_len(batches)` times. On
+// any other return value nothing is scheduled and `callback` is not called. The batches are
+// copied, so `batches` may be freed as soon as this function returns.
+//
+// # Safety
+// - handle must be a valid pointer returned by geneva_client_new
+// - batches must be a valid pointer returned by geneva_encode_and_compress_logs
+// - callback must be non-null and, together with user_data, safe to call from any thread
+//   until it has been called for every batch
+GenevaError geneva_upload_batches_async(GenevaClientHandle *handle,
+                                        const EncodedBatchesHandle *batches,
+                                        GenevaUploadCallback callback,
+                                        void *user_data);
+
+// Frees encoded batches handle
+//
+// # Safety
+// - batches must be a valid pointer returned by geneva_encode_and_compress_logs, or null
+// - batches must not be used after calling this function
+void geneva_batches_free(EncodedBatchesHandle *batches);
+
+// Frees a Geneva client handle
+//
+// Asynchronous uploads still in flight are cancelled: their callbacks are called with
+// `UploadCancelled` before this function returns (waiting at most 5 seconds for the runtime
+// to stop), and no callback runs afterwards. When called from an upload callback the runtime
+// is shut down in the background instead, so callbacks of other cancelled uploads may still
+// run after this function returns.
+//
+// # Safety
+// - client handle must be a valid pointer returned by geneva_client_new
+// - client handle must not be used after calling this function
+void geneva_client_free(GenevaClientHandle *handle);
 
 #ifdef __cplusplus
-}
-#endif
+}  // extern "C"
+#endif  // __cplusplus
 
-#endif // GENEVA_FFI_H
+#endif  /* GENEVA_FFI_H */
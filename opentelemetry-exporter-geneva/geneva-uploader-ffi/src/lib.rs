@@ -3,14 +3,17 @@
 // Allow #[repr(C)] and other FFI attributes without wrapping in unsafe blocks (standard FFI practice)
 #![allow(unsafe_attr_outside_unsafe)]
 
-use std::ffi::CStr;
+use std::cell::RefCell;
+use std::ffi::{c_void, CStr, CString};
+use std::fmt::Display;
 use std::os::raw::{c_char, c_int, c_uint};
 use std::ptr;
-use std::sync::OnceLock;
+use std::sync::Arc;
+use std::time::Duration;
 use tokio::runtime::Runtime;
 
 use geneva_uploader::client::{EncodedBatch, GenevaClient, GenevaClientConfig};
-use geneva_uploader::AuthMethod;
+use geneva_uploader::{AuthMethod, ErrorCategory};
 use opentelemetry_proto::tonic::collector::logs::v1::ExportLogsServiceRequest;
 use opentelemetry_proto::tonic::collector::trace::v1::ExportTraceServiceRequest;
 use prost::Message;
@@ -19,29 +22,25 @@ use std::path::PathBuf;
 /// Magic number for handle validation
 const GENEVA_HANDLE_MAGIC: u64 = 0xFEED_BEEF;
 
-/// Shared Tokio runtime for async operations
+/// How long geneva_client_free waits for the client's runtime to stop
+const RUNTIME_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Creates the Tokio runtime owned by a client handle.
 /// TODO: Consider making runtime configurable via FFI in the future:
 /// - Thread count configuration (currently uses available_parallelism())
 /// - Runtime type selection (multi_thread vs current_thread)
-/// - Per-client runtimes vs shared global runtime
 /// - External runtime integration (accept user-provided runtime handle)
-/// - Runtime lifecycle management for FFI (shutdown, cleanup)
-static RUNTIME: OnceLock<Runtime> = OnceLock::new(); // TODO - Consider using LazyLock once msrv is 1.80.
-
-fn runtime() -> &'static Runtime {
-    RUNTIME.get_or_init(|| {
-        tokio::runtime::Builder::new_multi_thread()
-            .worker_threads(
-                std::thread::available_parallelism()
-                    .map(|n| n.get())
-                    .unwrap_or(4),
-            )
-            .thread_name("geneva-ffi-worker")
-            .enable_time()
-            .enable_io() // Only enable time + I/O for Geneva's needs
-            .build()
-            .expect("Failed to create Tokio runtime for Geneva FFI")
-    })
+fn new_runtime() -> std::io::Result<Runtime> {
+    tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(
+            std::thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(4),
+        )
+        .thread_name("geneva-ffi-worker")
+        .enable_time()
+        .enable_io() // Only enable time + I/O for Geneva's needs
+        .build()
 }
 
 /// Trait for handles that support validation
@@ -53,13 +52,16 @@ trait ValidatedHandle {
 /// Generic validation function that works for any ValidatedHandle
 unsafe fn validate_handle<T: ValidatedHandle>(handle: *const T) -> GenevaError {
     if handle.is_null() {
-        return GenevaError::NullPointer;
+        return fail(GenevaError::NullPointer, "handle is null");
     }
 
     let handle_ref = unsafe { handle.as_ref().unwrap() };
 
     if handle_ref.magic() != GENEVA_HANDLE_MAGIC {
-        return GenevaError::InvalidData;
+        return fail(
+            GenevaError::InvalidData,
+            "handle is invalid or already freed",
+        );
     }
 
     GenevaError::Success
@@ -77,7 +79,39 @@ unsafe fn clear_handle_magic<T: ValidatedHandle>(handle: *mut T) {
 /// Opaque handle for GenevaClient
 pub struct GenevaClientHandle {
     magic: u64, // Magic number for handle validation
-    client: GenevaClient,
+    client: Arc<GenevaClient>,
+    // Runs all uploads of this client; taken and shut down by geneva_client_free
+    runtime: Option<Runtime>,
+}
+
+impl GenevaClientHandle {
+    fn new(client: GenevaClient, runtime: Runtime) -> Self {
+        Self {
+            magic: GENEVA_HANDLE_MAGIC,
+            client: Arc::new(client),
+            runtime: Some(runtime),
+        }
+    }
+
+    fn runtime(&self) -> &Runtime {
+        self.runtime
+            .as_ref()
+            .expect("runtime is only taken when the handle is freed")
+    }
+
+    /// Uploads `batch` on the client's runtime and reports the result through `completion`.
+    fn spawn_upload(&self, batch: EncodedBatch, completion: UploadCompletion) {
+        let client = self.client.clone();
+        self.runtime().spawn(async move {
+            // Move the whole guard into the task, not just the field assigned below
+            let mut completion = completion;
+            completion.result = match client.upload_batch(&batch).await {
+                Ok(_) => GenevaError::Success,
+                Err(e) => GenevaError::from_upload_error(&e),
+            };
+            // `completion` is dropped here, invoking the callback
+        });
+    }
 }
 
 impl ValidatedHandle for GenevaClientHandle {
@@ -106,24 +140,40 @@ impl ValidatedHandle for EncodedBatchesHandle {
     }
 }
 
-/// Configuration for certificate auth (valid only when auth_method == 1)
+/// Auth method selecting Managed Identity (`GenevaConfig::auth.msi`)
+pub const GENEVA_AUTH_MANAGED_IDENTITY: c_int = 0;
+/// Auth method selecting certificate auth (`GenevaConfig::auth.cert`)
+pub const GENEVA_AUTH_CERTIFICATE: c_int = 1;
+
+/// Configuration for certificate auth (valid only when auth_method == GENEVA_AUTH_CERTIFICATE)
 #[repr(C)]
 #[derive(Copy, Clone)]
 pub struct GenevaCertAuthConfig {
-    pub cert_path: *const c_char,     // Path to certificate file
-    pub cert_password: *const c_char, // Certificate password
+    /// Path to certificate file
+    pub cert_path: *const c_char,
+    /// Certificate password
+    pub cert_password: *const c_char,
 }
 
+/// Configuration for managed identity auth (valid only when auth_method == GENEVA_AUTH_MANAGED_IDENTITY)
 #[repr(C)]
 #[derive(Copy, Clone)]
 pub struct GenevaMSIAuthConfig {
-    pub objid: *const c_char, // Optional: Azure AD object id; reserved for future use
+    /// Optional: object ID of a user-assigned identity as NUL-terminated GUID string,
+    /// e.g. "00000000-0000-0000-0000-000000000000". NULL selects the system-assigned identity.
+    pub objid: *const c_char,
+    /// Required: resource (audience) to request tokens for
+    pub resource: *const c_char,
 }
 
+/// Tagged union for auth-specific configuration.
+/// The active member is determined by `auth_method` in GenevaConfig.
 #[repr(C)]
 pub union GenevaAuthConfig {
-    pub msi: GenevaMSIAuthConfig,   // Valid when auth_method == 0
-    pub cert: GenevaCertAuthConfig, // Valid when auth_method == 1
+    /// Valid when auth_method == GENEVA_AUTH_MANAGED_IDENTITY
+    pub msi: GenevaMSIAuthConfig,
+    /// Valid when auth_method == GENEVA_AUTH_CERTIFICATE
+    pub cert: GenevaCertAuthConfig,
 }
 
 /// Configuration structure for Geneva client (C-compatible, tagged union)
@@ -135,17 +185,19 @@ pub struct GenevaConfig {
     pub namespace_name: *const c_char,
     pub region: *const c_char,
     pub config_major_version: c_uint,
-    pub auth_method: c_int, // 0 = ManagedIdentity, 1 = Certificate
+    /// GENEVA_AUTH_MANAGED_IDENTITY or GENEVA_AUTH_CERTIFICATE
+    pub auth_method: c_int,
     pub tenant: *const c_char,
     pub role_name: *const c_char,
     pub role_instance: *const c_char,
-    pub auth: GenevaAuthConfig, // Active member selected by auth_method
+    /// Active member selected by auth_method
+    pub auth: GenevaAuthConfig,
 }
 
 /// Error codes returned by FFI functions
 /// TODO: Use cbindgen to auto-generate geneva_errors.h from this enum to eliminate duplication
 #[repr(C)]
-#[derive(PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum GenevaError {
     // Base codes (stable)
     Success = 0,
@@ -174,6 +226,52 @@ pub enum GenevaError {
     MissingTenant = 135,
     MissingRoleName = 136,
     MissingRoleInstance = 137,
+
+    // Granular upload errors (granular UPLOAD_FAILED)
+    UploadAuthFailed = 140,
+    UploadTransient = 141,
+    UploadRejected = 142,
+    UploadCancelled = 143,
+}
+
+/// Completion callback for asynchronous uploads.
+///
+/// Called exactly once per scheduled batch with the batch index, the upload result and the
+/// `user_data` passed when scheduling. Runs on a worker thread of the client's runtime;
+/// callbacks for different batches may run concurrently.
+pub type GenevaUploadCallback =
+    Option<unsafe extern "C" fn(index: usize, result: GenevaError, user_data: *mut c_void)>;
+
+/// Invokes the completion callback when dropped, with `UploadCancelled` unless a result was
+/// recorded. Dropping happens either when the upload task finishes or when the task is
+/// dropped because the client's runtime is shutting down.
+struct UploadCompletion {
+    callback: unsafe extern "C" fn(usize, GenevaError, *mut c_void),
+    user_data: *mut c_void,
+    index: usize,
+    result: GenevaError,
+}
+
+// SAFETY: the caller of the async upload functions guarantees that `user_data` may be used
+// from any thread until the callback has run.
+unsafe impl Send for UploadCompletion {}
+
+impl Drop for UploadCompletion {
+    fn drop(&mut self) {
+        unsafe { (self.callback)(self.index, self.result, self.user_data) };
+    }
+}
+
+impl GenevaError {
+    /// Error code for a failed `GenevaClient::upload_batch`.
+    fn from_upload_error(err: &geneva_uploader::GenevaError) -> Self {
+        match err.category() {
+            ErrorCategory::Auth => GenevaError::UploadAuthFailed,
+            _ if err.is_retryable() => GenevaError::UploadTransient,
+            ErrorCategory::Http => GenevaError::UploadRejected,
+            _ => GenevaError::UploadFailed,
+        }
+    }
 }
 
 /// Safely converts a C string to Rust String
@@ -188,10 +286,47 @@ unsafe fn c_str_to_string(ptr: *const c_char, field_name: &str) -> Result<String
     }
 }
 
+thread_local! {
+    /// Message describing the most recent error on this thread
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Records `message` as this thread's last error and returns `code`
+fn fail(code: GenevaError, message: impl Display) -> GenevaError {
+    // Interior NUL bytes cannot be represented in a C string
+    let message = message.to_string().replace('\0', " ");
+    let message = CString::new(message).unwrap_or_default();
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+    code
+}
+
+fn index_out_of_range(index: usize, batches: &EncodedBatchesHandle) -> GenevaError {
+    fail(
+        GenevaError::IndexOutOfRange,
+        format!(
+            "batch index {index} is out of range ({} batches)",
+            batches.batches.len()
+        ),
+    )
+}
+
+/// Returns a description of the most recent error returned on the calling thread
+///
+/// Returns NULL if no function has failed on this thread yet. The message is not cleared by
+/// successful calls. Results passed to upload callbacks do not set it.
+///
+/// # Safety
+/// - the returned string is owned by the library and is valid until the next FFI call on
+///   the same thread; callers that need it longer must copy it
+#[no_mangle]
+pub unsafe extern "C" fn geneva_last_error_message() -> *const c_char {
+    LAST_ERROR.with(|last| last.borrow().as_ref().map_or(ptr::null(), |m| m.as_ptr()))
+}
+
 /// Creates a new Geneva client with explicit result semantics (no TLS needed).
 ///
 /// On success: returns GenevaError::Success and writes a non-null handle into *out_handle.
-/// On failure: returns an error code and writes a short diagnostic message into err_msg if provided.
+/// On failure: returns an error code; geneva_last_error_message describes the failure.
 ///
 /// # Safety
 /// - config must be a valid pointer to a GenevaConfig struct
@@ -204,7 +339,7 @@ pub unsafe extern "C" fn geneva_client_new(
 ) -> GenevaError {
     // Validate pointers
     if config.is_null() || out_handle.is_null() {
-        return GenevaError::NullPointer;
+        return fail(GenevaError::NullPointer, "config or out_handle is null");
     }
     unsafe { *out_handle = ptr::null_mut() };
 
@@ -212,103 +347,132 @@ pub unsafe extern "C" fn geneva_client_new(
 
     // Validate required fields with granular error codes
     if config.endpoint.is_null() {
-        return GenevaError::MissingEndpoint;
+        return fail(GenevaError::MissingEndpoint, "endpoint is null");
     }
     if config.environment.is_null() {
-        return GenevaError::MissingEnvironment;
+        return fail(GenevaError::MissingEnvironment, "environment is null");
     }
     if config.account.is_null() {
-        return GenevaError::MissingAccount;
+        return fail(GenevaError::MissingAccount, "account is null");
     }
     if config.namespace_name.is_null() {
-        return GenevaError::MissingNamespace;
+        return fail(GenevaError::MissingNamespace, "namespace_name is null");
     }
     if config.region.is_null() {
-        return GenevaError::MissingRegion;
+        return fail(GenevaError::MissingRegion, "region is null");
     }
     if config.tenant.is_null() {
-        return GenevaError::MissingTenant;
+        return fail(GenevaError::MissingTenant, "tenant is null");
     }
     if config.role_name.is_null() {
-        return GenevaError::MissingRoleName;
+        return fail(GenevaError::MissingRoleName, "role_name is null");
     }
     if config.role_instance.is_null() {
-        return GenevaError::MissingRoleInstance;
+        return fail(GenevaError::MissingRoleInstance, "role_instance is null");
     }
 
     // Convert C strings to Rust strings
     let endpoint = match unsafe { c_str_to_string(config.endpoint, "endpoint") } {
         Ok(s) => s,
-        Err(_e) => {
-            return GenevaError::InvalidConfig;
+        Err(e) => {
+            return fail(GenevaError::InvalidConfig, e);
         }
     };
     let environment = match unsafe { c_str_to_string(config.environment, "environment") } {
         Ok(s) => s,
-        Err(_e) => {
-            return GenevaError::InvalidConfig;
+        Err(e) => {
+            return fail(GenevaError::InvalidConfig, e);
         }
     };
     let account = match unsafe { c_str_to_string(config.account, "account") } {
         Ok(s) => s,
-        Err(_e) => {
-            return GenevaError::InvalidConfig;
+        Err(e) => {
+            return fail(GenevaError::InvalidConfig, e);
         }
     };
     let namespace = match unsafe { c_str_to_string(config.namespace_name, "namespace_name") } {
         Ok(s) => s,
-        Err(_e) => {
-            return GenevaError::InvalidConfig;
+        Err(e) => {
+            return fail(GenevaError::InvalidConfig, e);
         }
     };
     let region = match unsafe { c_str_to_string(config.region, "region") } {
         Ok(s) => s,
-        Err(_e) => {
-            return GenevaError::InvalidConfig;
+        Err(e) => {
+            return fail(GenevaError::InvalidConfig, e);
         }
     };
     let tenant = match unsafe { c_str_to_string(config.tenant, "tenant") } {
         Ok(s) => s,
-        Err(_e) => {
-            return GenevaError::InvalidConfig;
+        Err(e) => {
+            return fail(GenevaError::InvalidConfig, e);
         }
     };
     let role_name = match unsafe { c_str_to_string(config.role_name, "role_name") } {
         Ok(s) => s,
-        Err(_e) => {
-            return GenevaError::InvalidConfig;
+        Err(e) => {
+            return fail(GenevaError::InvalidConfig, e);
         }
     };
     let role_instance = match unsafe { c_str_to_string(config.role_instance, "role_instance") } {
         Ok(s) => s,
-        Err(_e) => {
-            return GenevaError::InvalidConfig;
+        Err(e) => {
+            return fail(GenevaError::InvalidConfig, e);
         }
     };
 
     // Auth method conversion
+    let mut msi_resource = None;
     let auth_method = match config.auth_method {
-        0 => AuthMethod::ManagedIdentity,
-        1 => {
+        GENEVA_AUTH_MANAGED_IDENTITY => {
+            // Managed identity: read fields from tagged union
+            let msi = unsafe { config.auth.msi };
+            if msi.resource.is_null() {
+                return fail(GenevaError::InvalidConfig, "auth.msi.resource is null");
+            }
+            msi_resource = match unsafe { c_str_to_string(msi.resource, "resource") } {
+                Ok(s) => Some(s),
+                Err(e) => {
+                    return fail(GenevaError::InvalidConfig, e);
+                }
+            };
+            if msi.objid.is_null() {
+                AuthMethod::ManagedIdentity
+            } else {
+                match unsafe { c_str_to_string(msi.objid, "objid") } {
+                    Ok(object_id) => AuthMethod::UserManagedIdentityByObjectId { object_id },
+                    Err(e) => {
+                        return fail(GenevaError::InvalidConfig, e);
+                    }
+                }
+            }
+        }
+        GENEVA_AUTH_CERTIFICATE => {
             // Certificate authentication: read fields from tagged union
             let cert = unsafe { config.auth.cert };
             if cert.cert_path.is_null() {
-                return GenevaError::InvalidCertConfig;
+                return fail(
+                    GenevaError::InvalidCertConfig,
+                    "auth.cert.cert_path is null",
+                );
             }
             if cert.cert_password.is_null() {
-                return GenevaError::InvalidCertConfig;
+                return fail(
+                    GenevaError::InvalidCertConfig,
+                    "auth.cert.cert_password is null",
+                );
             }
             let cert_path = match unsafe { c_str_to_string(cert.cert_path, "cert_path") } {
                 Ok(s) => PathBuf::from(s),
-                Err(_e) => {
-                    return GenevaError::InvalidConfig;
+                Err(e) => {
+                    return fail(GenevaError::InvalidConfig, e);
                 }
             };
             let cert_password =
                 match unsafe { c_str_to_string(cert.cert_password, "cert_password") } {
                     Ok(s) => s,
-                    Err(_e) => {
-                        return GenevaError::InvalidConfig;
+                    Err(e) => {
+                        return fail(GenevaError::InvalidConfig, e);
                     }
                 };
             AuthMethod::Certificate {
@@ -316,37 +480,59 @@ pub unsafe extern "C" fn geneva_client_new(
                 password: cert_password,
             }
         }
-        _ => {
-            return GenevaError::InvalidAuthMethod;
+        other => {
+            return fail(
+                GenevaError::InvalidAuthMethod,
+                format!("unknown auth_method {other}"),
+            );
         }
     };
 
     // Build client config
-    let geneva_config = GenevaClientConfig {
-        endpoint,
-        environment,
-        account,
-        namespace,
-        region,
-        config_major_version: config.config_major_version,
-        auth_method,
-        tenant,
-        role_name,
-        role_instance,
+    let mut builder = GenevaClientConfig::builder()
+        .endpoint(endpoint)
+        .environment(environment)
+        .account(account)
+        .namespace(namespace)
+        .region(region)
+        .config_major_version(config.config_major_version)
+        .auth_method(auth_method)
+        .tenant(tenant)
+        .role_name(role_name)
+        .role_instance(role_instance);
+    if let Some(msi_resource) = msi_resource {
+        builder = builder.msi_resource(msi_resource);
+    }
+    let geneva_config = match builder.build() {
+        Ok(geneva_config) => geneva_config,
+        Err(e) => {
+            return fail(GenevaError::InvalidConfig, e);
+        }
     };
 
-    // Create client
-    let client = match GenevaClient::new(geneva_config) {
-        Ok(client) => client,
-        Err(_e) => {
-            return GenevaError::InitializationFailed;
+    let runtime = match new_runtime() {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            return fail(GenevaError::InitializationFailed, e);
         }
     };
 
-    let handle = GenevaClientHandle {
-        magic: GENEVA_HANDLE_MAGIC,
-        client,
+    // Create client within the runtime so that any background tasks it starts run there
+    let client = {
+        let _guard = runtime.enter();
+        GenevaClient::new(geneva_config)
+    };
+    let client = match client {
+        Ok(client) => client,
+        Err(e) if e.category() == ErrorCategory::Auth => {
+            return fail(GenevaError::InvalidCertConfig, e);
+        }
+        Err(e) => {
+            return fail(GenevaError::InitializationFailed, e);
+        }
     };
+
+    let handle = GenevaClientHandle::new(client, runtime);
     unsafe { *out_handle = Box::into_raw(Box::new(handle)) };
     GenevaError::Success
 }
@@ -366,18 +552,18 @@ pub unsafe extern "C" fn geneva_encode_and_compress_logs(
     out_batches: *mut *mut EncodedBatchesHandle,
 ) -> GenevaError {
     if out_batches.is_null() {
-        return GenevaError::NullPointer;
+        return fail(GenevaError::NullPointer, "out_batches is null");
     }
     unsafe { *out_batches = ptr::null_mut() };
 
     if handle.is_null() {
-        return GenevaError::NullPointer;
+        return fail(GenevaError::NullPointer, "handle is null");
     }
     if data.is_null() {
-        return GenevaError::NullPointer;
+        return fail(GenevaError::NullPointer, "data is null");
     }
     if data_len == 0 {
-        return GenevaError::EmptyInput;
+        return fail(GenevaError::EmptyInput, "data_len is 0");
     }
 
     // Validate handle first
@@ -391,8 +577,8 @@ pub unsafe extern "C" fn geneva_encode_and_compress_logs(
 
     let logs_data: ExportLogsServiceRequest = match Message::decode(data_slice) {
         Ok(data) => data,
-        Err(_e) => {
-            return GenevaError::DecodeFailed;
+        Err(e) => {
+            return fail(GenevaError::DecodeFailed, e);
         }
     };
 
@@ -406,7 +592,7 @@ pub unsafe extern "C" fn geneva_encode_and_compress_logs(
             unsafe { *out_batches = Box::into_raw(Box::new(h)) };
             GenevaError::Success
         }
-        Err(_e) => GenevaError::InternalError,
+        Err(e) => fail(GenevaError::InternalError, e),
     }
 }
 
@@ -425,18 +611,18 @@ pub unsafe extern "C" fn geneva_encode_and_compress_spans(
     out_batches: *mut *mut EncodedBatchesHandle,
 ) -> GenevaError {
     if out_batches.is_null() {
-        return GenevaError::NullPointer;
+        return fail(GenevaError::NullPointer, "out_batches is null");
     }
     unsafe { *out_batches = ptr::null_mut() };
 
     if handle.is_null() {
-        return GenevaError::NullPointer;
+        return fail(GenevaError::NullPointer, "handle is null");
     }
     if data.is_null() {
-        return GenevaError::NullPointer;
+        return fail(GenevaError::NullPointer, "data is null");
     }
     if data_len == 0 {
-        return GenevaError::EmptyInput;
+        return fail(GenevaError::EmptyInput, "data_len is 0");
     }
 
     // Validate handle first
@@ -450,8 +636,8 @@ pub unsafe extern "C" fn geneva_encode_and_compress_spans(
 
     let spans_data: ExportTraceServiceRequest = match Message::decode(data_slice) {
         Ok(data) => data,
-        Err(_e) => {
-            return GenevaError::DecodeFailed;
+        Err(e) => {
+            return fail(GenevaError::DecodeFailed, e);
         }
     };
 
@@ -465,7 +651,7 @@ pub unsafe extern "C" fn geneva_encode_and_compress_spans(
             unsafe { *out_batches = Box::into_raw(Box::new(h)) };
             GenevaError::Success
         }
-        Err(_e) => GenevaError::InternalError,
+        Err(e) => fail(GenevaError::InternalError, e),
     }
 }
 
@@ -486,6 +672,66 @@ pub unsafe extern "C" fn geneva_batches_len(batches: *const EncodedBatchesHandle
     }
 }
 
+/// Contents and metadata of an encoded batch
+///
+/// Pointers borrow from the batches handle and stay valid until it is freed. Strings are
+/// UTF-8 and not NUL-terminated.
+#[repr(C)]
+pub struct GenevaBatchInfo {
+    /// Event name the batch is uploaded under
+    pub event_name: *const c_char,
+    pub event_name_len: usize,
+    /// LZ4 chunked compressed payload, as sent to the ingestion gateway
+    pub data: *const u8,
+    pub data_len: usize,
+    /// Earliest event time in nanoseconds since the Unix epoch
+    pub start_time_unix_nano: u64,
+    /// Latest event time in nanoseconds since the Unix epoch
+    pub end_time_unix_nano: u64,
+    /// Semicolon-separated MD5 schema IDs of the schemas in the batch
+    pub schema_ids: *const c_char,
+    pub schema_ids_len: usize,
+}
+
+/// Reads the contents and metadata of a specific batch
+///
+/// # Safety
+/// - batches must be a valid pointer returned by geneva_encode_and_compress_logs
+/// - index must be less than the value returned by geneva_batches_len
+/// - out_info must be a valid pointer to a GenevaBatchInfo
+#[no_mangle]
+pub unsafe extern "C" fn geneva_batch_info(
+    batches: *const EncodedBatchesHandle,
+    index: usize,
+    out_info: *mut GenevaBatchInfo,
+) -> GenevaError {
+    match unsafe { validate_handle(batches) } {
+        GenevaError::Success => {}
+        error => return error,
+    }
+    if out_info.is_null() {
+        return fail(GenevaError::NullPointer, "out_info is null");
+    }
+
+    // Safe to dereference after validation
+    let batches_ref = unsafe { batches.as_ref().unwrap() };
+    let Some(batch) = batches_ref.batches.get(index) else {
+        return index_out_of_range(index, batches_ref);
+    };
+    let info = GenevaBatchInfo {
+        event_name: batch.event_name.as_ptr().cast(),
+        event_name_len: batch.event_name.len(),
+        data: batch.data.as_ptr(),
+        data_len: batch.data.len(),
+        start_time_unix_nano: batch.metadata.start_time,
+        end_time_unix_nano: batch.metadata.end_time,
+        schema_ids: batch.metadata.schema_ids.as_ptr().cast(),
+        schema_ids_len: batch.metadata.schema_ids.len(),
+    };
+    unsafe { out_info.write(info) };
+    GenevaError::Success
+}
+
 /// Uploads a specific batch synchronously
 ///
 /// # Safety
@@ -514,18 +760,141 @@ pub unsafe extern "C" fn geneva_upload_batch_sync(
     let batches_ref = unsafe { batches.as_ref().unwrap() };
 
     if index >= batches_ref.batches.len() {
-        return GenevaError::IndexOutOfRange;
+        return index_out_of_range(index, batches_ref);
+    }
+
+    // Blocking on the client's runtime from one of its own worker threads (e.g. inside an
+    // upload callback) would panic.
+    if tokio::runtime::Handle::try_current().is_ok() {
+        return fail(
+            GenevaError::InternalError,
+            "geneva_upload_batch_sync must not be called from an upload callback",
+        );
     }
 
     let batch = &batches_ref.batches[index];
     let client = &handle_ref.client;
-    let res = runtime().block_on(async move { client.upload_batch(batch).await });
+    let res = handle_ref
+        .runtime()
+        .block_on(async move { client.upload_batch(batch).await });
     match res {
         Ok(_) => GenevaError::Success,
-        Err(_e) => GenevaError::UploadFailed,
+        Err(e) => fail(GenevaError::from_upload_error(&e), e),
     }
 }
 
+/// Uploads a specific batch asynchronously on the client's runtime
+///
+/// Returns `Success` once the upload is scheduled; `callback` then receives the upload result.
+/// On any other return value nothing is scheduled and `callback` is not called. The batch is
+/// copied, so `batches` may be freed as soon as this function returns.
+///
+/// # Safety
+/// - handle must be a valid pointer returned by geneva_client_new
+/// - batches must be a valid pointer returned by geneva_encode_and_compress_logs
+/// - index must be less than the value returned by geneva_batches_len
+/// - callback must be non-null and, together with user_data, safe to call from any thread
+///   until it has been called
+#[no_mangle]
+pub unsafe extern "C" fn geneva_upload_batch_async(
+    handle: *mut GenevaClientHandle,
+    batches: *const EncodedBatchesHandle,
+    index: usize,
+    callback: GenevaUploadCallback,
+    user_data: *mut c_void,
+) -> GenevaError {
+    let (handle_ref, batches_ref, callback) =
+        match unsafe { validate_async_upload(handle, batches, callback) } {
+            Ok(refs) => refs,
+            Err(error) => return error,
+        };
+
+    let Some(batch) = batches_ref.batches.get(index) else {
+        return index_out_of_range(index, batches_ref);
+    };
+    handle_ref.spawn_upload(
+        batch.clone(),
+        UploadCompletion {
+            callback,
+            user_data,
+            index,
+            result: GenevaError::UploadCancelled,
+        },
+    );
+    GenevaError::Success
+}
+
+/// Uploads all batches asynchronously on the client's runtime
+///
+/// Batches are uploaded concurrently. Returns `Success` once the uploads are scheduled;
+/// `callback` is then called once per batch, i.e. `geneva_batches_len(batches)` times. On
+/// any other return value nothing is scheduled and `callback` is not called. The batches are
+/// copied, so `batches` may be freed as soon as this function returns.
+///
+/// # Safety
+/// - handle must be a valid pointer returned by geneva_client_new
+/// - batches must be a valid pointer returned by geneva_encode_and_compress_logs
+/// - callback must be non-null and, together with user_data, safe to call from any thread
+///   until it has been called for every batch
+#[no_mangle]
+pub unsafe extern "C" fn geneva_upload_batches_async(
+    handle: *mut GenevaClientHandle,
+    batches: *const EncodedBatchesHandle,
+    callback: GenevaUploadCallback,
+    user_data: *mut c_void,
+) -> GenevaError {
+    let (handle_ref, batches_ref, callback) =
+        match unsafe { validate_async_upload(handle, batches, callback) } {
+            Ok(refs) => refs,
+            Err(error) => return error,
+        };
+
+    for (index, batch) in batches_ref.batches.iter().enumerate() {
+        handle_ref.spawn_upload(
+            batch.clone(),
+            UploadCompletion {
+                callback,
+                user_data,
+                index,
+                result: GenevaError::UploadCancelled,
+            },
+        );
+    }
+    GenevaError::Success
+}
+
+/// Validates the arguments shared by the async upload functions
+unsafe fn validate_async_upload<'a>(
+    handle: *mut GenevaClientHandle,
+    batches: *const EncodedBatchesHandle,
+    callback: GenevaUploadCallback,
+) -> Result<
+    (
+        &'a GenevaClientHandle,
+        &'a EncodedBatchesHandle,
+        unsafe extern "C" fn(usize, GenevaError, *mut c_void),
+    ),
+    GenevaError,
+> {
+    match unsafe { validate_handle(handle) } {
+        GenevaError::Success => {}
+        error => return Err(error),
+    }
+    match unsafe { validate_handle(batches) } {
+        GenevaError::Success => {}
+        error => return Err(error),
+    }
+    let Some(callback) = callback else {
+        return Err(fail(GenevaError::NullPointer, "callback is null"));
+    };
+    // Safe to dereference after validation
+    Ok((
+        unsafe { handle.as_ref().unwrap() },
+        unsafe { batches.as_ref().unwrap() },
+        callback,
+    ))
+}
+
 /// Frees encoded batches handle
 ///
 /// # Safety
@@ -539,7 +908,13 @@ pub unsafe extern "C" fn geneva_batches_free(batches: *mut EncodedBatchesHandle)
     }
 }
 
-// Frees a Geneva client handle
+/// Frees a Geneva client handle
+///
+/// Asynchronous uploads still in flight are cancelled: their callbacks are called with
+/// `UploadCancelled` before this function returns (waiting at most 5 seconds for the runtime
+/// to stop), and no callback runs afterwards. When called from an upload callback the runtime
+/// is shut down in the background instead, so callbacks of other cancelled uploads may still
+/// run after this function returns.
 ///
 /// # Safety
 /// - client handle must be a valid pointer returned by geneva_client_new
@@ -548,7 +923,15 @@ pub unsafe extern "C" fn geneva_batches_free(batches: *mut EncodedBatchesHandle)
 pub unsafe extern "C" fn geneva_client_free(handle: *mut GenevaClientHandle) {
     if !handle.is_null() {
         unsafe { clear_handle_magic(handle) };
-        let _ = unsafe { Box::from_raw(handle) };
+        let mut handle = unsafe { Box::from_raw(handle) };
+        if let Some(runtime) = handle.runtime.take() {
+            if tokio::runtime::Handle::try_current().is_ok() {
+                // A runtime cannot be shut down synchronously from one of its own threads
+                runtime.shutdown_background();
+            } else {
+                runtime.shutdown_timeout(RUNTIME_SHUTDOWN_TIMEOUT);
+            }
+        }
     }
 }
 
@@ -558,6 +941,13 @@ mod tests {
     use opentelemetry_proto::tonic::collector::logs::v1::ExportLogsServiceRequest;
     use prost::Message;
     use std::ffi::CString;
+    use std::sync::OnceLock;
+
+    // Runtime for the mock servers used by the tests
+    fn runtime() -> &'static Runtime {
+        static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+        RUNTIME.get_or_init(|| new_runtime().unwrap())
+    }
 
     // Build a minimal unsigned JWT with the Endpoint claim and an exp. Matches what extract_endpoint_from_token expects.
     fn generate_mock_jwt_and_expiry(endpoint: &str, ttl_secs: i64) -> (String, String) {
@@ -603,6 +993,29 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_upload_error_codes() {
+        let http = |status: u16, retryable: bool| geneva_uploader::GenevaError::Upload {
+            event_name: "Log".to_string(),
+            spooled: false,
+            spool_error: None,
+            source: Box::new(geneva_uploader::GenevaError::Http {
+                status,
+                message: String::new(),
+                retryable,
+            }),
+        };
+        assert!(GenevaError::from_upload_error(&http(503, true)) == GenevaError::UploadTransient);
+        assert!(GenevaError::from_upload_error(&http(400, false)) == GenevaError::UploadRejected);
+
+        let auth = geneva_uploader::GenevaError::Auth {
+            message: String::new(),
+            status: Some(401),
+            retryable: true,
+        };
+        assert!(GenevaError::from_upload_error(&auth) == GenevaError::UploadAuthFailed);
+    }
+
     #[test]
     fn test_geneva_client_free_with_null() {
         unsafe {
@@ -635,7 +1048,10 @@ mod tests {
                 role_name: role_name.as_ptr(),
                 role_instance: role_instance.as_ptr(),
                 auth: GenevaAuthConfig {
-                    msi: GenevaMSIAuthConfig { objid: ptr::null() },
+                    msi: GenevaMSIAuthConfig {
+                        objid: ptr::null(),
+                        resource: ptr::null(),
+                    },
                 },
             };
 
@@ -646,6 +1062,44 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_managed_identity_requires_resource() {
+        unsafe {
+            let endpoint = CString::new("https://test.geneva.com").unwrap();
+            let environment = CString::new("test").unwrap();
+            let account = CString::new("testaccount").unwrap();
+            let namespace = CString::new("testns").unwrap();
+            let region = CString::new("testregion").unwrap();
+            let tenant = CString::new("testtenant").unwrap();
+            let role_name = CString::new("testrole").unwrap();
+            let role_instance = CString::new("testinstance").unwrap();
+
+            let config = GenevaConfig {
+                endpoint: endpoint.as_ptr(),
+                environment: environment.as_ptr(),
+                account: account.as_ptr(),
+                namespace_name: namespace.as_ptr(),
+                region: region.as_ptr(),
+                config_major_version: 1,
+                auth_method: 0, // Managed identity without a resource
+                tenant: tenant.as_ptr(),
+                role_name: role_name.as_ptr(),
+                role_instance: role_instance.as_ptr(),
+                auth: GenevaAuthConfig {
+                    msi: GenevaMSIAuthConfig {
+                        objid: ptr::null(),
+                        resource: ptr::null(),
+                    },
+                },
+            };
+
+            let mut out: *mut GenevaClientHandle = std::ptr::null_mut();
+            let rc = geneva_client_new(&config, &mut out);
+            assert_eq!(rc as u32, GenevaError::InvalidConfig as u32);
+            assert!(out.is_null());
+        }
+    }
+
     #[test]
     fn test_invalid_auth_method() {
         unsafe {
@@ -670,7 +1124,10 @@ mod tests {
                 role_name: role_name.as_ptr(),
                 role_instance: role_instance.as_ptr(),
                 auth: GenevaAuthConfig {
-                    msi: GenevaMSIAuthConfig { objid: ptr::null() },
+                    msi: GenevaMSIAuthConfig {
+                        objid: ptr::null(),
+                        resource: ptr::null(),
+                    },
                 },
             };
 
@@ -821,25 +1278,23 @@ mod tests {
         });
 
         // Build a real GenevaClient using MockAuth (no mTLS), then wrap it in the FFI handle.
-        let cfg = GenevaClientConfig {
-            endpoint: mock_server.uri(),
-            environment: "test".to_string(),
-            account: "test".to_string(),
-            namespace: "testns".to_string(),
-            region: "testregion".to_string(),
-            config_major_version: 1,
-            auth_method: AuthMethod::MockAuth,
-            tenant: "testtenant".to_string(),
-            role_name: "testrole".to_string(),
-            role_instance: "testinstance".to_string(),
-        };
+        let cfg = GenevaClientConfig::builder()
+            .endpoint(mock_server.uri())
+            .environment("test")
+            .account("test")
+            .namespace("testns")
+            .region("testregion")
+            .config_major_version(1)
+            .auth_method(AuthMethod::MockAuth)
+            .tenant("testtenant")
+            .role_name("testrole")
+            .role_instance("testinstance")
+            .build()
+            .expect("valid test config");
         let client = GenevaClient::new(cfg).expect("failed to create GenevaClient with MockAuth");
 
         // Wrap into an FFI-compatible handle
-        let handle = GenevaClientHandle {
-            magic: GENEVA_HANDLE_MAGIC,
-            client,
-        };
+        let handle = GenevaClientHandle::new(client, new_runtime().unwrap());
         // Keep the boxed handle alive until we explicitly free it via FFI
         let mut handle_box = Box::new(handle);
         let handle_ptr: *mut GenevaClientHandle = &mut *handle_box;
@@ -930,25 +1385,23 @@ mod tests {
         });
 
         // Build client with MockAuth
-        let cfg = GenevaClientConfig {
-            endpoint: mock_server.uri(),
-            environment: "test".to_string(),
-            account: "test".to_string(),
-            namespace: "testns".to_string(),
-            region: "testregion".to_string(),
-            config_major_version: 1,
-            auth_method: AuthMethod::MockAuth,
-            tenant: "testtenant".to_string(),
-            role_name: "testrole".to_string(),
-            role_instance: "testinstance".to_string(),
-        };
+        let cfg = GenevaClientConfig::builder()
+            .endpoint(mock_server.uri())
+            .environment("test")
+            .account("test")
+            .namespace("testns")
+            .region("testregion")
+            .config_major_version(1)
+            .auth_method(AuthMethod::MockAuth)
+            .tenant("testtenant")
+            .role_name("testrole")
+            .role_instance("testinstance")
+            .build()
+            .expect("valid test config");
         let client = GenevaClient::new(cfg).expect("failed to create GenevaClient with MockAuth");
 
         // Wrap client into FFI handle
-        let mut handle_box = Box::new(GenevaClientHandle {
-            magic: GENEVA_HANDLE_MAGIC,
-            client,
-        });
+        let mut handle_box = Box::new(GenevaClientHandle::new(client, new_runtime().unwrap()));
         let handle_ptr: *mut GenevaClientHandle = &mut *handle_box;
 
         // Build ExportLogsServiceRequest with two different event_names
@@ -1048,4 +1501,260 @@ mod tests {
         unsafe { geneva_client_free(raw_handle) };
         drop(mock_server);
     }
+
+    #[test]
+    fn test_upload_async_with_nulls() {
+        unsafe extern "C" fn noop(_index: usize, _result: GenevaError, _user_data: *mut c_void) {}
+        unsafe {
+            let rc = geneva_upload_batch_async(
+                ptr::null_mut(),
+                ptr::null(),
+                0,
+                Some(noop),
+                ptr::null_mut(),
+            );
+            assert_eq!(rc, GenevaError::NullPointer);
+            let rc =
+                geneva_upload_batches_async(ptr::null_mut(), ptr::null(), None, ptr::null_mut());
+            assert_eq!(rc, GenevaError::NullPointer);
+        }
+    }
+
+    type UploadResults = std::sync::Mutex<Vec<(usize, GenevaError)>>;
+
+    unsafe extern "C" fn record_result(index: usize, result: GenevaError, user_data: *mut c_void) {
+        let results = unsafe { &*(user_data as *const UploadResults) };
+        results.lock().unwrap().push((index, result));
+    }
+
+    // Mounts the config service and an ingestion gateway answering after `post_delay`
+    #[cfg(feature = "mock_auth")]
+    fn mock_geneva_client(
+        mock_server: &wiremock::MockServer,
+        post_delay: Duration,
+    ) -> GenevaClient {
+        use wiremock::matchers::method;
+        use wiremock::{Mock, ResponseTemplate};
+
+        let ingestion_endpoint = mock_server.uri();
+        let (auth_token, auth_token_expiry) =
+            generate_mock_jwt_and_expiry(&ingestion_endpoint, 24 * 3600);
+        runtime().block_on(async {
+            Mock::given(method("GET"))
+                .respond_with(ResponseTemplate::new(200).set_body_string(format!(
+                    r#"{{
+                        "IngestionGatewayInfo": {{
+                            "Endpoint": "{ingestion_endpoint}",
+                            "AuthToken": "{auth_token}",
+                            "AuthTokenExpiryTime": "{auth_token_expiry}"
+                        }},
+                        "StorageAccountKeys": [{{
+                            "AccountMonikerName": "testdiagaccount",
+                            "AccountGroupName": "testgroup",
+                            "IsPrimaryMoniker": true
+                        }}],
+                        "TagId": "test"
+                    }}"#
+                )))
+                .mount(mock_server)
+                .await;
+            Mock::given(method("POST"))
+                .respond_with(
+                    ResponseTemplate::new(202)
+                        .set_body_string(r#"{"ticket":"accepted"}"#)
+                        .set_delay(post_delay),
+                )
+                .mount(mock_server)
+                .await;
+        });
+
+        let cfg = GenevaClientConfig::builder()
+            .endpoint(mock_server.uri())
+            .environment("test")
+            .account("test")
+            .namespace("testns")
+            .region("testregion")
+            .config_major_version(1)
+            .auth_method(AuthMethod::MockAuth)
+            .tenant("testtenant")
+            .role_name("testrole")
+            .role_instance("testinstance")
+            .build()
+            .expect("valid test config");
+        GenevaClient::new(cfg).expect("failed to create GenevaClient with MockAuth")
+    }
+
+    #[cfg(feature = "mock_auth")]
+    fn encode_two_batches(handle_ptr: *mut GenevaClientHandle) -> *mut EncodedBatchesHandle {
+        let scope_logs = opentelemetry_proto::tonic::logs::v1::ScopeLogs {
+            log_records: ["EventA", "EventB"]
+                .into_iter()
+                .map(
+                    |event_name| opentelemetry_proto::tonic::logs::v1::LogRecord {
+                        observed_time_unix_nano: 1_700_000_000_000_000_001,
+                        event_name: event_name.to_string(),
+                        ..Default::default()
+                    },
+                )
+                .collect(),
+            ..Default::default()
+        };
+        let bytes = ExportLogsServiceRequest {
+            resource_logs: vec![opentelemetry_proto::tonic::logs::v1::ResourceLogs {
+                scope_logs: vec![scope_logs],
+                ..Default::default()
+            }],
+        }
+        .encode_to_vec();
+
+        let mut batches_ptr: *mut EncodedBatchesHandle = std::ptr::null_mut();
+        let rc = unsafe {
+            geneva_encode_and_compress_logs(
+                handle_ptr,
+                bytes.as_ptr(),
+                bytes.len(),
+                &mut batches_ptr,
+            )
+        };
+        assert_eq!(rc, GenevaError::Success, "encode failed");
+        assert_eq!(unsafe { geneva_batches_len(batches_ptr) }, 2);
+        batches_ptr
+    }
+
+    #[test]
+    #[cfg(feature = "mock_auth")]
+    fn test_upload_batches_async_reports_each_batch() {
+        let mock_server = runtime().block_on(wiremock::MockServer::start());
+        let client = mock_geneva_client(&mock_server, Duration::ZERO);
+        let handle_ptr = Box::into_raw(Box::new(GenevaClientHandle::new(
+            client,
+            new_runtime().unwrap(),
+        )));
+        let batches_ptr = encode_two_batches(handle_ptr);
+
+        let results = UploadResults::default();
+        let user_data = &results as *const UploadResults as *mut c_void;
+        let rc = unsafe {
+            geneva_upload_batches_async(handle_ptr, batches_ptr, Some(record_result), user_data)
+        };
+        assert_eq!(rc, GenevaError::Success);
+        // The batches were copied; the handle can go away while uploads run.
+        unsafe { geneva_batches_free(batches_ptr) };
+
+        for _ in 0..500 {
+            if results.lock().unwrap().len() == 2 {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        let mut reported = results.lock().unwrap().clone();
+        reported.sort_by_key(|(index, _)| *index);
+        assert_eq!(
+            reported,
+            vec![(0, GenevaError::Success), (1, GenevaError::Success)]
+        );
+
+        unsafe { geneva_client_free(handle_ptr) };
+    }
+
+    #[test]
+    #[cfg(feature = "mock_auth")]
+    fn test_client_free_cancels_pending_async_uploads() {
+        let mock_server = runtime().block_on(wiremock::MockServer::start());
+        let client = mock_geneva_client(&mock_server, Duration::from_secs(30));
+        let handle_ptr = Box::into_raw(Box::new(GenevaClientHandle::new(
+            client,
+            new_runtime().unwrap(),
+        )));
+        let batches_ptr = encode_two_batches(handle_ptr);
+
+        let results = UploadResults::default();
+        let user_data = &results as *const UploadResults as *mut c_void;
+        let rc = unsafe {
+            geneva_upload_batch_async(handle_ptr, batches_ptr, 1, Some(record_result), user_data)
+        };
+        assert_eq!(rc, GenevaError::Success);
+        let rc = unsafe {
+            geneva_upload_batch_async(handle_ptr, batches_ptr, 2, Some(record_result), user_data)
+        };
+        assert_eq!(rc, GenevaError::IndexOutOfRange);
+
+        unsafe {
+            geneva_batches_free(batches_ptr);
+            geneva_client_free(handle_ptr);
+        }
+        // The callback ran before geneva_client_free returned
+        assert_eq!(
+            *results.lock().unwrap(),
+            vec![(1, GenevaError::UploadCancelled)]
+        );
+    }
+
+    #[test]
+    fn test_last_error_message() {
+        unsafe {
+            let mut out: *mut GenevaClientHandle = std::ptr::null_mut();
+            let rc = geneva_client_new(std::ptr::null(), &mut out);
+            assert_eq!(rc, GenevaError::NullPointer);
+            let message = CStr::from_ptr(geneva_last_error_message());
+            assert_eq!(message.to_str().unwrap(), "config or out_handle is null");
+
+            let mut info = std::mem::MaybeUninit::<GenevaBatchInfo>::uninit();
+            let rc = geneva_batch_info(ptr::null(), 0, info.as_mut_ptr());
+            assert_eq!(rc, GenevaError::NullPointer);
+            let message = CStr::from_ptr(geneva_last_error_message());
+            assert_eq!(message.to_str().unwrap(), "handle is null");
+        }
+
+        // The message is per thread
+        let other = std::thread::spawn(|| unsafe { geneva_last_error_message().is_null() });
+        assert!(other.join().unwrap());
+    }
+
+    #[test]
+    #[cfg(feature = "mock_auth")]
+    fn test_batch_info() {
+        let mock_server = runtime().block_on(wiremock::MockServer::start());
+        let client = mock_geneva_client(&mock_server, Duration::ZERO);
+        let handle_ptr = Box::into_raw(Box::new(GenevaClientHandle::new(
+            client,
+            new_runtime().unwrap(),
+        )));
+        let batches_ptr = encode_two_batches(handle_ptr);
+
+        let mut event_names = Vec::new();
+        for index in 0..2 {
+            let mut info = std::mem::MaybeUninit::<GenevaBatchInfo>::uninit();
+            let rc = unsafe { geneva_batch_info(batches_ptr, index, info.as_mut_ptr()) };
+            assert_eq!(rc, GenevaError::Success);
+            let info = unsafe { info.assume_init() };
+
+            let event_name = unsafe {
+                std::slice::from_raw_parts(info.event_name.cast::<u8>(), info.event_name_len)
+            };
+            event_names.push(String::from_utf8(event_name.to_vec()).unwrap());
+            let data = unsafe { std::slice::from_raw_parts(info.data, info.data_len) };
+            assert!(!data.is_empty());
+            assert_eq!(info.start_time_unix_nano, 1_700_000_000_000_000_001);
+            assert_eq!(info.end_time_unix_nano, 1_700_000_000_000_000_001);
+            // One schema: a 32-character MD5 hex digest
+            assert_eq!(info.schema_ids_len, 32);
+        }
+        event_names.sort();
+        assert_eq!(event_names, vec!["EventA", "EventB"]);
+
+        let mut info = std::mem::MaybeUninit::<GenevaBatchInfo>::uninit();
+        let rc = unsafe { geneva_batch_info(batches_ptr, 2, info.as_mut_ptr()) };
+        assert_eq!(rc, GenevaError::IndexOutOfRange);
+        let message = unsafe { CStr::from_ptr(geneva_last_error_message()) };
+        assert_eq!(
+            message.to_str().unwrap(),
+            "batch index 2 is out of range (2 batches)"
+        );
+
+        unsafe {
+            geneva_batches_free(batches_ptr);
+            geneva_client_free(handle_ptr);
+        }
+    }
 }
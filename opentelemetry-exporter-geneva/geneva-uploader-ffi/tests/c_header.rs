@@ -0,0 +1,32 @@
+//! Checks that include/geneva_ffi.h matches the header cbindgen generates from src/lib.rs.
+//!
+//! Run with `GENEVA_FFI_UPDATE_HEADER=1` to rewrite the checked-in header.
+
+use std::path::Path;
+
+#[test]
+fn c_header_is_up_to_date() {
+    let crate_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let config = cbindgen::Config::from_file(crate_dir.join("cbindgen.toml"))
+        .expect("failed to read cbindgen.toml");
+    let mut generated = Vec::new();
+    cbindgen::Builder::new()
+        .with_config(config)
+        .with_src(crate_dir.join("src/lib.rs"))
+        .generate()
+        .expect("failed to generate C header")
+        .write(&mut generated);
+    let generated = String::from_utf8(generated).expect("header is not UTF-8");
+
+    let header_path = crate_dir.join("include/geneva_ffi.h");
+    if std::env::var_os("GENEVA_FFI_UPDATE_HEADER").is_some() {
+        std::fs::write(&header_path, &generated).expect("failed to write header");
+        return;
+    }
+    let checked_in = std::fs::read_to_string(&header_path).expect("failed to read header");
+    assert!(
+        checked_in == generated,
+        "include/geneva_ffi.h is out of date; regenerate it with \
+         `GENEVA_FFI_UPDATE_HEADER=1 cargo test -p geneva-uploader-ffi --test c_header`"
+    );
+}
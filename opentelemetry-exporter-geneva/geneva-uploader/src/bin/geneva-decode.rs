@@ -0,0 +1,77 @@
+//! Dumps Geneva batch files as JSON.
+//!
+//! Accepts spooled batches (`*.batch` files from the on-disk spool), raw LZ4-chunked upload
+//! bodies and uncompressed CentralBlobs. Each file is printed as one JSON document holding the
+//! upload metadata, the Bond schemas and the decoded rows.
+//!
+//! ```text
+//! geneva-decode [--compact] [FILE...]
+//! ```
+//!
+//! Reads standard input when no file (or `-`) is given.
+
+use geneva_uploader::decode_batch_file;
+use std::io::{self, Read, Write};
+use std::process::ExitCode;
+
+const USAGE: &str = "Usage: geneva-decode [--compact] [FILE...]
+
+Decodes Geneva batch files (spooled batches, LZ4-chunked upload bodies or
+uncompressed CentralBlobs) and prints each one as JSON.
+Reads standard input when no file (or -) is given.
+
+Options:
+  --compact   print one JSON document per line
+  -h, --help  print this help";
+
+fn main() -> ExitCode {
+    let mut compact = false;
+    let mut files = Vec::new();
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "--compact" => compact = true,
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return ExitCode::SUCCESS;
+            }
+            "-" => files.push(arg),
+            option if option.starts_with('-') => {
+                eprintln!("geneva-decode: unknown option {option}\n\n{USAGE}");
+                return ExitCode::from(2);
+            }
+            _ => files.push(arg),
+        }
+    }
+    if files.is_empty() {
+        files.push("-".to_string());
+    }
+
+    let mut status = ExitCode::SUCCESS;
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    for file in &files {
+        if let Err(e) = dump(file, compact, &mut out) {
+            eprintln!("geneva-decode: {file}: {e}");
+            status = ExitCode::FAILURE;
+        }
+    }
+    status
+}
+
+fn dump(file: &str, compact: bool, out: &mut impl Write) -> Result<(), Box<dyn std::error::Error>> {
+    let bytes = if file == "-" {
+        let mut bytes = Vec::new();
+        io::stdin().read_to_end(&mut bytes)?;
+        bytes
+    } else {
+        std::fs::read(file)?
+    };
+    let blob = decode_batch_file(&bytes)?;
+    if compact {
+        serde_json::to_writer(&mut *out, &blob)?;
+    } else {
+        serde_json::to_writer_pretty(&mut *out, &blob)?;
+    }
+    writeln!(out)?;
+    Ok(())
+}
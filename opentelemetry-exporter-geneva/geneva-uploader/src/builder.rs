@@ -0,0 +1,695 @@
+// Builder and environment-variable configuration for GenevaClientConfig
+
+use crate::client::GenevaClientConfig;
+use crate::config_service::client::AuthMethod;
+use crate::config_service::token_refresh::TokenRefreshConfig;
+use crate::ingestion_service::retry::RetryPolicy;
+use crate::payload_encoder::attribute_encoding::AttributeEncoding;
+use crate::payload_encoder::batch_limits::BatchLimits;
+use crate::payload_encoder::column_mapping::ColumnMapping;
+use crate::payload_encoder::resource_mapping::ResourceMapping;
+use crate::spool::file_spool::SpoolConfig;
+use opentelemetry::metrics::Meter;
+use percent_encoding::percent_decode_str;
+use std::path::PathBuf;
+use thiserror::Error;
+
+/// Errors returned when building a [`GenevaClientConfig`].
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ConfigError {
+    /// A required setting was not provided.
+    #[error("missing required setting `{0}`")]
+    Missing(&'static str),
+    /// A setting has an invalid value.
+    #[error("invalid value for `{setting}`: {reason}")]
+    Invalid {
+        setting: &'static str,
+        reason: String,
+    },
+    /// A required environment variable is not set.
+    #[error("environment variable {0} is not set")]
+    MissingEnvVar(&'static str),
+    /// An environment variable has an invalid value.
+    #[error("invalid value for environment variable {name}: {reason}")]
+    InvalidEnvVar { name: &'static str, reason: String },
+}
+
+/// Builder for [`GenevaClientConfig`], created with [`GenevaClientConfig::builder`].
+///
+/// `endpoint`, `environment`, `account`, `namespace`, `region`, `config_major_version`,
+/// `auth_method`, `tenant`, `role_name` and `role_instance` are required; everything else
+/// defaults to the same values as [`GenevaClientConfig`]'s documented defaults.
+#[derive(Debug, Default)]
+pub struct GenevaClientConfigBuilder {
+    endpoint: Option<String>,
+    environment: Option<String>,
+    account: Option<String>,
+    namespace: Option<String>,
+    region: Option<String>,
+    config_major_version: Option<u32>,
+    auth_method: Option<AuthMethod>,
+    msi_resource: Option<String>,
+    msi_endpoint: Option<String>,
+    tenant: Option<String>,
+    role_name: Option<String>,
+    role_instance: Option<String>,
+    spool: Option<SpoolConfig>,
+    retry_policy: RetryPolicy,
+    attribute_encoding: AttributeEncoding,
+    resource_mapping: ResourceMapping,
+    column_mapping: ColumnMapping,
+    batch_limits: BatchLimits,
+    meter: Option<Meter>,
+    token_refresh: TokenRefreshConfig,
+}
+
+impl GenevaClientConfigBuilder {
+    /// Geneva Config Service endpoint URL (`http` or `https`).
+    pub fn endpoint(mut self, endpoint: impl Into<String>) -> Self {
+        self.endpoint = Some(endpoint.into());
+        self
+    }
+
+    pub fn environment(mut self, environment: impl Into<String>) -> Self {
+        self.environment = Some(environment.into());
+        self
+    }
+
+    pub fn account(mut self, account: impl Into<String>) -> Self {
+        self.account = Some(account.into());
+        self
+    }
+
+    pub fn namespace(mut self, namespace: impl Into<String>) -> Self {
+        self.namespace = Some(namespace.into());
+        self
+    }
+
+    /// Azure region name, e.g. `eastus`. Normalized to lowercase.
+    pub fn region(mut self, region: impl Into<String>) -> Self {
+        self.region = Some(region.into());
+        self
+    }
+
+    pub fn config_major_version(mut self, version: u32) -> Self {
+        self.config_major_version = Some(version);
+        self
+    }
+
+    pub fn auth_method(mut self, auth_method: AuthMethod) -> Self {
+        self.auth_method = Some(auth_method);
+        self
+    }
+
+    /// Resource (audience) for Managed Identity tokens.
+    pub fn msi_resource(mut self, resource: impl Into<String>) -> Self {
+        self.msi_resource = Some(resource.into());
+        self
+    }
+
+    /// Managed Identity token endpoint speaking the IMDS protocol, e.g. a local metadata proxy.
+    /// Defaults to the Azure IMDS endpoint.
+    pub fn msi_endpoint(mut self, endpoint: impl Into<String>) -> Self {
+        self.msi_endpoint = Some(endpoint.into());
+        self
+    }
+
+    pub fn tenant(mut self, tenant: impl Into<String>) -> Self {
+        self.tenant = Some(tenant.into());
+        self
+    }
+
+    pub fn role_name(mut self, role_name: impl Into<String>) -> Self {
+        self.role_name = Some(role_name.into());
+        self
+    }
+
+    pub fn role_instance(mut self, role_instance: impl Into<String>) -> Self {
+        self.role_instance = Some(role_instance.into());
+        self
+    }
+
+    /// Enables the on-disk spool for failed uploads.
+    pub fn spool(mut self, spool: SpoolConfig) -> Self {
+        self.spool = Some(spool);
+        self
+    }
+
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    pub fn attribute_encoding(mut self, attribute_encoding: AttributeEncoding) -> Self {
+        self.attribute_encoding = attribute_encoding;
+        self
+    }
+
+    pub fn resource_mapping(mut self, resource_mapping: ResourceMapping) -> Self {
+        self.resource_mapping = resource_mapping;
+        self
+    }
+
+    pub fn column_mapping(mut self, column_mapping: ColumnMapping) -> Self {
+        self.column_mapping = column_mapping;
+        self
+    }
+
+    pub fn batch_limits(mut self, batch_limits: BatchLimits) -> Self {
+        self.batch_limits = batch_limits;
+        self
+    }
+
+    /// Meter used to record the client's own metrics.
+    pub fn meter(mut self, meter: Meter) -> Self {
+        self.meter = Some(meter);
+        self
+    }
+
+    /// Ingestion token renewal and on-disk token cache.
+    pub fn token_refresh(mut self, token_refresh: TokenRefreshConfig) -> Self {
+        self.token_refresh = token_refresh;
+        self
+    }
+
+    /// Validates the settings and returns the configuration.
+    pub fn build(self) -> Result<GenevaClientConfig, ConfigError> {
+        let endpoint = required(self.endpoint, "endpoint")?;
+        validate_endpoint(&endpoint, "endpoint")?;
+        let environment = required_segment(self.environment, "environment")?;
+        let account = required_segment(self.account, "account")?;
+        let namespace = required_segment(self.namespace, "namespace")?;
+        let region = required(self.region, "region")?.trim().to_ascii_lowercase();
+        if region.is_empty() || !region.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Err(ConfigError::Invalid {
+                setting: "region",
+                reason: format!("`{region}` is not an Azure region name such as `eastus`"),
+            });
+        }
+        let config_major_version = self
+            .config_major_version
+            .ok_or(ConfigError::Missing("config_major_version"))?;
+        if config_major_version == 0 {
+            return Err(ConfigError::Invalid {
+                setting: "config_major_version",
+                reason: "must be at least 1".to_string(),
+            });
+        }
+        let auth_method = self
+            .auth_method
+            .ok_or(ConfigError::Missing("auth_method"))?;
+        let is_managed_identity = matches!(
+            auth_method,
+            AuthMethod::ManagedIdentity
+                | AuthMethod::UserManagedIdentity { .. }
+                | AuthMethod::UserManagedIdentityByObjectId { .. }
+                | AuthMethod::UserManagedIdentityByResourceId { .. }
+        );
+        if is_managed_identity && self.msi_resource.as_deref().map_or(true, str::is_empty) {
+            return Err(ConfigError::Missing("msi_resource"));
+        }
+        if let Some(msi_endpoint) = self.msi_endpoint.as_deref() {
+            validate_endpoint(msi_endpoint, "msi_endpoint")?;
+        }
+        let limits = &self.batch_limits;
+        if limits.max_events == 0
+            || limits.max_uncompressed_bytes == 0
+            || limits.max_compressed_bytes == 0
+        {
+            return Err(ConfigError::Invalid {
+                setting: "batch_limits",
+                reason: "limits must be at least 1".to_string(),
+            });
+        }
+        if self.token_refresh.retry_interval.is_zero() {
+            return Err(ConfigError::Invalid {
+                setting: "token_refresh",
+                reason: "retry_interval must not be zero".to_string(),
+            });
+        }
+
+        Ok(GenevaClientConfig {
+            endpoint,
+            environment,
+            account,
+            namespace,
+            region,
+            config_major_version,
+            auth_method,
+            msi_resource: self.msi_resource,
+            msi_endpoint: self.msi_endpoint,
+            tenant: required_segment(self.tenant, "tenant")?,
+            role_name: required_segment(self.role_name, "role_name")?,
+            role_instance: required_segment(self.role_instance, "role_instance")?,
+            spool: self.spool,
+            retry_policy: self.retry_policy,
+            attribute_encoding: self.attribute_encoding,
+            resource_mapping: self.resource_mapping,
+            column_mapping: self.column_mapping,
+            batch_limits: self.batch_limits,
+            meter: self.meter,
+            token_refresh: self.token_refresh,
+        })
+    }
+}
+
+fn required(value: Option<String>, setting: &'static str) -> Result<String, ConfigError> {
+    value.ok_or(ConfigError::Missing(setting))
+}
+
+/// Values that end up in the `key=value/...` upload metadata must be non-empty and must not
+/// contain its separators.
+fn required_segment(value: Option<String>, setting: &'static str) -> Result<String, ConfigError> {
+    let value = required(value, setting)?;
+    if value.trim().is_empty() {
+        return Err(ConfigError::Invalid {
+            setting,
+            reason: "must not be empty".to_string(),
+        });
+    }
+    if value.contains(['/', '=']) {
+        return Err(ConfigError::Invalid {
+            setting,
+            reason: "must not contain '/' or '='".to_string(),
+        });
+    }
+    Ok(value)
+}
+
+fn validate_endpoint(endpoint: &str, setting: &'static str) -> Result<(), ConfigError> {
+    let url = reqwest::Url::parse(endpoint).map_err(|e| ConfigError::Invalid {
+        setting,
+        reason: e.to_string(),
+    })?;
+    if !matches!(url.scheme(), "http" | "https") || url.host_str().is_none() {
+        return Err(ConfigError::Invalid {
+            setting,
+            reason: format!("`{endpoint}` is not an http(s) URL"),
+        });
+    }
+    Ok(())
+}
+
+impl GenevaClientConfig {
+    /// Returns a builder that validates the configuration.
+    pub fn builder() -> GenevaClientConfigBuilder {
+        GenevaClientConfigBuilder::default()
+    }
+
+    /// Reads the configuration from environment variables.
+    ///
+    /// | Variable | Setting |
+    /// |---|---|
+    /// | `GENEVA_ENDPOINT`, `GENEVA_ENVIRONMENT`, `GENEVA_ACCOUNT`, `GENEVA_NAMESPACE`, `GENEVA_REGION`, `GENEVA_CONFIG_MAJOR_VERSION` | required |
+    /// | `GENEVA_TENANT` | tenant, default `default` |
+    /// | `GENEVA_ROLE_NAME` | role name, falling back to `OTEL_SERVICE_NAME` |
+    /// | `GENEVA_ROLE_INSTANCE` | role instance, falling back to `service.instance.id` in `OTEL_RESOURCE_ATTRIBUTES`, then `HOSTNAME` |
+    /// | `GENEVA_AUTH_METHOD` | `certificate` (default when `GENEVA_CERT_PATH` is set) or `managed_identity` |
+    /// | `GENEVA_CERT_PATH`, `GENEVA_CERT_PASSWORD` | PKCS#12 certificate |
+    /// | `GENEVA_CERT_PATH`, `GENEVA_KEY_PATH` | PEM certificate and PKCS#8 key |
+    /// | `GENEVA_MSI_CLIENT_ID`, `GENEVA_MSI_OBJECT_ID`, `GENEVA_MSI_RESOURCE_ID` | user-assigned identity (system-assigned if none is set) |
+    /// | `GENEVA_MSI_RESOURCE` | Managed Identity token resource |
+    /// | `GENEVA_MSI_ENDPOINT` | Managed Identity token endpoint, default the Azure IMDS endpoint |
+    /// | `GENEVA_SPOOL_DIR` | enables the on-disk spool in this directory |
+    /// | `GENEVA_TOKEN_CACHE_FILE` | persists the ingestion token to this file |
+    /// | `GENEVA_RETRY_MAX_ATTEMPTS` | retry policy attempts (`1` disables retries) |
+    /// | `GENEVA_BATCH_MAX_EVENTS`, `GENEVA_BATCH_MAX_UNCOMPRESSED_BYTES`, `GENEVA_BATCH_MAX_COMPRESSED_BYTES` | batch limits |
+    pub fn from_env() -> Result<Self, ConfigError> {
+        Self::from_env_with(|name| std::env::var(name).ok().filter(|v| !v.is_empty()))
+    }
+
+    pub(crate) fn from_env_with(
+        var: impl Fn(&'static str) -> Option<String>,
+    ) -> Result<Self, ConfigError> {
+        let require = |name: &'static str| var(name).ok_or(ConfigError::MissingEnvVar(name));
+        let parse_u32 = |name: &'static str, value: String| {
+            value
+                .trim()
+                .parse::<u32>()
+                .map_err(|e| ConfigError::InvalidEnvVar {
+                    name,
+                    reason: e.to_string(),
+                })
+        };
+
+        let mut builder = Self::builder()
+            .endpoint(require("GENEVA_ENDPOINT")?)
+            .environment(require("GENEVA_ENVIRONMENT")?)
+            .account(require("GENEVA_ACCOUNT")?)
+            .namespace(require("GENEVA_NAMESPACE")?)
+            .region(require("GENEVA_REGION")?)
+            .config_major_version(parse_u32(
+                "GENEVA_CONFIG_MAJOR_VERSION",
+                require("GENEVA_CONFIG_MAJOR_VERSION")?,
+            )?)
+            .tenant(var("GENEVA_TENANT").unwrap_or_else(|| "default".to_string()))
+            .role_name(
+                var("GENEVA_ROLE_NAME")
+                    .or_else(|| var("OTEL_SERVICE_NAME"))
+                    .ok_or(ConfigError::MissingEnvVar("GENEVA_ROLE_NAME"))?,
+            )
+            .role_instance(
+                var("GENEVA_ROLE_INSTANCE")
+                    .or_else(|| {
+                        var("OTEL_RESOURCE_ATTRIBUTES")
+                            .and_then(|attrs| resource_attribute(&attrs, "service.instance.id"))
+                    })
+                    .or_else(|| var("HOSTNAME"))
+                    .ok_or(ConfigError::MissingEnvVar("GENEVA_ROLE_INSTANCE"))?,
+            )
+            .auth_method(auth_method_from_env(&var)?);
+
+        if let Some(resource) = var("GENEVA_MSI_RESOURCE") {
+            builder = builder.msi_resource(resource);
+        }
+        if let Some(endpoint) = var("GENEVA_MSI_ENDPOINT") {
+            builder = builder.msi_endpoint(endpoint);
+        }
+        if let Some(dir) = var("GENEVA_SPOOL_DIR") {
+            builder = builder.spool(SpoolConfig::new(dir));
+        }
+        if let Some(path) = var("GENEVA_TOKEN_CACHE_FILE") {
+            builder = builder.token_refresh(TokenRefreshConfig {
+                cache_file: Some(path.into()),
+                ..TokenRefreshConfig::default()
+            });
+        }
+        if let Some(attempts) = var("GENEVA_RETRY_MAX_ATTEMPTS") {
+            let max_attempts = parse_u32("GENEVA_RETRY_MAX_ATTEMPTS", attempts)?.max(1);
+            builder = builder.retry_policy(RetryPolicy {
+                max_attempts,
+                ..RetryPolicy::default()
+            });
+        }
+
+        let mut batch_limits = BatchLimits::default();
+        for (name, limit) in [
+            ("GENEVA_BATCH_MAX_EVENTS", &mut batch_limits.max_events),
+            (
+                "GENEVA_BATCH_MAX_UNCOMPRESSED_BYTES",
+                &mut batch_limits.max_uncompressed_bytes,
+            ),
+            (
+                "GENEVA_BATCH_MAX_COMPRESSED_BYTES",
+                &mut batch_limits.max_compressed_bytes,
+            ),
+        ] {
+            if let Some(value) = var(name) {
+                *limit = value
+                    .trim()
+                    .parse::<usize>()
+                    .map_err(|e| ConfigError::InvalidEnvVar {
+                        name,
+                        reason: e.to_string(),
+                    })?;
+            }
+        }
+
+        builder.batch_limits(batch_limits).build()
+    }
+}
+
+fn auth_method_from_env(
+    var: &impl Fn(&'static str) -> Option<String>,
+) -> Result<AuthMethod, ConfigError> {
+    let method = match var("GENEVA_AUTH_METHOD") {
+        Some(method) => method.trim().to_ascii_lowercase(),
+        None if var("GENEVA_CERT_PATH").is_some() => "certificate".to_string(),
+        None => return Err(ConfigError::MissingEnvVar("GENEVA_AUTH_METHOD")),
+    };
+    match method.as_str() {
+        "certificate" | "cert" => {
+            let cert_path = PathBuf::from(
+                var("GENEVA_CERT_PATH").ok_or(ConfigError::MissingEnvVar("GENEVA_CERT_PATH"))?,
+            );
+            match var("GENEVA_KEY_PATH") {
+                Some(key_path) => Ok(AuthMethod::CertificatePem {
+                    cert_path,
+                    key_path: PathBuf::from(key_path),
+                }),
+                None => Ok(AuthMethod::Certificate {
+                    path: cert_path,
+                    password: var("GENEVA_CERT_PASSWORD").unwrap_or_default(),
+                }),
+            }
+        }
+        "managed_identity" | "msi" => Ok(
+            match (
+                var("GENEVA_MSI_CLIENT_ID"),
+                var("GENEVA_MSI_OBJECT_ID"),
+                var("GENEVA_MSI_RESOURCE_ID"),
+            ) {
+                (Some(client_id), _, _) => AuthMethod::UserManagedIdentity { client_id },
+                (None, Some(object_id), _) => {
+                    AuthMethod::UserManagedIdentityByObjectId { object_id }
+                }
+                (None, None, Some(resource_id)) => {
+                    AuthMethod::UserManagedIdentityByResourceId { resource_id }
+                }
+                (None, None, None) => AuthMethod::ManagedIdentity,
+            },
+        ),
+        other => Err(ConfigError::InvalidEnvVar {
+            name: "GENEVA_AUTH_METHOD",
+            reason: format!(
+                "unknown auth method `{other}`, expected `certificate` or `managed_identity`"
+            ),
+        }),
+    }
+}
+
+/// Looks up `key` in an `OTEL_RESOURCE_ATTRIBUTES` value (`key1=value1,key2=value2`),
+/// whose keys and values are percent-encoded.
+fn resource_attribute(attributes: &str, key: &str) -> Option<String> {
+    let decode = |s: &str| {
+        percent_decode_str(s.trim())
+            .decode_utf8_lossy()
+            .into_owned()
+    };
+    attributes.split(',').find_map(|pair| {
+        let (k, v) = pair.split_once('=')?;
+        (decode(k) == key).then(|| decode(v))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn builder() -> GenevaClientConfigBuilder {
+        GenevaClientConfig::builder()
+            .endpoint("https://geneva.example.com")
+            .environment("Test")
+            .account("acct")
+            .namespace("ns")
+            .region("EastUS")
+            .config_major_version(2)
+            .auth_method(AuthMethod::Certificate {
+                path: PathBuf::from("/tmp/client.p12"),
+                password: String::new(),
+            })
+            .tenant("tenant")
+            .role_name("role")
+            .role_instance("instance")
+    }
+
+    #[test]
+    fn test_builder_validates() {
+        let cfg = builder().build().unwrap();
+        assert_eq!(cfg.region, "eastus");
+        assert!(cfg.spool.is_none());
+
+        assert_eq!(
+            builder().namespace("").build().unwrap_err(),
+            ConfigError::Invalid {
+                setting: "namespace",
+                reason: "must not be empty".to_string()
+            }
+        );
+        assert!(matches!(
+            builder().region("east us").build(),
+            Err(ConfigError::Invalid {
+                setting: "region",
+                ..
+            })
+        ));
+        assert!(matches!(
+            builder().endpoint("not a url").build(),
+            Err(ConfigError::Invalid {
+                setting: "endpoint",
+                ..
+            })
+        ));
+        assert!(matches!(
+            builder().endpoint("ftp://geneva.example.com").build(),
+            Err(ConfigError::Invalid {
+                setting: "endpoint",
+                ..
+            })
+        ));
+        assert!(matches!(
+            builder().role_name("a/b").build(),
+            Err(ConfigError::Invalid {
+                setting: "role_name",
+                ..
+            })
+        ));
+        assert_eq!(
+            GenevaClientConfig::builder().build().unwrap_err(),
+            ConfigError::Missing("endpoint")
+        );
+        assert_eq!(
+            builder()
+                .auth_method(AuthMethod::ManagedIdentity)
+                .build()
+                .unwrap_err(),
+            ConfigError::Missing("msi_resource")
+        );
+        assert!(matches!(
+            builder().msi_endpoint("169.254.169.254").build(),
+            Err(ConfigError::Invalid {
+                setting: "msi_endpoint",
+                ..
+            })
+        ));
+        assert!(matches!(
+            builder()
+                .batch_limits(BatchLimits {
+                    max_events: 0,
+                    ..BatchLimits::default()
+                })
+                .build(),
+            Err(ConfigError::Invalid {
+                setting: "batch_limits",
+                ..
+            })
+        ));
+        assert!(matches!(
+            builder()
+                .token_refresh(TokenRefreshConfig {
+                    retry_interval: std::time::Duration::ZERO,
+                    ..TokenRefreshConfig::default()
+                })
+                .build(),
+            Err(ConfigError::Invalid {
+                setting: "token_refresh",
+                ..
+            })
+        ));
+    }
+
+    fn from_vars(vars: &[(&'static str, &str)]) -> Result<GenevaClientConfig, ConfigError> {
+        let vars: HashMap<&str, String> = vars.iter().map(|(k, v)| (*k, v.to_string())).collect();
+        GenevaClientConfig::from_env_with(|name| vars.get(name).cloned())
+    }
+
+    const BASE: &[(&str, &str)] = &[
+        ("GENEVA_ENDPOINT", "https://geneva.example.com"),
+        ("GENEVA_ENVIRONMENT", "Test"),
+        ("GENEVA_ACCOUNT", "acct"),
+        ("GENEVA_NAMESPACE", "ns"),
+        ("GENEVA_REGION", "westus2"),
+        ("GENEVA_CONFIG_MAJOR_VERSION", "2"),
+    ];
+
+    #[test]
+    fn test_from_env_certificate() {
+        let mut vars = BASE.to_vec();
+        vars.extend([
+            ("GENEVA_CERT_PATH", "/tmp/client.p12"),
+            ("GENEVA_CERT_PASSWORD", "secret"),
+            ("OTEL_SERVICE_NAME", "checkout"),
+            (
+                "OTEL_RESOURCE_ATTRIBUTES",
+                "deployment.environment=prod, service.instance.id=pod%2D7",
+            ),
+            ("GENEVA_RETRY_MAX_ATTEMPTS", "1"),
+            ("GENEVA_BATCH_MAX_EVENTS", "500"),
+        ]);
+        let cfg = from_vars(&vars).unwrap();
+        assert_eq!(cfg.role_name, "checkout");
+        assert_eq!(cfg.role_instance, "pod-7");
+        assert_eq!(cfg.tenant, "default");
+        assert_eq!(cfg.config_major_version, 2);
+        assert_eq!(cfg.retry_policy.max_attempts, 1);
+        assert_eq!(cfg.batch_limits.max_events, 500);
+        assert_eq!(
+            cfg.batch_limits.max_compressed_bytes,
+            BatchLimits::default().max_compressed_bytes
+        );
+        assert!(matches!(
+            cfg.auth_method,
+            AuthMethod::Certificate { ref password, .. } if password == "secret"
+        ));
+    }
+
+    #[test]
+    fn test_from_env_managed_identity() {
+        let mut vars = BASE.to_vec();
+        vars.extend([
+            ("GENEVA_AUTH_METHOD", "managed_identity"),
+            ("GENEVA_MSI_CLIENT_ID", "client-id"),
+            ("GENEVA_MSI_RESOURCE", "https://monitor.example"),
+            (
+                "GENEVA_MSI_ENDPOINT",
+                "http://127.0.0.1:8080/metadata/identity/oauth2/token",
+            ),
+            ("GENEVA_ROLE_NAME", "role"),
+            ("HOSTNAME", "vm-1"),
+            ("GENEVA_SPOOL_DIR", "/var/spool/geneva"),
+            ("GENEVA_TOKEN_CACHE_FILE", "/var/cache/geneva/token.json"),
+        ]);
+        let cfg = from_vars(&vars).unwrap();
+        assert!(matches!(
+            cfg.auth_method,
+            AuthMethod::UserManagedIdentity { ref client_id } if client_id == "client-id"
+        ));
+        assert_eq!(cfg.msi_resource.as_deref(), Some("https://monitor.example"));
+        assert_eq!(
+            cfg.msi_endpoint.as_deref(),
+            Some("http://127.0.0.1:8080/metadata/identity/oauth2/token")
+        );
+        assert_eq!(cfg.role_instance, "vm-1");
+        assert!(cfg.spool.is_some());
+        assert_eq!(
+            cfg.token_refresh.cache_file.as_deref(),
+            Some(std::path::Path::new("/var/cache/geneva/token.json"))
+        );
+    }
+
+    #[test]
+    fn test_from_env_errors() {
+        assert_eq!(
+            from_vars(&BASE[1..]).unwrap_err(),
+            ConfigError::MissingEnvVar("GENEVA_ENDPOINT")
+        );
+
+        let mut vars = BASE.to_vec();
+        vars.push(("GENEVA_CONFIG_MAJOR_VERSION", "two"));
+        vars.remove(5);
+        assert!(matches!(
+            from_vars(&vars),
+            Err(ConfigError::InvalidEnvVar {
+                name: "GENEVA_CONFIG_MAJOR_VERSION",
+                ..
+            })
+        ));
+
+        let mut vars = BASE.to_vec();
+        vars.extend([("GENEVA_ROLE_NAME", "role"), ("HOSTNAME", "vm-1")]);
+        assert_eq!(
+            from_vars(&vars).unwrap_err(),
+            ConfigError::MissingEnvVar("GENEVA_AUTH_METHOD")
+        );
+        vars.push(("GENEVA_AUTH_METHOD", "kerberos"));
+        assert!(matches!(
+            from_vars(&vars),
+            Err(ConfigError::InvalidEnvVar {
+                name: "GENEVA_AUTH_METHOD",
+                ..
+            })
+        ));
+    }
+}
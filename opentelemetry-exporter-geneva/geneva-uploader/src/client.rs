@@ -1,9 +1,21 @@
 //! High-level GenevaClient for user code. Wraps config_service and ingestion_service.
 
 use crate::config_service::client::{AuthMethod, GenevaConfigClient, GenevaConfigClientConfig};
+use crate::config_service::token_refresh::{spawn_refresh_task, TokenRefreshConfig};
+use crate::error::GenevaError;
+use crate::ingestion_service::retry::RetryPolicy;
 use crate::ingestion_service::uploader::{GenevaUploader, GenevaUploaderConfig};
+use crate::payload_encoder::attribute_encoding::AttributeEncoding;
+use crate::payload_encoder::batch_limits::BatchLimits;
+use crate::payload_encoder::column_mapping::ColumnMapping;
+use crate::payload_encoder::decoder::{decode_payload, DecodeError, DecodedBlob};
 use crate::payload_encoder::otlp_encoder::OtlpEncoder;
+use crate::payload_encoder::resource_mapping::{RecordContext, ResourceMapping};
+use crate::self_metrics::SelfMetrics;
+use crate::spool::file_spool::{FileSpool, SpoolConfig};
+use opentelemetry::metrics::Meter;
 use opentelemetry_proto::tonic::logs::v1::ResourceLogs;
+use opentelemetry_proto::tonic::metrics::v1::ResourceMetrics;
 use opentelemetry_proto::tonic::trace::v1::ResourceSpans;
 use std::sync::Arc;
 
@@ -16,8 +28,19 @@ pub struct EncodedBatch {
     pub metadata: crate::payload_encoder::central_blob::BatchMetadata,
 }
 
+impl EncodedBatch {
+    /// Decodes the compressed payload back into its schemas and rows, e.g. to inspect a batch
+    /// rejected by the ingestion gateway.
+    pub fn decode(&self) -> Result<DecodedBlob, DecodeError> {
+        decode_payload(&self.data)
+    }
+}
+
 /// Configuration for GenevaClient (user-facing)
+///
+/// Build it with [`GenevaClientConfig::builder`] or [`GenevaClientConfig::from_env`].
 #[derive(Clone, Debug)]
+#[non_exhaustive]
 pub struct GenevaClientConfig {
     pub endpoint: String,
     pub environment: String,
@@ -26,9 +49,31 @@ pub struct GenevaClientConfig {
     pub region: String,
     pub config_major_version: u32,
     pub auth_method: AuthMethod,
+    /// Resource (audience) for Managed Identity tokens. Required when `auth_method` is a Managed Identity.
+    pub msi_resource: Option<String>,
+    /// Managed Identity token endpoint speaking the IMDS protocol. `None` uses the Azure IMDS endpoint.
+    pub msi_endpoint: Option<String>,
     pub tenant: String,
     pub role_name: String,
     pub role_instance: String,
+    /// Optional on-disk spool for batches that fail to upload. `None` disables spooling.
+    pub spool: Option<SpoolConfig>,
+    /// Retry policy applied to each batch upload. Use `RetryPolicy::no_retry()` to disable retries.
+    pub retry_policy: RetryPolicy,
+    /// Encoding of array, kvlist and bytes attribute values and non-string log bodies.
+    pub attribute_encoding: AttributeEncoding,
+    /// Promotion of resource and instrumentation-scope attributes into logs and spans.
+    pub resource_mapping: ResourceMapping,
+    /// Column renames and attribute filtering, redaction, hashing and type coercion.
+    pub column_mapping: ColumnMapping,
+    /// Maximum event count and uncompressed/compressed size of each uploaded batch.
+    pub batch_limits: BatchLimits,
+    /// Meter used to record the client's own metrics (records and batches encoded, compressed
+    /// and uncompressed bytes, upload outcomes and latency, token cache hits and refreshes).
+    /// `None` disables self-observability.
+    pub meter: Option<Meter>,
+    /// Background renewal of the ingestion token and optional on-disk token cache.
+    pub token_refresh: TokenRefreshConfig,
     // Add event name/version here if constant, or per-upload if you want them per call.
 }
 
@@ -38,10 +83,16 @@ pub struct GenevaClient {
     uploader: Arc<GenevaUploader>,
     encoder: OtlpEncoder,
     metadata: String,
+    spool: Option<Arc<FileSpool>>,
+    metrics: Option<Arc<SelfMetrics>>,
 }
 
 impl GenevaClient {
-    pub fn new(cfg: GenevaClientConfig) -> Result<Self, String> {
+    pub fn new(cfg: GenevaClientConfig) -> Result<Self, GenevaError> {
+        let metrics = cfg
+            .meter
+            .as_ref()
+            .map(|meter| Arc::new(SelfMetrics::new(meter)));
         let config_client_config = GenevaConfigClientConfig {
             endpoint: cfg.endpoint,
             environment: cfg.environment.clone(),
@@ -50,10 +101,13 @@ impl GenevaClient {
             region: cfg.region,
             config_major_version: cfg.config_major_version,
             auth_method: cfg.auth_method,
+            msi_resource: cfg.msi_resource,
+            msi_endpoint: cfg.msi_endpoint,
         };
         let config_client = Arc::new(
-            GenevaConfigClient::new(config_client_config)
-                .map_err(|e| format!("GenevaConfigClient init failed: {e}"))?,
+            GenevaConfigClient::new(config_client_config)?
+                .with_metrics(metrics.clone())
+                .with_token_refresh(cfg.token_refresh),
         );
 
         let source_identity = format!(
@@ -73,55 +127,178 @@ impl GenevaClient {
             source_identity,
             environment: cfg.environment,
             config_version: config_version.clone(),
+            retry_policy: cfg.retry_policy,
+            metrics: metrics.clone(),
         };
 
-        let uploader = GenevaUploader::from_config_client(config_client, uploader_config)
-            .map_err(|e| format!("GenevaUploader init failed: {e}"))?;
+        let uploader = GenevaUploader::from_config_client(config_client, uploader_config)?;
 
-        Ok(Self {
+        let spool = cfg
+            .spool
+            .map(|spool_cfg| {
+                FileSpool::open(spool_cfg)
+                    .map(Arc::new)
+                    .map_err(|e| GenevaError::Spool {
+                        message: format!("failed to open spool: {e}"),
+                    })
+            })
+            .transpose()?;
+
+        let client = Self {
             uploader: Arc::new(uploader),
-            encoder: OtlpEncoder::new(),
+            encoder: OtlpEncoder::with_config(
+                cfg.attribute_encoding,
+                cfg.resource_mapping,
+                cfg.column_mapping,
+                cfg.batch_limits,
+            )
+            .with_metrics(metrics.clone()),
             metadata,
-        })
+            spool,
+            metrics,
+        };
+        client.ensure_background_tasks();
+        Ok(client)
     }
 
     /// Encode OTLP logs into LZ4 chunked compressed batches.
     pub fn encode_and_compress_logs(
         &self,
         logs: &[ResourceLogs],
-    ) -> Result<Vec<EncodedBatch>, String> {
-        let log_iter = logs
-            .iter()
-            .flat_map(|resource_log| resource_log.scope_logs.iter())
-            .flat_map(|scope_log| scope_log.log_records.iter());
+    ) -> Result<Vec<EncodedBatch>, GenevaError> {
+        let log_iter = logs.iter().flat_map(|resource_log| {
+            let resource = resource_log
+                .resource
+                .as_ref()
+                .map_or(&[][..], |r| &r.attributes[..]);
+            resource_log.scope_logs.iter().flat_map(move |scope_log| {
+                let context = RecordContext {
+                    resource,
+                    scope: scope_log.scope.as_ref(),
+                };
+                scope_log.log_records.iter().map(move |log| (log, context))
+            })
+        });
 
-        self.encoder
-            .encode_log_batch(log_iter, &self.metadata)
-            .map_err(|e| format!("Compression failed: {e}"))
+        self.encoder.encode_log_batch(log_iter, &self.metadata)
     }
 
     /// Encode OTLP spans into LZ4 chunked compressed batches.
     pub fn encode_and_compress_spans(
         &self,
         spans: &[ResourceSpans],
-    ) -> Result<Vec<EncodedBatch>, String> {
-        let span_iter = spans
+    ) -> Result<Vec<EncodedBatch>, GenevaError> {
+        let span_iter = spans.iter().flat_map(|resource_span| {
+            let resource = resource_span
+                .resource
+                .as_ref()
+                .map_or(&[][..], |r| &r.attributes[..]);
+            resource_span
+                .scope_spans
+                .iter()
+                .flat_map(move |scope_span| {
+                    let context = RecordContext {
+                        resource,
+                        scope: scope_span.scope.as_ref(),
+                    };
+                    scope_span.spans.iter().map(move |span| (span, context))
+                })
+        });
+
+        self.encoder.encode_span_batch(span_iter, &self.metadata)
+    }
+
+    /// Encode OTLP metrics into compressed batches, one row per data point.
+    pub fn encode_and_compress_metrics(
+        &self,
+        metrics: &[ResourceMetrics],
+    ) -> Result<Vec<EncodedBatch>, GenevaError> {
+        let metric_iter = metrics
             .iter()
-            .flat_map(|resource_span| resource_span.scope_spans.iter())
-            .flat_map(|scope_span| scope_span.spans.iter());
+            .flat_map(|resource_metric| resource_metric.scope_metrics.iter())
+            .flat_map(|scope_metric| scope_metric.metrics.iter());
 
         self.encoder
-            .encode_span_batch(span_iter, &self.metadata)
-            .map_err(|e| format!("Compression failed: {e}"))
+            .encode_metric_batch(metric_iter, &self.metadata)
     }
 
     /// Upload a single compressed batch.
-    /// This allows for granular control over uploads, including custom retry logic for individual batches.
-    pub async fn upload_batch(&self, batch: &EncodedBatch) -> Result<(), String> {
-        self.uploader
+    /// Transient failures are retried according to `GenevaClientConfig::retry_policy`.
+    ///
+    /// If a spool is configured, a batch that fails to upload with a retryable error is persisted
+    /// to disk and replayed in the background; the upload error is still returned to the caller.
+    pub async fn upload_batch(&self, batch: &EncodedBatch) -> Result<(), GenevaError> {
+        self.ensure_background_tasks();
+        match self
+            .uploader
             .upload(batch.data.clone(), &batch.event_name, &batch.metadata)
             .await
-            .map(|_| ())
-            .map_err(|e| format!("Geneva upload failed: {e} Event: {}", batch.event_name))
+        {
+            Ok(_) => Ok(()),
+            Err(e) => {
+                let source: GenevaError = e.into();
+                // Permanent rejections would fail again on replay, only spool transient failures
+                let stored = match &self.spool {
+                    Some(spool) if source.is_retryable() => {
+                        let spool = Arc::clone(spool);
+                        let batch = batch.clone();
+                        // Blocking file writes and fsync, kept off the async worker threads
+                        Some(
+                            tokio::task::spawn_blocking(move || spool.store(&batch))
+                                .await
+                                .map_err(|e| e.to_string())
+                                .and_then(|stored| stored.map_err(|e| e.to_string())),
+                        )
+                    }
+                    _ => None,
+                };
+                let spooled = matches!(stored, Some(Ok(_)));
+                if !spooled {
+                    if let Some(metrics) = &self.metrics {
+                        metrics.record_dropped("upload_failed", batch.metadata.event_count);
+                    }
+                }
+                Err(GenevaError::Upload {
+                    event_name: batch.event_name.clone(),
+                    spooled,
+                    spool_error: stored.and_then(Result::err),
+                    source: Box::new(source),
+                })
+            }
+        }
+    }
+
+    /// Records `batch` as dropped because an exporter-side upload queue was full, in the
+    /// `geneva.batches.dropped` and `geneva.records.dropped` metrics. Does nothing unless
+    /// `GenevaClientConfig::meter` is set.
+    pub fn record_queue_overflow(&self, batch: &EncodedBatch) {
+        if let Some(metrics) = &self.metrics {
+            metrics.record_dropped("queue_full", batch.metadata.event_count);
+        }
+    }
+
+    /// Replay batches persisted in the spool, oldest first.
+    ///
+    /// The background task started by the client already does this every
+    /// `SpoolConfig::replay_interval`; this method allows triggering a pass explicitly,
+    /// e.g. right before shutdown. Returns the number of batches uploaded.
+    pub async fn replay_spooled(&self) -> Result<usize, GenevaError> {
+        match &self.spool {
+            Some(spool) => Ok(crate::spool::replay(spool, &self.uploader).await?),
+            None => Ok(0),
+        }
+    }
+
+    /// Number of batches currently waiting in the spool.
+    pub fn spooled_batches(&self) -> usize {
+        self.spool.as_ref().map_or(0, |spool| spool.pending().len())
+    }
+
+    /// Starts the token refresher and the spool replay task, once a Tokio runtime is available
+    fn ensure_background_tasks(&self) {
+        spawn_refresh_task(&self.uploader.config_client);
+        if let Some(spool) = &self.spool {
+            crate::spool::spawn_replay_task(spool, &self.uploader);
+        }
     }
 }
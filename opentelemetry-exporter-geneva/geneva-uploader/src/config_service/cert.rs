@@ -0,0 +1,140 @@
+// Client certificate loading for mTLS to the Geneva Config Service
+
+use crate::config_service::client::{AuthMethod, GenevaConfigClientError, Result};
+use native_tls::Identity;
+use std::fs;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+/// Where the client certificate used for mTLS comes from.
+#[derive(Clone, Debug)]
+pub(crate) enum CertificateSource {
+    Pkcs12File {
+        path: PathBuf,
+        password: String,
+    },
+    PemFiles {
+        cert_path: PathBuf,
+        key_path: PathBuf,
+    },
+    Pkcs12Bytes {
+        pkcs12: Vec<u8>,
+        password: String,
+    },
+    PemBytes {
+        cert_pem: Vec<u8>,
+        key_pem: Vec<u8>,
+    },
+}
+
+/// Modification time and length of each certificate file, compared before the files are
+/// read and hashed so that an unchanged certificate costs one `stat` per file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct FileStamp(Vec<(SystemTime, u64)>);
+
+/// A parsed identity together with a fingerprint of the bytes it was built from.
+pub(crate) struct LoadedIdentity {
+    pub(crate) identity: Identity,
+    pub(crate) fingerprint: String,
+}
+
+impl CertificateSource {
+    /// Returns the certificate source for certificate-based auth methods, `None` otherwise.
+    pub(crate) fn from_auth_method(auth_method: &AuthMethod) -> Option<Self> {
+        match auth_method {
+            AuthMethod::Certificate { path, password } => Some(Self::Pkcs12File {
+                path: path.clone(),
+                password: password.clone(),
+            }),
+            AuthMethod::CertificatePem {
+                cert_path,
+                key_path,
+            } => Some(Self::PemFiles {
+                cert_path: cert_path.clone(),
+                key_path: key_path.clone(),
+            }),
+            AuthMethod::CertificateBytes { pkcs12, password } => Some(Self::Pkcs12Bytes {
+                pkcs12: pkcs12.clone(),
+                password: password.clone(),
+            }),
+            AuthMethod::CertificatePemBytes { cert_pem, key_pem } => Some(Self::PemBytes {
+                cert_pem: cert_pem.clone(),
+                key_pem: key_pem.clone(),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Whether the certificate is read from disk and can therefore change while running.
+    pub(crate) fn is_file_based(&self) -> bool {
+        matches!(self, Self::Pkcs12File { .. } | Self::PemFiles { .. })
+    }
+
+    /// Stamp of the certificate file(s), or `None` for in-memory certificates and when a
+    /// file's metadata cannot be read.
+    pub(crate) fn file_stamp(&self) -> Option<FileStamp> {
+        let paths = match self {
+            Self::Pkcs12File { path, .. } => vec![path],
+            Self::PemFiles {
+                cert_path,
+                key_path,
+            } => vec![cert_path, key_path],
+            Self::Pkcs12Bytes { .. } | Self::PemBytes { .. } => return None,
+        };
+        paths
+            .into_iter()
+            .map(|path| {
+                let metadata = fs::metadata(path).ok()?;
+                Some((metadata.modified().ok()?, metadata.len()))
+            })
+            .collect::<Option<_>>()
+            .map(FileStamp)
+    }
+
+    /// Reads and parses the certificate.
+    pub(crate) fn load(&self) -> Result<LoadedIdentity> {
+        let (first, second) = self.read()?;
+        let identity = match self {
+            Self::Pkcs12File { password, .. } | Self::Pkcs12Bytes { password, .. } => {
+                Identity::from_pkcs12(&first, password)
+            }
+            Self::PemFiles { .. } | Self::PemBytes { .. } => Identity::from_pkcs8(&first, &second),
+        }
+        .map_err(|e| GenevaConfigClientError::Certificate(e.to_string()))?;
+        Ok(LoadedIdentity {
+            identity,
+            fingerprint: fingerprint(&first, &second),
+        })
+    }
+
+    /// Fingerprint of the current certificate material, used to detect rotation on disk.
+    pub(crate) fn current_fingerprint(&self) -> Result<String> {
+        let (first, second) = self.read()?;
+        Ok(fingerprint(&first, &second))
+    }
+
+    /// Returns the certificate bytes and, for PEM sources, the private key bytes.
+    fn read(&self) -> Result<(Vec<u8>, Vec<u8>)> {
+        let read_file = |path: &PathBuf| {
+            fs::read(path).map_err(|e| {
+                GenevaConfigClientError::Certificate(format!("{}: {e}", path.display()))
+            })
+        };
+        Ok(match self {
+            Self::Pkcs12File { path, .. } => (read_file(path)?, Vec::new()),
+            Self::PemFiles {
+                cert_path,
+                key_path,
+            } => (read_file(cert_path)?, read_file(key_path)?),
+            Self::Pkcs12Bytes { pkcs12, .. } => (pkcs12.clone(), Vec::new()),
+            Self::PemBytes { cert_pem, key_pem } => (cert_pem.clone(), key_pem.clone()),
+        })
+    }
+}
+
+fn fingerprint(first: &[u8], second: &[u8]) -> String {
+    let mut ctx = md5::Context::new();
+    ctx.consume(first);
+    ctx.consume(second);
+    format!("{:x}", ctx.finalize())
+}
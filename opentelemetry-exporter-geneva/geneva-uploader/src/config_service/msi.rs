@@ -0,0 +1,329 @@
+// Managed Identity token acquisition from the Azure Instance Metadata Service (IMDS)
+
+use crate::config_service::client::{GenevaConfigClientError, Result};
+use chrono::{DateTime, Utc};
+use reqwest::{header::ACCEPT, Client};
+use serde::Deserialize;
+use std::sync::RwLock;
+use std::time::Duration;
+
+/// Default IMDS token endpoint, reachable from Azure VMs, VM scale sets and AKS nodes.
+pub(crate) const IMDS_TOKEN_ENDPOINT: &str =
+    "http://169.254.169.254/metadata/identity/oauth2/token";
+const IMDS_API_VERSION: &str = "2018-02-01";
+
+/// Tokens are refreshed this long before they expire.
+const REFRESH_MARGIN_MINUTES: i64 = 5;
+
+/// Identity to request a token for.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum ManagedIdentitySelector {
+    SystemAssigned,
+    ClientId(String),
+    ObjectId(String),
+    ResourceId(String),
+}
+
+#[derive(Debug, Deserialize)]
+struct ImdsTokenResponse {
+    access_token: String,
+    // IMDS returns both as strings of seconds
+    expires_on: Option<String>,
+    expires_in: Option<String>,
+}
+
+struct CachedToken {
+    token: String,
+    expires_at: DateTime<Utc>,
+}
+
+/// Acquires and caches access tokens from IMDS for the configured identity and resource.
+pub(crate) struct ImdsTokenProvider {
+    http_client: Client,
+    endpoint: String,
+    identity: ManagedIdentitySelector,
+    resource: String,
+    cached: RwLock<Option<CachedToken>>,
+    // Held while fetching, so callers that find the token expired share one IMDS request
+    fetch_lock: tokio::sync::Mutex<()>,
+}
+
+impl ImdsTokenProvider {
+    /// Requests tokens from `endpoint`, or from [`IMDS_TOKEN_ENDPOINT`] when it is `None`.
+    pub(crate) fn new(
+        identity: ManagedIdentitySelector,
+        resource: String,
+        endpoint: Option<String>,
+    ) -> Result<Self> {
+        // IMDS is link-local and answers quickly; don't let a missing endpoint stall uploads.
+        let http_client = Client::builder()
+            .timeout(Duration::from_secs(10))
+            .no_proxy()
+            .build()?;
+        Ok(Self {
+            http_client,
+            endpoint: endpoint.unwrap_or_else(|| IMDS_TOKEN_ENDPOINT.to_string()),
+            identity,
+            resource,
+            cached: RwLock::new(None),
+            fetch_lock: tokio::sync::Mutex::new(()),
+        })
+    }
+
+    /// Returns a valid access token, fetching a new one from IMDS when none is cached or the
+    /// cached one expires within the refresh margin.
+    pub(crate) async fn get_token(&self) -> Result<String> {
+        if let Some(token) = self.cached_token() {
+            return Ok(token);
+        }
+
+        let _fetch = self.fetch_lock.lock().await;
+        // Another caller may have refreshed the token while this one waited for the lock
+        if let Some(token) = self.cached_token() {
+            return Ok(token);
+        }
+        let fresh = self.fetch_token().await?;
+        let token = fresh.token.clone();
+        let mut guard = self
+            .cached
+            .write()
+            .map_err(|_| GenevaConfigClientError::InternalError("RwLock poisoned".to_string()))?;
+        *guard = Some(fresh);
+        Ok(token)
+    }
+
+    fn cached_token(&self) -> Option<String> {
+        let guard = self.cached.read().ok()?;
+        let cached = guard.as_ref()?;
+        (cached.expires_at > Utc::now() + chrono::Duration::minutes(REFRESH_MARGIN_MINUTES))
+            .then(|| cached.token.clone())
+    }
+
+    /// Drops the cached token so that the next call fetches a fresh one.
+    pub(crate) fn invalidate(&self) {
+        if let Ok(mut guard) = self.cached.write() {
+            *guard = None;
+        }
+    }
+
+    async fn fetch_token(&self) -> Result<CachedToken> {
+        let mut query = vec![
+            ("api-version", IMDS_API_VERSION),
+            ("resource", self.resource.as_str()),
+        ];
+        match &self.identity {
+            ManagedIdentitySelector::SystemAssigned => {}
+            ManagedIdentitySelector::ClientId(id) => query.push(("client_id", id)),
+            ManagedIdentitySelector::ObjectId(id) => query.push(("object_id", id)),
+            ManagedIdentitySelector::ResourceId(id) => query.push(("msi_res_id", id)),
+        }
+
+        let response = self
+            .http_client
+            .get(&self.endpoint)
+            .query(&query)
+            .header("Metadata", "true")
+            .header(ACCEPT, "application/json")
+            .send()
+            .await?;
+        let status = response.status();
+        let body = response.text().await?;
+        if !status.is_success() {
+            return Err(GenevaConfigClientError::ManagedIdentity {
+                status: Some(status.as_u16()),
+                message: body,
+            });
+        }
+
+        let parsed: ImdsTokenResponse =
+            serde_json::from_str(&body).map_err(|e| GenevaConfigClientError::ManagedIdentity {
+                status: None,
+                message: format!("Failed to parse IMDS token response: {e}"),
+            })?;
+        let expires_at =
+            token_expiry(&parsed).ok_or_else(|| GenevaConfigClientError::ManagedIdentity {
+                status: None,
+                message: "IMDS token response has no valid expiry".to_string(),
+            })?;
+
+        Ok(CachedToken {
+            token: parsed.access_token,
+            expires_at,
+        })
+    }
+}
+
+/// Prefers the absolute `expires_on`; falls back to `expires_in` relative to now.
+fn token_expiry(response: &ImdsTokenResponse) -> Option<DateTime<Utc>> {
+    if let Some(expires_on) = response.expires_on.as_deref() {
+        if let Some(at) = expires_on
+            .trim()
+            .parse::<i64>()
+            .ok()
+            .and_then(|secs| DateTime::from_timestamp(secs, 0))
+        {
+            return Some(at);
+        }
+    }
+    let expires_in = response.expires_in.as_deref()?.trim().parse::<i64>().ok()?;
+    Some(Utc::now() + chrono::Duration::seconds(expires_in))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{header, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn token_body(token: &str, expires_in_secs: i64) -> serde_json::Value {
+        let expires_on = Utc::now().timestamp() + expires_in_secs;
+        serde_json::json!({
+            "access_token": token,
+            "expires_on": expires_on.to_string(),
+            "expires_in": expires_in_secs.to_string(),
+            "token_type": "Bearer",
+            "resource": "https://monitor.example"
+        })
+    }
+
+    fn provider(server: &MockServer, identity: ManagedIdentitySelector) -> ImdsTokenProvider {
+        ImdsTokenProvider::new(
+            identity,
+            "https://monitor.example".to_string(),
+            Some(format!("{}/metadata/identity/oauth2/token", server.uri())),
+        )
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_system_assigned_token_is_cached() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/metadata/identity/oauth2/token"))
+            .and(header("Metadata", "true"))
+            .and(query_param("api-version", IMDS_API_VERSION))
+            .and(query_param("resource", "https://monitor.example"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(token_body("tok-1", 3600)))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let provider = provider(&server, ManagedIdentitySelector::SystemAssigned);
+        assert_eq!(provider.get_token().await.unwrap(), "tok-1");
+        assert_eq!(provider.get_token().await.unwrap(), "tok-1");
+    }
+
+    #[tokio::test]
+    async fn test_user_assigned_identity_is_sent() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(query_param("client_id", "my-client-id"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(token_body("tok-c", 3600)))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(query_param("msi_res_id", "/subscriptions/x/identity"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(token_body("tok-r", 3600)))
+            .mount(&server)
+            .await;
+
+        let by_client = provider(
+            &server,
+            ManagedIdentitySelector::ClientId("my-client-id".into()),
+        );
+        assert_eq!(by_client.get_token().await.unwrap(), "tok-c");
+        let by_resource = provider(
+            &server,
+            ManagedIdentitySelector::ResourceId("/subscriptions/x/identity".into()),
+        );
+        assert_eq!(by_resource.get_token().await.unwrap(), "tok-r");
+    }
+
+    #[tokio::test]
+    async fn test_token_close_to_expiry_is_refreshed() {
+        let server = MockServer::start().await;
+        // Expires within the refresh margin, so every call fetches again
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(token_body("short", 60)))
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let provider = provider(&server, ManagedIdentitySelector::SystemAssigned);
+        provider.get_token().await.unwrap();
+        provider.get_token().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_callers_share_one_fetch() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(token_body("tok", 3600))
+                    .set_delay(Duration::from_millis(100)),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let provider = provider(&server, ManagedIdentitySelector::SystemAssigned);
+        let tokens = futures::future::join_all((0..8).map(|_| provider.get_token())).await;
+        assert!(tokens.into_iter().all(|token| token.unwrap() == "tok"));
+    }
+
+    #[tokio::test]
+    async fn test_invalidate_forces_refetch() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(token_body("tok", 3600)))
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let provider = provider(&server, ManagedIdentitySelector::SystemAssigned);
+        provider.get_token().await.unwrap();
+        provider.invalidate();
+        provider.get_token().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_imds_error_is_reported() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(400).set_body_string("Identity not found"))
+            .mount(&server)
+            .await;
+
+        let provider = provider(&server, ManagedIdentitySelector::ObjectId("nope".into()));
+        match provider.get_token().await {
+            Err(GenevaConfigClientError::ManagedIdentity { status, message }) => {
+                assert_eq!(status, Some(400));
+                assert_eq!(message, "Identity not found");
+            }
+            other => panic!(
+                "Expected ManagedIdentity error, got: {:?}",
+                other.map(|_| ())
+            ),
+        }
+    }
+
+    #[test]
+    fn test_token_expiry_falls_back_to_expires_in() {
+        let response = ImdsTokenResponse {
+            access_token: String::new(),
+            expires_on: Some("not-a-number".into()),
+            expires_in: Some("120".into()),
+        };
+        let expiry = token_expiry(&response).unwrap();
+        let delta = expiry - Utc::now();
+        assert!(delta > chrono::Duration::seconds(100) && delta <= chrono::Duration::seconds(120));
+
+        let missing = ImdsTokenResponse {
+            access_token: String::new(),
+            expires_on: None,
+            expires_in: None,
+        };
+        assert!(token_expiry(&missing).is_none());
+    }
+}
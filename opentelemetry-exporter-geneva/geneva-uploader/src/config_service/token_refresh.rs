@@ -0,0 +1,380 @@
+// Background renewal of the ingestion token and its on-disk cache
+
+use crate::config_service::client::{
+    AuthMethod, GenevaConfigClient, GenevaConfigClientConfig, IngestionGatewayInfo, MonikerInfo,
+};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+
+const CACHE_FORMAT_VERSION: u32 = 1;
+
+/// Renewal of the ingestion token obtained from the Geneva config service.
+///
+/// A background task renews the token `refresh_before_expiry` ahead of its expiry, so uploads
+/// do not wait for the config service. While a renewal is pending or failing, uploads keep
+/// using the current token as long as it is still valid.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TokenRefreshConfig {
+    /// How long before expiry the token is renewed. Default: 5 minutes.
+    pub refresh_before_expiry: Duration,
+    /// Delay between two renewal attempts after a failure, and the minimum delay between two
+    /// renewals when the config service issues very short-lived tokens. Default: 30 seconds.
+    pub retry_interval: Duration,
+    /// File holding the last token obtained, so that a restarted process can upload without
+    /// waiting for the config service. It contains a bearer token and is created with
+    /// owner-only permissions on Unix. `None` disables persistence.
+    pub cache_file: Option<PathBuf>,
+}
+
+impl Default for TokenRefreshConfig {
+    fn default() -> Self {
+        TokenRefreshConfig {
+            refresh_before_expiry: Duration::from_secs(5 * 60),
+            retry_interval: Duration::from_secs(30),
+            cache_file: None,
+        }
+    }
+}
+
+/// Last good config service response, as persisted in `TokenRefreshConfig::cache_file`
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct PersistedToken {
+    version: u32,
+    /// Identifies the config service account the token was issued for
+    key: String,
+    endpoint: String,
+    auth_token: String,
+    auth_token_expiry_time: String,
+    moniker: String,
+    account_group: String,
+}
+
+impl PersistedToken {
+    pub(crate) fn new(
+        config: &GenevaConfigClientConfig,
+        gateway: &IngestionGatewayInfo,
+        moniker: &MonikerInfo,
+    ) -> Self {
+        PersistedToken {
+            version: CACHE_FORMAT_VERSION,
+            key: cache_key(config),
+            endpoint: gateway.endpoint.clone(),
+            auth_token: gateway.auth_token.clone(),
+            auth_token_expiry_time: gateway.auth_token_expiry_time.clone(),
+            moniker: moniker.name.clone(),
+            account_group: moniker.account_group.clone(),
+        }
+    }
+
+    /// Reads the cached token, if the file exists and was written for `config`
+    pub(crate) fn load(
+        path: &Path,
+        config: &GenevaConfigClientConfig,
+    ) -> Option<(IngestionGatewayInfo, MonikerInfo)> {
+        let bytes = fs::read(path).ok()?;
+        let persisted: PersistedToken = serde_json::from_slice(&bytes).ok()?;
+        if persisted.version != CACHE_FORMAT_VERSION || persisted.key != cache_key(config) {
+            return None;
+        }
+        Some((
+            IngestionGatewayInfo {
+                endpoint: persisted.endpoint,
+                auth_token: persisted.auth_token,
+                auth_token_expiry_time: persisted.auth_token_expiry_time,
+            },
+            MonikerInfo {
+                name: persisted.moniker,
+                account_group: persisted.account_group,
+            },
+        ))
+    }
+
+    /// Writes the token next to `path` and renames it into place, so readers never see a
+    /// partial file
+    pub(crate) fn store(&self, path: &Path) -> io::Result<()> {
+        let json = serde_json::to_vec(self)?;
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        let mut tmp_name = path.as_os_str().to_owned();
+        tmp_name.push(".tmp");
+        let tmp = PathBuf::from(tmp_name);
+
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = options.open(&tmp)?;
+        file.write_all(&json)?;
+        file.sync_all()?;
+        drop(file);
+        fs::rename(&tmp, path)
+    }
+}
+
+fn cache_key(config: &GenevaConfigClientConfig) -> String {
+    format!(
+        "{}|{}|{}|{}|{}|{}|{}",
+        config.endpoint,
+        config.environment,
+        config.account,
+        config.namespace,
+        config.region,
+        config.config_major_version,
+        auth_identity(config)
+    )
+}
+
+/// Auth method and the identity it authenticates as, so that a token is not reused after
+/// switching certificates or managed identities. Secrets are left out and in-memory
+/// certificates are represented by their digest.
+fn auth_identity(config: &GenevaConfigClientConfig) -> String {
+    let msi_resource = config.msi_resource.as_deref().unwrap_or_default();
+    match &config.auth_method {
+        AuthMethod::Certificate { path, .. } => format!("certificate:{}", path.display()),
+        AuthMethod::CertificatePem {
+            cert_path,
+            key_path,
+        } => format!(
+            "certificate_pem:{}:{}",
+            cert_path.display(),
+            key_path.display()
+        ),
+        AuthMethod::CertificateBytes { pkcs12, .. } => {
+            format!("certificate_bytes:{:x}", md5::compute(pkcs12))
+        }
+        AuthMethod::CertificatePemBytes { cert_pem, .. } => {
+            format!("certificate_pem_bytes:{:x}", md5::compute(cert_pem))
+        }
+        AuthMethod::ManagedIdentity => format!("msi:system:{msi_resource}"),
+        AuthMethod::UserManagedIdentity { client_id } => {
+            format!("msi:client_id={client_id}:{msi_resource}")
+        }
+        AuthMethod::UserManagedIdentityByObjectId { object_id } => {
+            format!("msi:object_id={object_id}:{msi_resource}")
+        }
+        AuthMethod::UserManagedIdentityByResourceId { resource_id } => {
+            format!("msi:resource_id={resource_id}:{msi_resource}")
+        }
+        #[cfg(feature = "mock_auth")]
+        AuthMethod::MockAuth => "mock".to_string(),
+    }
+}
+
+/// Spawns the token renewal loop on the current Tokio runtime, once per config client.
+///
+/// The loop sleeps until the cached token is due for renewal, or until an upload finds it due
+/// and wakes the loop up. It holds only a weak reference to the client and exits once the
+/// client is dropped. Does nothing when called outside a Tokio runtime; tokens are then
+/// renewed on the upload path.
+pub(crate) fn spawn_refresh_task(client: &Arc<GenevaConfigClient>) {
+    let Ok(handle) = tokio::runtime::Handle::try_current() else {
+        return;
+    };
+    if !client.mark_refresher_started() {
+        return;
+    }
+
+    let wake_up = client.refresh_wake_up();
+    let client: Weak<GenevaConfigClient> = Arc::downgrade(client);
+    handle.spawn(async move {
+        while let Some(delay) = refresh_pass(&client).await {
+            // Woken up early when an upload finds the token due for renewal
+            let _ = tokio::time::timeout(delay, wake_up.notified()).await;
+        }
+    });
+}
+
+/// Renews the token if it is due and returns the delay until the next pass, or `None` once
+/// the client is gone
+async fn refresh_pass(client: &Weak<GenevaConfigClient>) -> Option<Duration> {
+    let client = client.upgrade()?;
+    if client.next_refresh_delay() == Some(Duration::ZERO) {
+        // A failure is retried on a later pass; uploads keep the current token meanwhile
+        let _ = client.refresh_ingestion_info().await;
+    }
+    // Without a cached token there is nothing to renew until an upload fetches one
+    Some(client.next_refresh_delay().unwrap_or(Duration::MAX))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(namespace: &str) -> GenevaConfigClientConfig {
+        GenevaConfigClientConfig {
+            endpoint: "https://config.example.com".to_string(),
+            environment: "test".to_string(),
+            account: "test".to_string(),
+            namespace: namespace.to_string(),
+            region: "testregion".to_string(),
+            config_major_version: 1,
+            auth_method: AuthMethod::ManagedIdentity,
+            msi_resource: None,
+            msi_endpoint: None,
+        }
+    }
+
+    #[test]
+    fn test_persisted_token_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tokens").join("geneva.json");
+        let gateway = IngestionGatewayInfo {
+            endpoint: "https://gateway.example.com".to_string(),
+            auth_token: "token".to_string(),
+            auth_token_expiry_time: "2099-01-01T00:00:00Z".to_string(),
+        };
+        let moniker = MonikerInfo {
+            name: "diagaccount".to_string(),
+            account_group: "group".to_string(),
+        };
+        PersistedToken::new(&config("ns"), &gateway, &moniker)
+            .store(&path)
+            .unwrap();
+
+        let (loaded_gateway, loaded_moniker) = PersistedToken::load(&path, &config("ns")).unwrap();
+        assert_eq!(loaded_gateway.endpoint, gateway.endpoint);
+        assert_eq!(loaded_gateway.auth_token, gateway.auth_token);
+        assert_eq!(
+            loaded_gateway.auth_token_expiry_time,
+            gateway.auth_token_expiry_time
+        );
+        assert_eq!(loaded_moniker.name, moniker.name);
+        assert_eq!(loaded_moniker.account_group, moniker.account_group);
+
+        // A token issued for another namespace or identity is ignored
+        assert!(PersistedToken::load(&path, &config("other")).is_none());
+        let mut other_identity = config("ns");
+        other_identity.auth_method = AuthMethod::UserManagedIdentity {
+            client_id: "client-id".to_string(),
+        };
+        assert!(PersistedToken::load(&path, &other_identity).is_none());
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        fs::write(&path, b"not json").unwrap();
+        assert!(PersistedToken::load(&path, &config("ns")).is_none());
+        assert!(PersistedToken::load(&dir.path().join("missing.json"), &config("ns")).is_none());
+    }
+
+    #[cfg(feature = "mock_gateway")]
+    mod with_mock_gateway {
+        use super::super::TokenRefreshConfig;
+        use crate::mock::MockGeneva;
+        use crate::test_support::logs;
+        use crate::{GenevaClient, GenevaError};
+        use std::time::Duration;
+
+        fn client(mock: &MockGeneva, token_refresh: TokenRefreshConfig) -> GenevaClient {
+            let mut cfg = mock.client_config();
+            cfg.token_refresh = token_refresh;
+            GenevaClient::new(cfg).unwrap()
+        }
+
+        async fn upload(client: &GenevaClient) -> Result<(), GenevaError> {
+            let batches = client.encode_and_compress_logs(&logs("Log")).unwrap();
+            client.upload_batch(&batches[0]).await
+        }
+
+        async fn wait_for_config_requests(mock: &MockGeneva, count: usize) {
+            tokio::time::timeout(Duration::from_secs(5), async {
+                while mock.config_requests() < count {
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                }
+            })
+            .await
+            .expect("config service called");
+        }
+
+        #[tokio::test]
+        async fn test_restarted_client_uses_persisted_token() {
+            let mock = MockGeneva::start().await;
+            let dir = tempfile::tempdir().unwrap();
+            let token_refresh = TokenRefreshConfig {
+                cache_file: Some(dir.path().join("token.json")),
+                ..TokenRefreshConfig::default()
+            };
+
+            upload(&client(&mock, token_refresh.clone())).await.unwrap();
+            assert_eq!(mock.config_requests(), 1);
+
+            upload(&client(&mock, token_refresh)).await.unwrap();
+            assert_eq!(mock.config_requests(), 1);
+            assert_eq!(mock.events().len(), 2);
+        }
+
+        #[tokio::test]
+        async fn test_concurrent_refreshes_are_coalesced() {
+            let mock = MockGeneva::start().await;
+            mock.set_config_latency(Duration::from_millis(200));
+            let client = client(&mock, TokenRefreshConfig::default());
+
+            let (a, b, c, d) = tokio::join!(
+                upload(&client),
+                upload(&client),
+                upload(&client),
+                upload(&client)
+            );
+            for result in [a, b, c, d] {
+                result.unwrap();
+            }
+            assert_eq!(mock.config_requests(), 1);
+        }
+
+        #[tokio::test]
+        async fn test_stale_token_served_while_config_service_fails() {
+            let mock = MockGeneva::start().await;
+            mock.set_token_lifetime(Duration::from_secs(4 * 60));
+            let client = client(
+                &mock,
+                TokenRefreshConfig {
+                    retry_interval: Duration::from_millis(50),
+                    ..TokenRefreshConfig::default()
+                },
+            );
+            upload(&client).await.unwrap();
+
+            mock.fail_config_requests(1_000, 503);
+            upload(&client).await.unwrap();
+            // The refresher keeps retrying while uploads use the current token
+            wait_for_config_requests(&mock, 3).await;
+            upload(&client).await.unwrap();
+
+            let statuses: Vec<u16> = mock.uploads().iter().map(|u| u.status).collect();
+            assert_eq!(statuses, vec![202, 202, 202]);
+        }
+
+        #[tokio::test]
+        async fn test_token_renewed_before_expiry() {
+            let mock = MockGeneva::start().await;
+            mock.set_token_lifetime(Duration::from_secs(10 * 60));
+            let client = client(
+                &mock,
+                TokenRefreshConfig {
+                    refresh_before_expiry: Duration::from_secs(10 * 60 - 1),
+                    retry_interval: Duration::from_millis(100),
+                    ..TokenRefreshConfig::default()
+                },
+            );
+            upload(&client).await.unwrap();
+            assert_eq!(mock.config_requests(), 1);
+
+            // Renewed without any upload waiting for it
+            wait_for_config_requests(&mock, 2).await;
+            upload(&client).await.unwrap();
+            assert_eq!(mock.config_requests(), 2);
+        }
+    }
+}
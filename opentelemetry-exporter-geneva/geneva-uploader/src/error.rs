@@ -0,0 +1,339 @@
+// Public error type returned by GenevaClient
+
+use crate::builder::ConfigError;
+use crate::config_service::client::GenevaConfigClientError;
+use crate::ingestion_service::uploader::{
+    is_retryable_reqwest_error, is_retryable_status, GenevaUploaderError,
+};
+use std::fmt;
+use thiserror::Error;
+
+/// Broad category of a [`GenevaError`], e.g. for labelling error metrics.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorCategory {
+    /// Invalid or incomplete configuration.
+    Config,
+    /// Certificate, token or Managed Identity failure, or a 401/403 response.
+    Auth,
+    /// Connection failure or timeout before an HTTP response was received.
+    Transport,
+    /// HTTP error response from the config service or the ingestion gateway.
+    Http,
+    /// Encoding or compressing telemetry failed.
+    Encoding,
+    /// The on-disk spool could not be opened.
+    Spool,
+    /// Unexpected response or other internal failure.
+    Internal,
+}
+
+impl ErrorCategory {
+    /// Stable lowercase name of the category.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorCategory::Config => "config",
+            ErrorCategory::Auth => "auth",
+            ErrorCategory::Transport => "transport",
+            ErrorCategory::Http => "http",
+            ErrorCategory::Encoding => "encoding",
+            ErrorCategory::Spool => "spool",
+            ErrorCategory::Internal => "internal",
+        }
+    }
+}
+
+impl fmt::Display for ErrorCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Error returned by [`GenevaClient`](crate::GenevaClient).
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum GenevaError {
+    /// Invalid configuration.
+    Config(#[from] ConfigError),
+    /// Authentication failed, either while obtaining credentials or because a request was
+    /// rejected with 401/403.
+    Auth {
+        message: String,
+        status: Option<u16>,
+        retryable: bool,
+    },
+    /// The request could not be sent or no response was received.
+    Transport { message: String, retryable: bool },
+    /// The config service or ingestion gateway answered with an error status.
+    Http {
+        status: u16,
+        message: String,
+        retryable: bool,
+    },
+    /// Encoding or compressing a batch failed.
+    Encoding {
+        message: String,
+        event_name: Option<String>,
+    },
+    /// The on-disk spool could not be opened.
+    Spool { message: String },
+    /// Any other failure.
+    Internal { message: String },
+    /// Uploading the batch for `event_name` failed.
+    Upload {
+        event_name: String,
+        /// Whether the batch was persisted to the spool for replay.
+        spooled: bool,
+        /// Why the batch could not be spooled, if spooling was attempted and failed.
+        spool_error: Option<String>,
+        #[source]
+        source: Box<GenevaError>,
+    },
+}
+
+impl GenevaError {
+    /// Category of the underlying failure.
+    pub fn category(&self) -> ErrorCategory {
+        match self {
+            GenevaError::Config(_) => ErrorCategory::Config,
+            GenevaError::Auth { .. } => ErrorCategory::Auth,
+            GenevaError::Transport { .. } => ErrorCategory::Transport,
+            GenevaError::Http { .. } => ErrorCategory::Http,
+            GenevaError::Encoding { .. } => ErrorCategory::Encoding,
+            GenevaError::Spool { .. } => ErrorCategory::Spool,
+            GenevaError::Internal { .. } => ErrorCategory::Internal,
+            GenevaError::Upload { source, .. } => source.category(),
+        }
+    }
+
+    /// HTTP status of the failed request, if a response was received.
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            GenevaError::Auth { status, .. } => *status,
+            GenevaError::Http { status, .. } => Some(*status),
+            GenevaError::Upload { source, .. } => source.status(),
+            _ => None,
+        }
+    }
+
+    /// Whether the operation may succeed if tried again later.
+    ///
+    /// Uploads are already retried according to `GenevaClientConfig::retry_policy`, so a
+    /// retryable upload error means the retry budget was exhausted.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            GenevaError::Auth { retryable, .. }
+            | GenevaError::Transport { retryable, .. }
+            | GenevaError::Http { retryable, .. } => *retryable,
+            GenevaError::Upload { source, .. } => source.is_retryable(),
+            _ => false,
+        }
+    }
+
+    /// Event name of the batch being encoded or uploaded, if known.
+    pub fn event_name(&self) -> Option<&str> {
+        match self {
+            GenevaError::Encoding { event_name, .. } => event_name.as_deref(),
+            GenevaError::Upload { event_name, .. } => Some(event_name),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn encoding(message: impl fmt::Display, event_name: &str) -> Self {
+        GenevaError::Encoding {
+            message: message.to_string(),
+            event_name: Some(event_name.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for GenevaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GenevaError::Config(e) => write!(f, "invalid configuration: {e}"),
+            GenevaError::Auth {
+                message,
+                status: Some(status),
+                ..
+            } => write!(f, "authentication failed (status {status}): {message}"),
+            GenevaError::Auth { message, .. } => write!(f, "authentication failed: {message}"),
+            GenevaError::Transport { message, .. } => write!(f, "transport error: {message}"),
+            GenevaError::Http {
+                status, message, ..
+            } => write!(f, "request failed with status {status}: {message}"),
+            GenevaError::Encoding {
+                message,
+                event_name: Some(event_name),
+            } => write!(f, "encoding of event {event_name} failed: {message}"),
+            GenevaError::Encoding { message, .. } => write!(f, "encoding failed: {message}"),
+            GenevaError::Spool { message } => write!(f, "spool error: {message}"),
+            GenevaError::Internal { message } => write!(f, "internal error: {message}"),
+            GenevaError::Upload {
+                event_name,
+                spooled,
+                spool_error,
+                source,
+            } => {
+                write!(f, "Geneva upload failed for event {event_name}: {source}")?;
+                if *spooled {
+                    f.write_str(", batch spooled for replay")?;
+                } else if let Some(e) = spool_error {
+                    write!(f, ", spooling failed: {e}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+fn is_auth_status(status: u16) -> bool {
+    matches!(status, 401 | 403)
+}
+
+impl From<GenevaConfigClientError> for GenevaError {
+    fn from(err: GenevaConfigClientError) -> Self {
+        let retryable = err.is_retryable();
+        let message = err.to_string();
+        match err {
+            GenevaConfigClientError::AuthInfoNotFound(_)
+            | GenevaConfigClientError::JwtTokenError(_)
+            | GenevaConfigClientError::Certificate(_) => GenevaError::Auth {
+                message,
+                status: None,
+                retryable,
+            },
+            GenevaConfigClientError::ManagedIdentity { status, .. } => GenevaError::Auth {
+                message,
+                status,
+                retryable,
+            },
+            GenevaConfigClientError::Http(e) => GenevaError::Transport {
+                message,
+                retryable: is_retryable_reqwest_error(&e),
+            },
+            GenevaConfigClientError::RequestFailed { status, .. } if is_auth_status(status) => {
+                GenevaError::Auth {
+                    message,
+                    status: Some(status),
+                    retryable,
+                }
+            }
+            GenevaConfigClientError::RequestFailed { status, .. } => GenevaError::Http {
+                status,
+                message,
+                retryable: is_retryable_status(status),
+            },
+            GenevaConfigClientError::SerdeJson(_)
+            | GenevaConfigClientError::MonikerNotFound(_)
+            | GenevaConfigClientError::InternalError(_) => GenevaError::Internal { message },
+        }
+    }
+}
+
+impl From<GenevaUploaderError> for GenevaError {
+    fn from(err: GenevaUploaderError) -> Self {
+        let retryable = err.is_retryable();
+        match err {
+            GenevaUploaderError::ConfigClient(e) => e.into(),
+            GenevaUploaderError::Http { message, retryable } => {
+                GenevaError::Transport { message, retryable }
+            }
+            GenevaUploaderError::UploadFailed {
+                status, message, ..
+            } if is_auth_status(status) => GenevaError::Auth {
+                message,
+                status: Some(status),
+                retryable,
+            },
+            GenevaUploaderError::UploadFailed {
+                status, message, ..
+            } => GenevaError::Http {
+                status,
+                message,
+                retryable,
+            },
+            GenevaUploaderError::SerdeJson(e) => GenevaError::Internal {
+                message: e.to_string(),
+            },
+            GenevaUploaderError::InternalError(message) => GenevaError::Internal { message },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_uploader_error_mapping() {
+        let err: GenevaError = GenevaUploaderError::UploadFailed {
+            status: 503,
+            message: "busy".to_string(),
+            retry_after: None,
+        }
+        .into();
+        assert_eq!(err.category(), ErrorCategory::Http);
+        assert_eq!(err.status(), Some(503));
+        assert!(err.is_retryable());
+
+        let err: GenevaError = GenevaUploaderError::UploadFailed {
+            status: 401,
+            message: "expired".to_string(),
+            retry_after: None,
+        }
+        .into();
+        assert_eq!(err.category(), ErrorCategory::Auth);
+
+        let err: GenevaError = GenevaUploaderError::UploadFailed {
+            status: 400,
+            message: "bad schema".to_string(),
+            retry_after: None,
+        }
+        .into();
+        assert!(!err.is_retryable());
+
+        let err: GenevaError = GenevaUploaderError::ConfigClient(
+            GenevaConfigClientError::Certificate("unreadable".to_string()),
+        )
+        .into();
+        assert_eq!(err.category(), ErrorCategory::Auth);
+        assert_eq!(err.status(), None);
+    }
+
+    #[test]
+    fn test_upload_error_delegates_to_source() {
+        let err = GenevaError::Upload {
+            event_name: "Log".to_string(),
+            spooled: true,
+            spool_error: None,
+            source: Box::new(GenevaError::Http {
+                status: 500,
+                message: "oops".to_string(),
+                retryable: true,
+            }),
+        };
+        assert_eq!(err.category(), ErrorCategory::Http);
+        assert_eq!(err.status(), Some(500));
+        assert!(err.is_retryable());
+        assert_eq!(err.event_name(), Some("Log"));
+        assert_eq!(
+            err.to_string(),
+            "Geneva upload failed for event Log: request failed with status 500: oops, batch spooled for replay"
+        );
+        assert!(std::error::Error::source(&err).is_some());
+
+        let err = GenevaError::Upload {
+            event_name: "Log".to_string(),
+            spooled: false,
+            spool_error: Some("No space left on device".to_string()),
+            source: Box::new(GenevaError::Transport {
+                message: "timed out".to_string(),
+                retryable: true,
+            }),
+        };
+        assert_eq!(
+            err.to_string(),
+            "Geneva upload failed for event Log: transport error: timed out, spooling failed: No space left on device"
+        );
+    }
+}
@@ -1,3 +1,4 @@
+pub(crate) mod retry;
 pub(crate) mod uploader;
 
 #[cfg(test)]
@@ -7,7 +8,7 @@ mod tests {
     mod test_helpers {
         use crate::{
             AuthMethod, GenevaConfigClient, GenevaConfigClientConfig, GenevaUploader,
-            GenevaUploaderConfig,
+            GenevaUploaderConfig, RetryPolicy,
         };
         use std::env;
         use std::fs;
@@ -51,6 +52,8 @@ mod tests {
                 source_identity,
                 environment: environment.clone(),
                 config_version,
+                retry_policy: RetryPolicy::no_retry(),
+                metrics: None,
             };
 
             let config = GenevaConfigClientConfig {
@@ -64,6 +67,8 @@ mod tests {
                     path: cert_path,
                     password: cert_password,
                 },
+                msi_resource: None,
+                msi_endpoint: None,
             };
 
             // Build client and uploader
@@ -123,6 +128,7 @@ mod tests {
             start_time: 1_700_000_000_000_000_000,
             end_time: 1_700_000_300_000_000_000,
             schema_ids: "075bcd15e5b2ed60f26e66085ac2b2e8".to_string(), // Example MD5 hash
+            event_count: 1,
         };
 
         let response = ctx
@@ -190,6 +196,7 @@ mod tests {
             start_time: 1_700_000_000_000_000_000,
             end_time: 1_700_000_300_000_000_000,
             schema_ids: "075bcd15e5b2ed60f26e66085ac2b2e8".to_string(), // Example MD5 hash
+            event_count: 1,
         };
 
         let _ = ctx
@@ -217,6 +224,7 @@ mod tests {
                     start_time: 1_700_000_000_000_000_000,
                     end_time: 1_700_000_300_000_000_000,
                     schema_ids: "075bcd15e5b2ed60f26e66085ac2b2e8".to_string(), // Example MD5 hash
+                    event_count: 1,
                 };
 
                 let resp = uploader
@@ -249,3 +257,174 @@ mod tests {
         println!("⏱️ Total elapsed for {parallel_uploads} parallel uploads: {total_time:.2?}");
     }
 }
+
+#[cfg(all(test, feature = "mock_auth"))]
+mod retry_tests {
+    use crate::client::GenevaClient;
+    use crate::test_support::{client_config, logs, mount_config_service};
+    use crate::{GenevaError, RetryPolicy};
+    use std::time::{Duration, Instant};
+    use wiremock::http::Method;
+    use wiremock::matchers::method;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn fast_policy() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(10),
+            max_backoff: Duration::from_millis(50),
+            multiplier: 2.0,
+            jitter: 0.0,
+            max_elapsed: Some(Duration::from_secs(10)),
+        }
+    }
+
+    async fn upload_with(server: &MockServer, policy: RetryPolicy) -> Result<(), GenevaError> {
+        let mut cfg = client_config(&server.uri());
+        cfg.retry_policy = policy;
+        let client = GenevaClient::new(cfg).unwrap();
+        let batches = client.encode_and_compress_logs(&logs("Log")).unwrap();
+        client.upload_batch(&batches[0]).await
+    }
+
+    async fn count_requests(server: &MockServer, m: Method) -> usize {
+        server
+            .received_requests()
+            .await
+            .unwrap()
+            .iter()
+            .filter(|r| r.method == m)
+            .count()
+    }
+
+    async fn mount_accepting_gateway(server: &MockServer) {
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(202).set_body_string(r#"{"ticket":"accepted"}"#))
+            .mount(server)
+            .await;
+    }
+
+    #[tokio::test]
+    async fn test_server_error_is_retried() {
+        let server = MockServer::start().await;
+        mount_config_service(&server).await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(2)
+            .mount(&server)
+            .await;
+        mount_accepting_gateway(&server).await;
+
+        upload_with(&server, fast_policy()).await.unwrap();
+        assert_eq!(count_requests(&server, Method::Post).await, 3);
+    }
+
+    #[tokio::test]
+    async fn test_retries_stop_at_max_attempts() {
+        let server = MockServer::start().await;
+        mount_config_service(&server).await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(500))
+            .mount(&server)
+            .await;
+
+        let err = upload_with(&server, fast_policy()).await.unwrap_err();
+        assert_eq!(err.status(), Some(500), "unexpected error: {err}");
+        assert_eq!(count_requests(&server, Method::Post).await, 3);
+    }
+
+    #[tokio::test]
+    async fn test_bad_request_is_not_retried() {
+        let server = MockServer::start().await;
+        mount_config_service(&server).await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(400).set_body_string("invalid schema"))
+            .mount(&server)
+            .await;
+
+        let err = upload_with(&server, fast_policy()).await.unwrap_err();
+        assert_eq!(err.status(), Some(400), "unexpected error: {err}");
+        assert!(!err.is_retryable());
+        assert_eq!(count_requests(&server, Method::Post).await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_unauthorized_refreshes_token() {
+        let server = MockServer::start().await;
+        mount_config_service(&server).await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(401))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        mount_accepting_gateway(&server).await;
+
+        upload_with(&server, fast_policy()).await.unwrap();
+        assert_eq!(count_requests(&server, Method::Post).await, 2);
+        // The cached token was dropped after the 401, so it was fetched again.
+        assert_eq!(count_requests(&server, Method::Get).await, 2);
+    }
+
+    #[tokio::test]
+    async fn test_retry_after_is_honoured() {
+        let server = MockServer::start().await;
+        mount_config_service(&server).await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "1"))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        mount_accepting_gateway(&server).await;
+
+        let policy = RetryPolicy {
+            max_backoff: Duration::from_secs(2),
+            ..fast_policy()
+        };
+        let start = Instant::now();
+        upload_with(&server, policy).await.unwrap();
+        assert!(start.elapsed() >= Duration::from_secs(1));
+        assert_eq!(count_requests(&server, Method::Post).await, 2);
+    }
+
+    #[tokio::test]
+    async fn test_retry_after_is_capped_at_max_backoff() {
+        let server = MockServer::start().await;
+        mount_config_service(&server).await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(503).insert_header("Retry-After", "3600"))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        mount_accepting_gateway(&server).await;
+
+        let policy = RetryPolicy {
+            max_elapsed: None,
+            ..fast_policy()
+        };
+        let start = Instant::now();
+        upload_with(&server, policy).await.unwrap();
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert_eq!(count_requests(&server, Method::Post).await, 2);
+    }
+
+    #[tokio::test]
+    async fn test_retry_beyond_deadline_is_not_attempted() {
+        let server = MockServer::start().await;
+        mount_config_service(&server).await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(503).insert_header("Retry-After", "30"))
+            .mount(&server)
+            .await;
+
+        let policy = RetryPolicy {
+            max_backoff: Duration::from_secs(60),
+            max_elapsed: Some(Duration::from_secs(5)),
+            ..fast_policy()
+        };
+        let start = Instant::now();
+        let err = upload_with(&server, policy).await.unwrap_err();
+        assert_eq!(err.status(), Some(503), "unexpected error: {err}");
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert_eq!(count_requests(&server, Method::Post).await, 1);
+    }
+}
@@ -0,0 +1,166 @@
+//! Retry policy for uploads to the Geneva Ingestion Gateway (GIG).
+
+use rand::Rng;
+use std::time::Duration;
+
+/// Retry policy applied to every batch upload.
+///
+/// Failed attempts are retried with exponential backoff and jitter while the error is
+/// retryable (timeouts, connection failures, 408, 429, 5xx and 401 token rejections) and
+/// neither `max_attempts` nor `max_elapsed` has been reached. Permanent failures such as
+/// 400 (bad request / schema errors) are returned immediately.
+///
+/// A `Retry-After` header sent by the gateway takes precedence over the computed backoff, up to
+/// `max_backoff`.
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    /// Total number of attempts, including the first one. `1` disables retries.
+    pub max_attempts: u32,
+    /// Backoff before the first retry.
+    pub initial_backoff: Duration,
+    /// Upper bound for the delay between two attempts, including `Retry-After` hints.
+    pub max_backoff: Duration,
+    /// Factor applied to the backoff after every failed attempt.
+    pub multiplier: f64,
+    /// Fraction (`0.0..=1.0`) of each backoff that is randomized, to avoid synchronized retries.
+    pub jitter: f64,
+    /// Overall deadline for one upload, measured from the first attempt. No retry is scheduled
+    /// if it would start after the deadline. `None` means attempts are only bounded by `max_attempts`.
+    pub max_elapsed: Option<Duration>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 4,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(10),
+            multiplier: 2.0,
+            jitter: 0.2,
+            max_elapsed: Some(Duration::from_secs(60)),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Policy that performs a single attempt and never retries.
+    pub fn no_retry() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Self::default()
+        }
+    }
+
+    /// Backoff before retry number `retry` (1-based), without jitter.
+    pub(crate) fn base_backoff(&self, retry: u32) -> Duration {
+        let exp = self
+            .multiplier
+            .max(1.0)
+            .powi(retry.saturating_sub(1) as i32);
+        let secs = self.initial_backoff.as_secs_f64() * exp;
+        if !secs.is_finite() || secs >= self.max_backoff.as_secs_f64() {
+            self.max_backoff
+        } else {
+            Duration::from_secs_f64(secs)
+        }
+    }
+
+    /// Backoff before retry number `retry` (1-based), with jitter applied.
+    ///
+    /// The result is uniformly distributed in `[base * (1 - jitter), base]`.
+    pub(crate) fn backoff(&self, retry: u32) -> Duration {
+        let base = self.base_backoff(retry);
+        let jitter = self.jitter.clamp(0.0, 1.0);
+        if jitter == 0.0 {
+            return base;
+        }
+        let factor = 1.0 - jitter * rand::rng().random::<f64>();
+        base.mul_f64(factor)
+    }
+}
+
+/// Parses an HTTP `Retry-After` header value, given either in seconds or as an HTTP date.
+pub(crate) fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    let delta = date.with_timezone(&chrono::Utc) - chrono::Utc::now();
+    Some(delta.to_std().unwrap_or(Duration::ZERO))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base_backoff_grows_and_is_capped() {
+        let policy = RetryPolicy {
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_millis(450),
+            multiplier: 2.0,
+            ..RetryPolicy::default()
+        };
+        assert_eq!(policy.base_backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.base_backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.base_backoff(3), Duration::from_millis(400));
+        assert_eq!(policy.base_backoff(4), Duration::from_millis(450));
+        assert_eq!(policy.base_backoff(1000), Duration::from_millis(450));
+    }
+
+    #[test]
+    fn test_jitter_stays_within_bounds() {
+        let policy = RetryPolicy {
+            initial_backoff: Duration::from_millis(1000),
+            jitter: 0.5,
+            ..RetryPolicy::default()
+        };
+        for _ in 0..100 {
+            let backoff = policy.backoff(1);
+            assert!(backoff >= Duration::from_millis(500), "{backoff:?}");
+            assert!(backoff <= Duration::from_millis(1000), "{backoff:?}");
+        }
+
+        let no_jitter = RetryPolicy {
+            jitter: 0.0,
+            ..policy
+        };
+        assert_eq!(no_jitter.backoff(1), Duration::from_millis(1000));
+    }
+
+    #[test]
+    fn test_parse_retry_after() {
+        assert_eq!(parse_retry_after("7"), Some(Duration::from_secs(7)));
+        assert_eq!(parse_retry_after(" 0 "), Some(Duration::ZERO));
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"),
+            Some(Duration::ZERO)
+        );
+        let future = (chrono::Utc::now() + chrono::Duration::seconds(120)).to_rfc2822();
+        let parsed = parse_retry_after(&future).unwrap();
+        assert!(parsed > Duration::from_secs(100) && parsed <= Duration::from_secs(120));
+        assert_eq!(parse_retry_after("soon"), None);
+    }
+
+    #[tokio::test]
+    async fn test_reqwest_error_classification() {
+        use crate::ingestion_service::uploader::is_retryable_reqwest_error;
+
+        // Nothing listens on port 1
+        let err = reqwest::Client::new()
+            .get("http://127.0.0.1:1")
+            .send()
+            .await
+            .unwrap_err();
+        assert!(is_retryable_reqwest_error(&err), "{err}");
+
+        // An invalid request fails the same way on every attempt
+        let err = reqwest::Client::new()
+            .get("http://127.0.0.1:1")
+            .header("bad header", "value")
+            .build()
+            .unwrap_err();
+        assert!(!is_retryable_reqwest_error(&err), "{err}");
+    }
+}
@@ -1,5 +1,7 @@
 use crate::config_service::client::{GenevaConfigClient, GenevaConfigClientError};
+use crate::ingestion_service::retry::{parse_retry_after, RetryPolicy};
 use crate::payload_encoder::central_blob::BatchMetadata;
+use crate::self_metrics::SelfMetrics;
 use reqwest::{header, Client};
 use serde::Deserialize;
 use serde_json::Value;
@@ -7,7 +9,7 @@ use std::collections::HashMap;
 use std::error::Error as StdError;
 use std::fmt::Write;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use thiserror::Error;
 use url::form_urlencoded::byte_serialize;
 use uuid::Uuid;
@@ -15,25 +17,79 @@ use uuid::Uuid;
 /// Error types for the Geneva Uploader
 #[derive(Debug, Error)]
 pub(crate) enum GenevaUploaderError {
-    #[error("HTTP error: {0}")]
-    Http(String),
+    #[error("HTTP error: {message}")]
+    Http { message: String, retryable: bool },
     #[error("JSON error: {0}")]
     SerdeJson(#[from] serde_json::Error),
     #[error("Config service error: {0}")]
-    ConfigClient(String),
+    ConfigClient(#[from] GenevaConfigClientError),
     #[allow(dead_code)]
     #[error("Upload failed with status {status}: {message}")]
-    UploadFailed { status: u16, message: String },
+    UploadFailed {
+        status: u16,
+        message: String,
+        retry_after: Option<Duration>,
+    },
     #[allow(dead_code)]
     #[error("Internal error: {0}")]
     InternalError(String),
 }
 
-impl From<GenevaConfigClientError> for GenevaUploaderError {
-    fn from(err: GenevaConfigClientError) -> Self {
-        // This preserves the original error message format from the code
-        GenevaUploaderError::ConfigClient(format!("GenevaConfigClient error: {err}"))
+impl GenevaUploaderError {
+    /// Whether the failure is transient and the upload may succeed if retried.
+    ///
+    /// Timeouts, connection failures, 408, 429 and 5xx responses are retryable, as is 401
+    /// (expired or revoked token) since the token is refreshed before the next attempt.
+    /// Everything else, e.g. 400 for a malformed payload or schema, is permanent.
+    pub(crate) fn is_retryable(&self) -> bool {
+        match self {
+            GenevaUploaderError::Http { retryable, .. } => *retryable,
+            GenevaUploaderError::ConfigClient(e) => e.is_retryable(),
+            GenevaUploaderError::UploadFailed { status, .. } => {
+                *status == 401 || is_retryable_status(*status)
+            }
+            GenevaUploaderError::SerdeJson(_) | GenevaUploaderError::InternalError(_) => false,
+        }
+    }
+
+    /// Whether the gateway rejected the auth token.
+    pub(crate) fn is_auth_rejected(&self) -> bool {
+        matches!(self, GenevaUploaderError::UploadFailed { status: 401, .. })
     }
+
+    /// HTTP status of a rejected upload, or the kind of failure when the gateway did not answer.
+    pub(crate) fn status_label(&self) -> String {
+        match self {
+            GenevaUploaderError::UploadFailed { status, .. } => status.to_string(),
+            GenevaUploaderError::Http { .. } => "transport".to_string(),
+            GenevaUploaderError::ConfigClient(_) => "config_service".to_string(),
+            GenevaUploaderError::SerdeJson(_) => "invalid_response".to_string(),
+            GenevaUploaderError::InternalError(_) => "internal".to_string(),
+        }
+    }
+
+    /// Delay requested by the gateway through the `Retry-After` header, if any.
+    pub(crate) fn retry_after(&self) -> Option<Duration> {
+        match self {
+            GenevaUploaderError::UploadFailed { retry_after, .. } => *retry_after,
+            _ => None,
+        }
+    }
+}
+
+/// HTTP statuses worth retrying: request timeout, throttling and server errors.
+pub(crate) fn is_retryable_status(status: u16) -> bool {
+    status == 408 || status == 429 || (500..600).contains(&status)
+}
+
+/// Whether a transport-level error is transient: a timeout, a connection failure or a
+/// retryable status. Builder, redirect and body encoding failures would fail the same way again.
+pub(crate) fn is_retryable_reqwest_error(err: &reqwest::Error) -> bool {
+    err.is_timeout()
+        || err.is_connect()
+        || err
+            .status()
+            .is_some_and(|status| is_retryable_status(status.as_u16()))
 }
 
 impl From<reqwest::Error> for GenevaUploaderError {
@@ -82,7 +138,10 @@ impl From<reqwest::Error> for GenevaUploaderError {
             write!(&mut msg, ", (no io::Error in source chain)").ok();
         }
 
-        GenevaUploaderError::Http(msg)
+        GenevaUploaderError::Http {
+            message: msg,
+            retryable: is_retryable_reqwest_error(&err),
+        }
     }
 }
 
@@ -106,6 +165,8 @@ pub(crate) struct GenevaUploaderConfig {
     #[allow(dead_code)]
     pub environment: String,
     pub config_version: String,
+    pub retry_policy: RetryPolicy,
+    pub metrics: Option<Arc<SelfMetrics>>,
 }
 
 /// Client for uploading data to Geneva Ingestion Gateway (GIG)
@@ -197,22 +258,80 @@ impl GenevaUploader {
         Ok(query)
     }
 
-    /// Uploads data to the ingestion gateway
+    /// Uploads data to the ingestion gateway, retrying according to the configured `RetryPolicy`
+    ///
+    /// When the gateway rejects the auth token (401), the cached token is dropped so that the
+    /// next attempt fetches a fresh one from the config service.
     ///
     /// # Arguments
     /// * `data` - The encoded data to upload (already in the required format)
     /// * `event_name` - Name of the event
-    /// * `event_version` - Version of the event
     /// * `metadata` - Batch metadata containing timestamps and schema information
     ///
     /// # Returns
-    /// * `Result<IngestionResponse>` - The response containing the ticket ID or an error
+    /// * `Result<IngestionResponse>` - The response containing the ticket ID, or the error of the last attempt
     #[allow(dead_code)]
     pub(crate) async fn upload(
         &self,
         data: Vec<u8>,
         event_name: &str,
         metadata: &BatchMetadata,
+    ) -> Result<IngestionResponse> {
+        let result = self.upload_with_retries(data, event_name, metadata).await;
+        if let Some(metrics) = &self.config.metrics {
+            metrics.record_upload(result.is_ok());
+        }
+        result
+    }
+
+    async fn upload_with_retries(
+        &self,
+        data: Vec<u8>,
+        event_name: &str,
+        metadata: &BatchMetadata,
+    ) -> Result<IngestionResponse> {
+        let policy = &self.config.retry_policy;
+        let started = Instant::now();
+        let mut attempt = 1;
+        loop {
+            let attempt_started = Instant::now();
+            let result = self.upload_once(data.clone(), event_name, metadata).await;
+            if let Some(metrics) = &self.config.metrics {
+                metrics.record_upload_attempt(
+                    attempt_started.elapsed(),
+                    result.as_ref().err().map(GenevaUploaderError::status_label),
+                );
+            }
+            let err = match result {
+                Ok(response) => return Ok(response),
+                Err(e) => e,
+            };
+            if attempt >= policy.max_attempts.max(1) || !err.is_retryable() {
+                return Err(err);
+            }
+            if err.is_auth_rejected() {
+                self.config_client.invalidate_cached_token();
+            }
+            // A `Retry-After` hint is capped like the computed backoff
+            let delay = err
+                .retry_after()
+                .map_or_else(|| policy.backoff(attempt), |d| d.min(policy.max_backoff));
+            if let Some(max_elapsed) = policy.max_elapsed {
+                if started.elapsed() + delay > max_elapsed {
+                    return Err(err);
+                }
+            }
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
+    /// Performs a single upload attempt
+    async fn upload_once(
+        &self,
+        data: Vec<u8>,
+        event_name: &str,
+        metadata: &BatchMetadata,
     ) -> Result<IngestionResponse> {
         // Always get fresh auth info
         let (auth_info, moniker_info, monitoring_endpoint) =
@@ -242,6 +361,11 @@ impl GenevaUploader {
             .send()
             .await?;
         let status = response.status();
+        let retry_after = response
+            .headers()
+            .get(header::RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(parse_retry_after);
         let body = response.text().await?;
 
         if status == reqwest::StatusCode::ACCEPTED {
@@ -253,6 +377,7 @@ impl GenevaUploader {
             Err(GenevaUploaderError::UploadFailed {
                 status: status.as_u16(),
                 message: body,
+                retry_after,
             })
         }
     }
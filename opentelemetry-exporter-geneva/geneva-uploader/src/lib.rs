@@ -1,11 +1,19 @@
+mod builder;
 mod config_service;
+mod error;
 mod ingestion_service;
 mod payload_encoder;
+mod self_metrics;
+mod spool;
 
 pub mod client;
+#[cfg(feature = "mock_gateway")]
+pub mod mock;
 
 #[cfg(test)]
 mod bench;
+#[cfg(all(test, feature = "mock_auth"))]
+mod test_support;
 
 #[allow(unused_imports)]
 pub(crate) use config_service::client::{
@@ -17,6 +25,21 @@ pub(crate) use ingestion_service::uploader::{
     GenevaUploader, GenevaUploaderConfig, GenevaUploaderError, Result,
 };
 
+pub use builder::{ConfigError, GenevaClientConfigBuilder};
 pub use client::EncodedBatch;
 pub use client::{GenevaClient, GenevaClientConfig};
 pub use config_service::client::AuthMethod;
+pub use config_service::token_refresh::TokenRefreshConfig;
+pub use error::{ErrorCategory, GenevaError};
+pub use ingestion_service::retry::RetryPolicy;
+pub use payload_encoder::attribute_encoding::{
+    AttributeEncoding, BytesEncoding, ComplexValueEncoding,
+};
+pub use payload_encoder::batch_limits::BatchLimits;
+pub use payload_encoder::column_mapping::{AttributeFilter, ColumnMapping, ColumnType};
+pub use payload_encoder::decoder::{
+    decode_batch_file, decode_blob, decode_payload, DecodeError, DecodedBlob, DecodedEvent,
+    DecodedField, DecodedSchema, DecodedValue,
+};
+pub use payload_encoder::resource_mapping::{AttributeSelection, ResourceMapping};
+pub use spool::file_spool::SpoolConfig;
@@ -0,0 +1,461 @@
+//! In-process mock of the Geneva config service and ingestion gateway for integration tests.
+//!
+//! [`MockGeneva`] serves the config-service `GenevaResponse` and the
+//! `api/v1/ingestion/ingest` endpoint from a local HTTP server, decodes every uploaded
+//! LZ4-chunked CentralBlob back into Bond rows, and lets tests inject failures, latency and
+//! token expiry. Point a client at it with [`MockGeneva::client_config`]:
+//!
+//! ```no_run
+//! # async fn example() -> Result<(), geneva_uploader::GenevaError> {
+//! use geneva_uploader::mock::MockGeneva;
+//! use geneva_uploader::GenevaClient;
+//!
+//! let mock = MockGeneva::start().await;
+//! let client = GenevaClient::new(mock.client_config())?;
+//! // ... encode and upload batches ...
+//! for event in mock.events() {
+//!     println!("{} {:?}", event.event_name, event.get("body"));
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Requires the `mock_gateway` feature, which also enables `mock_auth`. Not to be enabled in
+//! production builds.
+
+use crate::client::GenevaClientConfig;
+use crate::config_service::client::{mock_jwt, AuthMethod};
+use crate::payload_encoder::decoder::decode_payload;
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+use uuid::Uuid;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, Request, Respond, ResponseTemplate};
+
+pub use crate::payload_encoder::decoder::{
+    DecodeError, DecodedBlob, DecodedEvent, DecodedField, DecodedSchema, DecodedValue,
+};
+
+const INGEST_PATH: &str = "/api/v1/ingestion/ingest";
+
+/// An upload received by the mock ingestion gateway.
+#[derive(Clone, Debug)]
+pub struct ReceivedUpload {
+    /// Value of the `event` query parameter.
+    pub event_name: String,
+    /// All query parameters (`namespace`, `schemaIds`, `startTime`, `endTime`, ...).
+    pub query: HashMap<String, String>,
+    /// Compressed request body.
+    pub body: Vec<u8>,
+    /// Status the mock answered with: 202 when accepted.
+    pub status: u16,
+    /// The decoded body, or why it could not be decoded (the upload is then rejected with 400).
+    pub blob: Result<DecodedBlob, DecodeError>,
+}
+
+impl ReceivedUpload {
+    /// Whether the mock accepted the upload.
+    pub fn accepted(&self) -> bool {
+        self.status == 202
+    }
+}
+
+struct MockState {
+    config_requests: usize,
+    config_failures: VecDeque<u16>,
+    config_latency: Duration,
+    token_lifetime: chrono::Duration,
+    tokens: HashMap<String, DateTime<Utc>>,
+    upload_failures: VecDeque<u16>,
+    upload_latency: Duration,
+    recording: bool,
+    uploads: Vec<ReceivedUpload>,
+}
+
+/// Mock Geneva config service and ingestion gateway. See the [module docs](self).
+///
+/// Every request is answered from shared state, so failures and latency can be changed
+/// while a client is running. The server stops when the mock is dropped.
+pub struct MockGeneva {
+    server: MockServer,
+    state: Arc<Mutex<MockState>>,
+}
+
+impl MockGeneva {
+    /// Starts the mock on a random local port.
+    pub async fn start() -> Self {
+        let server = MockServer::start().await;
+        let state = Arc::new(Mutex::new(MockState {
+            config_requests: 0,
+            config_failures: VecDeque::new(),
+            config_latency: Duration::ZERO,
+            token_lifetime: chrono::Duration::hours(24),
+            tokens: HashMap::new(),
+            upload_failures: VecDeque::new(),
+            upload_latency: Duration::ZERO,
+            recording: true,
+            uploads: Vec::new(),
+        }));
+
+        Mock::given(method("POST"))
+            .and(path(INGEST_PATH))
+            .respond_with(IngestResponder {
+                state: Arc::clone(&state),
+            })
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .respond_with(ConfigResponder {
+                state: Arc::clone(&state),
+                gateway: server.uri(),
+            })
+            .mount(&server)
+            .await;
+
+        MockGeneva { server, state }
+    }
+
+    /// Base URL of the mock, used as the config-service endpoint and the gateway endpoint.
+    pub fn endpoint(&self) -> String {
+        self.server.uri()
+    }
+
+    /// Client configuration pointing at the mock with `AuthMethod::MockAuth`.
+    pub fn client_config(&self) -> GenevaClientConfig {
+        GenevaClientConfig::builder()
+            .endpoint(self.endpoint())
+            .environment("test")
+            .account("test")
+            .namespace("testns")
+            .region("testregion")
+            .config_major_version(1)
+            .auth_method(AuthMethod::MockAuth)
+            .tenant("testtenant")
+            .role_name("testrole")
+            .role_instance("testinstance")
+            .build()
+            .expect("mock configuration is valid")
+    }
+
+    /// Lifetime of tokens issued from now on (default 24 hours). By default the client renews
+    /// tokens that expire within five minutes, so a shorter lifetime makes it renew the token
+    /// as soon as it is used.
+    pub fn set_token_lifetime(&self, lifetime: Duration) {
+        self.state().token_lifetime =
+            chrono::Duration::from_std(lifetime).unwrap_or_else(|_| chrono::Duration::days(36_500));
+    }
+
+    /// Expires every token issued so far; uploads using them are rejected with 401 until
+    /// the client fetches a new one.
+    pub fn expire_tokens(&self) {
+        let now = Utc::now();
+        for expiry in self.state().tokens.values_mut() {
+            *expiry = now;
+        }
+    }
+
+    /// Answers the next `count` config-service requests with `status`.
+    pub fn fail_config_requests(&self, count: usize, status: u16) {
+        self.state()
+            .config_failures
+            .extend(std::iter::repeat(status).take(count));
+    }
+
+    /// Answers the next `count` uploads with `status`.
+    pub fn fail_uploads(&self, count: usize, status: u16) {
+        self.state()
+            .upload_failures
+            .extend(std::iter::repeat(status).take(count));
+    }
+
+    /// Delays every config-service response by `latency`.
+    pub fn set_config_latency(&self, latency: Duration) {
+        self.state().config_latency = latency;
+    }
+
+    /// Delays every upload response by `latency`.
+    pub fn set_upload_latency(&self, latency: Duration) {
+        self.state().upload_latency = latency;
+    }
+
+    /// Whether uploads are decoded and kept for [`uploads`](Self::uploads) (default `true`).
+    /// Disable for long-running load tests.
+    pub fn set_recording(&self, recording: bool) {
+        self.state().recording = recording;
+    }
+
+    /// Number of config-service requests received.
+    pub fn config_requests(&self) -> usize {
+        self.state().config_requests
+    }
+
+    /// Uploads received so far, including rejected ones, in arrival order.
+    pub fn uploads(&self) -> Vec<ReceivedUpload> {
+        self.state().uploads.clone()
+    }
+
+    /// Rows of all accepted uploads, in arrival order.
+    pub fn events(&self) -> Vec<DecodedEvent> {
+        self.state()
+            .uploads
+            .iter()
+            .filter(|u| u.accepted())
+            .filter_map(|u| u.blob.as_ref().ok())
+            .flat_map(|blob| blob.events.iter().cloned())
+            .collect()
+    }
+
+    /// Forgets recorded uploads and the config-request count.
+    pub fn clear(&self) {
+        let mut state = self.state();
+        state.uploads.clear();
+        state.config_requests = 0;
+    }
+
+    fn state(&self) -> MutexGuard<'_, MockState> {
+        lock(&self.state)
+    }
+}
+
+/// A panicking test thread must not hide the recorded state from the other assertions
+fn lock(state: &Mutex<MockState>) -> MutexGuard<'_, MockState> {
+    state.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+struct ConfigResponder {
+    state: Arc<Mutex<MockState>>,
+    gateway: String,
+}
+
+impl Respond for ConfigResponder {
+    fn respond(&self, _request: &Request) -> ResponseTemplate {
+        let mut state = lock(&self.state);
+        state.config_requests += 1;
+        let latency = state.config_latency;
+        if let Some(status) = state.config_failures.pop_front() {
+            return ResponseTemplate::new(status).set_delay(latency);
+        }
+
+        let expiry = Utc::now() + state.token_lifetime;
+        let token = mock_jwt(&self.gateway, expiry, &Uuid::new_v4().simple().to_string());
+        state.tokens.insert(token.clone(), expiry);
+        ResponseTemplate::new(200)
+            .set_delay(latency)
+            .set_body_json(serde_json::json!({
+                "IngestionGatewayInfo": {
+                    "Endpoint": self.gateway,
+                    "AuthToken": token,
+                    "AuthTokenExpiryTime": expiry.to_rfc3339()
+                },
+                "StorageAccountKeys": [{
+                    "AccountMonikerName": "mockdiagaccount",
+                    "AccountGroupName": "mockgroup",
+                    "IsPrimaryMoniker": true
+                }],
+                "TagId": "mock"
+            }))
+    }
+}
+
+struct IngestResponder {
+    state: Arc<Mutex<MockState>>,
+}
+
+impl Respond for IngestResponder {
+    fn respond(&self, request: &Request) -> ResponseTemplate {
+        let mut state = lock(&self.state);
+        let latency = state.upload_latency;
+        let token = request
+            .headers
+            .iter()
+            .find(|(name, _)| name.as_str().eq_ignore_ascii_case("authorization"))
+            .and_then(|(_, values)| values.last().as_str().strip_prefix("Bearer "))
+            .unwrap_or_default();
+
+        let (mut status, mut message) = if let Some(status) = state.upload_failures.pop_front() {
+            (status, "injected failure".to_string())
+        } else {
+            match state.tokens.get(token) {
+                None => (401, "unknown token".to_string()),
+                Some(expiry) if *expiry <= Utc::now() => (401, "token expired".to_string()),
+                Some(_) => (202, String::new()),
+            }
+        };
+
+        if state.recording {
+            let blob = decode_payload(&request.body);
+            if let (202, Err(e)) = (status, &blob) {
+                status = 400;
+                message = e.to_string();
+            }
+            let query: HashMap<String, String> = request.url.query_pairs().into_owned().collect();
+            state.uploads.push(ReceivedUpload {
+                event_name: query.get("event").cloned().unwrap_or_default(),
+                query,
+                body: request.body.clone(),
+                status,
+                blob,
+            });
+        }
+
+        let response = ResponseTemplate::new(status).set_delay(latency);
+        if status == 202 {
+            response.set_body_json(serde_json::json!({ "ticket": Uuid::new_v4().to_string() }))
+        } else {
+            response.set_body_string(message)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::GenevaClient;
+    use crate::{GenevaError, RetryPolicy};
+    use opentelemetry_proto::tonic::common::v1::any_value::Value;
+    use opentelemetry_proto::tonic::common::v1::{AnyValue, KeyValue};
+    use opentelemetry_proto::tonic::logs::v1::{LogRecord, ResourceLogs, ScopeLogs};
+    use std::time::Instant;
+
+    fn logs(event_name: &str, user: &str) -> Vec<ResourceLogs> {
+        vec![ResourceLogs {
+            scope_logs: vec![ScopeLogs {
+                log_records: vec![LogRecord {
+                    observed_time_unix_nano: 1_700_000_000_000_000_000,
+                    event_name: event_name.to_string(),
+                    severity_number: 9,
+                    attributes: vec![KeyValue {
+                        key: "user".to_string(),
+                        value: Some(AnyValue {
+                            value: Some(Value::StringValue(user.to_string())),
+                        }),
+                    }],
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+            ..Default::default()
+        }]
+    }
+
+    async fn upload(client: &GenevaClient, event_name: &str) -> Result<(), GenevaError> {
+        let batches = client
+            .encode_and_compress_logs(&logs(event_name, "alice"))
+            .unwrap();
+        client.upload_batch(&batches[0]).await
+    }
+
+    fn client(mock: &MockGeneva, max_attempts: u32) -> GenevaClient {
+        let mut cfg = mock.client_config();
+        cfg.retry_policy = RetryPolicy {
+            max_attempts,
+            initial_backoff: Duration::from_millis(10),
+            jitter: 0.0,
+            ..RetryPolicy::default()
+        };
+        GenevaClient::new(cfg).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_uploads_are_decoded() {
+        let mock = MockGeneva::start().await;
+        let client = client(&mock, 1);
+        upload(&client, "Checkout").await.unwrap();
+
+        let uploads = mock.uploads();
+        assert_eq!(uploads.len(), 1);
+        assert!(uploads[0].accepted());
+        assert_eq!(uploads[0].event_name, "Checkout");
+        assert_eq!(uploads[0].query["namespace"], "testns");
+        let events = mock.events();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].event_name, "Checkout");
+        assert_eq!(
+            events[0].get("user").and_then(|v| v.as_str()),
+            Some("alice")
+        );
+        assert_eq!(mock.config_requests(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_injected_failures() {
+        let mock = MockGeneva::start().await;
+        let client = client(&mock, 2);
+
+        mock.fail_uploads(1, 503);
+        upload(&client, "Log").await.unwrap();
+        let statuses: Vec<u16> = mock.uploads().iter().map(|u| u.status).collect();
+        assert_eq!(statuses, vec![503, 202]);
+
+        mock.fail_uploads(1, 400);
+        let err = upload(&client, "Log").await.unwrap_err();
+        assert_eq!(err.status(), Some(400));
+        assert_eq!(mock.events().len(), 1);
+
+        mock.fail_config_requests(1, 500);
+        let fresh = self::client(&mock, 1);
+        let err = upload(&fresh, "Log").await.unwrap_err();
+        assert_eq!(err.status(), Some(500));
+    }
+
+    #[tokio::test]
+    async fn test_expired_token_is_renewed() {
+        let mock = MockGeneva::start().await;
+        let client = client(&mock, 2);
+        upload(&client, "Log").await.unwrap();
+
+        mock.expire_tokens();
+        upload(&client, "Log").await.unwrap();
+        let statuses: Vec<u16> = mock.uploads().iter().map(|u| u.status).collect();
+        assert_eq!(statuses, vec![202, 401, 202]);
+        assert_eq!(mock.config_requests(), 2);
+
+        // Short-lived tokens are served while they are renewed in the background
+        mock.set_token_lifetime(Duration::from_secs(60));
+        mock.clear();
+        let mut cfg = mock.client_config();
+        cfg.token_refresh.retry_interval = Duration::from_millis(100);
+        let client = GenevaClient::new(cfg).unwrap();
+        upload(&client, "Log").await.unwrap();
+        upload(&client, "Log").await.unwrap();
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while mock.config_requests() < 2 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("token renewed in the background");
+        assert_eq!(mock.uploads().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_upload_latency() {
+        let mock = MockGeneva::start().await;
+        let client = client(&mock, 1);
+        upload(&client, "Log").await.unwrap();
+
+        mock.set_upload_latency(Duration::from_millis(200));
+        let started = Instant::now();
+        upload(&client, "Log").await.unwrap();
+        assert!(started.elapsed() >= Duration::from_millis(200));
+    }
+
+    #[tokio::test]
+    async fn test_undecodable_upload_is_rejected() {
+        let mock = MockGeneva::start().await;
+        let client = client(&mock, 1);
+        let mut batch = client
+            .encode_and_compress_logs(&logs("Log", "alice"))
+            .unwrap()
+            .remove(0);
+        batch.data.truncate(batch.data.len() / 2);
+
+        let err = client.upload_batch(&batch).await.unwrap_err();
+        assert_eq!(err.status(), Some(400));
+        let uploads = mock.uploads();
+        assert!(uploads[0].blob.is_err());
+        assert!(mock.events().is_empty());
+    }
+}
@@ -0,0 +1,319 @@
+// Mapping of OTLP AnyValue attribute values (including nested ones) to Bond field types
+
+use crate::payload_encoder::bond_encoder::{BondDataType, BondWriter};
+use base64::{engine::general_purpose, Engine as _};
+use opentelemetry_proto::tonic::common::v1::any_value::Value;
+use opentelemetry_proto::tonic::common::v1::{AnyValue, KeyValue};
+
+/// How array and key-value list attribute values are encoded.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ComplexValueEncoding {
+    /// Serialize the value as a JSON string (arrays become JSON arrays, kvlists JSON objects).
+    #[default]
+    Json,
+    /// Encode arrays whose elements all share one scalar type (string, int, double or bool)
+    /// as a Bond list of that type. Key-value lists, nested arrays and mixed-type arrays
+    /// fall back to a JSON string.
+    BondList,
+}
+
+/// How bytes attribute values are encoded.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BytesEncoding {
+    /// Standard base64 string.
+    #[default]
+    Base64,
+    /// Bond list of `uint8`.
+    BondList,
+}
+
+/// Encoding of attribute values (and non-string log bodies) that have no direct Bond
+/// equivalent. Scalar strings, ints, doubles and bools are always written as native Bond types.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AttributeEncoding {
+    /// Encoding of array and kvlist values.
+    pub complex_values: ComplexValueEncoding,
+    /// Encoding of bytes values. Bytes nested inside JSON are always base64 strings.
+    pub bytes: BytesEncoding,
+    /// Maximum nesting depth of arrays and kvlists serialized to JSON. A top-level array or
+    /// kvlist is depth 1; values nested deeper are written as `null`.
+    pub max_depth: usize,
+}
+
+impl Default for AttributeEncoding {
+    fn default() -> Self {
+        Self {
+            complex_values: ComplexValueEncoding::default(),
+            bytes: BytesEncoding::default(),
+            max_depth: 8,
+        }
+    }
+}
+
+impl AttributeEncoding {
+    /// Bond type and, for lists, element type used to encode `value`.
+    pub(crate) fn bond_type(&self, value: &Value) -> (BondDataType, Option<BondDataType>) {
+        match value {
+            Value::StringValue(_) => (BondDataType::BT_STRING, None),
+            Value::IntValue(_) => (BondDataType::BT_INT64, None),
+            Value::DoubleValue(_) => (BondDataType::BT_DOUBLE, None),
+            Value::BoolValue(_) => (BondDataType::BT_BOOL, None),
+            Value::BytesValue(_) => match self.bytes {
+                BytesEncoding::Base64 => (BondDataType::BT_STRING, None),
+                BytesEncoding::BondList => (BondDataType::BT_LIST, Some(BondDataType::BT_UINT8)),
+            },
+            Value::ArrayValue(array) => match self.complex_values {
+                ComplexValueEncoding::BondList => match list_element_type(&array.values) {
+                    Some(element_type) => (BondDataType::BT_LIST, Some(element_type)),
+                    None => (BondDataType::BT_STRING, None),
+                },
+                ComplexValueEncoding::Json => (BondDataType::BT_STRING, None),
+            },
+            Value::KvlistValue(_) => (BondDataType::BT_STRING, None),
+        }
+    }
+
+    /// Write `value` using the type previously returned by [`Self::bond_type`].
+    pub(crate) fn write_value(&self, buffer: &mut Vec<u8>, value: &Value, type_id: BondDataType) {
+        match (value, type_id) {
+            (Value::StringValue(s), BondDataType::BT_STRING) => BondWriter::write_string(buffer, s),
+            (Value::IntValue(i), BondDataType::BT_INT64) => BondWriter::write_numeric(buffer, *i),
+            (Value::DoubleValue(d), BondDataType::BT_DOUBLE) => {
+                BondWriter::write_numeric(buffer, *d)
+            }
+            (Value::BoolValue(b), BondDataType::BT_BOOL) => BondWriter::write_bool(buffer, *b),
+            (Value::BytesValue(bytes), BondDataType::BT_STRING) => {
+                BondWriter::write_string(buffer, &general_purpose::STANDARD.encode(bytes))
+            }
+            (Value::BytesValue(bytes), BondDataType::BT_LIST) => {
+                BondWriter::write_list_len(buffer, bytes.len());
+                buffer.extend_from_slice(bytes);
+            }
+            (Value::ArrayValue(array), BondDataType::BT_LIST) => {
+                BondWriter::write_list_len(buffer, array.values.len());
+                for element in array.values.iter().filter_map(|v| v.value.as_ref()) {
+                    self.write_value(buffer, element, self.bond_type(element).0);
+                }
+            }
+            (Value::ArrayValue(_) | Value::KvlistValue(_), BondDataType::BT_STRING) => {
+                BondWriter::write_string(buffer, &self.to_json_string(value))
+            }
+            // The schema declared another type for this field; a zero value of that type keeps
+            // the rest of the row aligned with the schema
+            _ => BondWriter::write_default(buffer, type_id),
+        }
+    }
+
+    /// Serialize `value` as JSON, honouring `max_depth`.
+    pub(crate) fn to_json_string(&self, value: &Value) -> String {
+        self.to_json(Some(value), 0).to_string()
+    }
+
+    fn to_json(&self, value: Option<&Value>, depth: usize) -> serde_json::Value {
+        use serde_json::Value as Json;
+        match value {
+            None => Json::Null,
+            Some(Value::StringValue(s)) => Json::String(s.clone()),
+            Some(Value::IntValue(i)) => Json::from(*i),
+            // NaN and infinities have no JSON representation and become null
+            Some(Value::DoubleValue(d)) => Json::from(*d),
+            Some(Value::BoolValue(b)) => Json::Bool(*b),
+            Some(Value::BytesValue(bytes)) => Json::String(general_purpose::STANDARD.encode(bytes)),
+            Some(Value::ArrayValue(_) | Value::KvlistValue(_)) if depth >= self.max_depth => {
+                Json::Null
+            }
+            Some(Value::ArrayValue(array)) => Json::Array(
+                array
+                    .values
+                    .iter()
+                    .map(|v| self.to_json(v.value.as_ref(), depth + 1))
+                    .collect(),
+            ),
+            Some(Value::KvlistValue(kvlist)) => Json::Object(
+                kvlist
+                    .values
+                    .iter()
+                    .map(|kv| {
+                        let value = kv.value.as_ref().and_then(|v| v.value.as_ref());
+                        (kv.key.clone(), self.to_json(value, depth + 1))
+                    })
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// Element type shared by every element of a scalar array, if any.
+/// Empty arrays are encoded as lists of strings.
+fn list_element_type(values: &[AnyValue]) -> Option<BondDataType> {
+    let mut element_type = None;
+    for value in values {
+        let this = match value.value.as_ref()? {
+            Value::StringValue(_) => BondDataType::BT_STRING,
+            Value::IntValue(_) => BondDataType::BT_INT64,
+            Value::DoubleValue(_) => BondDataType::BT_DOUBLE,
+            Value::BoolValue(_) => BondDataType::BT_BOOL,
+            _ => return None,
+        };
+        match element_type {
+            None => element_type = Some(this),
+            Some(seen) if seen != this => return None,
+            Some(_) => {}
+        }
+    }
+    Some(element_type.unwrap_or(BondDataType::BT_STRING))
+}
+
+/// Returns the value of an attribute, skipping attributes without one.
+pub(crate) fn attribute_value(attr: &KeyValue) -> Option<&Value> {
+    attr.value.as_ref().and_then(|v| v.value.as_ref())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry_proto::tonic::common::v1::{ArrayValue, KeyValueList};
+
+    fn any(value: Value) -> AnyValue {
+        AnyValue { value: Some(value) }
+    }
+
+    fn array(values: Vec<Value>) -> Value {
+        Value::ArrayValue(ArrayValue {
+            values: values.into_iter().map(any).collect(),
+        })
+    }
+
+    fn kvlist(values: Vec<(&str, Value)>) -> Value {
+        Value::KvlistValue(KeyValueList {
+            values: values
+                .into_iter()
+                .map(|(k, v)| KeyValue {
+                    key: k.to_string(),
+                    value: Some(any(v)),
+                })
+                .collect(),
+        })
+    }
+
+    /// Reads a Bond string written by `BondWriter::write_string`
+    fn read_string(buffer: &[u8]) -> String {
+        let len = u32::from_le_bytes(buffer[..4].try_into().unwrap()) as usize;
+        assert_eq!(buffer.len(), 4 + len);
+        String::from_utf8(buffer[4..].to_vec()).unwrap()
+    }
+
+    fn encode(encoding: &AttributeEncoding, value: &Value) -> (BondDataType, Vec<u8>) {
+        let (type_id, _) = encoding.bond_type(value);
+        let mut buffer = Vec::new();
+        encoding.write_value(&mut buffer, value, type_id);
+        (type_id, buffer)
+    }
+
+    #[test]
+    fn test_kvlist_json_round_trip() {
+        let value = kvlist(vec![
+            ("user", Value::StringValue("alice".into())),
+            ("age", Value::IntValue(42)),
+            ("score", Value::DoubleValue(1.5)),
+            ("tags", array(vec![Value::StringValue("a".into())])),
+            ("raw", Value::BytesValue(vec![1, 2, 3])),
+        ]);
+        let (type_id, buffer) = encode(&AttributeEncoding::default(), &value);
+        assert_eq!(type_id, BondDataType::BT_STRING);
+
+        let decoded: serde_json::Value = serde_json::from_str(&read_string(&buffer)).unwrap();
+        assert_eq!(
+            decoded,
+            serde_json::json!({
+                "user": "alice",
+                "age": 42,
+                "score": 1.5,
+                "tags": ["a"],
+                "raw": "AQID"
+            })
+        );
+    }
+
+    #[test]
+    fn test_type_mismatch_writes_default_of_declared_type() {
+        let encoding = AttributeEncoding::default();
+        let value = Value::StringValue("not a number".into());
+        for (type_id, expected) in [
+            (BondDataType::BT_INT64, vec![0; 8]),
+            (BondDataType::BT_BOOL, vec![0]),
+            (BondDataType::BT_LIST, vec![0; 4]),
+        ] {
+            let mut buffer = Vec::new();
+            encoding.write_value(&mut buffer, &value, type_id);
+            assert_eq!(buffer, expected);
+        }
+    }
+
+    #[test]
+    fn test_max_depth_truncates_nested_values() {
+        let nested = array(vec![array(vec![array(vec![Value::IntValue(1)])])]);
+        let encoding = AttributeEncoding {
+            max_depth: 2,
+            ..AttributeEncoding::default()
+        };
+        assert_eq!(encoding.to_json_string(&nested), "[[null]]");
+
+        let unlimited = AttributeEncoding::default();
+        assert_eq!(unlimited.to_json_string(&nested), "[[[1]]]");
+    }
+
+    #[test]
+    fn test_bytes_encodings() {
+        let value = Value::BytesValue(vec![0xde, 0xad, 0xbe, 0xef]);
+
+        let (type_id, buffer) = encode(&AttributeEncoding::default(), &value);
+        assert_eq!(type_id, BondDataType::BT_STRING);
+        let decoded = general_purpose::STANDARD
+            .decode(read_string(&buffer))
+            .unwrap();
+        assert_eq!(decoded, vec![0xde, 0xad, 0xbe, 0xef]);
+
+        let as_list = AttributeEncoding {
+            bytes: BytesEncoding::BondList,
+            ..AttributeEncoding::default()
+        };
+        assert_eq!(
+            as_list.bond_type(&value),
+            (BondDataType::BT_LIST, Some(BondDataType::BT_UINT8))
+        );
+        let (_, buffer) = encode(&as_list, &value);
+        assert_eq!(buffer, vec![4, 0, 0, 0, 0xde, 0xad, 0xbe, 0xef]);
+    }
+
+    #[test]
+    fn test_bond_list_for_homogeneous_arrays() {
+        let encoding = AttributeEncoding {
+            complex_values: ComplexValueEncoding::BondList,
+            ..AttributeEncoding::default()
+        };
+
+        let ints = array(vec![Value::IntValue(1), Value::IntValue(-2)]);
+        assert_eq!(
+            encoding.bond_type(&ints),
+            (BondDataType::BT_LIST, Some(BondDataType::BT_INT64))
+        );
+        let (_, buffer) = encode(&encoding, &ints);
+        let mut expected = vec![2, 0, 0, 0];
+        expected.extend_from_slice(&1i64.to_le_bytes());
+        expected.extend_from_slice(&(-2i64).to_le_bytes());
+        assert_eq!(buffer, expected);
+
+        let strings = array(vec![Value::StringValue("x".into())]);
+        let (_, buffer) = encode(&encoding, &strings);
+        assert_eq!(buffer, vec![1, 0, 0, 0, 1, 0, 0, 0, b'x']);
+
+        // Mixed arrays and kvlists fall back to JSON
+        let mixed = array(vec![Value::IntValue(1), Value::StringValue("x".into())]);
+        let (type_id, buffer) = encode(&encoding, &mixed);
+        assert_eq!(type_id, BondDataType::BT_STRING);
+        assert_eq!(read_string(&buffer), r#"[1,"x"]"#);
+        let map = kvlist(vec![("k", Value::BoolValue(true))]);
+        assert_eq!(encoding.bond_type(&map), (BondDataType::BT_STRING, None));
+    }
+}
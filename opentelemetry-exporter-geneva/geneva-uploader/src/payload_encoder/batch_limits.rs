@@ -0,0 +1,298 @@
+// Size limits for encoded batches and splitting of oversized batches
+
+use crate::client::EncodedBatch;
+use crate::error::GenevaError;
+use crate::payload_encoder::central_blob::{
+    BatchMetadata, CentralBlob, CentralEventEntry, CentralSchemaEntry,
+};
+use crate::payload_encoder::lz4_chunked_compression::lz4_chunked_compression;
+use crate::self_metrics::{SelfMetrics, Signal};
+use std::sync::Arc;
+
+/// Upper bounds for a single [`EncodedBatch`](crate::EncodedBatch).
+///
+/// Events sharing an event name are split across as many batches as needed to respect every
+/// limit. Each batch carries only the schemas its events use and the time range of its own
+/// events. An event that exceeds a byte limit on its own is still sent, alone in its batch.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BatchLimits {
+    /// Maximum number of events (rows) per batch. Default: 10 000.
+    pub max_events: usize,
+    /// Maximum size of the encoded blob before compression, in bytes. Default: 8 MiB.
+    pub max_uncompressed_bytes: usize,
+    /// Maximum size of the LZ4-compressed upload body, in bytes. Default: 1 MiB.
+    pub max_compressed_bytes: usize,
+}
+
+impl BatchLimits {
+    /// No limits: one batch per event name, however large.
+    pub fn unlimited() -> Self {
+        BatchLimits {
+            max_events: usize::MAX,
+            max_uncompressed_bytes: usize::MAX,
+            max_compressed_bytes: usize::MAX,
+        }
+    }
+}
+
+impl Default for BatchLimits {
+    fn default() -> Self {
+        BatchLimits {
+            max_events: 10_000,
+            max_uncompressed_bytes: 8 * 1024 * 1024,
+            max_compressed_bytes: 1024 * 1024,
+        }
+    }
+}
+
+/// Event accumulated for a batch, with the time range it contributes
+struct PendingEvent {
+    entry: CentralEventEntry,
+    start_time: u64,
+    end_time: u64,
+}
+
+/// Events and schemas of one event name, accumulated before they are encoded into one or
+/// more [`EncodedBatch`]es
+pub(crate) struct PendingBatch {
+    event_name: Arc<String>,
+    signal: Signal,
+    schemas: Vec<CentralSchemaEntry>,
+    events: Vec<PendingEvent>,
+}
+
+impl PendingBatch {
+    pub(crate) fn new(event_name: &str, signal: Signal) -> Self {
+        PendingBatch {
+            event_name: Arc::new(event_name.to_string()),
+            signal,
+            schemas: Vec::new(),
+            events: Vec::new(),
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    pub(crate) fn has_schema(&self, schema_id: u64) -> bool {
+        self.schemas.iter().any(|s| s.id == schema_id)
+    }
+
+    /// Adds a schema; callers check `has_schema` first so each schema is stored once
+    pub(crate) fn add_schema(&mut self, schema: CentralSchemaEntry) {
+        self.schemas.push(schema);
+    }
+
+    /// Adds a row. `start_time` and `end_time` are in nanoseconds since the Unix epoch, with
+    /// 0 meaning unknown; they are the same value for point-in-time records.
+    pub(crate) fn push(
+        &mut self,
+        schema_id: u64,
+        level: u8,
+        row: Vec<u8>,
+        start_time: u64,
+        end_time: u64,
+    ) {
+        self.events.push(PendingEvent {
+            entry: CentralEventEntry {
+                schema_id,
+                level,
+                event_name: Arc::clone(&self.event_name),
+                row,
+            },
+            start_time,
+            end_time,
+        });
+    }
+
+    /// Encodes and compresses the events, split into as many batches as `limits` require,
+    /// and records the result in `metrics` when self-observability is enabled
+    pub(crate) fn encode(
+        self,
+        metadata: &str,
+        limits: &BatchLimits,
+        metrics: Option<&SelfMetrics>,
+    ) -> Result<Vec<EncodedBatch>, GenevaError> {
+        let records = self.events.len();
+        let header_len = CentralBlob::header_len(metadata);
+        let schema_len = |id: u64| {
+            self.schemas
+                .iter()
+                .find(|s| s.id == id)
+                .map_or(0, CentralSchemaEntry::encoded_len)
+        };
+
+        // Split by event count and exact uncompressed size first
+        let mut parts: Vec<Vec<PendingEvent>> = Vec::new();
+        let mut part: Vec<PendingEvent> = Vec::new();
+        let mut part_schemas: Vec<u64> = Vec::new();
+        let mut part_len = header_len;
+        for event in self.events {
+            let new_schema = !part_schemas.contains(&event.entry.schema_id);
+            let added_len = event.entry.encoded_len()
+                + if new_schema {
+                    schema_len(event.entry.schema_id)
+                } else {
+                    0
+                };
+            if !part.is_empty()
+                && (part.len() >= limits.max_events
+                    || part_len.saturating_add(added_len) > limits.max_uncompressed_bytes)
+            {
+                parts.push(std::mem::take(&mut part));
+                part_schemas.clear();
+                part_len = header_len;
+            }
+            if !part_schemas.contains(&event.entry.schema_id) {
+                part_schemas.push(event.entry.schema_id);
+                part_len += schema_len(event.entry.schema_id);
+            }
+            part_len += event.entry.encoded_len();
+            part.push(event);
+        }
+        if !part.is_empty() {
+            parts.push(part);
+        }
+
+        let mut batches = Vec::with_capacity(parts.len());
+        let mut uncompressed_bytes = 0;
+        for part in parts {
+            encode_part(
+                &self.event_name,
+                &self.schemas,
+                part,
+                metadata,
+                limits,
+                &mut batches,
+                &mut uncompressed_bytes,
+            )?;
+        }
+        if let Some(metrics) = metrics {
+            let compressed_bytes = batches.iter().map(|b| b.data.len()).sum();
+            metrics.record_encoded(
+                self.signal,
+                records,
+                batches.len(),
+                uncompressed_bytes,
+                compressed_bytes,
+            );
+        }
+        Ok(batches)
+    }
+}
+
+/// Encodes one part, halving it until the compressed size fits. `uncompressed_bytes` is
+/// increased by the size of the blobs actually sent.
+fn encode_part(
+    event_name: &str,
+    schemas: &[CentralSchemaEntry],
+    events: Vec<PendingEvent>,
+    metadata: &str,
+    limits: &BatchLimits,
+    batches: &mut Vec<EncodedBatch>,
+    uncompressed_bytes: &mut usize,
+) -> Result<(), GenevaError> {
+    // Schemas in first-seen order, restricted to the ones this part uses
+    let schemas: Vec<CentralSchemaEntry> = schemas
+        .iter()
+        .filter(|s| events.iter().any(|e| e.entry.schema_id == s.id))
+        .cloned()
+        .collect();
+
+    let mut start_time = u64::MAX;
+    let mut end_time = 0u64;
+    for event in &events {
+        if event.start_time != 0 {
+            start_time = start_time.min(event.start_time);
+        }
+        if event.end_time != 0 {
+            end_time = end_time.max(event.end_time);
+        }
+    }
+    let batch_metadata = BatchMetadata {
+        start_time: if start_time == u64::MAX {
+            0
+        } else {
+            start_time
+        },
+        end_time,
+        schema_ids: format_schema_ids(&schemas),
+        event_count: events.len(),
+    };
+
+    let (entries, times): (Vec<_>, Vec<_>) = events
+        .into_iter()
+        .map(|e| (e.entry, (e.start_time, e.end_time)))
+        .unzip();
+    let blob = CentralBlob {
+        version: 1,
+        format: 2,
+        metadata: metadata.to_string(),
+        schemas,
+        events: entries,
+    };
+    let uncompressed = blob.to_bytes();
+    let compressed =
+        lz4_chunked_compression(&uncompressed).map_err(|e| GenevaError::encoding(e, event_name))?;
+
+    if compressed.len() > limits.max_compressed_bytes && blob.events.len() > 1 {
+        let all_schemas = blob.schemas;
+        let mut events: Vec<PendingEvent> = blob
+            .events
+            .into_iter()
+            .zip(times)
+            .map(|(entry, (start_time, end_time))| PendingEvent {
+                entry,
+                start_time,
+                end_time,
+            })
+            .collect();
+        let second = events.split_off(events.len() / 2);
+        encode_part(
+            event_name,
+            &all_schemas,
+            events,
+            metadata,
+            limits,
+            batches,
+            uncompressed_bytes,
+        )?;
+        return encode_part(
+            event_name,
+            &all_schemas,
+            second,
+            metadata,
+            limits,
+            batches,
+            uncompressed_bytes,
+        );
+    }
+
+    *uncompressed_bytes += uncompressed.len();
+    batches.push(EncodedBatch {
+        event_name: event_name.to_string(),
+        data: compressed,
+        metadata: batch_metadata,
+    });
+    Ok(())
+}
+
+/// Schema IDs formatted as MD5 hashes separated by semicolons
+fn format_schema_ids(schemas: &[CentralSchemaEntry]) -> String {
+    use std::fmt::Write;
+
+    // Pre-allocate capacity: Each MD5 hash is 32 hex chars + 1 semicolon (except last)
+    let estimated_capacity = schemas.len() * 32 + schemas.len().saturating_sub(1);
+    schemas.iter().enumerate().fold(
+        String::with_capacity(estimated_capacity),
+        |mut acc, (i, s)| {
+            if i > 0 {
+                acc.push(';');
+            }
+            let md5_hash = md5::compute(s.id.to_le_bytes());
+            write!(&mut acc, "{md5_hash:x}").unwrap();
+            acc
+        },
+    )
+}
@@ -56,11 +56,11 @@ macro_rules! impl_to_le_bytes {
     };
 }
 
-impl_to_le_bytes!(i32, i64, u32, f64);
+impl_to_le_bytes!(u8, i32, i64, u32, u64, f64);
 
 impl BondWriter {
     /// Write primitive numeric type to buffer in little-endian format
-    /// Works for i32, i64, u32, f64.
+    /// Works for u8, i32, i64, u32, u64, f64.
     #[inline]
     pub fn write_numeric<T>(buffer: &mut Vec<u8>, value: T)
     where
@@ -83,6 +83,38 @@ impl BondWriter {
         buffer.push(if value { 1u8 } else { 0u8 });
     }
 
+    /// Write the element count of a list value (Bond BT_LIST format)
+    /// The elements themselves follow, each written with the writer matching the element type.
+    pub fn write_list_len(buffer: &mut Vec<u8>, len: usize) {
+        //TODO - check if the length is less than 2^32-1
+        Self::write_numeric(buffer, len as u32);
+    }
+
+    /// Write the zero value of `type_id`: `false`, `0`, or an empty string or container
+    /// Keeps the row aligned with its schema when a field has no value of the declared type.
+    pub fn write_default(buffer: &mut Vec<u8>, type_id: BondDataType) {
+        let width = match type_id {
+            BondDataType::BT_BOOL | BondDataType::BT_UINT8 | BondDataType::BT_INT8 => 1,
+            BondDataType::BT_UINT16 | BondDataType::BT_INT16 => 2,
+            BondDataType::BT_UINT32
+            | BondDataType::BT_INT32
+            | BondDataType::BT_FLOAT
+            // Length or element count prefix
+            | BondDataType::BT_STRING
+            | BondDataType::BT_WSTRING
+            | BondDataType::BT_LIST
+            | BondDataType::BT_SET
+            | BondDataType::BT_MAP => 4,
+            BondDataType::BT_UINT64 | BondDataType::BT_INT64 | BondDataType::BT_DOUBLE => 8,
+            // Never used as a row field type
+            BondDataType::BT_STOP
+            | BondDataType::BT_STOP_BASE
+            | BondDataType::BT_STRUCT
+            | BondDataType::BT_UNAVAILABLE => 0,
+        };
+        buffer.resize(buffer.len() + width, 0);
+    }
+
     /// Write a WSTRING value to buffer (Bond BT_WSTRING format)
     /// Character count prefix + UTF-16LE bytes
     #[allow(dead_code)] // May be used in future, for now used in tests
@@ -103,6 +135,8 @@ pub(crate) struct FieldDef {
     pub name: Cow<'static, str>,
     pub field_id: u16,
     pub type_id: BondDataType,
+    /// Element type of `BT_LIST` fields, `None` for scalar fields
+    pub element_type: Option<BondDataType>,
 }
 
 /// Schema definition that can be built dynamically
@@ -197,6 +231,10 @@ impl DynamicSchema {
         size
     }
 
+    /// Size of the nested element typedef written for list fields:
+    /// type (u8) + struct_def (u16) + element (u8) + key (u8) + bonded_type (u8)
+    const ELEMENT_TYPEDEF_SIZE: usize = 6;
+
     /// Calculate the exact size of the encoded schema in bytes
     pub(crate) fn calculate_exact_encoded_size(&self) -> usize {
         // Start with fixed overhead
@@ -213,6 +251,11 @@ impl DynamicSchema {
             // Fixed overhead per field
             size += Self::calculate_per_field_fixed_overhead();
 
+            // Nested element typedef for list fields
+            if field.element_type.is_some() {
+                size += Self::ELEMENT_TYPEDEF_SIZE;
+            }
+
             // Variable field name
             size += 4 + field.name.len(); // name length + bytes
 
@@ -322,7 +365,18 @@ fn write_field_def<W: Write>(writer: &mut W, field: &FieldDef, is_last: bool) ->
 
     // Additional type info (all zeros for primitives)
     writer.write_all(&0u16.to_le_bytes())?; // struct_def
-    writer.write_all(&[0u8])?; // element
+    match field.element_type {
+        // Lists carry their element typedef inline, flagged by a present-count of 1
+        Some(element_type) => {
+            writer.write_all(&[1u8])?; // element present
+            writer.write_all(&[element_type as u8])?;
+            writer.write_all(&0u16.to_le_bytes())?; // struct_def
+            writer.write_all(&[0u8])?; // element
+            writer.write_all(&[0u8])?; // key
+            writer.write_all(&[0u8])?; // bonded_type
+        }
+        None => writer.write_all(&[0u8])?, // element
+    }
     writer.write_all(&[0u8])?; // key
     writer.write_all(&[0u8])?; // bonded_type
     writer.write_all(&[0u8])?; // default_value_present
@@ -415,16 +469,19 @@ mod tests {
                 name: Cow::Borrowed("field1"),
                 type_id: BondDataType::BT_DOUBLE,
                 field_id: 1,
+                element_type: None,
             },
             FieldDef {
                 name: Cow::Borrowed("field2"),
                 type_id: BondDataType::BT_STRING,
                 field_id: 2,
+                element_type: None,
             },
             FieldDef {
                 name: Cow::Borrowed("field3"),
                 type_id: BondDataType::BT_INT32,
                 field_id: 3,
+                element_type: None,
             },
         ];
 
@@ -440,16 +497,19 @@ mod tests {
                 name: Cow::Borrowed("timestamp"),
                 type_id: BondDataType::BT_STRING,
                 field_id: 1,
+                element_type: None,
             },
             FieldDef {
                 name: Cow::Borrowed("severity"),
                 type_id: BondDataType::BT_INT32,
                 field_id: 2,
+                element_type: None,
             },
             FieldDef {
                 name: Cow::Borrowed("message"),
                 type_id: BondDataType::BT_STRING,
                 field_id: 3,
+                element_type: None,
             },
         ];
 
@@ -467,11 +527,13 @@ mod tests {
                 name: Cow::Owned(dynamic_field_name),
                 type_id: BondDataType::BT_STRING,
                 field_id: 1,
+                element_type: None,
             },
             FieldDef {
                 name: Cow::Borrowed("static_field"),
                 type_id: BondDataType::BT_INT32,
                 field_id: 2,
+                element_type: None,
             },
         ];
 
@@ -499,8 +561,14 @@ mod tests {
             let fields: Vec<FieldDef> = (0..field_count)
                 .map(|i| FieldDef {
                     name: Cow::Owned(format!("field_with_long_name_{i}")),
-                    type_id: BondDataType::BT_STRING,
+                    // Mix in list fields, which carry an extra element typedef
+                    type_id: if i % 3 == 0 {
+                        BondDataType::BT_LIST
+                    } else {
+                        BondDataType::BT_STRING
+                    },
                     field_id: i as u16 + 1,
+                    element_type: (i % 3 == 0).then_some(BondDataType::BT_INT64),
                 })
                 .collect();
 
@@ -6,6 +6,7 @@ use std::sync::Arc;
 
 /// Metadata for a batch of events
 #[derive(Debug, Clone)]
+#[non_exhaustive]
 pub struct BatchMetadata {
     /// Start time of the earliest event in nanoseconds since Unix epoch
     pub start_time: u64,
@@ -13,6 +14,8 @@ pub struct BatchMetadata {
     pub end_time: u64,
     /// Schema IDs present in this batch formatted as MD5 hashes separated by semicolons
     pub schema_ids: String,
+    /// Number of events (rows) in the batch
+    pub event_count: usize,
 }
 
 impl BatchMetadata {
@@ -67,6 +70,7 @@ fn utf8_to_utf16le_bytes(s: &str) -> Vec<u8> {
 }
 
 /// Schema entry for central blob
+#[derive(Clone)]
 #[allow(dead_code)]
 pub(crate) struct CentralSchemaEntry {
     pub id: u64,
@@ -83,6 +87,20 @@ pub(crate) struct CentralEventEntry {
     pub row: Vec<u8>,
 }
 
+impl CentralSchemaEntry {
+    /// Encoded size of this schema entry in the blob
+    pub(crate) fn encoded_len(&self) -> usize {
+        2 + 8 + 16 + 4 + self.schema.as_bytes().len() + 8
+    }
+}
+
+impl CentralEventEntry {
+    /// Encoded size of this event entry in the blob, including the Simple Protocol header
+    pub(crate) fn encoded_len(&self) -> usize {
+        2 + 8 + 1 + 2 + self.event_name.encode_utf16().count() * 2 + 4 + 4 + self.row.len() + 8
+    }
+}
+
 const TERMINATOR: u64 = 0xdeadc0dedeadc0de;
 
 /// CentralBlob Protocol Payload Structure
@@ -132,6 +150,11 @@ pub(crate) struct CentralBlob {
 }
 
 impl CentralBlob {
+    /// Encoded size of the header and metadata
+    pub(crate) fn header_len(metadata: &str) -> usize {
+        8 + 4 + metadata.encode_utf16().count() * 2
+    }
+
     #[allow(dead_code)]
     pub(crate) fn to_bytes(&self) -> Vec<u8> {
         // Estimate buffer size:
@@ -163,20 +186,16 @@ impl CentralBlob {
                 (e, evname_utf16)
             })
             .collect::<Vec<_>>();
-        let mut estimated_size = 8 + 4 + meta_utf16.len();
+        let mut estimated_size = Self::header_len(&self.metadata);
         estimated_size += self
             .schemas
             .iter()
-            .map(|s| 2 + 8 + 16 + 4 + s.schema.as_bytes().len() + 8)
+            .map(CentralSchemaEntry::encoded_len)
             .sum::<usize>();
-        estimated_size += events_with_utf16
+        estimated_size += self
+            .events
             .iter()
-            .map(|(e, evname_utf16)| {
-                let row_len = {
-                    4 + &e.row.len() // SP header (4), row_bytes
-                };
-                2 + 8 + 1 + 2 + 4 + evname_utf16.len() + row_len + 8
-            })
+            .map(CentralEventEntry::encoded_len)
             .sum::<usize>();
 
         let mut buf = Vec::with_capacity(estimated_size);
@@ -243,11 +262,13 @@ mod tests {
                 name: Cow::Borrowed("foo"),
                 type_id: crate::payload_encoder::bond_encoder::BondDataType::BT_INT32,
                 field_id: 1u16,
+                element_type: None,
             },
             FieldDef {
                 name: Cow::Borrowed("bar"),
                 type_id: crate::payload_encoder::bond_encoder::BondDataType::BT_STRING,
                 field_id: 2u16,
+                element_type: None,
             },
         ];
         let schema_obj = BondEncodedSchema::from_fields("TestStruct", "test.namespace", fields);
@@ -292,5 +313,11 @@ mod tests {
 
         // Only assert that the payload is created and non-empty
         assert!(!payload.is_empty());
+
+        // Size helpers used for batch splitting must match the encoded size exactly
+        let expected_len = CentralBlob::header_len(metadata)
+            + blob.schemas[0].encoded_len()
+            + blob.events[0].encoded_len();
+        assert_eq!(payload.len(), expected_len);
     }
 }
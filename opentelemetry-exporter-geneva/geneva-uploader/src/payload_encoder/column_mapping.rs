@@ -0,0 +1,226 @@
+// Renaming of Bond columns and filtering / transformation of attribute values
+
+use crate::payload_encoder::attribute_encoding::AttributeEncoding;
+use opentelemetry_proto::tonic::common::v1::any_value::Value;
+use sha2::{Digest, Sha256};
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+/// Replacement value written for redacted attributes.
+pub(crate) const REDACTED: &str = "[REDACTED]";
+
+/// Which attributes become columns.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum AttributeFilter {
+    /// Every attribute.
+    #[default]
+    All,
+    /// Only attributes with these keys.
+    Allow(Vec<String>),
+    /// Every attribute except those with these keys.
+    Deny(Vec<String>),
+}
+
+/// Column type an attribute is coerced to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColumnType {
+    String,
+    Int64,
+    Double,
+    Bool,
+}
+
+/// Mapping from OTLP fields and attributes to Geneva columns.
+///
+/// Applied while the schema is built, so a given mapping always produces the same schema
+/// for the same input shape. Attribute rules (filter, redact, hash, coerce) are keyed by the
+/// attribute key and apply to record attributes, promoted resource/scope attributes and
+/// metric data point attributes. Renames apply to any column, built-in or attribute.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ColumnMapping {
+    /// Column renames, from the default column name (e.g. `SeverityNumber`, `env_dt_traceId`
+    /// or an attribute key) to the name used in the Geneva table.
+    pub renames: HashMap<String, String>,
+    /// Attributes that become columns.
+    pub attribute_filter: AttributeFilter,
+    /// Attributes whose values are replaced by `[REDACTED]`.
+    pub redact: Vec<String>,
+    /// Attributes whose values are replaced by the hex SHA-256 of `hash_salt` followed by the
+    /// value, so they can still be correlated without exposing the original.
+    pub hash: Vec<String>,
+    /// Salt prepended to hashed values.
+    pub hash_salt: String,
+    /// Attributes coerced to a fixed column type. Values that cannot be converted are dropped.
+    pub coerce: HashMap<String, ColumnType>,
+}
+
+impl ColumnMapping {
+    /// Name of the column for a field or attribute
+    pub(crate) fn column_name<'a>(&'a self, name: &'a str) -> &'a str {
+        self.renames.get(name).map_or(name, String::as_str)
+    }
+
+    /// Renames a schema field, keeping the original allocation when there is no rename
+    pub(crate) fn rename(&self, name: Cow<'static, str>) -> Cow<'static, str> {
+        match self.renames.get(name.as_ref()) {
+            Some(renamed) => Cow::Owned(renamed.clone()),
+            None => name,
+        }
+    }
+
+    /// Value written for attribute `key`, or `None` if the attribute is filtered out or
+    /// cannot be coerced to its configured type.
+    pub(crate) fn map_attribute<'v>(
+        &self,
+        key: &str,
+        value: &'v Value,
+        encoding: &AttributeEncoding,
+    ) -> Option<Cow<'v, Value>> {
+        let included = match &self.attribute_filter {
+            AttributeFilter::All => true,
+            AttributeFilter::Allow(keys) => keys.iter().any(|k| k == key),
+            AttributeFilter::Deny(keys) => !keys.iter().any(|k| k == key),
+        };
+        if !included {
+            return None;
+        }
+
+        let value = if self.redact.iter().any(|k| k == key) {
+            Cow::Owned(Value::StringValue(REDACTED.to_string()))
+        } else if self.hash.iter().any(|k| k == key) {
+            let mut hasher = Sha256::new();
+            hasher.update(self.hash_salt.as_bytes());
+            hasher.update(value_to_string(value, encoding).as_bytes());
+            Cow::Owned(Value::StringValue(hex::encode(hasher.finalize())))
+        } else {
+            Cow::Borrowed(value)
+        };
+
+        match self.coerce.get(key) {
+            Some(column_type) => coerce(&value, *column_type, encoding).map(Cow::Owned),
+            None => Some(value),
+        }
+    }
+}
+
+fn value_to_string(value: &Value, encoding: &AttributeEncoding) -> String {
+    match value {
+        Value::StringValue(s) => s.clone(),
+        Value::IntValue(i) => i.to_string(),
+        Value::DoubleValue(d) => d.to_string(),
+        Value::BoolValue(b) => b.to_string(),
+        Value::BytesValue(bytes) => hex::encode(bytes),
+        Value::ArrayValue(_) | Value::KvlistValue(_) => encoding.to_json_string(value),
+    }
+}
+
+fn coerce(value: &Value, column_type: ColumnType, encoding: &AttributeEncoding) -> Option<Value> {
+    Some(match (column_type, value) {
+        (ColumnType::String, _) => Value::StringValue(value_to_string(value, encoding)),
+        (ColumnType::Int64, Value::IntValue(i)) => Value::IntValue(*i),
+        (ColumnType::Int64, Value::DoubleValue(d)) if d.is_finite() => Value::IntValue(*d as i64),
+        (ColumnType::Int64, Value::BoolValue(b)) => Value::IntValue(*b as i64),
+        (ColumnType::Int64, Value::StringValue(s)) => Value::IntValue(s.trim().parse().ok()?),
+        (ColumnType::Double, Value::DoubleValue(d)) => Value::DoubleValue(*d),
+        (ColumnType::Double, Value::IntValue(i)) => Value::DoubleValue(*i as f64),
+        (ColumnType::Double, Value::StringValue(s)) => Value::DoubleValue(s.trim().parse().ok()?),
+        (ColumnType::Bool, Value::BoolValue(b)) => Value::BoolValue(*b),
+        (ColumnType::Bool, Value::IntValue(i)) => Value::BoolValue(*i != 0),
+        (ColumnType::Bool, Value::StringValue(s)) => Value::BoolValue(s.trim().parse().ok()?),
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn string(s: &str) -> Value {
+        Value::StringValue(s.to_string())
+    }
+
+    fn map(mapping: &ColumnMapping, key: &str, value: &Value) -> Option<Value> {
+        mapping
+            .map_attribute(key, value, &AttributeEncoding::default())
+            .map(Cow::into_owned)
+    }
+
+    #[test]
+    fn test_filters() {
+        let allow = ColumnMapping {
+            attribute_filter: AttributeFilter::Allow(vec!["keep".to_string()]),
+            ..ColumnMapping::default()
+        };
+        assert!(map(&allow, "keep", &string("v")).is_some());
+        assert!(map(&allow, "other", &string("v")).is_none());
+
+        let deny = ColumnMapping {
+            attribute_filter: AttributeFilter::Deny(vec!["user.email".to_string()]),
+            ..ColumnMapping::default()
+        };
+        assert!(map(&deny, "user.email", &string("a@b.c")).is_none());
+        assert!(map(&deny, "other", &string("v")).is_some());
+    }
+
+    #[test]
+    fn test_redact_and_hash() {
+        let mapping = ColumnMapping {
+            redact: vec!["password".to_string()],
+            hash: vec!["user.id".to_string()],
+            hash_salt: "salt".to_string(),
+            ..ColumnMapping::default()
+        };
+        assert_eq!(
+            map(&mapping, "password", &string("hunter2")),
+            Some(string(REDACTED))
+        );
+
+        let hashed = map(&mapping, "user.id", &string("alice")).unwrap();
+        let expected = hex::encode(Sha256::digest(b"saltalice"));
+        assert_eq!(hashed, string(&expected));
+        // Same input, same hash; numbers hash via their string form
+        assert_eq!(map(&mapping, "user.id", &string("alice")).unwrap(), hashed);
+        assert_eq!(
+            map(&mapping, "user.id", &Value::IntValue(42)).unwrap(),
+            string(&hex::encode(Sha256::digest(b"salt42")))
+        );
+    }
+
+    #[test]
+    fn test_coercion() {
+        let mapping = ColumnMapping {
+            coerce: HashMap::from([
+                ("status".to_string(), ColumnType::Int64),
+                ("ratio".to_string(), ColumnType::Double),
+                ("id".to_string(), ColumnType::String),
+                ("flag".to_string(), ColumnType::Bool),
+            ]),
+            ..ColumnMapping::default()
+        };
+        assert_eq!(
+            map(&mapping, "status", &string(" 200 ")),
+            Some(Value::IntValue(200))
+        );
+        assert_eq!(map(&mapping, "status", &string("ok")), None);
+        assert_eq!(
+            map(&mapping, "ratio", &Value::IntValue(3)),
+            Some(Value::DoubleValue(3.0))
+        );
+        assert_eq!(map(&mapping, "id", &Value::IntValue(7)), Some(string("7")));
+        assert_eq!(
+            map(&mapping, "flag", &Value::IntValue(0)),
+            Some(Value::BoolValue(false))
+        );
+    }
+
+    #[test]
+    fn test_renames() {
+        let mapping = ColumnMapping {
+            renames: HashMap::from([("SeverityNumber".to_string(), "Level".to_string())]),
+            ..ColumnMapping::default()
+        };
+        assert_eq!(mapping.column_name("SeverityNumber"), "Level");
+        assert_eq!(mapping.column_name("body"), "body");
+        assert_eq!(mapping.rename(Cow::Borrowed("SeverityNumber")), "Level");
+    }
+}
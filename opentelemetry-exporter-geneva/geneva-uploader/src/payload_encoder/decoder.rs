@@ -0,0 +1,683 @@
+// Decoding of LZ4-chunked CentralBlob payloads back into Bond schemas and rows
+
+use crate::payload_encoder::bond_encoder::BondDataType;
+use crate::payload_encoder::lz4_chunked_compression::lz4_chunked_decompression;
+use crate::spool::file_spool::{deserialize_batch, SPOOL_MAGIC};
+use serde::ser::SerializeMap;
+use serde::{Serialize, Serializer};
+use thiserror::Error;
+
+/// Error returned when a payload cannot be decoded.
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The LZ4 chunk framing or compressed data is invalid.
+    #[error("invalid LZ4 data: {0}")]
+    Decompression(String),
+    /// The data ends in the middle of an entry.
+    #[error("unexpected end of data at offset {0}")]
+    Truncated(usize),
+    /// The data does not follow the CentralBlob or Bond layout.
+    #[error("invalid payload at offset {offset}: {reason}")]
+    Invalid { offset: usize, reason: String },
+}
+
+/// A decoded CentralBlob: upload metadata, Bond schemas and one entry per row.
+///
+/// Serializes to JSON with each row as an object of column values in schema order.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct DecodedBlob {
+    pub version: u32,
+    pub format: u32,
+    /// `namespace=.../eventVersion=.../tenant=.../role=.../roleinstance=...` metadata string.
+    pub metadata: String,
+    pub schemas: Vec<DecodedSchema>,
+    pub events: Vec<DecodedEvent>,
+}
+
+/// A Bond schema carried in the blob.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct DecodedSchema {
+    pub id: u64,
+    /// MD5 of the schema ID, as listed in the `schemaIds` upload parameter.
+    pub id_md5: String,
+    /// MD5 of the schema definition.
+    #[serde(serialize_with = "serialize_hex")]
+    pub md5: [u8; 16],
+    pub struct_name: String,
+    pub qualified_name: String,
+    pub fields: Vec<DecodedField>,
+}
+
+/// A field of a [`DecodedSchema`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct DecodedField {
+    pub name: String,
+    pub field_id: u16,
+    /// Bond type, e.g. `string`, `int64` or `list<uint8>`.
+    #[serde(rename = "type")]
+    pub type_name: String,
+    #[serde(skip)]
+    type_id: u8,
+    #[serde(skip)]
+    element_type: Option<u8>,
+}
+
+/// One row of the blob, with its values in schema order.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct DecodedEvent {
+    pub schema_id: u64,
+    pub level: u8,
+    pub event_name: String,
+    #[serde(serialize_with = "serialize_fields")]
+    pub fields: Vec<(String, DecodedValue)>,
+}
+
+impl DecodedEvent {
+    /// Value of the column `name`, if the row's schema has it.
+    pub fn get(&self, name: &str) -> Option<&DecodedValue> {
+        self.fields
+            .iter()
+            .find_map(|(field, value)| (field == name).then_some(value))
+    }
+}
+
+/// A decoded Bond value. Integers are widened to 64 bits; the exact Bond type is in the
+/// schema's [`DecodedField::type_name`].
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum DecodedValue {
+    Bool(bool),
+    Int(i64),
+    UInt(u64),
+    Double(f64),
+    String(String),
+    List(Vec<DecodedValue>),
+}
+
+impl DecodedValue {
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            DecodedValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            DecodedValue::Int(v) => Some(*v),
+            DecodedValue::UInt(v) => i64::try_from(*v).ok(),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            DecodedValue::Double(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            DecodedValue::Bool(v) => Some(*v),
+            _ => None,
+        }
+    }
+}
+
+/// Decodes an LZ4-chunked CentralBlob, as found in `EncodedBatch::data` or an upload body.
+pub fn decode_payload(compressed: &[u8]) -> Result<DecodedBlob, DecodeError> {
+    let blob = lz4_chunked_decompression(compressed).map_err(DecodeError::Decompression)?;
+    decode_blob(&blob)
+}
+
+/// Decodes the contents of a batch file: a spooled batch (see
+/// [`SpoolConfig`](crate::SpoolConfig)), an LZ4-chunked upload body, or an uncompressed
+/// CentralBlob.
+pub fn decode_batch_file(bytes: &[u8]) -> Result<DecodedBlob, DecodeError> {
+    if bytes.starts_with(SPOOL_MAGIC) {
+        let (_, batch) = deserialize_batch(bytes).map_err(|e| DecodeError::Invalid {
+            offset: 0,
+            reason: e.to_string(),
+        })?;
+        return decode_payload(&batch.data);
+    }
+    match decode_payload(bytes) {
+        // Version 1, format 2: the blob was saved before compression
+        Err(DecodeError::Decompression(_)) if bytes.starts_with(&[1, 0, 0, 0, 2, 0, 0, 0]) => {
+            decode_blob(bytes)
+        }
+        result => result,
+    }
+}
+
+/// Decodes an uncompressed CentralBlob.
+pub fn decode_blob(bytes: &[u8]) -> Result<DecodedBlob, DecodeError> {
+    let mut reader = Reader::new(bytes);
+    let version = reader.u32()?;
+    let format = reader.u32()?;
+    let metadata_len = reader.u32()? as usize;
+    let metadata = reader.utf16(metadata_len)?;
+
+    let mut schemas: Vec<DecodedSchema> = Vec::new();
+    let mut events = Vec::new();
+    while !reader.is_empty() {
+        let entity_type = reader.u16()?;
+        match entity_type {
+            0 => {
+                let id = reader.u64()?;
+                let md5 = reader.array()?;
+                let len = reader.u32()? as usize;
+                let offset = reader.offset;
+                let schema =
+                    parse_schema(id, md5, reader.take(len)?).map_err(|e| e.shifted(offset))?;
+                reader.terminator()?;
+                schemas.push(schema);
+            }
+            2 => {
+                let schema_id = reader.u64()?;
+                let level = reader.u8()?;
+                let name_len = reader.u16()? as usize;
+                let event_name = reader.utf16(name_len)?;
+                let row_len = reader.u32()? as usize;
+                let offset = reader.offset;
+                let row = reader.take(row_len)?;
+                let schema = schemas.iter().find(|s| s.id == schema_id).ok_or_else(|| {
+                    reader.invalid(format!("event references unknown schema {schema_id}"))
+                })?;
+                let fields = decode_row(row, schema).map_err(|e| e.shifted(offset))?;
+                reader.terminator()?;
+                events.push(DecodedEvent {
+                    schema_id,
+                    level,
+                    event_name,
+                    fields,
+                });
+            }
+            other => return Err(reader.invalid(format!("unknown entity type {other}"))),
+        }
+    }
+
+    Ok(DecodedBlob {
+        version,
+        format,
+        metadata,
+        schemas,
+        events,
+    })
+}
+
+/// Parses a schema written by `DynamicSchema::encode`
+fn parse_schema(id: u64, md5: [u8; 16], bytes: &[u8]) -> Result<DecodedSchema, DecodeError> {
+    let mut reader = Reader::new(bytes);
+    reader.simple_protocol_header()?;
+    let struct_count = reader.u32()?;
+    if struct_count != 1 {
+        return Err(reader.invalid(format!("expected 1 struct, found {struct_count}")));
+    }
+    let struct_name = reader.string()?;
+    let qualified_name = reader.string()?;
+    // attributes (4), modifier (1), default values (33), base def (4), padding (3)
+    reader.take(4 + 1 + 33 + 4 + 3)?;
+    let field_count = reader.u32()? as usize;
+
+    // The count is untrusted; every field takes at least one byte
+    let mut fields = Vec::with_capacity(field_count.min(reader.remaining()));
+    for i in 0..field_count {
+        let name = reader.string()?;
+        let _qualified_name = reader.string()?;
+        // attributes (4), modifier (1), default values (33), padding (3)
+        reader.take(4 + 1 + 33 + 3)?;
+        let field_id = reader.u16()?;
+        let type_id = reader.u8()?;
+        let _struct_def = reader.u16()?;
+        let element_type = match reader.u8()? {
+            0 => None,
+            _ => {
+                let element_type = reader.u8()?;
+                // struct_def (2), element (1), key (1), bonded_type (1)
+                reader.take(5)?;
+                Some(element_type)
+            }
+        };
+        // key, bonded_type, default_value_present
+        reader.take(3)?;
+        if i + 1 < field_count {
+            reader.take(8)?;
+        }
+        let type_name = match element_type {
+            Some(element_type) => format!("list<{}>", type_name(element_type)),
+            None => type_name(type_id).to_string(),
+        };
+        fields.push(DecodedField {
+            name,
+            field_id,
+            type_name,
+            type_id,
+            element_type,
+        });
+    }
+
+    Ok(DecodedSchema {
+        id,
+        id_md5: format!("{:x}", md5::compute(id.to_le_bytes())),
+        md5,
+        struct_name,
+        qualified_name,
+        fields,
+    })
+}
+
+/// Decodes a row (Simple Protocol header followed by the values in field order)
+fn decode_row(
+    row: &[u8],
+    schema: &DecodedSchema,
+) -> Result<Vec<(String, DecodedValue)>, DecodeError> {
+    let mut reader = Reader::new(row);
+    reader.simple_protocol_header()?;
+    let mut values = Vec::with_capacity(schema.fields.len());
+    for field in &schema.fields {
+        let value = match field.element_type {
+            Some(element_type) => {
+                let count = reader.u32()? as usize;
+                let mut items = Vec::with_capacity(count.min(reader.remaining()));
+                for _ in 0..count {
+                    items.push(reader.value(element_type)?);
+                }
+                DecodedValue::List(items)
+            }
+            None => reader.value(field.type_id)?,
+        };
+        values.push((field.name.clone(), value));
+    }
+    if !reader.is_empty() {
+        return Err(reader.invalid(format!("{} trailing bytes in row", reader.remaining())));
+    }
+    Ok(values)
+}
+
+fn serialize_hex<S: Serializer>(bytes: &[u8; 16], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&hex::encode(bytes))
+}
+
+/// Serializes row values as a JSON object, keeping schema order
+fn serialize_fields<S: Serializer>(
+    fields: &[(String, DecodedValue)],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let mut map = serializer.serialize_map(Some(fields.len()))?;
+    for (name, value) in fields {
+        map.serialize_entry(name, value)?;
+    }
+    map.end()
+}
+
+// Bond type ids, usable as match patterns
+const BT_BOOL: u8 = BondDataType::BT_BOOL as u8;
+const BT_UINT8: u8 = BondDataType::BT_UINT8 as u8;
+const BT_UINT16: u8 = BondDataType::BT_UINT16 as u8;
+const BT_UINT32: u8 = BondDataType::BT_UINT32 as u8;
+const BT_UINT64: u8 = BondDataType::BT_UINT64 as u8;
+const BT_FLOAT: u8 = BondDataType::BT_FLOAT as u8;
+const BT_DOUBLE: u8 = BondDataType::BT_DOUBLE as u8;
+const BT_STRING: u8 = BondDataType::BT_STRING as u8;
+const BT_INT8: u8 = BondDataType::BT_INT8 as u8;
+const BT_INT16: u8 = BondDataType::BT_INT16 as u8;
+const BT_INT32: u8 = BondDataType::BT_INT32 as u8;
+const BT_INT64: u8 = BondDataType::BT_INT64 as u8;
+const BT_WSTRING: u8 = BondDataType::BT_WSTRING as u8;
+
+fn type_name(type_id: u8) -> &'static str {
+    match type_id {
+        BT_BOOL => "bool",
+        BT_UINT8 => "uint8",
+        BT_UINT16 => "uint16",
+        BT_UINT32 => "uint32",
+        BT_UINT64 => "uint64",
+        BT_FLOAT => "float",
+        BT_DOUBLE => "double",
+        BT_STRING => "string",
+        BT_INT8 => "int8",
+        BT_INT16 => "int16",
+        BT_INT32 => "int32",
+        BT_INT64 => "int64",
+        BT_WSTRING => "wstring",
+        _ => "unsupported",
+    }
+}
+
+/// Little-endian cursor over a byte slice
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Reader { bytes, offset: 0 }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.offset >= self.bytes.len()
+    }
+
+    fn remaining(&self) -> usize {
+        self.bytes.len() - self.offset
+    }
+
+    fn invalid(&self, reason: String) -> DecodeError {
+        DecodeError::Invalid {
+            offset: self.offset,
+            reason,
+        }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], DecodeError> {
+        let end = self
+            .offset
+            .checked_add(len)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or(DecodeError::Truncated(self.offset))?;
+        let slice = &self.bytes[self.offset..end];
+        self.offset = end;
+        Ok(slice)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], DecodeError> {
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+
+    fn u8(&mut self) -> Result<u8, DecodeError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, DecodeError> {
+        Ok(u16::from_le_bytes(self.array()?))
+    }
+
+    fn u32(&mut self) -> Result<u32, DecodeError> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+
+    fn u64(&mut self) -> Result<u64, DecodeError> {
+        Ok(u64::from_le_bytes(self.array()?))
+    }
+
+    /// UTF-8 string prefixed with its byte length
+    fn string(&mut self) -> Result<String, DecodeError> {
+        let len = self.u32()? as usize;
+        let offset = self.offset;
+        String::from_utf8(self.take(len)?.to_vec()).map_err(|e| DecodeError::Invalid {
+            offset,
+            reason: e.to_string(),
+        })
+    }
+
+    /// UTF-16LE string of `len` bytes
+    fn utf16(&mut self, len: usize) -> Result<String, DecodeError> {
+        let offset = self.offset;
+        let units: Vec<u16> = self
+            .take(len)?
+            .chunks_exact(2)
+            .map(|c| u16::from_le_bytes([c[0], c[1]]))
+            .collect();
+        String::from_utf16(&units).map_err(|e| DecodeError::Invalid {
+            offset,
+            reason: e.to_string(),
+        })
+    }
+
+    fn simple_protocol_header(&mut self) -> Result<(), DecodeError> {
+        let header = self.take(4)?;
+        if header != [0x53, 0x50, 0x01, 0x00] {
+            return Err(DecodeError::Invalid {
+                offset: self.offset - 4,
+                reason: "missing Simple Protocol header".to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    fn terminator(&mut self) -> Result<(), DecodeError> {
+        let terminator = self.u64()?;
+        if terminator != 0xdeadc0dedeadc0de {
+            return Err(DecodeError::Invalid {
+                offset: self.offset - 8,
+                reason: format!("expected entry terminator, found {terminator:#x}"),
+            });
+        }
+        Ok(())
+    }
+
+    /// Scalar value of the given Bond type
+    fn value(&mut self, type_id: u8) -> Result<DecodedValue, DecodeError> {
+        let value = match type_id {
+            BT_BOOL => DecodedValue::Bool(self.u8()? != 0),
+            BT_UINT8 => DecodedValue::UInt(self.u8()?.into()),
+            BT_UINT16 => DecodedValue::UInt(self.u16()?.into()),
+            BT_UINT32 => DecodedValue::UInt(self.u32()?.into()),
+            BT_UINT64 => DecodedValue::UInt(self.u64()?),
+            BT_INT8 => DecodedValue::Int(i8::from_le_bytes(self.array()?).into()),
+            BT_INT16 => DecodedValue::Int(i16::from_le_bytes(self.array()?).into()),
+            BT_INT32 => DecodedValue::Int(i32::from_le_bytes(self.array()?).into()),
+            BT_INT64 => DecodedValue::Int(i64::from_le_bytes(self.array()?)),
+            BT_FLOAT => DecodedValue::Double(f32::from_le_bytes(self.array()?).into()),
+            BT_DOUBLE => DecodedValue::Double(f64::from_le_bytes(self.array()?)),
+            BT_STRING => DecodedValue::String(self.string()?),
+            BT_WSTRING => {
+                // Length is in UTF-16 code units
+                let len = self.u32()? as usize;
+                DecodedValue::String(self.utf16(len.saturating_mul(2))?)
+            }
+            other => return Err(self.invalid(format!("unsupported Bond type {other}"))),
+        };
+        Ok(value)
+    }
+}
+
+impl DecodeError {
+    /// Makes an offset within a nested schema or row relative to the enclosing blob
+    fn shifted(self, base: usize) -> Self {
+        match self {
+            DecodeError::Truncated(offset) => DecodeError::Truncated(base + offset),
+            DecodeError::Invalid { offset, reason } => DecodeError::Invalid {
+                offset: base + offset,
+                reason,
+            },
+            other => other,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::payload_encoder::attribute_encoding::{AttributeEncoding, BytesEncoding};
+    use crate::payload_encoder::batch_limits::BatchLimits;
+    use crate::payload_encoder::column_mapping::ColumnMapping;
+    use crate::payload_encoder::otlp_encoder::OtlpEncoder;
+    use crate::payload_encoder::resource_mapping::ResourceMapping;
+    use opentelemetry_proto::tonic::common::v1::any_value::Value;
+    use opentelemetry_proto::tonic::common::v1::{AnyValue, KeyValue};
+    use opentelemetry_proto::tonic::logs::v1::LogRecord;
+
+    fn attribute(key: &str, value: Value) -> KeyValue {
+        KeyValue {
+            key: key.to_string(),
+            value: Some(AnyValue { value: Some(value) }),
+        }
+    }
+
+    #[test]
+    fn test_decode_encoded_logs() {
+        let encoder = OtlpEncoder::with_config(
+            AttributeEncoding {
+                bytes: BytesEncoding::BondList,
+                ..AttributeEncoding::default()
+            },
+            ResourceMapping::default(),
+            ColumnMapping::default(),
+            BatchLimits::default(),
+        );
+        let log = LogRecord {
+            time_unix_nano: 1_700_000_000_000_000_000,
+            event_name: "Checkout".to_string(),
+            severity_number: 9,
+            severity_text: "INFO".to_string(),
+            body: Some(AnyValue {
+                value: Some(Value::StringValue("paid".to_string())),
+            }),
+            attributes: vec![
+                attribute("user", Value::StringValue("alice".to_string())),
+                attribute("items", Value::IntValue(3)),
+                attribute("express", Value::BoolValue(true)),
+                attribute("amount", Value::DoubleValue(9.5)),
+                attribute("digest", Value::BytesValue(vec![1, 2, 3])),
+            ],
+            ..Default::default()
+        };
+        let batches = encoder
+            .encode_log_batch([log].iter(), "namespace=test")
+            .unwrap();
+
+        let blob = decode_payload(&batches[0].data).unwrap();
+        assert_eq!(blob.metadata, "namespace=test");
+        assert_eq!(blob.schemas.len(), 1);
+        assert_eq!(blob.events.len(), 1);
+        let event = &blob.events[0];
+        assert_eq!(event.event_name, "Checkout");
+        assert_eq!(event.level, 9);
+        assert_eq!(event.schema_id, blob.schemas[0].id);
+        assert_eq!(
+            event.get("env_name").and_then(|v| v.as_str()),
+            Some("TestEnv")
+        );
+        assert_eq!(event.get("body").and_then(|v| v.as_str()), Some("paid"));
+        assert_eq!(
+            event.get("SeverityNumber").and_then(|v| v.as_i64()),
+            Some(9)
+        );
+        assert_eq!(event.get("user").and_then(|v| v.as_str()), Some("alice"));
+        assert_eq!(event.get("items").and_then(|v| v.as_i64()), Some(3));
+        assert_eq!(event.get("express").and_then(|v| v.as_bool()), Some(true));
+        assert_eq!(event.get("amount").and_then(|v| v.as_f64()), Some(9.5));
+        assert_eq!(
+            event.get("digest"),
+            Some(&DecodedValue::List(vec![
+                DecodedValue::UInt(1),
+                DecodedValue::UInt(2),
+                DecodedValue::UInt(3)
+            ]))
+        );
+        let digest_field = blob.schemas[0]
+            .fields
+            .iter()
+            .find(|f| f.name == "digest")
+            .unwrap();
+        assert_eq!(digest_field.type_name, "list<uint8>");
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        let batches = OtlpEncoder::new()
+            .encode_log_batch([LogRecord::default()].iter(), "namespace=test")
+            .unwrap();
+        let blob = lz4_chunked_decompression(&batches[0].data).unwrap();
+
+        assert!(matches!(
+            decode_blob(&blob[..blob.len() - 3]),
+            Err(DecodeError::Truncated(_))
+        ));
+        let mut corrupted = blob.clone();
+        let last = corrupted.len() - 1;
+        corrupted[last] = 0;
+        assert!(matches!(
+            decode_blob(&corrupted),
+            Err(DecodeError::Invalid { .. })
+        ));
+        assert!(matches!(
+            decode_payload(&[5, 0, 0, 0, 1]),
+            Err(DecodeError::Decompression(_))
+        ));
+
+        // A huge field count must not be preallocated
+        let mut schema = vec![0x53, 0x50, 0x01, 0x00];
+        schema.extend_from_slice(&1u32.to_le_bytes());
+        for name in ["S", "ns.S"] {
+            schema.extend_from_slice(&(name.len() as u32).to_le_bytes());
+            schema.extend_from_slice(name.as_bytes());
+        }
+        schema.extend_from_slice(&[0; 45]);
+        schema.extend_from_slice(&u32::MAX.to_le_bytes());
+        assert!(matches!(
+            parse_schema(1, [0; 16], &schema),
+            Err(DecodeError::Truncated(_))
+        ));
+    }
+
+    #[test]
+    fn test_decode_batch_file_formats() {
+        let batches = OtlpEncoder::new()
+            .encode_log_batch([LogRecord::default()].iter(), "namespace=test")
+            .unwrap();
+        let batch = &batches[0];
+        let expected = batch.decode().unwrap();
+
+        let spooled = crate::spool::file_spool::serialize_batch(batch, 0);
+        assert_eq!(decode_batch_file(&spooled).unwrap(), expected);
+        assert_eq!(decode_batch_file(&batch.data).unwrap(), expected);
+        let uncompressed = lz4_chunked_decompression(&batch.data).unwrap();
+        assert_eq!(decode_batch_file(&uncompressed).unwrap(), expected);
+
+        assert!(matches!(
+            decode_batch_file(&spooled[..10]),
+            Err(DecodeError::Invalid { offset: 0, .. })
+        ));
+    }
+
+    #[test]
+    fn test_decoded_blob_to_json() {
+        let log = LogRecord {
+            event_name: "Checkout".to_string(),
+            severity_number: 9,
+            attributes: vec![
+                attribute("user", Value::StringValue("alice".to_string())),
+                attribute("items", Value::IntValue(3)),
+            ],
+            ..Default::default()
+        };
+        let batches = OtlpEncoder::new()
+            .encode_log_batch([log].iter(), "namespace=test")
+            .unwrap();
+        let blob = batches[0].decode().unwrap();
+        let json = serde_json::to_value(&blob).unwrap();
+
+        assert_eq!(json["metadata"], "namespace=test");
+        let schema = &json["schemas"][0];
+        assert_eq!(
+            batches[0].metadata.schema_ids,
+            schema["id_md5"].as_str().unwrap()
+        );
+        assert_eq!(schema["md5"].as_str().unwrap().len(), 32);
+        assert!(schema["fields"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|f| f["name"] == "items" && f["type"] == "int64"));
+
+        let event = &json["events"][0];
+        assert_eq!(event["event_name"], "Checkout");
+        assert_eq!(event["level"], 9);
+        assert_eq!(event["fields"]["user"], "alice");
+        assert_eq!(event["fields"]["items"], 3);
+        // Columns keep their schema order
+        let event_json = serde_json::to_string(&blob.events[0]).unwrap();
+        let fields_json = &event_json[event_json.find("\"fields\":").unwrap()..];
+        let positions: Vec<usize> = blob.schemas[0]
+            .fields
+            .iter()
+            .map(|f| fields_json.find(&format!("\"{}\":", f.name)).unwrap())
+            .collect();
+        assert!(positions.windows(2).all(|w| w[0] < w[1]));
+    }
+}
@@ -1,4 +1,6 @@
-use lz4_flex::block::{compress_into, get_maximum_output_size};
+use lz4_flex::block::{compress_into, decompress_into, get_maximum_output_size};
+
+const CHUNK_SIZE: usize = 64 * 1024;
 
 /// Compresses input data in 64 KiB chunks using LZ4, writing each chunk's compressed data to a single
 /// pre-allocated buffer. Each chunk in the output is prefixed by a 4-byte (little-endian) length header
@@ -39,7 +41,30 @@ use lz4_flex::block::{compress_into, get_maximum_output_size};
 pub(crate) fn lz4_chunked_compression(
     input: &[u8],
 ) -> Result<Vec<u8>, lz4_flex::block::CompressError> {
-    lz4_chunked_compression_custom::<{ 64 * 1024 }>(input)
+    lz4_chunked_compression_custom::<CHUNK_SIZE>(input)
+}
+
+/// Reverses [`lz4_chunked_compression`]: reads each length-prefixed chunk and decompresses it
+/// into at most 64 KiB of output.
+pub(crate) fn lz4_chunked_decompression(input: &[u8]) -> Result<Vec<u8>, String> {
+    let mut output = Vec::with_capacity(input.len() * 2);
+    let mut chunk = vec![0u8; CHUNK_SIZE];
+    let mut offset = 0;
+    while offset < input.len() {
+        let header = input
+            .get(offset..offset + 4)
+            .ok_or_else(|| format!("truncated chunk header at offset {offset}"))?;
+        let len = u32::from_le_bytes(header.try_into().unwrap()) as usize;
+        offset += 4;
+        let data = input
+            .get(offset..offset + len)
+            .ok_or_else(|| format!("truncated chunk of {len} bytes at offset {offset}"))?;
+        let size = decompress_into(data, &mut chunk)
+            .map_err(|e| format!("invalid LZ4 chunk at offset {offset}: {e}"))?;
+        output.extend_from_slice(&chunk[..size]);
+        offset += len;
+    }
+    Ok(output)
 }
 
 #[allow(dead_code)]
@@ -91,7 +116,9 @@ pub(crate) fn lz4_chunked_compression_custom<const CHUNK_SIZE: usize>(
 
 #[cfg(test)]
 mod tests {
-    use crate::payload_encoder::lz4_chunked_compression::lz4_chunked_compression;
+    use crate::payload_encoder::lz4_chunked_compression::{
+        lz4_chunked_compression, lz4_chunked_decompression,
+    };
     use lz4_flex::block::decompress;
 
     #[test]
@@ -152,6 +179,15 @@ mod tests {
         assert_eq!(decompressed, input);
     }
 
+    #[test]
+    fn test_chunked_decompression_roundtrip() {
+        let input: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8).collect();
+        let compressed = lz4_chunked_compression(&input).unwrap();
+        assert_eq!(lz4_chunked_decompression(&compressed).unwrap(), input);
+        assert!(lz4_chunked_decompression(&[]).unwrap().is_empty());
+        assert!(lz4_chunked_decompression(&compressed[..compressed.len() - 1]).is_err());
+    }
+
     // Helper function to decompress chunked output
     // Each chunk: [4 bytes little-endian compressed_len][compressed data...]
     fn decompress_chunked_lz4(compressed: &[u8], total_uncompressed_len: usize) -> Vec<u8> {
@@ -1,7 +1,12 @@
+pub(crate) mod attribute_encoding;
+pub(crate) mod batch_limits;
 pub(crate) mod bond_encoder;
 pub(crate) mod central_blob;
+pub(crate) mod column_mapping;
+pub(crate) mod decoder;
 pub(crate) mod lz4_chunked_compression;
 pub mod otlp_encoder;
+pub(crate) mod resource_mapping;
 
 #[cfg(test)]
 mod tests {
@@ -113,26 +118,31 @@ mod tests {
                 name: "Float64Col".into(),
                 type_id: BondDataType::BT_DOUBLE,
                 field_id: 1u16,
+                element_type: None,
             },
             FieldDef {
                 name: "Int32Col".into(),
                 type_id: BondDataType::BT_INT32,
                 field_id: 2u16,
+                element_type: None,
             },
             FieldDef {
                 name: "Int64Col".into(),
                 type_id: BondDataType::BT_INT64,
                 field_id: 3u16,
+                element_type: None,
             },
             FieldDef {
                 name: "BoolCol".into(),
                 type_id: BondDataType::BT_BOOL,
                 field_id: 4u16,
+                element_type: None,
             },
             FieldDef {
                 name: "StringCol".into(),
                 type_id: BondDataType::BT_WSTRING,
                 field_id: 5u16,
+                element_type: None,
             },
         ];
 
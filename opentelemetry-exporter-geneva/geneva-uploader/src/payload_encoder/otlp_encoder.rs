@@ -1,12 +1,21 @@
 use crate::client::EncodedBatch;
+use crate::error::GenevaError;
+use crate::payload_encoder::attribute_encoding::{attribute_value, AttributeEncoding};
+use crate::payload_encoder::batch_limits::{BatchLimits, PendingBatch};
 use crate::payload_encoder::bond_encoder::{BondDataType, BondEncodedSchema, BondWriter, FieldDef};
-use crate::payload_encoder::central_blob::{
-    BatchMetadata, CentralBlob, CentralEventEntry, CentralSchemaEntry,
+use crate::payload_encoder::central_blob::CentralSchemaEntry;
+use crate::payload_encoder::column_mapping::ColumnMapping;
+use crate::payload_encoder::resource_mapping::{
+    ContextFields, ResourceMapping, WithContext, FIELD_ENV_CLOUD_ROLE,
+    FIELD_ENV_CLOUD_ROLE_INSTANCE, FIELD_ENV_CLOUD_ROLE_VER, FIELD_SCOPE_NAME, FIELD_SCOPE_VERSION,
 };
-use crate::payload_encoder::lz4_chunked_compression::lz4_chunked_compression;
+use crate::self_metrics::{SelfMetrics, Signal};
 use chrono::{TimeZone, Utc};
 use opentelemetry_proto::tonic::common::v1::any_value::Value;
 use opentelemetry_proto::tonic::logs::v1::LogRecord;
+use opentelemetry_proto::tonic::metrics::v1::{
+    metric::Data as MetricData, number_data_point, AggregationTemporality, Metric,
+};
 use opentelemetry_proto::tonic::trace::v1::Span;
 use std::borrow::Cow;
 use std::sync::Arc;
@@ -32,65 +41,173 @@ const FIELD_PARENT_ID: &str = "parentId";
 const FIELD_LINKS: &str = "links";
 const FIELD_STATUS_MESSAGE: &str = "statusMessage";
 
+/// Built-in columns of log rows. Attributes with these keys are dropped so that no schema
+/// has two fields with the same name.
+const LOG_COLUMNS: &[&str] = &[
+    FIELD_ENV_NAME,
+    FIELD_ENV_VER,
+    FIELD_TIMESTAMP,
+    FIELD_ENV_TIME,
+    FIELD_ENV_CLOUD_ROLE,
+    FIELD_ENV_CLOUD_ROLE_INSTANCE,
+    FIELD_ENV_CLOUD_ROLE_VER,
+    FIELD_TRACE_ID,
+    FIELD_SPAN_ID,
+    FIELD_TRACE_FLAGS,
+    FIELD_NAME,
+    FIELD_SEVERITY_NUMBER,
+    FIELD_SEVERITY_TEXT,
+    FIELD_BODY,
+    FIELD_SCOPE_NAME,
+    FIELD_SCOPE_VERSION,
+];
+
+/// Built-in columns of span rows, see [`LOG_COLUMNS`]
+const SPAN_COLUMNS: &[&str] = &[
+    FIELD_ENV_NAME,
+    FIELD_ENV_VER,
+    FIELD_TIMESTAMP,
+    FIELD_ENV_TIME,
+    FIELD_ENV_CLOUD_ROLE,
+    FIELD_ENV_CLOUD_ROLE_INSTANCE,
+    FIELD_ENV_CLOUD_ROLE_VER,
+    FIELD_KIND,
+    FIELD_START_TIME,
+    FIELD_SUCCESS,
+    FIELD_TRACE_ID,
+    FIELD_SPAN_ID,
+    FIELD_TRACE_FLAGS,
+    FIELD_NAME,
+    FIELD_TRACE_STATE,
+    FIELD_PARENT_ID,
+    FIELD_LINKS,
+    FIELD_STATUS_MESSAGE,
+    FIELD_SCOPE_NAME,
+    FIELD_SCOPE_VERSION,
+];
+
+// Metric-specific field constants
+const FIELD_DESCRIPTION: &str = "description";
+const FIELD_UNIT: &str = "unit";
+const FIELD_METRIC_TYPE: &str = "metricType";
+const FIELD_TEMPORALITY: &str = "temporality";
+const FIELD_IS_MONOTONIC: &str = "isMonotonic";
+const FIELD_VALUE: &str = "value";
+const FIELD_COUNT: &str = "count";
+const FIELD_SUM: &str = "sum";
+const FIELD_MIN: &str = "min";
+const FIELD_MAX: &str = "max";
+const FIELD_EXPLICIT_BOUNDS: &str = "explicitBounds";
+const FIELD_BUCKET_COUNTS: &str = "bucketCounts";
+const FIELD_SCALE: &str = "scale";
+const FIELD_ZERO_COUNT: &str = "zeroCount";
+const FIELD_POSITIVE_OFFSET: &str = "positiveOffset";
+const FIELD_POSITIVE_BUCKET_COUNTS: &str = "positiveBucketCounts";
+const FIELD_NEGATIVE_OFFSET: &str = "negativeOffset";
+const FIELD_NEGATIVE_BUCKET_COUNTS: &str = "negativeBucketCounts";
+
+/// Built-in columns of metric rows, see [`LOG_COLUMNS`]
+const METRIC_COLUMNS: &[&str] = &[
+    FIELD_ENV_NAME,
+    FIELD_ENV_VER,
+    FIELD_TIMESTAMP,
+    FIELD_ENV_TIME,
+    FIELD_NAME,
+    FIELD_DESCRIPTION,
+    FIELD_UNIT,
+    FIELD_METRIC_TYPE,
+    FIELD_TEMPORALITY,
+    FIELD_IS_MONOTONIC,
+    FIELD_START_TIME,
+    FIELD_VALUE,
+    FIELD_COUNT,
+    FIELD_SUM,
+    FIELD_MIN,
+    FIELD_MAX,
+    FIELD_EXPLICIT_BOUNDS,
+    FIELD_BUCKET_COUNTS,
+    FIELD_SCALE,
+    FIELD_ZERO_COUNT,
+    FIELD_POSITIVE_OFFSET,
+    FIELD_POSITIVE_BUCKET_COUNTS,
+    FIELD_NEGATIVE_OFFSET,
+    FIELD_NEGATIVE_BUCKET_COUNTS,
+];
+
 /// Encoder to write OTLP payload in bond form.
 #[derive(Clone)]
-pub(crate) struct OtlpEncoder;
+pub(crate) struct OtlpEncoder {
+    attribute_encoding: AttributeEncoding,
+    resource_mapping: ResourceMapping,
+    columns: ColumnMapping,
+    batch_limits: BatchLimits,
+    metrics: Option<Arc<SelfMetrics>>,
+}
 
 impl OtlpEncoder {
+    /// Create an encoder with the default attribute encoding
+    #[cfg(test)]
     pub(crate) fn new() -> Self {
-        OtlpEncoder {}
+        Self::with_config(
+            AttributeEncoding::default(),
+            ResourceMapping::default(),
+            ColumnMapping::default(),
+            BatchLimits::default(),
+        )
+    }
+
+    /// Create an encoder using the given mappings for complex attribute values, resource and
+    /// scope attributes, and column names, splitting batches according to `batch_limits`
+    pub(crate) fn with_config(
+        attribute_encoding: AttributeEncoding,
+        resource_mapping: ResourceMapping,
+        columns: ColumnMapping,
+        batch_limits: BatchLimits,
+    ) -> Self {
+        OtlpEncoder {
+            attribute_encoding,
+            resource_mapping,
+            columns,
+            batch_limits,
+            metrics: None,
+        }
+    }
+
+    /// Record encoded records, batches and sizes in `metrics`
+    pub(crate) fn with_metrics(mut self, metrics: Option<Arc<SelfMetrics>>) -> Self {
+        self.metrics = metrics;
+        self
     }
 
     /// Encode a batch of logs into a vector of (event_name, compressed_bytes, schema_ids, start_time_nanos, end_time_nanos)
+    /// Records are grouped by event name; a group is split into several batches when it
+    /// exceeds the encoder's `BatchLimits`.
     /// The returned `data` field contains LZ4 chunked compressed bytes.
     /// On compression failure, the error is returned (no logging, no fallback).
+    /// Items are either plain records or `(record, context)` pairs carrying the resource and
+    /// scope to promote according to the encoder's `ResourceMapping`.
     pub(crate) fn encode_log_batch<'a, I>(
         &self,
         logs: I,
         metadata: &str,
-    ) -> Result<Vec<EncodedBatch>, String>
+    ) -> Result<Vec<EncodedBatch>, GenevaError>
     where
-        I: IntoIterator<Item = &'a opentelemetry_proto::tonic::logs::v1::LogRecord>,
+        I: IntoIterator,
+        I::Item: Into<WithContext<'a, LogRecord>>,
     {
         use std::collections::HashMap;
 
-        // Internal struct to accumulate batch data before encoding
-        struct BatchData {
-            schemas: Vec<CentralSchemaEntry>,
-            events: Vec<CentralEventEntry>,
-            metadata: BatchMetadata,
-        }
-
-        impl BatchData {
-            fn format_schema_ids(&self) -> String {
-                use std::fmt::Write;
-
-                if self.schemas.is_empty() {
-                    return String::new();
-                }
-
-                // Pre-allocate capacity: Each MD5 hash is 32 hex chars + 1 semicolon (except last)
-                // Total: (32 chars per hash * num_schemas) + (semicolons = num_schemas - 1)
-                let estimated_capacity =
-                    self.schemas.len() * 32 + self.schemas.len().saturating_sub(1);
+        let mut batches: HashMap<String, PendingBatch> = HashMap::new();
 
-                self.schemas.iter().enumerate().fold(
-                    String::with_capacity(estimated_capacity),
-                    |mut acc, (i, s)| {
-                        if i > 0 {
-                            acc.push(';');
-                        }
-                        let md5_hash = md5::compute(s.id.to_le_bytes());
-                        write!(&mut acc, "{md5_hash:x}").unwrap();
-                        acc
-                    },
-                )
-            }
-        }
+        for item in logs {
+            let WithContext {
+                record: log_record,
+                context,
+            } = item.into();
+            let context =
+                self.resource_mapping
+                    .context_fields(context, &log_record.attributes, LOG_COLUMNS);
 
-        let mut batches: HashMap<String, BatchData> = HashMap::new();
-
-        for log_record in logs {
             // Get the timestamp - prefer time_unix_nano, fall back to observed_time_unix_nano if time_unix_nano is 0
             let timestamp = if log_record.time_unix_nano != 0 {
                 log_record.time_unix_nano
@@ -107,193 +224,232 @@ impl OtlpEncoder {
 
             // 1. Get schema with optimized single-pass field collection and schema ID calculation
             let (field_info, schema_id) =
-                Self::determine_fields_and_schema_id(log_record, event_name_str);
+                self.determine_fields_and_schema_id(log_record, &context, event_name_str);
 
             // 2. Encode row
-            let row_buffer = self.write_row_data(log_record, &field_info);
+            let row_buffer = self.write_row_data(log_record, &context, &field_info);
             let level = log_record.severity_number as u8;
 
-            // 3. Create or get existing batch entry with metadata tracking
+            // 3. Create or get existing batch entry
             let entry = batches
                 .entry(event_name_str.to_string())
-                .or_insert_with(|| BatchData {
-                    schemas: Vec::new(),
-                    events: Vec::new(),
-                    metadata: BatchMetadata {
-                        start_time: timestamp,
-                        end_time: timestamp,
-                        schema_ids: String::new(),
-                    },
-                });
-
-            // Update timestamp range
-            if timestamp != 0 {
-                entry.metadata.start_time = entry.metadata.start_time.min(timestamp);
-                entry.metadata.end_time = entry.metadata.end_time.max(timestamp);
-            }
+                .or_insert_with(|| PendingBatch::new(event_name_str, Signal::Logs));
 
             // 4. Add schema entry if not already present (multiple schemas per event_name batch)
-            if !entry.schemas.iter().any(|s| s.id == schema_id) {
-                let schema_entry = Self::create_schema(schema_id, field_info);
-                entry.schemas.push(schema_entry);
+            if !entry.has_schema(schema_id) {
+                entry.add_schema(self.create_schema(schema_id, field_info));
             }
 
-            // 5. Create CentralEventEntry directly (optimization: no intermediate EncodedRow)
-            let central_event = CentralEventEntry {
-                schema_id,
-                level,
-                event_name: Arc::new(event_name_str.to_string()),
-                row: row_buffer,
-            };
-            entry.events.push(central_event);
+            // 5. Add the row, tracking its timestamp for the batch time range
+            entry.push(schema_id, level, row_buffer, timestamp, timestamp);
         }
 
-        // 6. Encode blobs (one per event_name, potentially multiple schemas per blob)
+        // 6. Encode blobs (at least one per event_name, potentially multiple schemas per blob)
         let mut blobs = Vec::with_capacity(batches.len());
-        for (batch_event_name, mut batch_data) in batches {
-            let schema_ids_string = batch_data.format_schema_ids();
-            batch_data.metadata.schema_ids = schema_ids_string;
-
-            let blob = CentralBlob {
-                version: 1,
-                format: 2,
-                metadata: metadata.to_string(),
-                schemas: batch_data.schemas,
-                events: batch_data.events,
-            };
-            let uncompressed = blob.to_bytes();
-            let compressed = lz4_chunked_compression(&uncompressed)
-                .map_err(|e| format!("compression failed: {e}"))?;
-            blobs.push(EncodedBatch {
-                event_name: batch_event_name,
-                data: compressed,
-                metadata: batch_data.metadata,
-            });
+        for batch in batches.into_values() {
+            blobs.extend(batch.encode(metadata, &self.batch_limits, self.metrics.as_deref())?);
         }
         Ok(blobs)
     }
 
-    /// Encode a batch of spans into a single payload
-    /// All spans are grouped into a single batch with event_name "Span" for routing
+    /// Encode a batch of spans
+    /// All spans are grouped with event_name "Span" for routing, split into several batches
+    /// only when the encoder's `BatchLimits` require it
     /// The returned `data` field contains LZ4 chunked compressed bytes.
     /// On compression failure, the error is returned (no logging, no fallback).
+    /// Items are either plain spans or `(span, context)` pairs, as for logs.
     pub(crate) fn encode_span_batch<'a, I>(
         &self,
         spans: I,
         metadata: &str,
-    ) -> Result<Vec<EncodedBatch>, String>
+    ) -> Result<Vec<EncodedBatch>, GenevaError>
     where
-        I: IntoIterator<Item = &'a Span>,
+        I: IntoIterator,
+        I::Item: Into<WithContext<'a, Span>>,
     {
         // All spans use "Span" as event name for routing - no grouping by span name
         const EVENT_NAME: &str = "Span";
 
-        let mut schemas = Vec::new();
-        let mut events = Vec::new();
-        let mut start_time = u64::MAX;
-        let mut end_time = 0u64;
+        let mut batch = PendingBatch::new(EVENT_NAME, Signal::Spans);
+
+        for item in spans {
+            let WithContext {
+                record: span,
+                context,
+            } = item.into();
+            let context =
+                self.resource_mapping
+                    .context_fields(context, &span.attributes, SPAN_COLUMNS);
 
-        for span in spans {
             // 1. Get schema with optimized single-pass field collection and schema ID calculation
             let (field_info, schema_id) =
-                Self::determine_span_fields_and_schema_id(span, EVENT_NAME);
+                self.determine_span_fields_and_schema_id(span, &context, EVENT_NAME);
 
             // 2. Encode row
-            let row_buffer = self.write_span_row_data(span, &field_info);
+            let row_buffer = self.write_span_row_data(span, &context, &field_info);
             let level = 5; // Default level for spans (INFO equivalent)
 
-            // 3. Update timestamp range
-            if span.start_time_unix_nano != 0 {
-                start_time = start_time.min(span.start_time_unix_nano);
-            }
-            if span.end_time_unix_nano != 0 {
-                end_time = end_time.max(span.end_time_unix_nano);
-            }
-
-            // 4. Add schema entry if not already present
+            // 3. Add schema entry if not already present
             // TODO - This can have collision if different spans have same schema ID but different fields
-            if !schemas
-                .iter()
-                .any(|s: &CentralSchemaEntry| s.id == schema_id)
-            {
-                let schema_entry = Self::create_span_schema(schema_id, field_info);
-                schemas.push(schema_entry);
+            if !batch.has_schema(schema_id) {
+                batch.add_schema(self.create_span_schema(schema_id, field_info));
             }
 
-            // 5. Create CentralEventEntry
-            let central_event = CentralEventEntry {
+            // 4. Add the row, tracking the span start and end for the batch time range
+            batch.push(
                 schema_id,
                 level,
-                event_name: Arc::new(EVENT_NAME.to_string()),
-                row: row_buffer,
-            };
-            events.push(central_event);
+                row_buffer,
+                span.start_time_unix_nano,
+                span.end_time_unix_nano,
+            );
         }
 
         // Handle case with no spans
-        if events.is_empty() {
+        if batch.is_empty() {
             return Ok(Vec::new());
         }
 
-        // Format schema IDs
-        // TODO - this can be shared code with log batch
-        let schema_ids_string = {
-            use std::fmt::Write;
-            if schemas.is_empty() {
-                String::new()
-            } else {
-                let estimated_capacity = schemas.len() * 32 + schemas.len().saturating_sub(1);
-                schemas.iter().enumerate().fold(
-                    String::with_capacity(estimated_capacity),
-                    |mut acc, (i, s)| {
-                        if i > 0 {
-                            acc.push(';');
-                        }
-                        let md5_hash = md5::compute(s.id.to_le_bytes());
-                        write!(&mut acc, "{md5_hash:x}").unwrap();
-                        acc
-                    },
-                )
+        batch.encode(metadata, &self.batch_limits, self.metrics.as_deref())
+    }
+
+    /// Encode a batch of metrics
+    /// Every data point becomes one row; all rows use event_name "Metric" for routing and are
+    /// split into several batches only when the encoder's `BatchLimits` require it.
+    /// Sums, gauges, histograms and exponential histograms are supported, with the aggregation
+    /// temporality of each sum/histogram recorded as reported by the SDK. Summaries are skipped.
+    /// The returned `data` field contains LZ4 chunked compressed bytes.
+    /// On compression failure, the error is returned (no logging, no fallback).
+    pub(crate) fn encode_metric_batch<'a, I>(
+        &self,
+        metrics: I,
+        metadata: &str,
+    ) -> Result<Vec<EncodedBatch>, GenevaError>
+    where
+        I: IntoIterator<Item = &'a Metric>,
+    {
+        const EVENT_NAME: &str = "Metric";
+
+        let mut batch = PendingBatch::new(EVENT_NAME, Signal::Metrics);
+
+        for metric in metrics {
+            for row in self.metric_rows(metric) {
+                let time_unix_nano = row.time_unix_nano;
+                let (field_info, schema_id, row_buffer) = row.finish(EVENT_NAME, &self.columns);
+                if !batch.has_schema(schema_id) {
+                    batch.add_schema(self.create_metric_schema(schema_id, field_info));
+                }
+                // Default level for metrics (INFO equivalent)
+                batch.push(schema_id, 5, row_buffer, time_unix_nano, time_unix_nano);
             }
-        };
+        }
 
-        // Create single batch with all spans
-        let batch_metadata = BatchMetadata {
-            start_time: if start_time == u64::MAX {
-                0
-            } else {
-                start_time
-            },
-            end_time,
-            schema_ids: schema_ids_string,
-        };
+        if batch.is_empty() {
+            return Ok(Vec::new());
+        }
 
-        let blob = CentralBlob {
-            version: 1,
-            format: 2,
-            metadata: metadata.to_string(),
-            schemas,
-            events,
-        };
+        batch.encode(metadata, &self.batch_limits, self.metrics.as_deref())
+    }
 
-        let uncompressed = blob.to_bytes();
-        let compressed = lz4_chunked_compression(&uncompressed)
-            .map_err(|e| format!("compression failed: {e}"))?;
+    /// Build one row per data point of the metric
+    fn metric_rows(&self, metric: &Metric) -> Vec<MetricRow> {
+        let mut rows = Vec::new();
+        match &metric.data {
+            Some(MetricData::Sum(sum)) => {
+                for dp in &sum.data_points {
+                    let mut row = MetricRow::new(metric, "Sum", dp.time_unix_nano);
+                    row.temporality(sum.aggregation_temporality);
+                    row.bool(FIELD_IS_MONOTONIC, sum.is_monotonic);
+                    row.start_time(dp.start_time_unix_nano);
+                    row.number_value(dp.value.as_ref());
+                    row.attributes(&dp.attributes, self);
+                    rows.push(row);
+                }
+            }
+            Some(MetricData::Gauge(gauge)) => {
+                for dp in &gauge.data_points {
+                    let mut row = MetricRow::new(metric, "Gauge", dp.time_unix_nano);
+                    row.start_time(dp.start_time_unix_nano);
+                    row.number_value(dp.value.as_ref());
+                    row.attributes(&dp.attributes, self);
+                    rows.push(row);
+                }
+            }
+            Some(MetricData::Histogram(histogram)) => {
+                for dp in &histogram.data_points {
+                    let mut row = MetricRow::new(metric, "Histogram", dp.time_unix_nano);
+                    row.temporality(histogram.aggregation_temporality);
+                    row.start_time(dp.start_time_unix_nano);
+                    row.uint64(FIELD_COUNT, dp.count);
+                    row.optional_double(FIELD_SUM, dp.sum);
+                    row.optional_double(FIELD_MIN, dp.min);
+                    row.optional_double(FIELD_MAX, dp.max);
+                    row.json(FIELD_EXPLICIT_BOUNDS, &dp.explicit_bounds);
+                    row.json(FIELD_BUCKET_COUNTS, &dp.bucket_counts);
+                    row.attributes(&dp.attributes, self);
+                    rows.push(row);
+                }
+            }
+            Some(MetricData::ExponentialHistogram(histogram)) => {
+                for dp in &histogram.data_points {
+                    let mut row = MetricRow::new(metric, "ExponentialHistogram", dp.time_unix_nano);
+                    row.temporality(histogram.aggregation_temporality);
+                    row.start_time(dp.start_time_unix_nano);
+                    row.uint64(FIELD_COUNT, dp.count);
+                    row.optional_double(FIELD_SUM, dp.sum);
+                    row.optional_double(FIELD_MIN, dp.min);
+                    row.optional_double(FIELD_MAX, dp.max);
+                    row.int32(FIELD_SCALE, dp.scale);
+                    row.uint64(FIELD_ZERO_COUNT, dp.zero_count);
+                    if let Some(positive) = &dp.positive {
+                        row.int32(FIELD_POSITIVE_OFFSET, positive.offset);
+                        row.json(FIELD_POSITIVE_BUCKET_COUNTS, &positive.bucket_counts);
+                    }
+                    if let Some(negative) = &dp.negative {
+                        row.int32(FIELD_NEGATIVE_OFFSET, negative.offset);
+                        row.json(FIELD_NEGATIVE_BUCKET_COUNTS, &negative.bucket_counts);
+                    }
+                    row.attributes(&dp.attributes, self);
+                    rows.push(row);
+                }
+            }
+            // Summaries are not produced by the OpenTelemetry Rust SDK
+            Some(MetricData::Summary(_)) | None => {}
+        }
+        rows
+    }
+
+    /// Create metric schema - always creates a new CentralSchemaEntry
+    fn create_metric_schema(
+        &self,
+        schema_id: u64,
+        field_info: Vec<FieldDef>,
+    ) -> CentralSchemaEntry {
+        let field_info = self.rename_fields(field_info);
+        let schema = BondEncodedSchema::from_fields("OtlpMetricRecord", "telemetry", field_info);
+
+        let schema_bytes = schema.as_bytes();
+        let schema_md5 = md5::compute(schema_bytes).0;
 
-        Ok(vec![EncodedBatch {
-            event_name: EVENT_NAME.to_string(),
-            data: compressed,
-            metadata: batch_metadata,
-        }])
+        CentralSchemaEntry {
+            id: schema_id,
+            md5: schema_md5,
+            schema,
+        }
     }
 
     /// Determine fields and calculate schema ID in a single pass for optimal performance
-    fn determine_fields_and_schema_id(log: &LogRecord, event_name: &str) -> (Vec<FieldDef>, u64) {
+    fn determine_fields_and_schema_id(
+        &self,
+        log: &LogRecord,
+        context: &ContextFields,
+        event_name: &str,
+    ) -> (Vec<FieldDef>, u64) {
         use std::collections::hash_map::DefaultHasher;
         use std::hash::{Hash, Hasher};
 
         // Pre-allocate with estimated capacity to avoid reallocations
-        let estimated_capacity = 7 + 4 + log.attributes.len();
+        let estimated_capacity = 7 + 4 + log.attributes.len() + context.len();
         let mut fields = Vec::with_capacity(estimated_capacity);
 
         // Initialize hasher for schema ID calculation
@@ -301,49 +457,60 @@ impl OtlpEncoder {
         event_name.hash(&mut hasher);
 
         // Part A - Always present fields
-        fields.push((Cow::Borrowed(FIELD_ENV_NAME), BondDataType::BT_STRING));
-        fields.push((FIELD_ENV_VER.into(), BondDataType::BT_STRING));
-        fields.push((FIELD_TIMESTAMP.into(), BondDataType::BT_STRING));
-        fields.push((FIELD_ENV_TIME.into(), BondDataType::BT_STRING));
+        fields.push((Cow::Borrowed(FIELD_ENV_NAME), BondDataType::BT_STRING, None));
+        fields.push((FIELD_ENV_VER.into(), BondDataType::BT_STRING, None));
+        fields.push((FIELD_TIMESTAMP.into(), BondDataType::BT_STRING, None));
+        fields.push((FIELD_ENV_TIME.into(), BondDataType::BT_STRING, None));
+        for (name, _) in &context.part_a {
+            fields.push(((*name).into(), BondDataType::BT_STRING, None));
+        }
 
         // Part A extension - Conditional fields
         if !log.trace_id.is_empty() {
-            fields.push((FIELD_TRACE_ID.into(), BondDataType::BT_STRING));
+            fields.push((FIELD_TRACE_ID.into(), BondDataType::BT_STRING, None));
         }
         if !log.span_id.is_empty() {
-            fields.push((FIELD_SPAN_ID.into(), BondDataType::BT_STRING));
+            fields.push((FIELD_SPAN_ID.into(), BondDataType::BT_STRING, None));
         }
         if log.flags != 0 {
-            fields.push((FIELD_TRACE_FLAGS.into(), BondDataType::BT_UINT32));
+            fields.push((FIELD_TRACE_FLAGS.into(), BondDataType::BT_UINT32, None));
         }
 
         // Part B - Core log fields
         if !log.event_name.is_empty() {
-            fields.push((FIELD_NAME.into(), BondDataType::BT_STRING));
+            fields.push((FIELD_NAME.into(), BondDataType::BT_STRING, None));
         }
-        fields.push((FIELD_SEVERITY_NUMBER.into(), BondDataType::BT_INT32));
+        fields.push((FIELD_SEVERITY_NUMBER.into(), BondDataType::BT_INT32, None));
         if !log.severity_text.is_empty() {
-            fields.push((FIELD_SEVERITY_TEXT.into(), BondDataType::BT_STRING));
+            fields.push((FIELD_SEVERITY_TEXT.into(), BondDataType::BT_STRING, None));
         }
-        if let Some(body) = &log.body {
-            if let Some(Value::StringValue(_)) = &body.value {
-                // Only included in schema when body is a string value
-                fields.push((FIELD_BODY.into(), BondDataType::BT_STRING));
-            }
-            //TODO - handle other body types
+        if let Some(body) = log.body.as_ref().and_then(|b| b.value.as_ref()) {
+            // Non-string bodies use the same mapping as attribute values
+            let (type_id, element_type) = self.attribute_encoding.bond_type(body);
+            fields.push((FIELD_BODY.into(), type_id, element_type));
+        }
+        for (name, _) in &context.scope {
+            fields.push(((*name).into(), BondDataType::BT_STRING, None));
         }
 
-        // Part C - Dynamic attributes
-        for attr in &log.attributes {
-            if let Some(val) = attr.value.as_ref().and_then(|v| v.value.as_ref()) {
-                let type_id = match val {
-                    Value::StringValue(_) => BondDataType::BT_STRING,
-                    Value::IntValue(_) => BondDataType::BT_INT64,
-                    Value::DoubleValue(_) => BondDataType::BT_DOUBLE,
-                    Value::BoolValue(_) => BondDataType::BT_BOOL,
-                    _ => continue,
-                };
-                fields.push((attr.key.clone().into(), type_id));
+        // Part C - Dynamic attributes, except those that would duplicate a built-in column
+        for attr in log
+            .attributes
+            .iter()
+            .filter(|attr| !LOG_COLUMNS.contains(&attr.key.as_str()))
+        {
+            if let Some(val) =
+                attribute_value(attr).and_then(|v| self.mapped_attribute(&attr.key, v))
+            {
+                let (type_id, element_type) = self.attribute_encoding.bond_type(&val);
+                fields.push((attr.key.clone().into(), type_id, element_type));
+            }
+        }
+        // Promoted resource and scope attributes
+        for (key, val) in &context.extra {
+            if let Some(val) = self.mapped_attribute(key, val) {
+                let (type_id, element_type) = self.attribute_encoding.bond_type(&val);
+                fields.push((key.to_string().into(), type_id, element_type));
             }
         }
 
@@ -352,15 +519,19 @@ impl OtlpEncoder {
         let field_defs: Vec<FieldDef> = fields
             .into_iter()
             .enumerate()
-            .map(|(i, (name, type_id))| {
-                // Hash field name and type for schema ID
-                name.hash(&mut hasher);
+            .map(|(i, (name, type_id, element_type))| {
+                // Hash column name and type for schema ID
+                self.columns.column_name(&name).hash(&mut hasher);
                 type_id.hash(&mut hasher);
+                if let Some(element_type) = element_type {
+                    element_type.hash(&mut hasher);
+                }
 
                 FieldDef {
                     name,
                     type_id,
                     field_id: (i + 1) as u16,
+                    element_type,
                 }
             })
             .collect();
@@ -370,12 +541,17 @@ impl OtlpEncoder {
     }
 
     /// Determine span fields and calculate schema ID in a single pass for optimal performance
-    fn determine_span_fields_and_schema_id(span: &Span, event_name: &str) -> (Vec<FieldDef>, u64) {
+    fn determine_span_fields_and_schema_id(
+        &self,
+        span: &Span,
+        context: &ContextFields,
+        event_name: &str,
+    ) -> (Vec<FieldDef>, u64) {
         use std::collections::hash_map::DefaultHasher;
         use std::hash::{Hash, Hasher};
 
         // Pre-allocate with estimated capacity to avoid reallocations
-        let estimated_capacity = 15 + span.attributes.len(); // 7 always + 8 max conditional + attributes
+        let estimated_capacity = 15 + span.attributes.len() + context.len(); // 7 always + 8 max conditional + attributes + resource/scope
         let mut fields = Vec::with_capacity(estimated_capacity);
 
         // Initialize hasher for schema ID calculation
@@ -383,57 +559,70 @@ impl OtlpEncoder {
         event_name.hash(&mut hasher);
 
         // Part A - Always present fields for spans
-        fields.push((Cow::Borrowed(FIELD_ENV_NAME), BondDataType::BT_STRING));
-        fields.push((FIELD_ENV_VER.into(), BondDataType::BT_STRING));
-        fields.push((FIELD_TIMESTAMP.into(), BondDataType::BT_STRING));
-        fields.push((FIELD_ENV_TIME.into(), BondDataType::BT_STRING));
+        fields.push((Cow::Borrowed(FIELD_ENV_NAME), BondDataType::BT_STRING, None));
+        fields.push((FIELD_ENV_VER.into(), BondDataType::BT_STRING, None));
+        fields.push((FIELD_TIMESTAMP.into(), BondDataType::BT_STRING, None));
+        fields.push((FIELD_ENV_TIME.into(), BondDataType::BT_STRING, None));
+        for (name, _) in &context.part_a {
+            fields.push(((*name).into(), BondDataType::BT_STRING, None));
+        }
 
         // Span-specific required fields
-        fields.push((FIELD_KIND.into(), BondDataType::BT_INT32));
-        fields.push((FIELD_START_TIME.into(), BondDataType::BT_STRING));
-        fields.push((FIELD_SUCCESS.into(), BondDataType::BT_BOOL));
+        fields.push((FIELD_KIND.into(), BondDataType::BT_INT32, None));
+        fields.push((FIELD_START_TIME.into(), BondDataType::BT_STRING, None));
+        fields.push((FIELD_SUCCESS.into(), BondDataType::BT_BOOL, None));
 
         // Part A extension - Conditional fields
         if !span.trace_id.is_empty() {
-            fields.push((FIELD_TRACE_ID.into(), BondDataType::BT_STRING));
+            fields.push((FIELD_TRACE_ID.into(), BondDataType::BT_STRING, None));
         }
         if !span.span_id.is_empty() {
-            fields.push((FIELD_SPAN_ID.into(), BondDataType::BT_STRING));
+            fields.push((FIELD_SPAN_ID.into(), BondDataType::BT_STRING, None));
         }
         if span.flags != 0 {
-            fields.push((FIELD_TRACE_FLAGS.into(), BondDataType::BT_UINT32));
+            fields.push((FIELD_TRACE_FLAGS.into(), BondDataType::BT_UINT32, None));
         }
 
         // Part B - Span-specific optional fields
         if !span.name.is_empty() {
-            fields.push((FIELD_NAME.into(), BondDataType::BT_STRING));
+            fields.push((FIELD_NAME.into(), BondDataType::BT_STRING, None));
         }
         if !span.trace_state.is_empty() {
-            fields.push((FIELD_TRACE_STATE.into(), BondDataType::BT_STRING));
+            fields.push((FIELD_TRACE_STATE.into(), BondDataType::BT_STRING, None));
         }
         if !span.parent_span_id.is_empty() {
-            fields.push((FIELD_PARENT_ID.into(), BondDataType::BT_STRING));
+            fields.push((FIELD_PARENT_ID.into(), BondDataType::BT_STRING, None));
         }
         if !span.links.is_empty() {
-            fields.push((FIELD_LINKS.into(), BondDataType::BT_STRING));
+            fields.push((FIELD_LINKS.into(), BondDataType::BT_STRING, None));
         }
         if let Some(status) = &span.status {
             if !status.message.is_empty() {
-                fields.push((FIELD_STATUS_MESSAGE.into(), BondDataType::BT_STRING));
+                fields.push((FIELD_STATUS_MESSAGE.into(), BondDataType::BT_STRING, None));
             }
         }
+        for (name, _) in &context.scope {
+            fields.push(((*name).into(), BondDataType::BT_STRING, None));
+        }
 
-        // Part C - Dynamic attributes
-        for attr in &span.attributes {
-            if let Some(val) = attr.value.as_ref().and_then(|v| v.value.as_ref()) {
-                let type_id = match val {
-                    Value::StringValue(_) => BondDataType::BT_STRING,
-                    Value::IntValue(_) => BondDataType::BT_INT64,
-                    Value::DoubleValue(_) => BondDataType::BT_DOUBLE,
-                    Value::BoolValue(_) => BondDataType::BT_BOOL,
-                    _ => continue,
-                };
-                fields.push((attr.key.clone().into(), type_id));
+        // Part C - Dynamic attributes, except those that would duplicate a built-in column
+        for attr in span
+            .attributes
+            .iter()
+            .filter(|attr| !SPAN_COLUMNS.contains(&attr.key.as_str()))
+        {
+            if let Some(val) =
+                attribute_value(attr).and_then(|v| self.mapped_attribute(&attr.key, v))
+            {
+                let (type_id, element_type) = self.attribute_encoding.bond_type(&val);
+                fields.push((attr.key.clone().into(), type_id, element_type));
+            }
+        }
+        // Promoted resource and scope attributes
+        for (key, val) in &context.extra {
+            if let Some(val) = self.mapped_attribute(key, val) {
+                let (type_id, element_type) = self.attribute_encoding.bond_type(&val);
+                fields.push((key.to_string().into(), type_id, element_type));
             }
         }
 
@@ -441,15 +630,19 @@ impl OtlpEncoder {
         let field_defs: Vec<FieldDef> = fields
             .into_iter()
             .enumerate()
-            .map(|(i, (name, type_id))| {
-                // Hash field name and type for schema ID
-                name.hash(&mut hasher);
+            .map(|(i, (name, type_id, element_type))| {
+                // Hash column name and type for schema ID
+                self.columns.column_name(&name).hash(&mut hasher);
                 type_id.hash(&mut hasher);
+                if let Some(element_type) = element_type {
+                    element_type.hash(&mut hasher);
+                }
 
                 FieldDef {
                     name,
                     type_id,
                     field_id: (i + 1) as u16,
+                    element_type,
                 }
             })
             .collect();
@@ -458,8 +651,29 @@ impl OtlpEncoder {
         (field_defs, schema_id)
     }
 
+    /// Apply column renames to schema fields; rows are written by original field name
+    fn rename_fields(&self, field_info: Vec<FieldDef>) -> Vec<FieldDef> {
+        if self.columns.renames.is_empty() {
+            return field_info;
+        }
+        field_info
+            .into_iter()
+            .map(|field| FieldDef {
+                name: self.columns.rename(field.name),
+                ..field
+            })
+            .collect()
+    }
+
+    /// Attribute value after the column mapping rules, or `None` if the attribute is dropped
+    fn mapped_attribute<'v>(&self, key: &str, value: &'v Value) -> Option<Cow<'v, Value>> {
+        self.columns
+            .map_attribute(key, value, &self.attribute_encoding)
+    }
+
     /// Create schema - always creates a new CentralSchemaEntry
-    fn create_schema(schema_id: u64, field_info: Vec<FieldDef>) -> CentralSchemaEntry {
+    fn create_schema(&self, schema_id: u64, field_info: Vec<FieldDef>) -> CentralSchemaEntry {
+        let field_info = self.rename_fields(field_info);
         let schema = BondEncodedSchema::from_fields("OtlpLogRecord", "telemetry", field_info); //TODO - use actual struct name and namespace
 
         let schema_bytes = schema.as_bytes();
@@ -473,7 +687,8 @@ impl OtlpEncoder {
     }
 
     /// Create span schema - always creates a new CentralSchemaEntry
-    fn create_span_schema(schema_id: u64, field_info: Vec<FieldDef>) -> CentralSchemaEntry {
+    fn create_span_schema(&self, schema_id: u64, field_info: Vec<FieldDef>) -> CentralSchemaEntry {
+        let field_info = self.rename_fields(field_info);
         let schema = BondEncodedSchema::from_fields("OtlpSpanRecord", "telemetry", field_info);
 
         let schema_bytes = schema.as_bytes();
@@ -488,7 +703,12 @@ impl OtlpEncoder {
 
     /// Write span row data directly from Span
     // TODO - code duplication between write_span_row_data() and write_row_data() - consider extracting common field handling
-    fn write_span_row_data(&self, span: &Span, fields: &[FieldDef]) -> Vec<u8> {
+    fn write_span_row_data(
+        &self,
+        span: &Span,
+        context: &ContextFields,
+        fields: &[FieldDef],
+    ) -> Vec<u8> {
         let mut buffer = Vec::with_capacity(fields.len() * 50);
 
         // Pre-calculate timestamp (use start time as primary timestamp for both fields)
@@ -504,6 +724,14 @@ impl OtlpEncoder {
                 FIELD_KIND => {
                     BondWriter::write_numeric(&mut buffer, span.kind);
                 }
+                FIELD_ENV_CLOUD_ROLE
+                | FIELD_ENV_CLOUD_ROLE_INSTANCE
+                | FIELD_ENV_CLOUD_ROLE_VER
+                | FIELD_SCOPE_NAME
+                | FIELD_SCOPE_VERSION => {
+                    let value = context.column(field.name.as_ref()).unwrap_or_default();
+                    BondWriter::write_string(&mut buffer, value);
+                }
                 FIELD_START_TIME => {
                     BondWriter::write_string(&mut buffer, &formatted_timestamp);
                 }
@@ -557,13 +785,16 @@ impl OtlpEncoder {
                     }
                 }
                 _ => {
-                    // Handle dynamic attributes
-                    let attr = span
-                        .attributes
-                        .iter()
-                        .find(|a| a.key == field.name)
-                        .unwrap();
-                    self.write_attribute_value(&mut buffer, attr, field.type_id);
+                    // Handle dynamic attributes, then promoted resource and scope attributes
+                    if let Some(attr) = span.attributes.iter().find(|a| a.key == field.name) {
+                        self.write_attribute_value(&mut buffer, attr, field.type_id);
+                    } else if let Some(val) = context
+                        .attribute(&field.name)
+                        .and_then(|v| self.mapped_attribute(&field.name, v))
+                    {
+                        self.attribute_encoding
+                            .write_value(&mut buffer, &val, field.type_id);
+                    }
                 }
             }
         }
@@ -572,7 +803,12 @@ impl OtlpEncoder {
     }
 
     /// Write row data directly from LogRecord
-    fn write_row_data(&self, log: &LogRecord, sorted_fields: &[FieldDef]) -> Vec<u8> {
+    fn write_row_data(
+        &self,
+        log: &LogRecord,
+        context: &ContextFields,
+        sorted_fields: &[FieldDef],
+    ) -> Vec<u8> {
         let mut buffer = Vec::with_capacity(sorted_fields.len() * 50); //TODO - estimate better
 
         // Pre-calculate timestamp to avoid duplicate computation for FIELD_TIMESTAMP and FIELD_ENV_TIME
@@ -608,6 +844,14 @@ impl OtlpEncoder {
                 FIELD_NAME => {
                     BondWriter::write_string(&mut buffer, &log.event_name);
                 }
+                FIELD_ENV_CLOUD_ROLE
+                | FIELD_ENV_CLOUD_ROLE_INSTANCE
+                | FIELD_ENV_CLOUD_ROLE_VER
+                | FIELD_SCOPE_NAME
+                | FIELD_SCOPE_VERSION => {
+                    let value = context.column(field.name.as_ref()).unwrap_or_default();
+                    BondWriter::write_string(&mut buffer, value);
+                }
                 FIELD_SEVERITY_NUMBER => {
                     BondWriter::write_numeric(&mut buffer, log.severity_number)
                 }
@@ -615,11 +859,9 @@ impl OtlpEncoder {
                     BondWriter::write_string(&mut buffer, &log.severity_text);
                 }
                 FIELD_BODY => {
-                    // TODO - handle all types of body values - For now, we only handle string values
-                    if let Some(body) = &log.body {
-                        if let Some(Value::StringValue(s)) = &body.value {
-                            BondWriter::write_string(&mut buffer, s);
-                        }
+                    if let Some(body) = log.body.as_ref().and_then(|b| b.value.as_ref()) {
+                        self.attribute_encoding
+                            .write_value(&mut buffer, body, field.type_id);
                     }
                 }
                 _ => {
@@ -627,6 +869,12 @@ impl OtlpEncoder {
                     // TODO - optimize better - we could update determine_fields to also return a vec of bytes which has bond serialized attributes
                     if let Some(attr) = log.attributes.iter().find(|a| a.key == field.name) {
                         self.write_attribute_value(&mut buffer, attr, field.type_id);
+                    } else if let Some(val) = context
+                        .attribute(&field.name)
+                        .and_then(|v| self.mapped_attribute(&field.name, v))
+                    {
+                        self.attribute_encoding
+                            .write_value(&mut buffer, &val, field.type_id);
                     }
                 }
             }
@@ -697,25 +945,164 @@ impl OtlpEncoder {
         attr: &opentelemetry_proto::tonic::common::v1::KeyValue,
         expected_type: BondDataType,
     ) {
-        if let Some(val) = &attr.value {
-            match (&val.value, expected_type) {
-                (Some(Value::StringValue(s)), BondDataType::BT_STRING) => {
-                    BondWriter::write_string(buffer, s)
-                }
-                (Some(Value::IntValue(i)), BondDataType::BT_INT64) => {
-                    BondWriter::write_numeric(buffer, *i)
-                }
-                (Some(Value::DoubleValue(d)), BondDataType::BT_DOUBLE) => {
-                    BondWriter::write_numeric(buffer, *d)
-                }
-                (Some(Value::BoolValue(b)), BondDataType::BT_BOOL) => {
-                    // TODO - represent bool as BT_BOOL
-                    BondWriter::write_bool(buffer, *b)
-                }
-                _ => {} // TODO - handle more types
+        if let Some(val) = attribute_value(attr).and_then(|v| self.mapped_attribute(&attr.key, v)) {
+            self.attribute_encoding
+                .write_value(buffer, &val, expected_type);
+        }
+    }
+}
+
+/// A metric data point row, built field by field together with its schema.
+///
+/// Unlike logs and spans, the fields of a metric row depend on the data point type, so the
+/// row is written at the same time the field list is collected.
+struct MetricRow {
+    fields: Vec<(Cow<'static, str>, BondDataType, Option<BondDataType>)>,
+    buffer: Vec<u8>,
+    time_unix_nano: u64,
+}
+
+impl MetricRow {
+    /// Start a row with the Part A fields and the metric identity
+    fn new(metric: &Metric, metric_type: &str, time_unix_nano: u64) -> Self {
+        let mut row = MetricRow {
+            fields: Vec::with_capacity(16),
+            buffer: Vec::with_capacity(256),
+            time_unix_nano,
+        };
+        let formatted_timestamp = OtlpEncoder::format_timestamp(time_unix_nano);
+        row.string(FIELD_ENV_NAME, "TestEnv"); // TODO - placeholder
+        row.string(FIELD_ENV_VER, "4.0"); // TODO - placeholder
+        row.string(FIELD_TIMESTAMP, &formatted_timestamp);
+        row.string(FIELD_ENV_TIME, &formatted_timestamp);
+        row.string(FIELD_NAME, &metric.name);
+        if !metric.description.is_empty() {
+            row.string(FIELD_DESCRIPTION, &metric.description);
+        }
+        if !metric.unit.is_empty() {
+            row.string(FIELD_UNIT, &metric.unit);
+        }
+        row.string(FIELD_METRIC_TYPE, metric_type);
+        row
+    }
+
+    fn push_field(&mut self, name: impl Into<Cow<'static, str>>, type_id: BondDataType) {
+        self.fields.push((name.into(), type_id, None));
+    }
+
+    fn string(&mut self, name: impl Into<Cow<'static, str>>, value: &str) {
+        self.push_field(name, BondDataType::BT_STRING);
+        BondWriter::write_string(&mut self.buffer, value);
+    }
+
+    fn bool(&mut self, name: &'static str, value: bool) {
+        self.push_field(name, BondDataType::BT_BOOL);
+        BondWriter::write_bool(&mut self.buffer, value);
+    }
+
+    fn int32(&mut self, name: &'static str, value: i32) {
+        self.push_field(name, BondDataType::BT_INT32);
+        BondWriter::write_numeric(&mut self.buffer, value);
+    }
+
+    fn uint64(&mut self, name: &'static str, value: u64) {
+        self.push_field(name, BondDataType::BT_UINT64);
+        BondWriter::write_numeric(&mut self.buffer, value);
+    }
+
+    fn optional_double(&mut self, name: &'static str, value: Option<f64>) {
+        if let Some(value) = value {
+            self.push_field(name, BondDataType::BT_DOUBLE);
+            BondWriter::write_numeric(&mut self.buffer, value);
+        }
+    }
+
+    /// Arrays (bucket bounds and counts) are written as JSON strings, like span links
+    fn json<T: serde::Serialize>(&mut self, name: &'static str, values: &[T]) {
+        let json = serde_json::to_string(values).unwrap_or_else(|_| "[]".to_string());
+        self.string(name, &json);
+    }
+
+    fn temporality(&mut self, temporality: i32) {
+        let name = match AggregationTemporality::try_from(temporality) {
+            Ok(AggregationTemporality::Delta) => "Delta",
+            Ok(AggregationTemporality::Cumulative) => "Cumulative",
+            _ => "Unspecified",
+        };
+        self.string(FIELD_TEMPORALITY, name);
+    }
+
+    fn start_time(&mut self, start_time_unix_nano: u64) {
+        if start_time_unix_nano != 0 {
+            let formatted = OtlpEncoder::format_timestamp(start_time_unix_nano);
+            self.string(FIELD_START_TIME, &formatted);
+        }
+    }
+
+    /// Integer values keep their type so they are not rounded through f64
+    fn number_value(&mut self, value: Option<&number_data_point::Value>) {
+        match value {
+            Some(number_data_point::Value::AsInt(i)) => {
+                self.push_field(FIELD_VALUE, BondDataType::BT_INT64);
+                BondWriter::write_numeric(&mut self.buffer, *i);
+            }
+            Some(number_data_point::Value::AsDouble(d)) => {
+                self.push_field(FIELD_VALUE, BondDataType::BT_DOUBLE);
+                BondWriter::write_numeric(&mut self.buffer, *d);
             }
+            None => {}
         }
     }
+
+    /// Part C - data point attributes, except those that would duplicate a built-in column
+    fn attributes(
+        &mut self,
+        attributes: &[opentelemetry_proto::tonic::common::v1::KeyValue],
+        encoder: &OtlpEncoder,
+    ) {
+        let encoding = &encoder.attribute_encoding;
+        for attr in attributes
+            .iter()
+            .filter(|attr| !METRIC_COLUMNS.contains(&attr.key.as_str()))
+        {
+            if let Some(val) =
+                attribute_value(attr).and_then(|v| encoder.mapped_attribute(&attr.key, v))
+            {
+                let (type_id, element_type) = encoding.bond_type(&val);
+                self.fields
+                    .push((attr.key.clone().into(), type_id, element_type));
+                encoding.write_value(&mut self.buffer, &val, type_id);
+            }
+        }
+    }
+
+    /// Consume the row, returning its field definitions, schema ID and encoded bytes
+    fn finish(self, event_name: &str, columns: &ColumnMapping) -> (Vec<FieldDef>, u64, Vec<u8>) {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+
+        let mut hasher = DefaultHasher::new();
+        event_name.hash(&mut hasher);
+        let field_defs = self
+            .fields
+            .into_iter()
+            .enumerate()
+            .map(|(i, (name, type_id, element_type))| {
+                columns.column_name(&name).hash(&mut hasher);
+                type_id.hash(&mut hasher);
+                if let Some(element_type) = element_type {
+                    element_type.hash(&mut hasher);
+                }
+                FieldDef {
+                    name,
+                    type_id,
+                    field_id: (i + 1) as u16,
+                    element_type,
+                }
+            })
+            .collect();
+        (field_defs, hasher.finish(), self.buffer)
+    }
 }
 
 #[cfg(test)]
@@ -1155,7 +1542,11 @@ mod tests {
         };
 
         // Verify that both spans have name field in schema
-        let (fields1, _) = OtlpEncoder::determine_span_fields_and_schema_id(&span1, "Span");
+        let (fields1, _) = OtlpEncoder::new().determine_span_fields_and_schema_id(
+            &span1,
+            &ContextFields::default(),
+            "Span",
+        );
         let name_field_present1 = fields1
             .iter()
             .any(|field| field.name.as_ref() == FIELD_NAME);
@@ -1164,7 +1555,11 @@ mod tests {
             "Span with non-empty name should include 'name' field in schema"
         );
 
-        let (fields2, _) = OtlpEncoder::determine_span_fields_and_schema_id(&span2, "Span");
+        let (fields2, _) = OtlpEncoder::new().determine_span_fields_and_schema_id(
+            &span2,
+            &ContextFields::default(),
+            "Span",
+        );
         let name_field_present2 = fields2
             .iter()
             .any(|field| field.name.as_ref() == FIELD_NAME);
@@ -1241,4 +1636,611 @@ mod tests {
         assert!(single_result.starts_with('['));
         assert!(single_result.ends_with(']'));
     }
+
+    fn metric(name: &str, data: MetricData) -> Metric {
+        Metric {
+            name: name.to_string(),
+            description: "test metric".to_string(),
+            unit: "ms".to_string(),
+            data: Some(data),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_metric_encoding() {
+        use opentelemetry_proto::tonic::metrics::v1::{Gauge, NumberDataPoint, Sum};
+
+        let encoder = OtlpEncoder::new();
+        let attributes = vec![KeyValue {
+            key: "host".to_string(),
+            value: Some(AnyValue {
+                value: Some(Value::StringValue("web-1".to_string())),
+            }),
+        }];
+        let sum = metric(
+            "requests",
+            MetricData::Sum(Sum {
+                data_points: vec![
+                    NumberDataPoint {
+                        attributes: attributes.clone(),
+                        start_time_unix_nano: 1_700_000_000_000_000_000,
+                        time_unix_nano: 1_700_000_010_000_000_000,
+                        value: Some(number_data_point::Value::AsInt(42)),
+                        ..Default::default()
+                    },
+                    NumberDataPoint {
+                        attributes,
+                        start_time_unix_nano: 1_700_000_000_000_000_000,
+                        time_unix_nano: 1_700_000_020_000_000_000,
+                        value: Some(number_data_point::Value::AsInt(7)),
+                        ..Default::default()
+                    },
+                ],
+                aggregation_temporality: AggregationTemporality::Delta as i32,
+                is_monotonic: true,
+            }),
+        );
+        let gauge = metric(
+            "cpu",
+            MetricData::Gauge(Gauge {
+                data_points: vec![NumberDataPoint {
+                    time_unix_nano: 1_700_000_005_000_000_000,
+                    value: Some(number_data_point::Value::AsDouble(0.5)),
+                    ..Default::default()
+                }],
+            }),
+        );
+
+        let metadata = "namespace=testNamespace/eventVersion=Ver1v0";
+        let result = encoder
+            .encode_metric_batch([sum, gauge].iter(), metadata)
+            .unwrap();
+
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].event_name, "Metric"); // All metrics use "Metric" event name for routing
+        assert!(!result[0].data.is_empty());
+        // Both sum points share a schema; the gauge has its own
+        assert_eq!(result[0].metadata.schema_ids.matches(';').count(), 1);
+        assert_eq!(result[0].metadata.start_time, 1_700_000_005_000_000_000);
+        assert_eq!(result[0].metadata.end_time, 1_700_000_020_000_000_000);
+    }
+
+    #[test]
+    fn test_histogram_metric_rows() {
+        use opentelemetry_proto::tonic::metrics::v1::{
+            exponential_histogram_data_point::Buckets, ExponentialHistogram,
+            ExponentialHistogramDataPoint, Histogram, HistogramDataPoint,
+        };
+
+        let histogram = metric(
+            "latency",
+            MetricData::Histogram(Histogram {
+                data_points: vec![HistogramDataPoint {
+                    time_unix_nano: 1_700_000_000_000_000_000,
+                    count: 3,
+                    sum: Some(12.5),
+                    min: Some(1.0),
+                    max: Some(9.0),
+                    explicit_bounds: vec![5.0, 10.0],
+                    bucket_counts: vec![2, 1, 0],
+                    ..Default::default()
+                }],
+                aggregation_temporality: AggregationTemporality::Cumulative as i32,
+            }),
+        );
+        let rows = OtlpEncoder::new().metric_rows(&histogram);
+        assert_eq!(rows.len(), 1);
+        let names: Vec<&str> = rows[0].fields.iter().map(|(n, _, _)| n.as_ref()).collect();
+        for expected in [
+            FIELD_NAME,
+            FIELD_METRIC_TYPE,
+            FIELD_TEMPORALITY,
+            FIELD_COUNT,
+            FIELD_SUM,
+            FIELD_MIN,
+            FIELD_MAX,
+            FIELD_EXPLICIT_BOUNDS,
+            FIELD_BUCKET_COUNTS,
+        ] {
+            assert!(names.contains(&expected), "missing field {expected}");
+        }
+        assert!(!names.contains(&FIELD_START_TIME)); // start time not set
+
+        let exponential = metric(
+            "latency",
+            MetricData::ExponentialHistogram(ExponentialHistogram {
+                data_points: vec![ExponentialHistogramDataPoint {
+                    time_unix_nano: 1_700_000_000_000_000_000,
+                    count: 2,
+                    scale: 3,
+                    zero_count: 1,
+                    positive: Some(Buckets {
+                        offset: -2,
+                        bucket_counts: vec![1],
+                    }),
+                    ..Default::default()
+                }],
+                aggregation_temporality: AggregationTemporality::Delta as i32,
+            }),
+        );
+        let rows = OtlpEncoder::new().metric_rows(&exponential);
+        let names: Vec<&str> = rows[0].fields.iter().map(|(n, _, _)| n.as_ref()).collect();
+        assert!(names.contains(&FIELD_SCALE));
+        assert!(names.contains(&FIELD_POSITIVE_BUCKET_COUNTS));
+        assert!(!names.contains(&FIELD_NEGATIVE_BUCKET_COUNTS));
+    }
+
+    #[test]
+    fn test_metric_attributes_colliding_with_built_in_columns_are_dropped() {
+        use opentelemetry_proto::tonic::metrics::v1::{Gauge, NumberDataPoint};
+
+        let string_attr = |key: &str| KeyValue {
+            key: key.to_string(),
+            value: Some(AnyValue {
+                value: Some(Value::StringValue("shadow".to_string())),
+            }),
+        };
+        let gauge = metric(
+            "cpu",
+            MetricData::Gauge(Gauge {
+                data_points: vec![NumberDataPoint {
+                    attributes: vec![
+                        string_attr(FIELD_NAME),
+                        string_attr(FIELD_VALUE),
+                        string_attr(FIELD_UNIT),
+                        string_attr(FIELD_METRIC_TYPE),
+                        string_attr(FIELD_COUNT),
+                        string_attr("host"),
+                    ],
+                    time_unix_nano: 1_700_000_000_000_000_000,
+                    value: Some(number_data_point::Value::AsDouble(0.5)),
+                    ..Default::default()
+                }],
+            }),
+        );
+
+        let rows = OtlpEncoder::new().metric_rows(&gauge);
+        let names: Vec<&str> = rows[0].fields.iter().map(|(n, _, _)| n.as_ref()).collect();
+        for name in &names {
+            assert_eq!(
+                names.iter().filter(|n| *n == name).count(),
+                1,
+                "duplicate field {name}"
+            );
+        }
+        assert!(names.contains(&"host"));
+        assert!(!names.contains(&FIELD_COUNT)); // gauges have no count column
+
+        let result = OtlpEncoder::new()
+            .encode_metric_batch([gauge].iter(), "")
+            .unwrap();
+        assert_eq!(result.len(), 1);
+    }
+
+    #[test]
+    fn test_empty_metrics_produce_no_batches() {
+        let encoder = OtlpEncoder::new();
+        let empty = metric(
+            "nothing",
+            MetricData::Gauge(opentelemetry_proto::tonic::metrics::v1::Gauge {
+                data_points: vec![],
+            }),
+        );
+        let result = encoder.encode_metric_batch([empty].iter(), "").unwrap();
+        assert!(result.is_empty());
+    }
+
+    #[test]
+    fn test_nested_attributes_and_body_are_encoded() {
+        use opentelemetry_proto::tonic::common::v1::{ArrayValue, KeyValueList};
+
+        let kvlist = Value::KvlistValue(KeyValueList {
+            values: vec![KeyValue {
+                key: "id".to_string(),
+                value: Some(AnyValue {
+                    value: Some(Value::IntValue(7)),
+                }),
+            }],
+        });
+        let log = LogRecord {
+            observed_time_unix_nano: 1_700_000_000_000_000_000,
+            body: Some(AnyValue {
+                value: Some(kvlist.clone()),
+            }),
+            attributes: vec![
+                KeyValue {
+                    key: "tags".to_string(),
+                    value: Some(AnyValue {
+                        value: Some(Value::ArrayValue(ArrayValue {
+                            values: vec![AnyValue {
+                                value: Some(Value::StringValue("a".to_string())),
+                            }],
+                        })),
+                    }),
+                },
+                KeyValue {
+                    key: "payload".to_string(),
+                    value: Some(AnyValue {
+                        value: Some(Value::BytesValue(vec![1, 2, 3])),
+                    }),
+                },
+            ],
+            ..Default::default()
+        };
+
+        let encoder = OtlpEncoder::new();
+        let (fields, _) =
+            encoder.determine_fields_and_schema_id(&log, &ContextFields::default(), "Log");
+        let names: Vec<&str> = fields.iter().map(|f| f.name.as_ref()).collect();
+        assert!(names.contains(&FIELD_BODY));
+        assert!(names.contains(&"tags"));
+        assert!(names.contains(&"payload"));
+
+        let row = encoder.write_row_data(&log, &ContextFields::default(), &fields);
+        let row = String::from_utf8_lossy(&row);
+        assert!(row.contains(r#"{"id":7}"#), "body not serialized: {row}");
+        assert!(row.contains(r#"["a"]"#), "array not serialized: {row}");
+        assert!(row.contains("AQID"), "bytes not base64 encoded: {row}");
+
+        // Switching to Bond lists changes the schema
+        let list_encoder = OtlpEncoder::with_config(
+            AttributeEncoding {
+                complex_values: crate::ComplexValueEncoding::BondList,
+                ..AttributeEncoding::default()
+            },
+            ResourceMapping::default(),
+            ColumnMapping::default(),
+            BatchLimits::default(),
+        );
+        let (list_fields, _) =
+            list_encoder.determine_fields_and_schema_id(&log, &ContextFields::default(), "Log");
+        let tags = list_fields.iter().find(|f| f.name == "tags").unwrap();
+        assert_eq!(tags.type_id, BondDataType::BT_LIST);
+        assert_eq!(tags.element_type, Some(BondDataType::BT_STRING));
+        assert!(!list_encoder
+            .encode_log_batch([log].iter(), "namespace=test")
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_resource_and_scope_fields() {
+        use crate::payload_encoder::resource_mapping::{AttributeSelection, RecordContext};
+        use opentelemetry_proto::tonic::common::v1::InstrumentationScope;
+
+        let resource = vec![
+            KeyValue {
+                key: "service.name".to_string(),
+                value: Some(AnyValue {
+                    value: Some(Value::StringValue("checkout".to_string())),
+                }),
+            },
+            KeyValue {
+                key: "cloud.region".to_string(),
+                value: Some(AnyValue {
+                    value: Some(Value::StringValue("westus".to_string())),
+                }),
+            },
+        ];
+        let scope = InstrumentationScope {
+            name: "my-lib".to_string(),
+            ..Default::default()
+        };
+        let context = RecordContext {
+            resource: &resource,
+            scope: Some(&scope),
+        };
+        // Attributes named like built-in columns would duplicate schema fields
+        let span = Span {
+            name: "op".to_string(),
+            start_time_unix_nano: 1_700_000_000_000_000_000,
+            attributes: vec![KeyValue {
+                key: FIELD_ENV_CLOUD_ROLE.to_string(),
+                value: Some(AnyValue {
+                    value: Some(Value::StringValue("spoofed".to_string())),
+                }),
+            }],
+            ..Default::default()
+        };
+
+        let encoder = OtlpEncoder::with_config(
+            AttributeEncoding::default(),
+            ResourceMapping {
+                part_a: true,
+                resource_attributes: AttributeSelection::Only(vec!["cloud.region".to_string()]),
+                scope_name_version: true,
+                ..ResourceMapping::default()
+            },
+            ColumnMapping::default(),
+            BatchLimits::default(),
+        );
+        let fields =
+            encoder
+                .resource_mapping
+                .context_fields(context, &span.attributes, SPAN_COLUMNS);
+        let (schema, _) = encoder.determine_span_fields_and_schema_id(&span, &fields, "Span");
+        let names: Vec<&str> = schema.iter().map(|f| f.name.as_ref()).collect();
+        // Part A column right after the fixed Part A fields, promoted attribute at the end
+        assert_eq!(names[4], FIELD_ENV_CLOUD_ROLE);
+        assert!(names.contains(&FIELD_SCOPE_NAME));
+        assert_eq!(names.last(), Some(&"cloud.region"));
+        assert_eq!(
+            names.iter().filter(|n| **n == FIELD_ENV_CLOUD_ROLE).count(),
+            1
+        );
+
+        let row = encoder.write_span_row_data(&span, &fields, &schema);
+        let row = String::from_utf8_lossy(&row);
+        assert!(row.contains("checkout") && row.contains("my-lib") && row.contains("westus"));
+        assert!(!row.contains("spoofed"));
+
+        // Records without context produce no extra fields
+        let with_context = encoder
+            .encode_span_batch([(&span, context)], "test")
+            .unwrap();
+        let without = encoder.encode_span_batch([&span], "test").unwrap();
+        assert_ne!(
+            with_context[0].metadata.schema_ids,
+            without[0].metadata.schema_ids
+        );
+    }
+
+    #[test]
+    fn test_column_mapping_applied_to_schema_and_row() {
+        use crate::payload_encoder::column_mapping::{AttributeFilter, REDACTED};
+        use std::collections::HashMap;
+
+        let attr = |key: &str, value: &str| KeyValue {
+            key: key.to_string(),
+            value: Some(AnyValue {
+                value: Some(Value::StringValue(value.to_string())),
+            }),
+        };
+        let log = LogRecord {
+            observed_time_unix_nano: 1_700_000_000_000_000_000,
+            severity_number: 9,
+            attributes: vec![
+                attr("user.email", "alice@example.com"),
+                attr("password", "hunter2"),
+                attr("http.route", "/login"),
+            ],
+            ..Default::default()
+        };
+        let columns = ColumnMapping {
+            renames: HashMap::from([
+                ("SeverityNumber".to_string(), "Level".to_string()),
+                ("http.route".to_string(), "Route".to_string()),
+            ]),
+            attribute_filter: AttributeFilter::Deny(vec!["user.email".to_string()]),
+            redact: vec!["password".to_string()],
+            ..ColumnMapping::default()
+        };
+        let encoder = OtlpEncoder::with_config(
+            AttributeEncoding::default(),
+            ResourceMapping::default(),
+            columns,
+            BatchLimits::default(),
+        );
+
+        let (fields, schema_id) =
+            encoder.determine_fields_and_schema_id(&log, &ContextFields::default(), "Log");
+        let names: Vec<&str> = fields.iter().map(|f| f.name.as_ref()).collect();
+        assert!(!names.contains(&"user.email"));
+
+        let row = encoder.write_row_data(&log, &ContextFields::default(), &fields);
+        let row = String::from_utf8_lossy(&row);
+        assert!(row.contains(REDACTED) && !row.contains("hunter2"));
+        assert!(!row.contains("alice@example.com"));
+
+        let schema = encoder.create_schema(schema_id, fields);
+        let schema_bytes = String::from_utf8_lossy(schema.schema.as_bytes());
+        assert!(schema_bytes.contains("Level") && schema_bytes.contains("Route"));
+        assert!(!schema_bytes.contains("SeverityNumber"));
+
+        // Renames are part of the schema ID, and the ID is stable for a given mapping
+        let (_, default_id) = OtlpEncoder::new().determine_fields_and_schema_id(
+            &log,
+            &ContextFields::default(),
+            "Log",
+        );
+        let (_, again) =
+            encoder.determine_fields_and_schema_id(&log, &ContextFields::default(), "Log");
+        assert_ne!(schema_id, default_id);
+        assert_eq!(schema_id, again);
+    }
+
+    fn limited_encoder(batch_limits: BatchLimits) -> OtlpEncoder {
+        OtlpEncoder::with_config(
+            AttributeEncoding::default(),
+            ResourceMapping::default(),
+            ColumnMapping::default(),
+            batch_limits,
+        )
+    }
+
+    /// Log record whose `payload` attribute is `len` bytes of poorly compressible text
+    fn log_with_payload(time_unix_nano: u64, seed: u64, len: usize) -> LogRecord {
+        let mut state = seed.wrapping_mul(6364136223846793005).wrapping_add(1);
+        let payload: String = (0..len)
+            .map(|_| {
+                state = state
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                char::from(b'!' + ((state >> 33) % 90) as u8)
+            })
+            .collect();
+        LogRecord {
+            time_unix_nano,
+            event_name: "Big".to_string(),
+            attributes: vec![KeyValue {
+                key: "payload".to_string(),
+                value: Some(AnyValue {
+                    value: Some(Value::StringValue(payload)),
+                }),
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_batch_split_by_event_count() {
+        let encoder = limited_encoder(BatchLimits {
+            max_events: 2,
+            ..BatchLimits::default()
+        });
+        let logs: Vec<LogRecord> = (0..5)
+            .map(|i| LogRecord {
+                time_unix_nano: 1_700_000_000_000_000_000 + i * 1_000_000_000,
+                event_name: "Counted".to_string(),
+                ..Default::default()
+            })
+            .collect();
+
+        let mut result = encoder
+            .encode_log_batch(logs.iter(), "namespace=test")
+            .unwrap();
+        result.sort_by_key(|b| b.metadata.start_time);
+
+        let ranges: Vec<(u64, u64)> = result
+            .iter()
+            .map(|b| (b.metadata.start_time, b.metadata.end_time))
+            .collect();
+        assert_eq!(
+            ranges,
+            vec![
+                (1_700_000_000_000_000_000, 1_700_000_001_000_000_000),
+                (1_700_000_002_000_000_000, 1_700_000_003_000_000_000),
+                (1_700_000_004_000_000_000, 1_700_000_004_000_000_000),
+            ]
+        );
+        let counts: Vec<usize> = result.iter().map(|b| b.metadata.event_count).collect();
+        assert_eq!(counts, vec![2, 2, 1]);
+        for batch in &result {
+            assert_eq!(batch.event_name, "Counted");
+            assert_eq!(batch.metadata.schema_ids.split(';').count(), 1);
+        }
+    }
+
+    #[test]
+    fn test_batch_split_keeps_only_used_schemas() {
+        let encoder = limited_encoder(BatchLimits {
+            max_events: 2,
+            ..BatchLimits::default()
+        });
+        let plain = |time_unix_nano| LogRecord {
+            time_unix_nano,
+            event_name: "Mixed".to_string(),
+            ..Default::default()
+        };
+        let mut traced = plain(1_700_000_002_000_000_000);
+        traced.trace_id = vec![1; 16];
+        let logs = [
+            plain(1_700_000_000_000_000_000),
+            plain(1_700_000_001_000_000_000),
+            traced,
+        ];
+
+        let unsplit = OtlpEncoder::new()
+            .encode_log_batch(logs.iter(), "namespace=test")
+            .unwrap();
+        assert_eq!(unsplit.len(), 1);
+        let all_ids: Vec<&str> = unsplit[0].metadata.schema_ids.split(';').collect();
+        assert_eq!(all_ids.len(), 2);
+
+        let mut result = encoder
+            .encode_log_batch(logs.iter(), "namespace=test")
+            .unwrap();
+        result.sort_by_key(|b| b.metadata.start_time);
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0].metadata.schema_ids, all_ids[0]);
+        assert_eq!(result[1].metadata.schema_ids, all_ids[1]);
+    }
+
+    #[test]
+    fn test_batch_split_by_size() {
+        let logs: Vec<LogRecord> = (0..8)
+            .map(|i| log_with_payload(1_700_000_000_000_000_000 + i, i, 4_000))
+            .collect();
+
+        let unlimited = limited_encoder(BatchLimits::unlimited())
+            .encode_log_batch(logs.iter(), "namespace=test")
+            .unwrap();
+        assert_eq!(unlimited.len(), 1);
+
+        // Roughly two rows fit under the uncompressed limit
+        let result = limited_encoder(BatchLimits {
+            max_uncompressed_bytes: 10_000,
+            ..BatchLimits::default()
+        })
+        .encode_log_batch(logs.iter(), "namespace=test")
+        .unwrap();
+        assert_eq!(result.len(), 4);
+
+        // The random payload barely compresses, so the compressed limit also forces splits
+        let result = limited_encoder(BatchLimits {
+            max_compressed_bytes: 10_000,
+            ..BatchLimits::default()
+        })
+        .encode_log_batch(logs.iter(), "namespace=test")
+        .unwrap();
+        assert!(result.len() > 1);
+        assert!(result.iter().all(|b| b.data.len() <= 10_000));
+        let mut times: Vec<u64> = result.iter().map(|b| b.metadata.start_time).collect();
+        times.sort_unstable();
+        times.dedup();
+        assert_eq!(times.len(), result.len());
+        assert_eq!(times[0], 1_700_000_000_000_000_000);
+    }
+
+    #[test]
+    fn test_oversized_event_is_sent_alone() {
+        let encoder = limited_encoder(BatchLimits {
+            max_uncompressed_bytes: 1_000,
+            max_compressed_bytes: 1_000,
+            ..BatchLimits::default()
+        });
+        let logs = [
+            log_with_payload(1_700_000_000_000_000_000, 1, 4_000),
+            log_with_payload(1_700_000_001_000_000_000, 2, 4_000),
+        ];
+        let result = encoder
+            .encode_log_batch(logs.iter(), "namespace=test")
+            .unwrap();
+        assert_eq!(result.len(), 2);
+        assert!(result
+            .iter()
+            .all(|b| b.metadata.start_time == b.metadata.end_time));
+    }
+
+    #[test]
+    fn test_span_batch_split() {
+        let encoder = limited_encoder(BatchLimits {
+            max_events: 1,
+            ..BatchLimits::default()
+        });
+        let spans: Vec<Span> = (0..3)
+            .map(|i| Span {
+                trace_id: vec![1; 16],
+                span_id: vec![i as u8 + 1; 8],
+                name: "op".to_string(),
+                start_time_unix_nano: 1_700_000_000_000_000_000 + i * 10,
+                end_time_unix_nano: 1_700_000_000_000_000_005 + i * 10,
+                ..Default::default()
+            })
+            .collect();
+        let result = encoder
+            .encode_span_batch(spans.iter(), "namespace=test")
+            .unwrap();
+        assert_eq!(result.len(), 3);
+        for (i, batch) in result.iter().enumerate() {
+            let i = i as u64;
+            assert_eq!(batch.event_name, "Span");
+            assert_eq!(
+                batch.metadata.start_time,
+                1_700_000_000_000_000_000 + i * 10
+            );
+            assert_eq!(batch.metadata.end_time, 1_700_000_000_000_000_005 + i * 10);
+        }
+    }
 }
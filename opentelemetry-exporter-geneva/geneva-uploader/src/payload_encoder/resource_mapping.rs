@@ -0,0 +1,296 @@
+// Promotion of resource and instrumentation-scope attributes into encoded rows
+
+use crate::payload_encoder::attribute_encoding::attribute_value;
+use opentelemetry_proto::tonic::common::v1::any_value::Value;
+use opentelemetry_proto::tonic::common::v1::{InstrumentationScope, KeyValue};
+
+pub(crate) const FIELD_ENV_CLOUD_ROLE: &str = "env_cloud_role";
+pub(crate) const FIELD_ENV_CLOUD_ROLE_INSTANCE: &str = "env_cloud_roleInstance";
+pub(crate) const FIELD_ENV_CLOUD_ROLE_VER: &str = "env_cloud_roleVer";
+pub(crate) const FIELD_SCOPE_NAME: &str = "scopeName";
+pub(crate) const FIELD_SCOPE_VERSION: &str = "scopeVersion";
+
+/// Which attributes of a resource or instrumentation scope are written to every row.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum AttributeSelection {
+    /// No attributes.
+    #[default]
+    None,
+    /// All attributes.
+    All,
+    /// Only the attributes with these keys.
+    Only(Vec<String>),
+}
+
+impl AttributeSelection {
+    fn includes(&self, key: &str) -> bool {
+        match self {
+            AttributeSelection::None => false,
+            AttributeSelection::All => true,
+            AttributeSelection::Only(keys) => keys.iter().any(|k| k == key),
+        }
+    }
+}
+
+/// Controls how resource and instrumentation-scope attributes reach Geneva.
+///
+/// Promoted attributes are added as extra fields on each row of logs and spans. Record
+/// attributes take precedence: a resource or scope attribute is skipped when the record
+/// has an attribute with the same key. Attributes named like a built-in column, e.g.
+/// `name` or `env_cloud_role`, are never promoted.
+///
+/// Everything is off by default, so that existing schemas do not change.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ResourceMapping {
+    /// Fill the Part A columns `env_cloud_role` (from `service.name`), `env_cloud_roleInstance`
+    /// (from `service.instance.id`, falling back to `host.name`) and `env_cloud_roleVer`
+    /// (from `service.version`).
+    pub part_a: bool,
+    /// Resource attributes written as extra fields, named after the attribute key.
+    pub resource_attributes: AttributeSelection,
+    /// Write the instrumentation scope name and version as `scopeName` and `scopeVersion`.
+    pub scope_name_version: bool,
+    /// Instrumentation scope attributes written as extra fields, named after the attribute key.
+    pub scope_attributes: AttributeSelection,
+}
+
+/// Resource and scope a record was emitted under.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct RecordContext<'a> {
+    pub(crate) resource: &'a [KeyValue],
+    pub(crate) scope: Option<&'a InstrumentationScope>,
+}
+
+/// A record paired with its resource and scope.
+///
+/// Plain record references convert with an empty context, so encoder entry points accept
+/// either `&record` or `(&record, context)` items.
+pub(crate) struct WithContext<'a, T> {
+    pub(crate) record: &'a T,
+    pub(crate) context: RecordContext<'a>,
+}
+
+impl<'a, T> From<&'a T> for WithContext<'a, T> {
+    fn from(record: &'a T) -> Self {
+        Self {
+            record,
+            context: RecordContext::default(),
+        }
+    }
+}
+
+impl<'a, T> From<(&'a T, RecordContext<'a>)> for WithContext<'a, T> {
+    fn from((record, context): (&'a T, RecordContext<'a>)) -> Self {
+        Self { record, context }
+    }
+}
+
+/// Fields contributed to a row by its resource and scope, in schema order.
+#[derive(Debug, Default)]
+pub(crate) struct ContextFields<'a> {
+    /// Part A columns, written right after the always-present Part A fields
+    pub(crate) part_a: Vec<(&'static str, &'a str)>,
+    /// Scope name and version, written with the Part B fields
+    pub(crate) scope: Vec<(&'static str, &'a str)>,
+    /// Promoted resource and scope attributes, written after the record attributes
+    pub(crate) extra: Vec<(&'a str, &'a Value)>,
+}
+
+impl ContextFields<'_> {
+    pub(crate) fn len(&self) -> usize {
+        self.part_a.len() + self.scope.len() + self.extra.len()
+    }
+
+    /// Value of a Part A or scope column
+    pub(crate) fn column(&self, name: &str) -> Option<&str> {
+        self.part_a
+            .iter()
+            .chain(self.scope.iter())
+            .find(|(n, _)| *n == name)
+            .map(|(_, v)| *v)
+    }
+
+    /// Value of a promoted attribute
+    pub(crate) fn attribute(&self, key: &str) -> Option<&Value> {
+        self.extra.iter().find(|(k, _)| *k == key).map(|(_, v)| *v)
+    }
+}
+
+impl ResourceMapping {
+    /// Fields to add to a record emitted under `context`. `record_attributes` are the
+    /// record's own attributes, which shadow promoted attributes with the same key, and
+    /// `columns` the built-in columns of the record's rows, which are never promoted.
+    pub(crate) fn context_fields<'a>(
+        &self,
+        context: RecordContext<'a>,
+        record_attributes: &[KeyValue],
+        columns: &[&str],
+    ) -> ContextFields<'a> {
+        let mut fields = ContextFields::default();
+
+        if self.part_a {
+            let resource_string = |key: &str| {
+                context
+                    .resource
+                    .iter()
+                    .find_map(|kv| match attribute_value(kv) {
+                        Some(Value::StringValue(s)) if kv.key == key && !s.is_empty() => {
+                            Some(s.as_str())
+                        }
+                        _ => None,
+                    })
+            };
+            if let Some(role) = resource_string("service.name") {
+                fields.part_a.push((FIELD_ENV_CLOUD_ROLE, role));
+            }
+            if let Some(instance) =
+                resource_string("service.instance.id").or_else(|| resource_string("host.name"))
+            {
+                fields
+                    .part_a
+                    .push((FIELD_ENV_CLOUD_ROLE_INSTANCE, instance));
+            }
+            if let Some(version) = resource_string("service.version") {
+                fields.part_a.push((FIELD_ENV_CLOUD_ROLE_VER, version));
+            }
+        }
+
+        if let Some(scope) = context.scope {
+            if self.scope_name_version {
+                if !scope.name.is_empty() {
+                    fields.scope.push((FIELD_SCOPE_NAME, scope.name.as_str()));
+                }
+                if !scope.version.is_empty() {
+                    fields
+                        .scope
+                        .push((FIELD_SCOPE_VERSION, scope.version.as_str()));
+                }
+            }
+        }
+
+        let scope_attributes = context.scope.map(|s| &s.attributes[..]).unwrap_or(&[]);
+        let promoted = context
+            .resource
+            .iter()
+            .filter(|kv| self.resource_attributes.includes(&kv.key))
+            .chain(
+                scope_attributes
+                    .iter()
+                    .filter(|kv| self.scope_attributes.includes(&kv.key)),
+            );
+        for kv in promoted {
+            let Some(value) = attribute_value(kv) else {
+                continue;
+            };
+            let shadowed = columns.contains(&kv.key.as_str())
+                || record_attributes.iter().any(|a| a.key == kv.key)
+                || fields.extra.iter().any(|(k, _)| *k == kv.key);
+            if !shadowed {
+                fields.extra.push((kv.key.as_str(), value));
+            }
+        }
+
+        fields
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry_proto::tonic::common::v1::AnyValue;
+
+    fn kv(key: &str, value: &str) -> KeyValue {
+        KeyValue {
+            key: key.to_string(),
+            value: Some(AnyValue {
+                value: Some(Value::StringValue(value.to_string())),
+            }),
+        }
+    }
+
+    #[test]
+    fn test_part_a_and_scope_columns() {
+        let resource = vec![
+            kv("service.name", "checkout"),
+            kv("host.name", "vm-1"),
+            kv("service.version", "1.2.3"),
+        ];
+        let scope = InstrumentationScope {
+            name: "my-lib".to_string(),
+            version: "0.1".to_string(),
+            ..Default::default()
+        };
+        let context = RecordContext {
+            resource: &resource,
+            scope: Some(&scope),
+        };
+
+        let mapping = ResourceMapping {
+            part_a: true,
+            scope_name_version: true,
+            ..ResourceMapping::default()
+        };
+        let fields = mapping.context_fields(context, &[], &[]);
+        assert_eq!(
+            fields.part_a,
+            vec![
+                (FIELD_ENV_CLOUD_ROLE, "checkout"),
+                (FIELD_ENV_CLOUD_ROLE_INSTANCE, "vm-1"),
+                (FIELD_ENV_CLOUD_ROLE_VER, "1.2.3"),
+            ]
+        );
+        assert_eq!(
+            fields.scope,
+            vec![(FIELD_SCOPE_NAME, "my-lib"), (FIELD_SCOPE_VERSION, "0.1")]
+        );
+        assert!(fields.extra.is_empty());
+
+        assert_eq!(
+            ResourceMapping::default()
+                .context_fields(context, &[], &[])
+                .len(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_promoted_attributes_are_shadowed_by_record_attributes() {
+        let resource = vec![
+            kv("service.name", "checkout"),
+            kv("cloud.region", "westus"),
+            kv("deployment.environment", "prod"),
+            kv("name", "shadows a column"),
+        ];
+        let scope = InstrumentationScope {
+            name: "my-lib".to_string(),
+            attributes: vec![kv("library.kind", "http"), kv("cloud.region", "ignored")],
+            ..Default::default()
+        };
+        let mapping = ResourceMapping {
+            resource_attributes: AttributeSelection::Only(vec![
+                "cloud.region".to_string(),
+                "deployment.environment".to_string(),
+                "name".to_string(),
+            ]),
+            scope_attributes: AttributeSelection::All,
+            ..ResourceMapping::default()
+        };
+        let record_attributes = vec![kv("deployment.environment", "staging")];
+
+        let fields = mapping.context_fields(
+            RecordContext {
+                resource: &resource,
+                scope: Some(&scope),
+            },
+            &record_attributes,
+            &["name"],
+        );
+        let keys: Vec<&str> = fields.extra.iter().map(|(k, _)| *k).collect();
+        // Columns and record attributes win; resource wins over scope for duplicate keys
+        assert_eq!(keys, vec!["cloud.region", "library.kind"]);
+        assert_eq!(
+            fields.attribute("cloud.region"),
+            Some(&Value::StringValue("westus".to_string()))
+        );
+    }
+}
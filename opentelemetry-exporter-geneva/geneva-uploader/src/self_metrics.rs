@@ -0,0 +1,418 @@
+//! Internal metrics about encoding, uploads and auth tokens, recorded through the `Meter`
+//! supplied in `GenevaClientConfig::meter`.
+
+use opentelemetry::metrics::{Counter, Histogram, Meter};
+use opentelemetry::KeyValue;
+use std::time::Duration;
+
+/// Records encoded by the client, by `signal`.
+pub(crate) const RECORDS_ENCODED: &str = "geneva.records.encoded";
+/// Batches produced by the encoder, by `signal`.
+pub(crate) const BATCHES_ENCODED: &str = "geneva.batches.encoded";
+/// Size of the encoded batches before compression, by `signal`.
+pub(crate) const UNCOMPRESSED_BYTES: &str = "geneva.batches.uncompressed_size";
+/// Size of the encoded batches after LZ4 compression, by `signal`.
+pub(crate) const COMPRESSED_BYTES: &str = "geneva.batches.compressed_size";
+/// Batches handed to the uploader, by final `outcome` once retries are exhausted.
+pub(crate) const BATCHES_UPLOADED: &str = "geneva.upload.batches";
+/// Duration of each upload attempt, by `outcome`.
+pub(crate) const UPLOAD_DURATION: &str = "geneva.upload.duration";
+/// Failed upload attempts, by `status`: the HTTP status code, or the kind of failure when the
+/// gateway did not answer.
+pub(crate) const UPLOAD_FAILURES: &str = "geneva.upload.failures";
+/// Batches discarded without being uploaded, by `reason`: `queue_full` (an exporter upload
+/// queue overflowed) or `upload_failed` (the upload failed and the batch was not spooled).
+pub(crate) const BATCHES_DROPPED: &str = "geneva.batches.dropped";
+/// Records in the discarded batches, by `reason`.
+pub(crate) const RECORDS_DROPPED: &str = "geneva.records.dropped";
+/// Ingestion token lookups, by `result`: `hit`, `stale` (served while being renewed) or
+/// `miss`.
+pub(crate) const TOKEN_CACHE_LOOKUPS: &str = "geneva.token.cache.lookups";
+/// Duration of token refreshes against the config service, by `outcome`.
+pub(crate) const TOKEN_REFRESH_DURATION: &str = "geneva.token.refresh.duration";
+
+/// Bucket boundaries, in seconds, of the duration histograms. Uploads and token refreshes
+/// mostly take tens to hundreds of milliseconds, which the SDK default boundaries (meant for
+/// milliseconds) would put in the first bucket.
+const DURATION_BOUNDARIES: [f64; 14] = [
+    0.005, 0.01, 0.025, 0.05, 0.075, 0.1, 0.25, 0.5, 0.75, 1.0, 2.5, 5.0, 7.5, 10.0,
+];
+
+/// Kind of telemetry being encoded
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Signal {
+    Logs,
+    Spans,
+    Metrics,
+}
+
+impl Signal {
+    fn as_str(self) -> &'static str {
+        match self {
+            Signal::Logs => "logs",
+            Signal::Spans => "spans",
+            Signal::Metrics => "metrics",
+        }
+    }
+}
+
+/// Instruments created once from the caller's `Meter` and shared by the encoder, the uploader
+/// and the config client.
+#[derive(Debug)]
+pub(crate) struct SelfMetrics {
+    records_encoded: Counter<u64>,
+    batches_encoded: Counter<u64>,
+    uncompressed_bytes: Counter<u64>,
+    compressed_bytes: Counter<u64>,
+    batches_uploaded: Counter<u64>,
+    upload_duration: Histogram<f64>,
+    upload_failures: Counter<u64>,
+    batches_dropped: Counter<u64>,
+    records_dropped: Counter<u64>,
+    token_cache_lookups: Counter<u64>,
+    token_refresh_duration: Histogram<f64>,
+}
+
+impl SelfMetrics {
+    pub(crate) fn new(meter: &Meter) -> Self {
+        SelfMetrics {
+            records_encoded: meter
+                .u64_counter(RECORDS_ENCODED)
+                .with_description("Number of records encoded into Geneva batches.")
+                .with_unit("{record}")
+                .build(),
+            batches_encoded: meter
+                .u64_counter(BATCHES_ENCODED)
+                .with_description("Number of Geneva batches produced by the encoder.")
+                .with_unit("{batch}")
+                .build(),
+            uncompressed_bytes: meter
+                .u64_counter(UNCOMPRESSED_BYTES)
+                .with_description("Size of encoded Geneva batches before compression.")
+                .with_unit("By")
+                .build(),
+            compressed_bytes: meter
+                .u64_counter(COMPRESSED_BYTES)
+                .with_description("Size of encoded Geneva batches after compression.")
+                .with_unit("By")
+                .build(),
+            batches_uploaded: meter
+                .u64_counter(BATCHES_UPLOADED)
+                .with_description("Number of Geneva batches uploaded, by final outcome.")
+                .with_unit("{batch}")
+                .build(),
+            upload_duration: meter
+                .f64_histogram(UPLOAD_DURATION)
+                .with_description("Duration of Geneva upload attempts.")
+                .with_unit("s")
+                .with_boundaries(DURATION_BOUNDARIES.to_vec())
+                .build(),
+            upload_failures: meter
+                .u64_counter(UPLOAD_FAILURES)
+                .with_description("Number of failed Geneva upload attempts.")
+                .with_unit("{attempt}")
+                .build(),
+            batches_dropped: meter
+                .u64_counter(BATCHES_DROPPED)
+                .with_description("Number of Geneva batches discarded without being uploaded.")
+                .with_unit("{batch}")
+                .build(),
+            records_dropped: meter
+                .u64_counter(RECORDS_DROPPED)
+                .with_description("Number of records in discarded Geneva batches.")
+                .with_unit("{record}")
+                .build(),
+            token_cache_lookups: meter
+                .u64_counter(TOKEN_CACHE_LOOKUPS)
+                .with_description("Number of ingestion token lookups, by cache result.")
+                .with_unit("{lookup}")
+                .build(),
+            token_refresh_duration: meter
+                .f64_histogram(TOKEN_REFRESH_DURATION)
+                .with_description("Duration of ingestion token refreshes.")
+                .with_unit("s")
+                .with_boundaries(DURATION_BOUNDARIES.to_vec())
+                .build(),
+        }
+    }
+
+    /// Records the batches produced from `records` records of `signal`
+    pub(crate) fn record_encoded(
+        &self,
+        signal: Signal,
+        records: usize,
+        batches: usize,
+        uncompressed_bytes: usize,
+        compressed_bytes: usize,
+    ) {
+        let attributes = [KeyValue::new("signal", signal.as_str())];
+        self.records_encoded.add(records as u64, &attributes);
+        self.batches_encoded.add(batches as u64, &attributes);
+        self.uncompressed_bytes
+            .add(uncompressed_bytes as u64, &attributes);
+        self.compressed_bytes
+            .add(compressed_bytes as u64, &attributes);
+    }
+
+    /// Records one upload attempt. `failure` is the HTTP status or kind of failure of a failed
+    /// attempt.
+    pub(crate) fn record_upload_attempt(&self, duration: Duration, failure: Option<String>) {
+        let outcome = if failure.is_some() {
+            "failure"
+        } else {
+            "success"
+        };
+        self.upload_duration
+            .record(duration.as_secs_f64(), &[KeyValue::new("outcome", outcome)]);
+        if let Some(status) = failure {
+            self.upload_failures
+                .add(1, &[KeyValue::new("status", status)]);
+        }
+    }
+
+    /// Records the final outcome of a batch upload, after retries
+    pub(crate) fn record_upload(&self, success: bool) {
+        let outcome = if success { "success" } else { "failure" };
+        self.batches_uploaded
+            .add(1, &[KeyValue::new("outcome", outcome)]);
+    }
+
+    /// Records a batch of `records` records discarded for `reason`
+    pub(crate) fn record_dropped(&self, reason: &'static str, records: usize) {
+        let attributes = [KeyValue::new("reason", reason)];
+        self.batches_dropped.add(1, &attributes);
+        self.records_dropped.add(records as u64, &attributes);
+    }
+
+    pub(crate) fn record_token_lookup(&self, result: &'static str) {
+        self.token_cache_lookups
+            .add(1, &[KeyValue::new("result", result)]);
+    }
+
+    pub(crate) fn record_token_refresh(&self, duration: Duration, success: bool) {
+        let outcome = if success { "success" } else { "failure" };
+        self.token_refresh_duration
+            .record(duration.as_secs_f64(), &[KeyValue::new("outcome", outcome)]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::payload_encoder::batch_limits::BatchLimits;
+    use crate::payload_encoder::lz4_chunked_compression::lz4_chunked_decompression;
+    use crate::payload_encoder::otlp_encoder::OtlpEncoder;
+    use opentelemetry::metrics::MeterProvider;
+    use opentelemetry_proto::tonic::logs::v1::LogRecord;
+    use opentelemetry_sdk::metrics::data::{AggregatedMetrics, MetricData, ResourceMetrics};
+    use opentelemetry_sdk::metrics::{InMemoryMetricExporter, PeriodicReader, SdkMeterProvider};
+    use std::sync::Arc;
+
+    struct TestMeter {
+        provider: SdkMeterProvider,
+        exporter: InMemoryMetricExporter,
+    }
+
+    impl TestMeter {
+        fn new() -> Self {
+            let exporter = InMemoryMetricExporter::default();
+            let reader = PeriodicReader::builder(exporter.clone()).build();
+            let provider = SdkMeterProvider::builder().with_reader(reader).build();
+            TestMeter { provider, exporter }
+        }
+
+        fn meter(&self) -> Meter {
+            self.provider.meter("geneva-uploader-test")
+        }
+
+        /// Latest cumulative export
+        fn collect(&self) -> ResourceMetrics {
+            self.provider.force_flush().unwrap();
+            self.exporter.get_finished_metrics().unwrap().pop().unwrap()
+        }
+    }
+
+    /// Value of counter `name` for the data point carrying `attribute`
+    fn counter(metrics: &ResourceMetrics, name: &str, attribute: (&str, &str)) -> u64 {
+        metrics
+            .scope_metrics()
+            .flat_map(|scope| scope.metrics())
+            .filter(|metric| metric.name() == name)
+            .filter_map(|metric| match metric.data() {
+                AggregatedMetrics::U64(MetricData::Sum(sum)) => Some(
+                    sum.data_points()
+                        .filter(|point| {
+                            point.attributes().any(|kv| {
+                                kv.key.as_str() == attribute.0 && kv.value.as_str() == attribute.1
+                            })
+                        })
+                        .map(|point| point.value())
+                        .sum::<u64>(),
+                ),
+                _ => None,
+            })
+            .sum()
+    }
+
+    /// Number of measurements of histogram `name` for the data point carrying `attribute`
+    #[cfg(feature = "mock_auth")]
+    fn histogram_count(metrics: &ResourceMetrics, name: &str, attribute: (&str, &str)) -> u64 {
+        metrics
+            .scope_metrics()
+            .flat_map(|scope| scope.metrics())
+            .filter(|metric| metric.name() == name)
+            .filter_map(|metric| match metric.data() {
+                AggregatedMetrics::F64(MetricData::Histogram(histogram)) => Some(
+                    histogram
+                        .data_points()
+                        .filter(|point| {
+                            point.attributes().any(|kv| {
+                                kv.key.as_str() == attribute.0 && kv.value.as_str() == attribute.1
+                            })
+                        })
+                        .map(|point| {
+                            assert_eq!(point.bounds().collect::<Vec<_>>(), DURATION_BOUNDARIES);
+                            point.count()
+                        })
+                        .sum::<u64>(),
+                ),
+                _ => None,
+            })
+            .sum()
+    }
+
+    #[test]
+    fn test_encode_metrics() {
+        let test_meter = TestMeter::new();
+        let metrics = Arc::new(SelfMetrics::new(&test_meter.meter()));
+        let encoder = OtlpEncoder::new().with_metrics(Some(metrics.clone()));
+        let encoder_limited = OtlpEncoder::with_config(
+            Default::default(),
+            Default::default(),
+            Default::default(),
+            BatchLimits {
+                max_events: 2,
+                ..BatchLimits::default()
+            },
+        )
+        .with_metrics(Some(metrics));
+
+        let logs: Vec<LogRecord> = (0..3)
+            .map(|i| LogRecord {
+                event_name: format!("Event{}", i % 2),
+                severity_number: 9,
+                ..Default::default()
+            })
+            .collect();
+        let batches = encoder
+            .encode_log_batch(logs.iter(), "namespace=test")
+            .unwrap();
+        let same_name = vec![LogRecord::default(); 5];
+        let split = encoder_limited
+            .encode_log_batch(same_name.iter(), "namespace=test")
+            .unwrap();
+        assert_eq!(split.len(), 3);
+
+        let all: Vec<_> = batches.iter().chain(&split).collect();
+        let compressed: usize = all.iter().map(|b| b.data.len()).sum();
+        let uncompressed: usize = all
+            .iter()
+            .map(|b| lz4_chunked_decompression(&b.data).unwrap().len())
+            .sum();
+
+        let collected = test_meter.collect();
+        let logs = ("signal", "logs");
+        assert_eq!(counter(&collected, RECORDS_ENCODED, logs), 8);
+        assert_eq!(counter(&collected, BATCHES_ENCODED, logs), 5);
+        assert_eq!(
+            counter(&collected, COMPRESSED_BYTES, logs),
+            compressed as u64
+        );
+        assert_eq!(
+            counter(&collected, UNCOMPRESSED_BYTES, logs),
+            uncompressed as u64
+        );
+        assert_eq!(counter(&collected, RECORDS_ENCODED, ("signal", "spans")), 0);
+    }
+
+    #[cfg(feature = "mock_auth")]
+    #[tokio::test]
+    async fn test_upload_and_token_metrics() {
+        use crate::client::GenevaClient;
+        use crate::test_support::{client_config, logs, mount_config_service};
+        use crate::RetryPolicy;
+        use wiremock::matchers::method;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        mount_config_service(&server).await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(400))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(202).set_body_string(r#"{"ticket":"accepted"}"#))
+            .mount(&server)
+            .await;
+
+        let test_meter = TestMeter::new();
+        let mut cfg = client_config(&server.uri());
+        cfg.meter = Some(test_meter.meter());
+        cfg.retry_policy = RetryPolicy {
+            initial_backoff: Duration::from_millis(1),
+            jitter: 0.0,
+            ..RetryPolicy::default()
+        };
+        let client = GenevaClient::new(cfg).unwrap();
+
+        // 503 then 400: the second failure is permanent
+        let batches = client.encode_and_compress_logs(&logs("First")).unwrap();
+        assert!(client.upload_batch(&batches[0]).await.is_err());
+        let batches = client.encode_and_compress_logs(&logs("Second")).unwrap();
+        client.upload_batch(&batches[0]).await.unwrap();
+
+        let collected = test_meter.collect();
+        assert_eq!(counter(&collected, RECORDS_ENCODED, ("signal", "logs")), 2);
+        assert_eq!(
+            counter(&collected, BATCHES_UPLOADED, ("outcome", "success")),
+            1
+        );
+        assert_eq!(
+            counter(&collected, BATCHES_UPLOADED, ("outcome", "failure")),
+            1
+        );
+        assert_eq!(counter(&collected, UPLOAD_FAILURES, ("status", "503")), 1);
+        assert_eq!(counter(&collected, UPLOAD_FAILURES, ("status", "400")), 1);
+        // Without a spool, the rejected batch is lost
+        let upload_failed = ("reason", "upload_failed");
+        assert_eq!(counter(&collected, BATCHES_DROPPED, upload_failed), 1);
+        assert_eq!(counter(&collected, RECORDS_DROPPED, upload_failed), 1);
+        assert_eq!(
+            histogram_count(&collected, UPLOAD_DURATION, ("outcome", "failure")),
+            2
+        );
+        assert_eq!(
+            histogram_count(&collected, UPLOAD_DURATION, ("outcome", "success")),
+            1
+        );
+        // One refresh, then the cached token serves the remaining attempts
+        assert_eq!(
+            counter(&collected, TOKEN_CACHE_LOOKUPS, ("result", "miss")),
+            1
+        );
+        assert_eq!(
+            counter(&collected, TOKEN_CACHE_LOOKUPS, ("result", "hit")),
+            2
+        );
+        assert_eq!(
+            histogram_count(&collected, TOKEN_REFRESH_DURATION, ("outcome", "success")),
+            1
+        );
+    }
+}
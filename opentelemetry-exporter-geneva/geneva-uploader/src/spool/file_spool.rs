@@ -0,0 +1,532 @@
+use crate::client::EncodedBatch;
+use crate::payload_encoder::central_blob::BatchMetadata;
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const SPOOL_FILE_EXTENSION: &str = "batch";
+const SPOOL_TMP_EXTENSION: &str = "tmp";
+pub(crate) const SPOOL_MAGIC: &[u8; 4] = b"GSPL";
+const SPOOL_FORMAT_VERSION: u8 = 1;
+// magic (4) + version (1) + written_at (8) + start_time (8) + end_time (8) + event_count (4)
+const SPOOL_HEADER_SIZE: usize = 4 + 1 + 8 + 8 + 8 + 4;
+
+/// Configuration for the on-disk spool of batches that failed to upload.
+///
+/// Batches are persisted as already-encoded (LZ4 compressed) payloads together with their
+/// `BatchMetadata`, so replaying them does not require re-encoding and survives process restarts.
+#[derive(Clone, Debug)]
+pub struct SpoolConfig {
+    /// Directory holding the spooled batch files. Created if it does not exist.
+    pub directory: PathBuf,
+    /// Upper bound on the total size of spooled batch files, in bytes.
+    /// When a new batch does not fit, the oldest spooled batches are evicted first.
+    pub max_size_bytes: u64,
+    /// Spooled batches older than this are discarded instead of being replayed.
+    pub ttl: Duration,
+    /// Interval between background replay attempts.
+    pub replay_interval: Duration,
+}
+
+impl SpoolConfig {
+    /// Creates a spool configuration for `directory` with default limits
+    /// (100 MiB size cap, 24 hour TTL, replay every 30 seconds).
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        Self {
+            directory: directory.into(),
+            max_size_bytes: 100 * 1024 * 1024,
+            ttl: Duration::from_secs(24 * 60 * 60),
+            replay_interval: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Index entry for a batch file present in the spool directory.
+#[derive(Debug, Clone)]
+pub(crate) struct SpoolEntry {
+    pub(crate) seq: u64,
+    pub(crate) digest: String,
+    pub(crate) path: PathBuf,
+    pub(crate) size: u64,
+}
+
+#[derive(Default)]
+struct SpoolIndex {
+    // Ordered by sequence number, which is the order batches were spooled in.
+    entries: BTreeMap<u64, SpoolEntry>,
+    digests: HashSet<String>,
+    total_size: u64,
+}
+
+impl SpoolIndex {
+    fn insert(&mut self, entry: SpoolEntry) {
+        self.total_size += entry.size;
+        self.digests.insert(entry.digest.clone());
+        self.entries.insert(entry.seq, entry);
+    }
+
+    fn remove(&mut self, seq: u64) -> Option<SpoolEntry> {
+        let entry = self.entries.remove(&seq)?;
+        self.total_size = self.total_size.saturating_sub(entry.size);
+        self.digests.remove(&entry.digest);
+        Some(entry)
+    }
+}
+
+/// Durable FIFO spool of encoded batches backed by one file per batch.
+///
+/// File names are `{seq:020}-{digest}.batch`, so sorting the directory listing yields the
+/// spooling order across restarts, and the content digest allows skipping duplicate batches.
+/// Files are written to a temporary name and renamed into place, so a crash never leaves a
+/// partially written `.batch` file behind.
+pub(crate) struct FileSpool {
+    config: SpoolConfig,
+    next_seq: AtomicU64,
+    index: Mutex<SpoolIndex>,
+    // Set once the background replay task has been spawned for this spool.
+    replay_started: AtomicBool,
+    // Prevents concurrent replay passes from uploading the same batch twice.
+    replay_in_progress: AtomicBool,
+}
+
+impl std::fmt::Debug for FileSpool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FileSpool")
+            .field("config", &self.config)
+            .field("next_seq", &self.next_seq)
+            .finish()
+    }
+}
+
+impl FileSpool {
+    /// Opens (or creates) the spool directory and indexes the batches already present in it.
+    pub(crate) fn open(config: SpoolConfig) -> io::Result<Self> {
+        fs::create_dir_all(&config.directory)?;
+
+        let mut index = SpoolIndex::default();
+        let mut max_seq = 0u64;
+        for dir_entry in fs::read_dir(&config.directory)? {
+            let path = dir_entry?.path();
+            match path.extension().and_then(|e| e.to_str()) {
+                Some(SPOOL_TMP_EXTENSION) => {
+                    // Leftover from an interrupted write; never renamed into place.
+                    let _ = fs::remove_file(&path);
+                }
+                Some(SPOOL_FILE_EXTENSION) => {
+                    if let Some((seq, digest)) = parse_file_name(&path) {
+                        let size = fs::metadata(&path)?.len();
+                        max_seq = max_seq.max(seq);
+                        index.insert(SpoolEntry {
+                            seq,
+                            digest,
+                            path,
+                            size,
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(Self {
+            config,
+            next_seq: AtomicU64::new(max_seq + 1),
+            index: Mutex::new(index),
+            replay_started: AtomicBool::new(false),
+            replay_in_progress: AtomicBool::new(false),
+        })
+    }
+
+    pub(crate) fn config(&self) -> &SpoolConfig {
+        &self.config
+    }
+
+    /// Persists a batch. Returns `Ok(false)` if an identical batch is already spooled.
+    ///
+    /// If the spool would exceed `max_size_bytes`, the oldest batches are evicted first.
+    pub(crate) fn store(&self, batch: &EncodedBatch) -> io::Result<bool> {
+        let digest = batch_digest(batch);
+        let bytes = serialize_batch(batch, unix_now_secs());
+        let size = bytes.len() as u64;
+        if size > self.config.max_size_bytes {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "batch of {size} bytes exceeds spool size cap of {} bytes",
+                    self.config.max_size_bytes
+                ),
+            ));
+        }
+
+        let mut index = self.lock_index()?;
+        if index.digests.contains(&digest) {
+            return Ok(false);
+        }
+
+        while index.total_size + size > self.config.max_size_bytes {
+            let Some(oldest) = index.entries.keys().next().copied() else {
+                break;
+            };
+            if let Some(evicted) = index.remove(oldest) {
+                let _ = fs::remove_file(&evicted.path);
+            }
+        }
+
+        let seq = self.next_seq.fetch_add(1, Ordering::Relaxed);
+        let path = self
+            .config
+            .directory
+            .join(format!("{seq:020}-{digest}.{SPOOL_FILE_EXTENSION}"));
+        let tmp_path = path.with_extension(SPOOL_TMP_EXTENSION);
+        {
+            let mut file = fs::File::create(&tmp_path)?;
+            file.write_all(&bytes)?;
+            file.sync_all()?;
+        }
+        fs::rename(&tmp_path, &path)?;
+
+        index.insert(SpoolEntry {
+            seq,
+            digest,
+            path,
+            size,
+        });
+        Ok(true)
+    }
+
+    /// Returns the currently spooled entries, oldest first.
+    pub(crate) fn pending(&self) -> Vec<SpoolEntry> {
+        match self.index.lock() {
+            Ok(index) => index.entries.values().cloned().collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /// Reads a spooled batch back. Returns `Ok(None)` and removes the file if the batch
+    /// has outlived the configured TTL.
+    pub(crate) fn load(&self, entry: &SpoolEntry) -> io::Result<Option<EncodedBatch>> {
+        let bytes = fs::read(&entry.path)?;
+        let (written_at, batch) = deserialize_batch(&bytes)?;
+        let age = unix_now_secs().saturating_sub(written_at);
+        if age > self.config.ttl.as_secs() {
+            self.remove(entry.seq);
+            return Ok(None);
+        }
+        Ok(Some(batch))
+    }
+
+    /// Removes a batch from the spool, typically after it has been uploaded.
+    pub(crate) fn remove(&self, seq: u64) {
+        if let Ok(mut index) = self.index.lock() {
+            if let Some(entry) = index.remove(seq) {
+                let _ = fs::remove_file(&entry.path);
+            }
+        }
+    }
+
+    /// Total size in bytes of the spooled batch files.
+    #[allow(dead_code)]
+    pub(crate) fn total_size(&self) -> u64 {
+        self.index.lock().map(|i| i.total_size).unwrap_or(0)
+    }
+
+    /// Marks the background replay task as started. Returns `true` for the first caller only.
+    pub(crate) fn mark_replay_started(&self) -> bool {
+        !self.replay_started.swap(true, Ordering::AcqRel)
+    }
+
+    /// Acquires the replay guard. Returns `None` if another replay pass is already running.
+    pub(crate) fn begin_replay(&self) -> Option<ReplayGuard<'_>> {
+        if self.replay_in_progress.swap(true, Ordering::AcqRel) {
+            None
+        } else {
+            Some(ReplayGuard { spool: self })
+        }
+    }
+
+    fn lock_index(&self) -> io::Result<std::sync::MutexGuard<'_, SpoolIndex>> {
+        self.index
+            .lock()
+            .map_err(|_| io::Error::other("spool index lock poisoned"))
+    }
+}
+
+/// Releases the replay guard when dropped.
+pub(crate) struct ReplayGuard<'a> {
+    spool: &'a FileSpool,
+}
+
+impl Drop for ReplayGuard<'_> {
+    fn drop(&mut self) {
+        self.spool
+            .replay_in_progress
+            .store(false, Ordering::Release);
+    }
+}
+
+fn parse_file_name(path: &Path) -> Option<(u64, String)> {
+    let stem = path.file_stem()?.to_str()?;
+    let (seq, digest) = stem.split_once('-')?;
+    Some((seq.parse().ok()?, digest.to_string()))
+}
+
+fn unix_now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Content digest used to de-duplicate spooled batches.
+fn batch_digest(batch: &EncodedBatch) -> String {
+    let mut ctx = md5::Context::new();
+    ctx.consume(batch.event_name.as_bytes());
+    ctx.consume(batch.metadata.schema_ids.as_bytes());
+    ctx.consume(batch.metadata.start_time.to_le_bytes());
+    ctx.consume(batch.metadata.end_time.to_le_bytes());
+    ctx.consume(&batch.data);
+    format!("{:x}", ctx.finalize())
+}
+
+/// Spool file layout (all integers little-endian):
+/// magic `GSPL` | version `u8` | written_at `u64` (unix secs) | start_time `u64` | end_time `u64`
+/// | event_count `u32` | event_name (`u32` len + UTF-8) | schema_ids (`u32` len + UTF-8) | data (`u32` len + bytes)
+pub(crate) fn serialize_batch(batch: &EncodedBatch, written_at: u64) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(
+        SPOOL_HEADER_SIZE
+            + 12
+            + batch.event_name.len()
+            + batch.metadata.schema_ids.len()
+            + batch.data.len(),
+    );
+    buf.extend_from_slice(SPOOL_MAGIC);
+    buf.push(SPOOL_FORMAT_VERSION);
+    buf.extend_from_slice(&written_at.to_le_bytes());
+    buf.extend_from_slice(&batch.metadata.start_time.to_le_bytes());
+    buf.extend_from_slice(&batch.metadata.end_time.to_le_bytes());
+    buf.extend_from_slice(&(batch.metadata.event_count as u32).to_le_bytes());
+    for field in [
+        batch.event_name.as_bytes(),
+        batch.metadata.schema_ids.as_bytes(),
+        batch.data.as_slice(),
+    ] {
+        buf.extend_from_slice(&(field.len() as u32).to_le_bytes());
+        buf.extend_from_slice(field);
+    }
+    buf
+}
+
+pub(crate) fn deserialize_batch(bytes: &[u8]) -> io::Result<(u64, EncodedBatch)> {
+    fn invalid(msg: &str) -> io::Error {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("corrupt spool file: {msg}"),
+        )
+    }
+
+    if bytes.len() < SPOOL_HEADER_SIZE || &bytes[..4] != SPOOL_MAGIC {
+        return Err(invalid("bad header"));
+    }
+    if bytes[4] != SPOOL_FORMAT_VERSION {
+        return Err(invalid("unsupported version"));
+    }
+    let read_u64 =
+        |offset: usize| u64::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap());
+    let written_at = read_u64(5);
+    let start_time = read_u64(13);
+    let end_time = read_u64(21);
+    let event_count = u32::from_le_bytes(bytes[29..33].try_into().unwrap()) as usize;
+
+    let mut offset = SPOOL_HEADER_SIZE;
+    let mut next_field = || -> io::Result<&[u8]> {
+        let len_bytes = bytes
+            .get(offset..offset + 4)
+            .ok_or_else(|| invalid("truncated length"))?;
+        let len = u32::from_le_bytes(len_bytes.try_into().unwrap()) as usize;
+        let field = bytes
+            .get(offset + 4..offset + 4 + len)
+            .ok_or_else(|| invalid("truncated field"))?;
+        offset += 4 + len;
+        Ok(field)
+    };
+    let event_name =
+        String::from_utf8(next_field()?.to_vec()).map_err(|_| invalid("event name"))?;
+    let schema_ids =
+        String::from_utf8(next_field()?.to_vec()).map_err(|_| invalid("schema ids"))?;
+    let data = next_field()?.to_vec();
+
+    Ok((
+        written_at,
+        EncodedBatch {
+            event_name,
+            data,
+            metadata: BatchMetadata {
+                start_time,
+                end_time,
+                schema_ids,
+                event_count,
+            },
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn batch(event_name: &str, data: &[u8]) -> EncodedBatch {
+        EncodedBatch {
+            event_name: event_name.to_string(),
+            data: data.to_vec(),
+            metadata: BatchMetadata {
+                start_time: 1_700_000_000_000_000_000,
+                end_time: 1_700_000_001_000_000_000,
+                schema_ids: "075bcd15e5b2ed60f26e66085ac2b2e8".to_string(),
+                event_count: 3,
+            },
+        }
+    }
+
+    fn config(dir: &Path) -> SpoolConfig {
+        SpoolConfig::new(dir)
+    }
+
+    #[test]
+    fn test_store_and_load_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let spool = FileSpool::open(config(dir.path())).unwrap();
+
+        let original = batch("Log", b"compressed-bytes");
+        assert!(spool.store(&original).unwrap());
+
+        let pending = spool.pending();
+        assert_eq!(pending.len(), 1);
+        let loaded = spool.load(&pending[0]).unwrap().unwrap();
+        assert_eq!(loaded.event_name, original.event_name);
+        assert_eq!(loaded.data, original.data);
+        assert_eq!(loaded.metadata.start_time, original.metadata.start_time);
+        assert_eq!(loaded.metadata.end_time, original.metadata.end_time);
+        assert_eq!(loaded.metadata.schema_ids, original.metadata.schema_ids);
+        assert_eq!(loaded.metadata.event_count, original.metadata.event_count);
+    }
+
+    #[test]
+    fn test_duplicate_batches_are_stored_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let spool = FileSpool::open(config(dir.path())).unwrap();
+
+        assert!(spool.store(&batch("Log", b"same")).unwrap());
+        assert!(!spool.store(&batch("Log", b"same")).unwrap());
+        assert!(spool.store(&batch("Log", b"different")).unwrap());
+        assert_eq!(spool.pending().len(), 2);
+    }
+
+    #[test]
+    fn test_order_and_index_survive_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        {
+            let spool = FileSpool::open(config(dir.path())).unwrap();
+            for i in 0..3u8 {
+                spool.store(&batch(&format!("Event{i}"), &[i; 8])).unwrap();
+            }
+        }
+
+        let spool = FileSpool::open(config(dir.path())).unwrap();
+        let names: Vec<String> = spool
+            .pending()
+            .iter()
+            .map(|e| spool.load(e).unwrap().unwrap().event_name)
+            .collect();
+        assert_eq!(names, vec!["Event0", "Event1", "Event2"]);
+
+        // New batches continue the sequence after the recovered ones
+        spool.store(&batch("Event3", &[3; 8])).unwrap();
+        let last = spool.pending().pop().unwrap();
+        assert_eq!(spool.load(&last).unwrap().unwrap().event_name, "Event3");
+
+        // Dedup state is recovered as well
+        assert!(!spool.store(&batch("Event0", &[0; 8])).unwrap());
+    }
+
+    #[test]
+    fn test_size_cap_evicts_oldest() {
+        let dir = tempfile::tempdir().unwrap();
+        let one_batch_size = serialize_batch(&batch("Log", &[0; 100]), 0).len() as u64;
+        let mut cfg = config(dir.path());
+        cfg.max_size_bytes = one_batch_size * 2;
+        let spool = FileSpool::open(cfg).unwrap();
+
+        for i in 0..3u8 {
+            spool.store(&batch("Log", &[i; 100])).unwrap();
+        }
+
+        let pending = spool.pending();
+        assert_eq!(pending.len(), 2);
+        assert!(spool.total_size() <= one_batch_size * 2);
+        let first = spool.load(&pending[0]).unwrap().unwrap();
+        assert_eq!(first.data, vec![1; 100], "oldest batch should be evicted");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn test_batch_larger_than_cap_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut cfg = config(dir.path());
+        cfg.max_size_bytes = 16;
+        let spool = FileSpool::open(cfg).unwrap();
+
+        let err = spool.store(&batch("Log", &[0; 100])).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert!(spool.pending().is_empty());
+    }
+
+    #[test]
+    fn test_expired_batches_are_dropped_on_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut cfg = config(dir.path());
+        cfg.ttl = Duration::from_secs(60);
+
+        // Simulate a batch spooled by a previous process two minutes ago
+        let stale = serialize_batch(&batch("Log", b"old"), unix_now_secs() - 120);
+        let path = dir
+            .path()
+            .join(format!("{:020}-{}.batch", 1, "0".repeat(32)));
+        fs::write(&path, stale).unwrap();
+
+        let spool = FileSpool::open(cfg).unwrap();
+        let pending = spool.pending();
+        assert_eq!(pending.len(), 1);
+        assert!(spool.load(&pending[0]).unwrap().is_none());
+        assert!(spool.pending().is_empty());
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_leftover_temp_files_are_cleaned_up() {
+        let dir = tempfile::tempdir().unwrap();
+        let tmp = dir.path().join("00000000000000000001-abc.tmp");
+        fs::write(&tmp, b"partial").unwrap();
+
+        let spool = FileSpool::open(config(dir.path())).unwrap();
+        assert!(spool.pending().is_empty());
+        assert!(!tmp.exists());
+    }
+
+    #[test]
+    fn test_corrupt_file_is_reported() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir
+            .path()
+            .join(format!("{:020}-{}.batch", 1, "0".repeat(32)));
+        fs::write(&path, b"not a spool file").unwrap();
+
+        let spool = FileSpool::open(config(dir.path())).unwrap();
+        let pending = spool.pending();
+        let err = spool.load(&pending[0]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,218 @@
+pub(crate) mod file_spool;
+
+use crate::ingestion_service::uploader::{GenevaUploader, GenevaUploaderError};
+use file_spool::FileSpool;
+use std::sync::{Arc, Weak};
+
+/// Replays spooled batches in the order they were spooled.
+///
+/// Stops at the first upload that fails with a retryable error so that ordering is preserved;
+/// the failed batch and everything after it stay on disk for the next pass. Batches the
+/// gateway rejects permanently (e.g. 400 for a malformed payload) would never succeed, so
+/// they are dropped and the pass continues. Expired or unreadable batches are dropped too.
+///
+/// # Returns
+/// * `Ok(n)` - number of batches uploaded and removed from the spool in this pass
+/// * `Err(e)` - the retryable upload error that stopped the pass
+pub(crate) async fn replay(
+    spool: &FileSpool,
+    uploader: &GenevaUploader,
+) -> Result<usize, GenevaUploaderError> {
+    let Some(_guard) = spool.begin_replay() else {
+        // Another pass is running; it will pick up everything we would have.
+        return Ok(0);
+    };
+
+    let mut replayed = 0;
+    for entry in spool.pending() {
+        let batch = match spool.load(&entry) {
+            Ok(Some(batch)) => batch,
+            Ok(None) => continue, // expired
+            Err(_) => {
+                spool.remove(entry.seq);
+                continue;
+            }
+        };
+        match uploader
+            .upload(batch.data, &batch.event_name, &batch.metadata)
+            .await
+        {
+            Ok(_) => replayed += 1,
+            Err(e) if e.is_retryable() => return Err(e),
+            // Permanently rejected, keeping it would block the batches behind it
+            Err(_) => {}
+        }
+        spool.remove(entry.seq);
+    }
+    Ok(replayed)
+}
+
+/// Spawns the background replay loop on the current Tokio runtime, once per spool.
+///
+/// The task holds only a weak reference to the spool and exits once every `GenevaClient`
+/// sharing it has been dropped. Does nothing when called outside a Tokio runtime.
+pub(crate) fn spawn_replay_task(spool: &Arc<FileSpool>, uploader: &Arc<GenevaUploader>) {
+    let Ok(handle) = tokio::runtime::Handle::try_current() else {
+        return;
+    };
+    if !spool.mark_replay_started() {
+        return;
+    }
+
+    let interval = spool.config().replay_interval;
+    let spool: Weak<FileSpool> = Arc::downgrade(spool);
+    let uploader = Arc::clone(uploader);
+    handle.spawn(async move {
+        while let Some(spool) = spool.upgrade() {
+            let _ = replay(&spool, &uploader).await;
+            drop(spool);
+            tokio::time::sleep(interval).await;
+        }
+    });
+}
+
+#[cfg(all(test, feature = "mock_auth"))]
+mod tests {
+    use crate::client::GenevaClient;
+    use crate::test_support::{client_config, ingested_events, logs, mount_config_service};
+    use crate::{GenevaError, RetryPolicy, SpoolConfig};
+    use wiremock::matchers::method;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_failed_upload_is_spooled_and_replayed_in_order() {
+        let server = MockServer::start().await;
+        mount_config_service(&server).await;
+        // Gateway is down for the first two uploads
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(2)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(202).set_body_string(r#"{"ticket":"accepted"}"#))
+            .mount(&server)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut spool_cfg = SpoolConfig::new(dir.path());
+        // Keep the background task out of the way; replay is triggered explicitly below.
+        spool_cfg.replay_interval = std::time::Duration::from_secs(3600);
+        let mut cfg = client_config(&server.uri());
+        cfg.spool = Some(spool_cfg);
+        cfg.retry_policy = RetryPolicy::no_retry();
+        let client = GenevaClient::new(cfg.clone()).unwrap();
+
+        for event in ["First", "Second"] {
+            let batches = client.encode_and_compress_logs(&logs(event)).unwrap();
+            let err = client.upload_batch(&batches[0]).await.unwrap_err();
+            assert!(
+                matches!(err, GenevaError::Upload { spooled: true, .. }),
+                "unexpected error: {err}"
+            );
+        }
+        assert_eq!(client.spooled_batches(), 2);
+        drop(client);
+
+        // A new client (e.g. after a restart) picks up the spooled batches.
+        let client = GenevaClient::new(cfg).unwrap();
+        assert_eq!(client.spooled_batches(), 2);
+        // The background task may already have drained the spool on startup.
+        let replayed = client.replay_spooled().await.unwrap();
+        assert!(replayed <= 2);
+        assert_eq!(client.spooled_batches(), 0);
+
+        let events = ingested_events(&server).await;
+        assert_eq!(events, vec!["First", "Second", "First", "Second"]);
+    }
+
+    #[tokio::test]
+    async fn test_spooling_failure_is_reported() {
+        let server = MockServer::start().await;
+        mount_config_service(&server).await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(503))
+            .mount(&server)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut spool_cfg = SpoolConfig::new(dir.path());
+        // Too small for any batch
+        spool_cfg.max_size_bytes = 1;
+        let mut cfg = client_config(&server.uri());
+        cfg.spool = Some(spool_cfg);
+        cfg.retry_policy = RetryPolicy::no_retry();
+        let client = GenevaClient::new(cfg).unwrap();
+
+        let batches = client.encode_and_compress_logs(&logs("Event")).unwrap();
+        let err = client.upload_batch(&batches[0]).await.unwrap_err();
+        match &err {
+            GenevaError::Upload {
+                spooled: false,
+                spool_error: Some(spool_error),
+                ..
+            } => assert!(spool_error.contains("exceeds spool size cap")),
+            other => panic!("unexpected error: {other}"),
+        }
+        assert!(err.to_string().contains(", spooling failed: batch of"));
+        assert_eq!(client.spooled_batches(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_permanent_rejections_are_not_spooled_or_kept() {
+        let server = MockServer::start().await;
+        mount_config_service(&server).await;
+        // Rejected for good on the first upload, then the gateway is down twice
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(400))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(2)
+            .mount(&server)
+            .await;
+        // The first replayed batch turns out to be rejected as well
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(400))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(202).set_body_string(r#"{"ticket":"accepted"}"#))
+            .mount(&server)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut spool_cfg = SpoolConfig::new(dir.path());
+        spool_cfg.replay_interval = std::time::Duration::from_secs(3600);
+        let mut cfg = client_config(&server.uri());
+        cfg.spool = Some(spool_cfg);
+        cfg.retry_policy = RetryPolicy::no_retry();
+        let client = GenevaClient::new(cfg).unwrap();
+
+        let batches = client.encode_and_compress_logs(&logs("Rejected")).unwrap();
+        let err = client.upload_batch(&batches[0]).await.unwrap_err();
+        assert!(
+            matches!(err, GenevaError::Upload { spooled: false, .. }),
+            "unexpected error: {err}"
+        );
+        assert_eq!(client.spooled_batches(), 0);
+
+        for event in ["First", "Second"] {
+            let batches = client.encode_and_compress_logs(&logs(event)).unwrap();
+            client.upload_batch(&batches[0]).await.unwrap_err();
+        }
+        assert_eq!(client.spooled_batches(), 2);
+
+        // The rejected batch is dropped and does not block the one behind it
+        assert_eq!(client.replay_spooled().await.unwrap(), 1);
+        assert_eq!(client.spooled_batches(), 0);
+        let events = ingested_events(&server).await;
+        assert_eq!(
+            events,
+            vec!["Rejected", "First", "Second", "First", "Second"]
+        );
+    }
+}
@@ -0,0 +1,92 @@
+//! Helpers for tests that run the client against a wiremock config service and gateway.
+
+use crate::client::GenevaClientConfig;
+use crate::config_service::client::mock_jwt;
+use crate::{
+    AttributeEncoding, AuthMethod, BatchLimits, ColumnMapping, ResourceMapping, RetryPolicy,
+    TokenRefreshConfig,
+};
+use opentelemetry_proto::tonic::logs::v1::{LogRecord, ResourceLogs, ScopeLogs};
+use wiremock::http::Method;
+use wiremock::matchers::method;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+pub(crate) async fn mount_config_service(server: &MockServer) {
+    let token = mock_jwt(
+        &server.uri(),
+        chrono::Utc::now() + chrono::Duration::hours(24),
+        "dummy",
+    );
+    Mock::given(method("GET"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "IngestionGatewayInfo": {
+                "Endpoint": server.uri(),
+                "AuthToken": token,
+                "AuthTokenExpiryTime": "2099-01-01T00:00:00Z"
+            },
+            "StorageAccountKeys": [{
+                "AccountMonikerName": "testdiagaccount",
+                "AccountGroupName": "testgroup",
+                "IsPrimaryMoniker": true
+            }],
+            "TagId": "test"
+        })))
+        .mount(server)
+        .await;
+}
+
+pub(crate) fn client_config(endpoint: &str) -> GenevaClientConfig {
+    GenevaClientConfig {
+        endpoint: endpoint.to_string(),
+        environment: "test".to_string(),
+        account: "test".to_string(),
+        namespace: "testns".to_string(),
+        region: "testregion".to_string(),
+        config_major_version: 1,
+        auth_method: AuthMethod::MockAuth,
+        msi_resource: None,
+        msi_endpoint: None,
+        tenant: "testtenant".to_string(),
+        role_name: "testrole".to_string(),
+        role_instance: "testinstance".to_string(),
+        spool: None,
+        retry_policy: RetryPolicy::default(),
+        attribute_encoding: AttributeEncoding::default(),
+        resource_mapping: ResourceMapping::default(),
+        column_mapping: ColumnMapping::default(),
+        batch_limits: BatchLimits::default(),
+        meter: None,
+        token_refresh: TokenRefreshConfig::default(),
+    }
+}
+
+pub(crate) fn logs(event_name: &str) -> Vec<ResourceLogs> {
+    vec![ResourceLogs {
+        scope_logs: vec![ScopeLogs {
+            log_records: vec![LogRecord {
+                observed_time_unix_nano: 1_700_000_000_000_000_000,
+                event_name: event_name.to_string(),
+                severity_number: 9,
+                ..Default::default()
+            }],
+            ..Default::default()
+        }],
+        ..Default::default()
+    }]
+}
+
+pub(crate) async fn ingested_events(server: &MockServer) -> Vec<String> {
+    server
+        .received_requests()
+        .await
+        .unwrap()
+        .into_iter()
+        .filter(|r| r.method == Method::Post)
+        .filter_map(|r| {
+            r.url
+                .query_pairs()
+                .find(|(k, _)| k == "event")
+                .map(|(_, v)| v.to_string())
+        })
+        .collect()
+}
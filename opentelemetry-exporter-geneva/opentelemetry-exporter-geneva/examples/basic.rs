@@ -48,21 +48,22 @@ async fn main() {
     let role_instance =
         env::var("GENEVA_ROLE_INSTANCE").unwrap_or_else(|_| "default-instance".to_string());
 
-    let config = GenevaClientConfig {
-        endpoint,
-        environment,
-        account,
-        namespace,
-        region,
-        config_major_version,
-        auth_method: AuthMethod::Certificate {
+    let config = GenevaClientConfig::builder()
+        .endpoint(endpoint)
+        .environment(environment)
+        .account(account)
+        .namespace(namespace)
+        .region(region)
+        .config_major_version(config_major_version)
+        .auth_method(AuthMethod::Certificate {
             path: cert_path,
             password: cert_password,
-        },
-        tenant,
-        role_name,
-        role_instance,
-    };
+        })
+        .tenant(tenant)
+        .role_name(role_name)
+        .role_instance(role_instance)
+        .build()
+        .expect("Invalid Geneva client configuration");
 
     let geneva_client = GenevaClient::new(config).expect("Failed to create GenevaClient");
 
@@ -0,0 +1,105 @@
+//! run with `$ cargo run --example metrics_basic
+
+use geneva_uploader::client::{GenevaClient, GenevaClientConfig};
+use geneva_uploader::AuthMethod;
+use opentelemetry::{global, KeyValue};
+use opentelemetry_exporter_geneva::GenevaMetricExporter;
+use opentelemetry_sdk::metrics::periodic_reader_with_async_runtime::PeriodicReader;
+use opentelemetry_sdk::metrics::SdkMeterProvider;
+use opentelemetry_sdk::{runtime, Resource};
+use std::env;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/*
+export GENEVA_ENDPOINT="https://abc.azurewebsites.net"
+export GENEVA_ENVIRONMENT="Test"
+export GENEVA_ACCOUNT="myaccount"
+export GENEVA_NAMESPACE="myns"
+export GENEVA_REGION="eastus"
+export GENEVA_CERT_PATH="/tmp/client.p12"
+export GENEVA_CERT_PASSWORD="password"
+export GENEVA_CONFIG_MAJOR_VERSION=2
+*/
+
+#[tokio::main]
+async fn main() {
+    let endpoint = env::var("GENEVA_ENDPOINT").expect("GENEVA_ENDPOINT is required");
+    let environment = env::var("GENEVA_ENVIRONMENT").expect("GENEVA_ENVIRONMENT is required");
+    let account = env::var("GENEVA_ACCOUNT").expect("GENEVA_ACCOUNT is required");
+    let namespace = env::var("GENEVA_NAMESPACE").expect("GENEVA_NAMESPACE is required");
+    let region = env::var("GENEVA_REGION").expect("GENEVA_REGION is required");
+    let cert_path =
+        PathBuf::from(env::var("GENEVA_CERT_PATH").expect("GENEVA_CERT_PATH is required"));
+    let cert_password = env::var("GENEVA_CERT_PASSWORD").expect("GENEVA_CERT_PASSWORD is required");
+    let config_major_version: u32 = env::var("GENEVA_CONFIG_MAJOR_VERSION")
+        .expect("GENEVA_CONFIG_MAJOR_VERSION is required")
+        .parse()
+        .expect("GENEVA_CONFIG_MAJOR_VERSION must be a u32");
+
+    let tenant = env::var("GENEVA_TENANT").unwrap_or_else(|_| "default-tenant".to_string());
+    let role_name = env::var("GENEVA_ROLE_NAME").unwrap_or_else(|_| "default-role".to_string());
+    let role_instance =
+        env::var("GENEVA_ROLE_INSTANCE").unwrap_or_else(|_| "default-instance".to_string());
+
+    let config = GenevaClientConfig::builder()
+        .endpoint(endpoint)
+        .environment(environment)
+        .account(account)
+        .namespace(namespace)
+        .region(region)
+        .config_major_version(config_major_version)
+        .auth_method(AuthMethod::Certificate {
+            path: cert_path,
+            password: cert_password,
+        })
+        .tenant(tenant)
+        .role_name(role_name)
+        .role_instance(role_instance)
+        .build()
+        .expect("Invalid Geneva client configuration");
+
+    let geneva_client = GenevaClient::new(config).expect("Failed to create GenevaClient");
+
+    // Create Geneva metric exporter (delta temporality by default)
+    let exporter = GenevaMetricExporter::new(geneva_client);
+
+    // Export collected metrics every 10 seconds on the Tokio runtime
+    let reader = PeriodicReader::builder(exporter, runtime::Tokio)
+        .with_interval(Duration::from_secs(10))
+        .build();
+
+    let meter_provider = SdkMeterProvider::builder()
+        .with_reader(reader)
+        .with_resource(
+            Resource::builder()
+                .with_service_name("geneva-metrics-exporter-example")
+                .build(),
+        )
+        .build();
+    global::set_meter_provider(meter_provider.clone());
+
+    let meter = global::meter("geneva-metrics-example");
+    let requests = meter.u64_counter("http.server.requests").build();
+    let latency = meter
+        .f64_histogram("http.server.duration")
+        .with_unit("ms")
+        .build();
+
+    println!("Recording example measurements...");
+    for i in 0..20u64 {
+        let attributes = [
+            KeyValue::new("http.method", "GET"),
+            KeyValue::new("http.status_code", if i % 5 == 0 { 500 } else { 200 }),
+        ];
+        requests.add(1, &attributes);
+        latency.record(10.0 + (i * 3) as f64, &attributes);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+
+    // Shutdown flushes the remaining measurements
+    meter_provider
+        .shutdown()
+        .expect("Failed to shutdown meter provider");
+    println!("Meter provider shut down successfully!");
+}
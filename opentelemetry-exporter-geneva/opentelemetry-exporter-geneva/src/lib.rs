@@ -4,7 +4,11 @@
 #![warn(missing_debug_implementations, missing_docs)]
 
 mod logs;
+mod metrics;
+mod queue;
 mod trace;
 
 pub use logs::*;
+pub use metrics::*;
+pub use queue::{OverflowPolicy, UploadQueueConfig, UploadQueueStats};
 pub use trace::*;
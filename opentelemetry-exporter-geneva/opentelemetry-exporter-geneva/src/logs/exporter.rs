@@ -5,7 +5,11 @@ use opentelemetry_proto::transform::common::tonic::ResourceAttributesWithSchema;
 use opentelemetry_proto::transform::logs::tonic::group_logs_by_resource_and_scope;
 use opentelemetry_sdk::error::{OTelSdkError, OTelSdkResult};
 use opentelemetry_sdk::logs::LogBatch;
+use std::io;
 use std::sync::{atomic, Arc};
+use std::time::Duration;
+
+use crate::queue::{UploadQueue, UploadQueueConfig, UploadQueueStats};
 
 /// An OpenTelemetry exporter that writes logs to Geneva exporter
 pub struct GenevaExporter {
@@ -13,6 +17,7 @@ pub struct GenevaExporter {
     _is_shutdown: atomic::AtomicBool,
     geneva_client: Arc<GenevaClient>,
     max_concurrent_uploads: usize,
+    upload_queue: Option<UploadQueue>,
 }
 
 // TODO - Add builder pattern for GenevaExporter to allow more flexible configuration
@@ -32,8 +37,33 @@ impl GenevaExporter {
             _is_shutdown: atomic::AtomicBool::new(false),
             geneva_client: Arc::new(geneva_client),
             max_concurrent_uploads,
+            upload_queue: None,
         }
     }
+
+    /// Upload batches from a bounded background queue instead of within `export`. Fails if
+    /// the upload thread cannot be started.
+    pub fn with_upload_queue(mut self, config: UploadQueueConfig) -> io::Result<Self> {
+        self.upload_queue = Some(UploadQueue::new(
+            Arc::clone(&self.geneva_client),
+            config,
+            self.max_concurrent_uploads,
+        )?);
+        Ok(self)
+    }
+
+    /// Queued and in-flight batches of the upload queue, if one is configured
+    pub fn upload_queue_stats(&self) -> Option<UploadQueueStats> {
+        self.upload_queue.as_ref().map(UploadQueue::stats)
+    }
+
+    /// Wait for queued and in-flight uploads to complete, up to the queue's `flush_timeout`.
+    /// `LogExporter` has no flush hook, so call this before reading what was uploaded.
+    pub fn force_flush(&self) -> OTelSdkResult {
+        self.upload_queue
+            .as_ref()
+            .map_or(Ok(()), UploadQueue::flush)
+    }
 }
 
 impl fmt::Debug for GenevaExporter {
@@ -49,8 +79,11 @@ impl opentelemetry_sdk::logs::LogExporter for GenevaExporter {
         // Encode and compress logs into batches
         let compressed_batches = match self.geneva_client.encode_and_compress_logs(&otlp) {
             Ok(batches) => batches,
-            Err(e) => return Err(OTelSdkError::InternalFailure(e)),
+            Err(e) => return Err(OTelSdkError::InternalFailure(e.to_string())),
         };
+        if let Some(queue) = &self.upload_queue {
+            return queue.push(compressed_batches).await;
+        }
 
         // Execute uploads concurrently within the same async task using buffer_unordered.
         // This processes up to max_concurrent_uploads batches simultaneously without
@@ -63,7 +96,7 @@ impl opentelemetry_sdk::logs::LogExporter for GenevaExporter {
                 async move { client.upload_batch(&batch).await }
             })
             .buffer_unordered(self.max_concurrent_uploads)
-            .filter_map(|result| async move { result.err() })
+            .filter_map(|result| async move { result.err().map(|e| e.to_string()) })
             .collect()
             .await;
 
@@ -81,4 +114,11 @@ impl opentelemetry_sdk::logs::LogExporter for GenevaExporter {
     fn set_resource(&mut self, resource: &opentelemetry_sdk::Resource) {
         self.resource = resource.into();
     }
+
+    fn shutdown_with_timeout(&self, timeout: Duration) -> OTelSdkResult {
+        match &self.upload_queue {
+            Some(queue) => queue.shutdown(timeout),
+            None => Ok(()),
+        }
+    }
 }
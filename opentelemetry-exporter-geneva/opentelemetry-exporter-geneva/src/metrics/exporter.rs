@@ -0,0 +1,136 @@
+use core::fmt;
+use futures::stream::{self, StreamExt};
+use geneva_uploader::client::GenevaClient;
+use opentelemetry_proto::tonic::collector::metrics::v1::ExportMetricsServiceRequest;
+use opentelemetry_sdk::error::{OTelSdkError, OTelSdkResult};
+use opentelemetry_sdk::metrics::data::ResourceMetrics;
+use opentelemetry_sdk::metrics::exporter::PushMetricExporter;
+use opentelemetry_sdk::metrics::Temporality;
+use std::io;
+use std::sync::{atomic, Arc};
+use std::time::Duration;
+
+use crate::queue::{UploadQueue, UploadQueueConfig, UploadQueueStats};
+
+/// An OpenTelemetry exporter that writes metrics to Geneva exporter
+pub struct GenevaMetricExporter {
+    is_shutdown: atomic::AtomicBool,
+    geneva_client: Arc<GenevaClient>,
+    max_concurrent_uploads: usize,
+    temporality: Temporality,
+    upload_queue: Option<UploadQueue>,
+}
+
+impl GenevaMetricExporter {
+    /// Create a new GenevaMetricExporter reporting delta temporality
+    pub fn new(geneva_client: GenevaClient) -> Self {
+        Self::new_with_concurrency(geneva_client, 4) // Default to 4 concurrent uploads
+    }
+
+    /// Create a new GenevaMetricExporter with custom concurrency level
+    pub fn new_with_concurrency(
+        geneva_client: GenevaClient,
+        max_concurrent_uploads: usize,
+    ) -> Self {
+        Self {
+            is_shutdown: atomic::AtomicBool::new(false),
+            geneva_client: Arc::new(geneva_client),
+            max_concurrent_uploads,
+            temporality: Temporality::Delta,
+            upload_queue: None,
+        }
+    }
+
+    /// Set the aggregation temporality requested from the SDK (defaults to delta)
+    pub fn with_temporality(mut self, temporality: Temporality) -> Self {
+        self.temporality = temporality;
+        self
+    }
+
+    /// Upload batches from a bounded background queue instead of within `export`. Fails if
+    /// the upload thread cannot be started.
+    pub fn with_upload_queue(mut self, config: UploadQueueConfig) -> io::Result<Self> {
+        self.upload_queue = Some(UploadQueue::new(
+            Arc::clone(&self.geneva_client),
+            config,
+            self.max_concurrent_uploads,
+        )?);
+        Ok(self)
+    }
+
+    /// Queued and in-flight batches of the upload queue, if one is configured
+    pub fn upload_queue_stats(&self) -> Option<UploadQueueStats> {
+        self.upload_queue.as_ref().map(UploadQueue::stats)
+    }
+}
+
+impl fmt::Debug for GenevaMetricExporter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Geneva metric exporter")
+    }
+}
+
+impl PushMetricExporter for GenevaMetricExporter {
+    async fn export(&self, metrics: &ResourceMetrics) -> OTelSdkResult {
+        if self.is_shutdown.load(atomic::Ordering::Relaxed) {
+            return Err(OTelSdkError::AlreadyShutdown);
+        }
+
+        let otlp = ExportMetricsServiceRequest::from(metrics);
+
+        // Encode and compress metrics into batches
+        let compressed_batches = match self
+            .geneva_client
+            .encode_and_compress_metrics(&otlp.resource_metrics)
+        {
+            Ok(batches) => batches,
+            Err(e) => return Err(OTelSdkError::InternalFailure(e.to_string())),
+        };
+        if let Some(queue) = &self.upload_queue {
+            return queue.push(compressed_batches).await;
+        }
+
+        // Execute uploads concurrently within the same async task using buffer_unordered,
+        // as done by the log and trace exporters.
+        let errors: Vec<String> = stream::iter(compressed_batches)
+            .map(|batch| {
+                let client = self.geneva_client.clone();
+                async move { client.upload_batch(&batch).await }
+            })
+            .buffer_unordered(self.max_concurrent_uploads)
+            .filter_map(|result| async move { result.err().map(|e| e.to_string()) })
+            .collect()
+            .await;
+
+        // Return error if any uploads failed
+        if !errors.is_empty() {
+            return Err(OTelSdkError::InternalFailure(format!(
+                "Upload failures: {}",
+                errors.join("; ")
+            )));
+        }
+
+        Ok(())
+    }
+
+    fn force_flush(&self) -> OTelSdkResult {
+        // Without a queue, metrics are uploaded synchronously in export()
+        self.upload_queue
+            .as_ref()
+            .map_or(Ok(()), UploadQueue::flush)
+    }
+
+    fn shutdown_with_timeout(&self, timeout: Duration) -> OTelSdkResult {
+        if self.is_shutdown.swap(true, atomic::Ordering::Relaxed) {
+            return Err(OTelSdkError::AlreadyShutdown);
+        }
+        match &self.upload_queue {
+            Some(queue) => queue.shutdown(timeout),
+            None => Ok(()),
+        }
+    }
+
+    fn temporality(&self) -> Temporality {
+        self.temporality
+    }
+}
@@ -0,0 +1,2 @@
+mod exporter;
+pub use exporter::GenevaMetricExporter;
@@ -0,0 +1,562 @@
+use geneva_uploader::client::{EncodedBatch, GenevaClient};
+use opentelemetry_sdk::error::{OTelSdkError, OTelSdkResult};
+use std::collections::VecDeque;
+use std::fmt;
+use std::io;
+use std::pin::pin;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use tokio::runtime::Handle;
+use tokio::sync::Notify;
+
+/// What `export` does with a batch when the upload queue is full
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Evict the oldest queued batch to make room for the new one
+    DropOldest,
+    /// Discard the new batch
+    DropNewest,
+    /// Make `export` wait until there is room, and discard the remaining batches of the
+    /// export once `timeout` has elapsed
+    Block {
+        /// Longest time a single export waits for room in the queue
+        timeout: Duration,
+    },
+}
+
+/// Configuration of the exporter-side upload queue.
+///
+/// With a queue, `export` only encodes the telemetry and hands the batches over to a
+/// background thread that uploads them, so a slow ingestion gateway does not stall the SDK
+/// processor. Batches dropped because the queue is full make `export` fail with a message
+/// saying how many records were lost, and are counted in the `geneva.batches.dropped` and
+/// `geneva.records.dropped` metrics when `GenevaClientConfig::meter` is set.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UploadQueueConfig {
+    /// Maximum number of encoded batches waiting for upload. Default: 128.
+    pub capacity: usize,
+    /// What to do when the queue is full. Default: `OverflowPolicy::DropNewest`.
+    pub overflow_policy: OverflowPolicy,
+    /// How long `force_flush` waits for queued and in-flight uploads. Default: 30 seconds.
+    pub flush_timeout: Duration,
+}
+
+impl Default for UploadQueueConfig {
+    fn default() -> Self {
+        UploadQueueConfig {
+            capacity: 128,
+            overflow_policy: OverflowPolicy::DropNewest,
+            flush_timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+/// State of the upload queue, as returned by the exporters' `upload_queue_stats`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct UploadQueueStats {
+    /// Batches waiting for upload
+    pub queued_batches: usize,
+    /// Batches being uploaded
+    pub in_flight_batches: usize,
+}
+
+/// Bounded queue of encoded batches, uploaded by a dedicated thread with at most
+/// `max_concurrent_uploads` uploads in flight.
+pub(crate) struct UploadQueue {
+    shared: Arc<Shared>,
+    client: Arc<GenevaClient>,
+    // Runtime of the upload thread, also driving the timers of blocked exports
+    runtime: Handle,
+    worker: Mutex<Option<JoinHandle<()>>>,
+}
+
+struct Shared {
+    config: UploadQueueConfig,
+    max_concurrent_uploads: usize,
+    state: Mutex<State>,
+    // Signalled when a batch leaves the queue or an upload completes, for `flush`
+    progress: Condvar,
+    // Wakes exports waiting for room when a batch leaves the queue or the queue closes
+    room: Notify,
+    // Wakes the dispatcher when a batch is queued, an upload completes or the queue closes
+    wake_up: Notify,
+}
+
+#[derive(Default)]
+struct State {
+    batches: VecDeque<EncodedBatch>,
+    in_flight: usize,
+    closed: bool,
+}
+
+impl UploadQueue {
+    pub(crate) fn new(
+        client: Arc<GenevaClient>,
+        config: UploadQueueConfig,
+        max_concurrent_uploads: usize,
+    ) -> io::Result<Self> {
+        let shared = Arc::new(Shared {
+            config: UploadQueueConfig {
+                capacity: config.capacity.max(1),
+                ..config
+            },
+            max_concurrent_uploads: max_concurrent_uploads.max(1),
+            state: Mutex::new(State::default()),
+            progress: Condvar::new(),
+            room: Notify::new(),
+            wake_up: Notify::new(),
+        });
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+        let handle = runtime.handle().clone();
+        let dispatcher = Arc::clone(&shared);
+        let uploader = Arc::clone(&client);
+        let worker = thread::Builder::new()
+            .name("geneva-upload".to_string())
+            .spawn(move || runtime.block_on(dispatch(dispatcher, uploader)))?;
+        Ok(Self {
+            shared,
+            client,
+            runtime: handle,
+            worker: Mutex::new(Some(worker)),
+        })
+    }
+
+    /// Queues `batches` for upload, applying the overflow policy when the queue is full
+    pub(crate) async fn push(&self, batches: Vec<EncodedBatch>) -> OTelSdkResult {
+        let shared = &self.shared;
+        let policy = shared.config.overflow_policy;
+        let deadline = match policy {
+            OverflowPolicy::Block { timeout } => Instant::now().checked_add(timeout),
+            _ => None,
+        };
+        if shared.lock().closed {
+            return Err(OTelSdkError::AlreadyShutdown);
+        }
+
+        let (mut dropped_batches, mut dropped_records) = (0u64, 0u64);
+        let mut enqueue = |state: &mut State, batch: EncodedBatch| {
+            let full = state.batches.len() >= shared.config.capacity;
+            // The queue may have been shut down while waiting for room
+            let dropped = if state.closed || (full && policy != OverflowPolicy::DropOldest) {
+                Some(batch)
+            } else {
+                let oldest = if full {
+                    state.batches.pop_front()
+                } else {
+                    None
+                };
+                state.batches.push_back(batch);
+                shared.wake_up.notify_one();
+                oldest
+            };
+            if let Some(dropped) = dropped {
+                dropped_batches += 1;
+                dropped_records += dropped.metadata.event_count as u64;
+                self.client.record_queue_overflow(&dropped);
+            }
+        };
+        match policy {
+            OverflowPolicy::Block { .. } => {
+                for batch in batches {
+                    self.wait_for_room(deadline).await;
+                    enqueue(&mut shared.lock(), batch);
+                }
+            }
+            _ => {
+                let mut state = shared.lock();
+                for batch in batches {
+                    enqueue(&mut state, batch);
+                }
+            }
+        }
+
+        if dropped_batches > 0 {
+            return Err(OTelSdkError::InternalFailure(format!(
+                "Upload queue full: dropped {dropped_batches} batches ({dropped_records} records)"
+            )));
+        }
+        Ok(())
+    }
+
+    /// Waits until the queue has room or is closed, or until `deadline`
+    async fn wait_for_room(&self, deadline: Option<Instant>) {
+        if self.shared.has_room() {
+            return;
+        }
+        let shared = Arc::clone(&self.shared);
+        let wait = async move {
+            loop {
+                let mut room = pin!(shared.room.notified());
+                room.as_mut().enable();
+                if shared.has_room() {
+                    return;
+                }
+                match deadline {
+                    Some(deadline) => {
+                        if tokio::time::timeout_at(deadline.into(), room)
+                            .await
+                            .is_err()
+                        {
+                            return;
+                        }
+                    }
+                    None => room.await,
+                }
+            }
+        };
+        // `export` may be polled outside of a Tokio runtime, so the wait runs on the upload
+        // thread's runtime. It only fails if that runtime is gone, i.e. the queue is closed.
+        let _ = self.runtime.spawn(wait).await;
+    }
+
+    /// Blocks until every queued batch has been uploaded or has failed
+    pub(crate) fn flush(&self) -> OTelSdkResult {
+        self.flush_with_timeout(self.shared.config.flush_timeout)
+    }
+
+    fn flush_with_timeout(&self, timeout: Duration) -> OTelSdkResult {
+        let shared = &self.shared;
+        let deadline = Instant::now().checked_add(timeout);
+        let mut state = shared.lock();
+        while !state.batches.is_empty() || state.in_flight > 0 {
+            let remaining = match deadline {
+                Some(deadline) => deadline.saturating_duration_since(Instant::now()),
+                None => Duration::MAX,
+            };
+            if remaining.is_zero() {
+                return Err(OTelSdkError::Timeout(timeout));
+            }
+            state = shared
+                .progress
+                .wait_timeout(state, remaining)
+                .unwrap_or_else(|e| e.into_inner())
+                .0;
+        }
+        Ok(())
+    }
+
+    /// Rejects new batches, then waits up to `timeout` for the queued ones to be uploaded
+    pub(crate) fn shutdown(&self, timeout: Duration) -> OTelSdkResult {
+        if std::mem::replace(&mut self.shared.lock().closed, true) {
+            return Err(OTelSdkError::AlreadyShutdown);
+        }
+        self.shared.notify_closed();
+        self.flush_with_timeout(timeout)?;
+        let worker = self.worker.lock().unwrap_or_else(|e| e.into_inner()).take();
+        if let Some(worker) = worker {
+            let _ = worker.join();
+        }
+        Ok(())
+    }
+
+    pub(crate) fn stats(&self) -> UploadQueueStats {
+        let state = self.shared.lock();
+        UploadQueueStats {
+            queued_batches: state.batches.len(),
+            in_flight_batches: state.in_flight,
+        }
+    }
+}
+
+impl Drop for UploadQueue {
+    fn drop(&mut self) {
+        // The worker thread uploads what is left, then exits
+        self.shared.lock().closed = true;
+        self.shared.notify_closed();
+    }
+}
+
+impl fmt::Debug for UploadQueue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UploadQueue")
+            .field("config", &self.shared.config)
+            .field("stats", &self.stats())
+            .finish()
+    }
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn has_room(&self) -> bool {
+        let state = self.lock();
+        state.closed || state.batches.len() < self.config.capacity
+    }
+
+    fn notify_closed(&self) {
+        self.room.notify_waiters();
+        self.wake_up.notify_one();
+    }
+}
+
+/// Starts uploads for queued batches, up to the concurrency limit, until the queue is closed
+/// and drained
+async fn dispatch(shared: Arc<Shared>, client: Arc<GenevaClient>) {
+    loop {
+        let next = {
+            let mut state = shared.lock();
+            let idle = state.batches.is_empty() && state.in_flight == 0;
+            if state.closed && idle {
+                break;
+            }
+            if state.in_flight < shared.max_concurrent_uploads {
+                let batch = state.batches.pop_front();
+                if batch.is_some() {
+                    state.in_flight += 1;
+                    shared.progress.notify_all();
+                    shared.room.notify_waiters();
+                }
+                batch
+            } else {
+                None
+            }
+        };
+        match next {
+            Some(batch) => {
+                tokio::spawn(upload(Arc::clone(&shared), Arc::clone(&client), batch));
+            }
+            None => shared.wake_up.notified().await,
+        }
+    }
+}
+
+/// Uploads one batch. Failures are counted by the client's own metrics.
+async fn upload(shared: Arc<Shared>, client: Arc<GenevaClient>, batch: EncodedBatch) {
+    let _ = client.upload_batch(&batch).await;
+    shared.lock().in_flight -= 1;
+    shared.progress.notify_all();
+    shared.wake_up.notify_one();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use geneva_uploader::mock::MockGeneva;
+    use opentelemetry::metrics::MeterProvider;
+    use opentelemetry_proto::tonic::logs::v1::{LogRecord, ResourceLogs, ScopeLogs};
+    use opentelemetry_sdk::metrics::data::{AggregatedMetrics, MetricData};
+    use opentelemetry_sdk::metrics::{InMemoryMetricExporter, PeriodicReader, SdkMeterProvider};
+
+    struct TestQueue {
+        client: Arc<GenevaClient>,
+        queue: UploadQueue,
+        provider: SdkMeterProvider,
+        exporter: InMemoryMetricExporter,
+    }
+
+    impl TestQueue {
+        fn new(mock: &MockGeneva, config: UploadQueueConfig) -> Self {
+            let exporter = InMemoryMetricExporter::default();
+            let reader = PeriodicReader::builder(exporter.clone()).build();
+            let provider = SdkMeterProvider::builder().with_reader(reader).build();
+            let mut client_config = mock.client_config();
+            client_config.meter = Some(provider.meter("geneva-queue-test"));
+            let client = Arc::new(GenevaClient::new(client_config).unwrap());
+            let queue = UploadQueue::new(Arc::clone(&client), config, 1).unwrap();
+            TestQueue {
+                client,
+                queue,
+                provider,
+                exporter,
+            }
+        }
+
+        fn batches(&self, event_names: &[&str]) -> Vec<EncodedBatch> {
+            event_names
+                .iter()
+                .flat_map(|name| {
+                    let logs = vec![ResourceLogs {
+                        scope_logs: vec![ScopeLogs {
+                            log_records: vec![
+                                LogRecord {
+                                    observed_time_unix_nano: 1_700_000_000_000_000_000,
+                                    event_name: name.to_string(),
+                                    ..Default::default()
+                                };
+                                2
+                            ],
+                            ..Default::default()
+                        }],
+                        ..Default::default()
+                    }];
+                    self.client.encode_and_compress_logs(&logs).unwrap()
+                })
+                .collect()
+        }
+
+        /// `flush` blocks the calling thread
+        fn flush(&self) -> OTelSdkResult {
+            tokio::task::block_in_place(|| self.queue.flush())
+        }
+
+        /// Value of the client's counter `name` for the data points carrying `attribute`
+        fn counter(&self, name: &str, attribute: (&str, &str)) -> u64 {
+            tokio::task::block_in_place(|| self.provider.force_flush()).unwrap();
+            let metrics = self.exporter.get_finished_metrics().unwrap();
+            let Some(latest) = metrics.last() else {
+                return 0;
+            };
+            latest
+                .scope_metrics()
+                .flat_map(|scope| scope.metrics())
+                .filter(|metric| metric.name() == name)
+                .filter_map(|metric| match metric.data() {
+                    AggregatedMetrics::U64(MetricData::Sum(sum)) => Some(
+                        sum.data_points()
+                            .filter(|point| {
+                                point.attributes().any(|kv| {
+                                    kv.key.as_str() == attribute.0
+                                        && kv.value.as_str() == attribute.1
+                                })
+                            })
+                            .map(|point| point.value())
+                            .sum::<u64>(),
+                    ),
+                    _ => None,
+                })
+                .sum()
+        }
+    }
+
+    const QUEUE_FULL: (&str, &str) = ("reason", "queue_full");
+
+    fn uploaded_events(mock: &MockGeneva) -> Vec<String> {
+        mock.uploads().into_iter().map(|u| u.event_name).collect()
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_drop_newest() {
+        let mock = MockGeneva::start().await;
+        let config = UploadQueueConfig {
+            capacity: 1,
+            ..UploadQueueConfig::default()
+        };
+        let test = TestQueue::new(&mock, config);
+
+        let batches = test.batches(&["A", "B", "C"]);
+        let err = test.queue.push(batches).await.unwrap_err();
+        assert!(err.to_string().contains("dropped 2 batches (4 records)"));
+        test.flush().unwrap();
+
+        assert_eq!(uploaded_events(&mock), vec!["A"]);
+        assert_eq!(test.counter("geneva.batches.dropped", QUEUE_FULL), 2);
+        assert_eq!(test.counter("geneva.records.dropped", QUEUE_FULL), 4);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_drop_oldest() {
+        let mock = MockGeneva::start().await;
+        let config = UploadQueueConfig {
+            capacity: 1,
+            overflow_policy: OverflowPolicy::DropOldest,
+            ..UploadQueueConfig::default()
+        };
+        let test = TestQueue::new(&mock, config);
+
+        let batches = test.batches(&["A", "B", "C"]);
+        assert!(test.queue.push(batches).await.is_err());
+        test.flush().unwrap();
+
+        assert_eq!(uploaded_events(&mock), vec!["C"]);
+        assert_eq!(test.counter("geneva.records.dropped", QUEUE_FULL), 4);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_block_with_timeout() {
+        let mock = MockGeneva::start().await;
+        mock.set_upload_latency(Duration::from_millis(300));
+        let config = UploadQueueConfig {
+            capacity: 1,
+            overflow_policy: OverflowPolicy::Block {
+                timeout: Duration::from_millis(50),
+            },
+            ..UploadQueueConfig::default()
+        };
+        let test = TestQueue::new(&mock, config);
+
+        test.queue.push(test.batches(&["A"])).await.unwrap();
+        while test.queue.stats().in_flight_batches == 0 {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        // B waits in the queue while A is uploaded; C finds no room in time
+        test.queue.push(test.batches(&["B"])).await.unwrap();
+        assert!(test.queue.push(test.batches(&["C"])).await.is_err());
+        test.flush().unwrap();
+
+        assert_eq!(uploaded_events(&mock), vec!["A", "B"]);
+        assert_eq!(test.counter("geneva.batches.dropped", QUEUE_FULL), 1);
+    }
+
+    #[test]
+    fn test_block_outside_of_tokio_runtime() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let mock = runtime.block_on(MockGeneva::start());
+        mock.set_upload_latency(Duration::from_millis(200));
+        let config = UploadQueueConfig {
+            capacity: 1,
+            overflow_policy: OverflowPolicy::Block {
+                timeout: Duration::from_secs(5),
+            },
+            ..UploadQueueConfig::default()
+        };
+        let test = runtime.block_on(async { TestQueue::new(&mock, config) });
+
+        // As with the SDK's batch processors, `export` is polled by a plain executor
+        let batches = test.batches(&["A", "B", "C"]);
+        futures::executor::block_on(test.queue.push(batches)).unwrap();
+        test.queue.flush().unwrap();
+
+        assert_eq!(uploaded_events(&mock), vec!["A", "B", "C"]);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_flush_waits_for_uploads() {
+        let mock = MockGeneva::start().await;
+        mock.set_upload_latency(Duration::from_millis(100));
+        mock.fail_uploads(1, 400);
+        let test = TestQueue::new(&mock, UploadQueueConfig::default());
+
+        test.queue
+            .push(test.batches(&["A", "B", "C"]))
+            .await
+            .unwrap();
+        test.flush().unwrap();
+
+        assert_eq!(mock.uploads().len(), 3);
+        assert_eq!(test.queue.stats(), UploadQueueStats::default());
+        let upload_failed = ("reason", "upload_failed");
+        assert_eq!(
+            test.counter("geneva.upload.batches", ("outcome", "success")),
+            2
+        );
+        assert_eq!(test.counter("geneva.records.dropped", upload_failed), 2);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_shutdown_drains_and_rejects_new_batches() {
+        let mock = MockGeneva::start().await;
+        mock.set_upload_latency(Duration::from_millis(50));
+        let test = TestQueue::new(&mock, UploadQueueConfig::default());
+        let shutdown = |queue: &UploadQueue| {
+            tokio::task::block_in_place(|| queue.shutdown(Duration::from_secs(5)))
+        };
+
+        test.queue.push(test.batches(&["A", "B"])).await.unwrap();
+        shutdown(&test.queue).unwrap();
+        assert_eq!(mock.uploads().len(), 2);
+
+        assert!(matches!(
+            test.queue.push(test.batches(&["C"])).await,
+            Err(OTelSdkError::AlreadyShutdown)
+        ));
+        assert!(matches!(
+            shutdown(&test.queue),
+            Err(OTelSdkError::AlreadyShutdown)
+        ));
+    }
+}
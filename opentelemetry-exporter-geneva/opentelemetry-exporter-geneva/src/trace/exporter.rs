@@ -5,7 +5,11 @@ use opentelemetry_proto::transform::common::tonic::ResourceAttributesWithSchema;
 use opentelemetry_proto::transform::trace::tonic::group_spans_by_resource_and_scope;
 use opentelemetry_sdk::error::{OTelSdkError, OTelSdkResult};
 use opentelemetry_sdk::trace::SpanExporter;
+use std::io;
 use std::sync::{atomic, Arc};
+use std::time::Duration;
+
+use crate::queue::{UploadQueue, UploadQueueConfig, UploadQueueStats};
 
 /// An OpenTelemetry exporter that writes spans to Geneva exporter
 pub struct GenevaTraceExporter {
@@ -13,6 +17,7 @@ pub struct GenevaTraceExporter {
     _is_shutdown: atomic::AtomicBool,
     geneva_client: Arc<GenevaClient>,
     max_concurrent_uploads: usize,
+    upload_queue: Option<UploadQueue>,
 }
 
 // TODO - Add builder pattern for GenevaTraceExporter to allow more flexible configuration
@@ -32,8 +37,25 @@ impl GenevaTraceExporter {
             _is_shutdown: atomic::AtomicBool::new(false),
             geneva_client: Arc::new(geneva_client),
             max_concurrent_uploads,
+            upload_queue: None,
         }
     }
+
+    /// Upload batches from a bounded background queue instead of within `export`. Fails if
+    /// the upload thread cannot be started.
+    pub fn with_upload_queue(mut self, config: UploadQueueConfig) -> io::Result<Self> {
+        self.upload_queue = Some(UploadQueue::new(
+            Arc::clone(&self.geneva_client),
+            config,
+            self.max_concurrent_uploads,
+        )?);
+        Ok(self)
+    }
+
+    /// Queued and in-flight batches of the upload queue, if one is configured
+    pub fn upload_queue_stats(&self) -> Option<UploadQueueStats> {
+        self.upload_queue.as_ref().map(UploadQueue::stats)
+    }
 }
 
 impl fmt::Debug for GenevaTraceExporter {
@@ -49,8 +71,11 @@ impl SpanExporter for GenevaTraceExporter {
         // Encode and compress spans into batches
         let compressed_batches = match self.geneva_client.encode_and_compress_spans(&otlp) {
             Ok(batches) => batches,
-            Err(e) => return Err(OTelSdkError::InternalFailure(e)),
+            Err(e) => return Err(OTelSdkError::InternalFailure(e.to_string())),
         };
+        if let Some(queue) = &self.upload_queue {
+            return queue.push(compressed_batches).await;
+        }
 
         // Execute uploads concurrently within the same async task using buffer_unordered.
         // This processes up to max_concurrent_uploads batches simultaneously without
@@ -63,7 +88,7 @@ impl SpanExporter for GenevaTraceExporter {
                 async move { client.upload_batch(&batch).await }
             })
             .buffer_unordered(self.max_concurrent_uploads)
-            .filter_map(|result| async move { result.err() })
+            .filter_map(|result| async move { result.err().map(|e| e.to_string()) })
             .collect()
             .await;
 
@@ -82,10 +107,19 @@ impl SpanExporter for GenevaTraceExporter {
         self.resource = resource.into();
     }
 
-    fn shutdown(&mut self) -> OTelSdkResult {
+    fn shutdown_with_timeout(&mut self, timeout: Duration) -> OTelSdkResult {
         // Set shutdown flag to true
         self._is_shutdown.store(true, atomic::Ordering::Relaxed);
         // TODO: Use the is_shutdown value in export() method to prevent exports after shutdown
-        Ok(())
+        match &self.upload_queue {
+            Some(queue) => queue.shutdown(timeout),
+            None => Ok(()),
+        }
+    }
+
+    fn force_flush(&mut self) -> OTelSdkResult {
+        self.upload_queue
+            .as_ref()
+            .map_or(Ok(()), UploadQueue::flush)
     }
 }
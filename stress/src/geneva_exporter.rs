@@ -33,7 +33,10 @@
         Progress: 449360 ops completed (449360 successful, 100.0%) in 30.01s = 14976.14 ops/sec
 
 */
-use geneva_uploader::{AuthMethod, GenevaClient, GenevaClientConfig};
+use geneva_uploader::{
+    AttributeEncoding, AuthMethod, BatchLimits, ColumnMapping, GenevaClient, GenevaClientConfig,
+    ResourceMapping, RetryPolicy, TokenRefreshConfig,
+};
 use opentelemetry_proto::tonic::common::v1::any_value::Value;
 use opentelemetry_proto::tonic::common::v1::{AnyValue, KeyValue};
 use opentelemetry_proto::tonic::logs::v1::{LogRecord, ResourceLogs, ScopeLogs};
@@ -43,9 +46,7 @@ use std::sync::Arc;
 mod async_throughput;
 use async_throughput::{ThroughputConfig, ThroughputTest};
 
-// Import mock server setup if needed
-use wiremock::matchers::{method, path_regex};
-use wiremock::{Mock, MockServer, ResponseTemplate};
+use geneva_uploader::mock::MockGeneva;
 
 // Helper functions
 fn create_test_logs(base_timestamp: u64) -> Vec<ResourceLogs> {
@@ -90,16 +91,7 @@ fn create_test_logs(base_timestamp: u64) -> Vec<ResourceLogs> {
     }]
 }
 
-fn generate_mock_jwt_and_expiry(_endpoint: &str, _ttl_secs: i64) -> (String, String) {
-    // This is the working token nginx config, updated with 1 year expiry
-    // Expires: June 30, 2026 (timestamp: 1782936000)
-    let token = "eyJhbGciOiJub25lIiwidHlwIjoiSldUIn0.eyJFbmRwb2ludCI6Imh0dHA6Ly9sb2NhbGhvc3Q6ODA4MC9pbmdlc3Rpb24iLCJleHAiOjE3ODI5MzYwMDB9.dummy";
-    let expiry = "2026-06-30T12:00:00+00:00";
-
-    (token.to_string(), expiry.to_string())
-}
-
-async fn init_client() -> Result<(GenevaClient, Option<String>), Box<dyn std::error::Error>> {
+async fn init_client() -> Result<(GenevaClient, Option<MockGeneva>), Box<dyn std::error::Error>> {
     // Check if we should use real endpoints
     if let Ok(endpoint) = std::env::var("GENEVA_ENDPOINT") {
         println!("Using real Geneva endpoint: {endpoint}");
@@ -121,9 +113,18 @@ async fn init_client() -> Result<(GenevaClient, Option<String>), Box<dyn std::er
                 password: std::env::var("GENEVA_CERT_PASSWORD")
                     .unwrap_or_else(|_| "test".to_string()),
             },
+            msi_resource: None,
             tenant: std::env::var("GENEVA_TENANT").unwrap_or_else(|_| "test".to_string()),
             role_name: std::env::var("GENEVA_ROLE").unwrap_or_else(|_| "test".to_string()),
             role_instance: std::env::var("GENEVA_INSTANCE").unwrap_or_else(|_| "test".to_string()),
+            spool: None,
+            retry_policy: RetryPolicy::default(),
+            attribute_encoding: AttributeEncoding::default(),
+            resource_mapping: ResourceMapping::default(),
+            column_mapping: ColumnMapping::default(),
+            batch_limits: BatchLimits::default(),
+            meter: None,
+            token_refresh: TokenRefreshConfig::default(),
         };
 
         let client = GenevaClient::new(config).map_err(std::io::Error::other)?;
@@ -139,67 +140,31 @@ async fn init_client() -> Result<(GenevaClient, Option<String>), Box<dyn std::er
             region: "test".to_string(),
             config_major_version: 1,
             auth_method: AuthMethod::MockAuth,
+            msi_resource: None,
             tenant: "test".to_string(),
             role_name: "test".to_string(),
             role_instance: "test".to_string(),
+            spool: None,
+            retry_policy: RetryPolicy::default(),
+            attribute_encoding: AttributeEncoding::default(),
+            resource_mapping: ResourceMapping::default(),
+            column_mapping: ColumnMapping::default(),
+            batch_limits: BatchLimits::default(),
+            meter: None,
+            token_refresh: TokenRefreshConfig::default(),
         };
 
         let client = GenevaClient::new(config).map_err(std::io::Error::other)?;
         Ok((client, None))
     } else {
-        println!("Using wiremock Geneva endpoints");
-
-        // Setup mock server
-        let mock_server = MockServer::start().await;
-        let ingestion_endpoint = format!("{}/ingestion", mock_server.uri());
-        let (auth_token, auth_token_expiry) =
-            generate_mock_jwt_and_expiry(&ingestion_endpoint, 24 * 3600);
-
-        // Mock config service (GET)
-        Mock::given(method("GET"))
-            .respond_with(ResponseTemplate::new(200).set_body_string(format!(
-                r#"{{
-                    "IngestionGatewayInfo": {{
-                        "Endpoint": "{}/ingestion",
-                        "AuthToken": "{auth_token}",
-                        "AuthTokenExpiryTime": "{auth_token_expiry}"
-                    }},
-                    "StorageAccountKeys": [{{
-                        "AccountMonikerName": "testdiagaccount",
-                        "AccountGroupName": "testgroup",
-                        "IsPrimaryMoniker": true
-                    }}],
-                    "TagId": "test"
-                }}"#,
-                mock_server.uri()
-            )))
-            .mount(&mock_server)
-            .await;
+        println!("Using in-process mock Geneva endpoints");
 
-        // Mock ingestion service (POST)
-        Mock::given(method("POST"))
-            .and(path_regex(r"^/ingestion.*"))
-            .respond_with({
-                ResponseTemplate::new(202).set_body_string(r#"{"ticket": "accepted"}"#)
-            })
-            .mount(&mock_server)
-            .await;
-
-        let config = GenevaClientConfig {
-            endpoint: mock_server.uri(),
-            environment: "test".to_string(),
-            account: "test".to_string(),
-            namespace: "test".to_string(),
-            region: "test".to_string(),
-            config_major_version: 1,
-            auth_method: AuthMethod::MockAuth,
-            tenant: "test".to_string(),
-            role_name: "test".to_string(),
-            role_instance: "test".to_string(),
-        };
+        // Skip decoding and recording uploads; only throughput matters here
+        let mock = MockGeneva::start().await;
+        mock.set_recording(false);
 
-        let client = GenevaClient::new(config).map_err(std::io::Error::other)?;
-        Ok((client, Some(mock_server.uri())))
+        let client = GenevaClient::new(mock.client_config()).map_err(std::io::Error::other)?;
+        Ok((client, Some(mock)))
     }
 }
 
@@ -270,7 +235,7 @@ async fn async_main(
         .unwrap_or("comparison");
 
     // Initialize client and test data
-    let (client, _mock_uri) = init_client().await?;
+    let (client, _mock) = init_client().await?;
     let client = Arc::new(client);
     let logs = Arc::new(create_test_logs(base_timestamp));
 
@@ -303,7 +268,9 @@ async fn async_main(
                 let client = client.clone();
                 let logs = logs.clone();
                 async move {
-                    let batches = client.encode_and_compress_logs(&logs)?;
+                    let batches = client
+                        .encode_and_compress_logs(&logs)
+                        .map_err(|e| format!("Failed to encode logs: {e}"))?;
 
                     // Upload batches sequentially TODO - use buffer_unordered for concurrency
                     for batch in &batches {